@@ -0,0 +1,110 @@
+//! Read throughput on a large synthetic graph, default SQLite settings vs --cache-size/--mmap-size
+//!
+//! Run with `cargo bench --bench read_throughput`.
+//! BENCH_ENTITIES (default 50000) and BENCH_LOOKUPS (default 20000) scale the run.
+
+use std::time::Instant;
+
+use memory_mcp_rs::graph::{Entity, KnowledgeGraph, Relation};
+use memory_mcp_rs::storage::{CacheSize, Database, DatabaseOptions};
+use tempfile::TempDir;
+
+fn env_usize(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Deterministic pseudo-random sequence (no extra dependency for a benchmark)
+fn lcg(state: &mut u64) -> u64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    *state >> 33
+}
+
+fn synthetic_graph(entities: usize) -> KnowledgeGraph {
+    let entities_vec: Vec<Entity> = (0..entities)
+        .map(|i| Entity {
+            name: format!("entity_{:07}", i),
+            entity_type: ["person", "organization", "project", "concept"][i % 4].to_string(),
+            observations: (0..4)
+                .map(|j| {
+                    format!(
+                        "Observation {} of entity {}: some representative text about its history and role",
+                        j, i
+                    )
+                })
+                .collect(),
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        })
+        .collect();
+    let relations = (0..entities)
+        .map(|i| Relation {
+            from: format!("entity_{:07}", i),
+            to: format!("entity_{:07}", (i * 7919 + 1) % entities),
+            relation_type: "related_to".to_string(),
+        })
+        .collect();
+    KnowledgeGraph {
+        entities: entities_vec,
+        relations,
+    }
+}
+
+/// Random open_nodes lookups per second
+fn measure(db: &Database, entities: usize, lookups: usize) -> f64 {
+    let mut state = 42;
+    let start = Instant::now();
+    for _ in 0..lookups {
+        let names: Vec<String> = (0..4)
+            .map(|_| format!("entity_{:07}", lcg(&mut state) as usize % entities))
+            .collect();
+        let graph = db.open_nodes(&names).expect("open_nodes failed");
+        assert!(!graph.entities.is_empty());
+    }
+    lookups as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let entities = env_usize("BENCH_ENTITIES", 50_000);
+    let lookups = env_usize("BENCH_LOOKUPS", 20_000);
+
+    let dir = TempDir::new().expect("Failed to create tempdir");
+    let path = dir.path().join("bench.db");
+    let start = Instant::now();
+    Database::open(&path)
+        .expect("Failed to open database")
+        .import_graph(&synthetic_graph(entities))
+        .expect("Failed to build synthetic graph");
+    let size_mib = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0) as f64 / 1048576.0;
+    println!(
+        "Built {} entities ({:.1} MiB) in {:.1?}",
+        entities,
+        size_mib,
+        start.elapsed()
+    );
+
+    let configs = [
+        ("default", DatabaseOptions::default()),
+        (
+            "cache 256m + mmap 1 GiB",
+            DatabaseOptions {
+                cache_size: Some(CacheSize::Kibibytes(256 * 1024)),
+                mmap_size: Some(1 << 30),
+                ..Default::default()
+            },
+        ),
+    ];
+    for (label, options) in configs {
+        let db = Database::open_with_options(&path, options).expect("Failed to open database");
+        // Warm-up pass fills the cache, the second pass is measured
+        measure(&db, entities, lookups);
+        let rate = measure(&db, entities, lookups);
+        println!("{:<24} {:>10.0} lookups/s", label, rate);
+    }
+}
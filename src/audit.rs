@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use memory_mcp_rs::checkpoint::format_timestamp;
+use serde_json::{json, Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Characters of a string argument kept in an audit record
+const MAX_ARGUMENT_CHARS: usize = 100;
+
+/// Append-only JSONL record of every tool call (--tool-audit-file)
+/// One line per call, written when the call finishes; independent of the tracing log
+pub struct ToolAuditLog {
+    file: Mutex<File>,
+}
+
+impl ToolAuditLog {
+    /// Open the audit file for appending, creating it if missing
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open tool audit file '{}'", path.display()))?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append the record of a finished call: error is None when it succeeded
+    /// Each record is written with a single append, so lines never interleave
+    pub fn record(
+        &self,
+        tool: &str,
+        arguments: Option<&Map<String, Value>>,
+        error: Option<&str>,
+        duration: Duration,
+    ) -> Result<()> {
+        let mut record = json!({
+            "timestamp": format_timestamp(SystemTime::now()),
+            "tool": tool,
+            "arguments": arguments.map(summarize_arguments).unwrap_or_else(|| json!({})),
+            "success": error.is_none(),
+            "durationMs": duration.as_millis() as u64,
+        });
+        if let Some(error) = error {
+            record["error"] = json!(error);
+        }
+        let mut line = record.to_string();
+        line.push('\n');
+
+        self.file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_all(line.as_bytes())
+            .context("Failed to write tool audit record")
+    }
+}
+
+/// Tool arguments shortened for an audit record: strings are cut to MAX_ARGUMENT_CHARS,
+/// arrays and objects are replaced by their size, numbers, booleans and null are kept
+fn summarize_arguments(arguments: &Map<String, Value>) -> Value {
+    let summary = arguments
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(s) if s.chars().count() > MAX_ARGUMENT_CHARS => {
+                    let cut: String = s.chars().take(MAX_ARGUMENT_CHARS).collect();
+                    json!(format!("{}...", cut))
+                }
+                Value::Array(items) => json!(format!("<{} items>", items.len())),
+                Value::Object(fields) => json!(format!("<{} fields>", fields.len())),
+                other => other.clone(),
+            };
+            (key.clone(), value)
+        })
+        .collect();
+    Value::Object(summary)
+}
@@ -0,0 +1,79 @@
+use crate::checkpoint::format_timestamp;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// File name prefix of startup backups in the backup directory
+pub const BACKUP_PREFIX: &str = "backup-";
+
+/// File extension of startup backups
+pub const BACKUP_EXTENSION: &str = "db";
+
+/// Outcome of a startup backup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupRun {
+    /// The backup file just written
+    pub path: PathBuf,
+    /// Older backups deleted to stay within the retention count (oldest first)
+    pub pruned: Vec<PathBuf>,
+}
+
+/// File a backup taken at `time` is stored in: `<dir>/backup-20240501T123456789Z.db`
+/// The timestamp has no separators, so names sort chronologically and are valid everywhere
+pub fn backup_path(dir: &Path, time: SystemTime) -> PathBuf {
+    let stamp: String = format_timestamp(time)
+        .chars()
+        .filter(|c| !matches!(c, '-' | ':' | '.'))
+        .collect();
+    dir.join(format!("{}{}.{}", BACKUP_PREFIX, stamp, BACKUP_EXTENSION))
+}
+
+/// List backups in the directory, oldest first (a missing directory has none)
+/// Only files named like backup_path are listed, so other files in the directory are safe
+pub fn list_backups(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read backup directory '{}'", dir.display()))
+        }
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("Failed to read backup directory '{}'", dir.display()))?
+            .path();
+        if path.extension().and_then(|e| e.to_str()) != Some(BACKUP_EXTENSION) {
+            continue;
+        }
+        let Some(stamp) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_prefix(BACKUP_PREFIX))
+        else {
+            continue;
+        };
+        if stamp.is_empty() || !stamp.chars().all(|c| c.is_ascii_alphanumeric()) {
+            continue;
+        }
+        if path.is_file() {
+            backups.push(path);
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
+
+/// Delete the oldest backups so that at most `keep` remain, returning the deleted files
+pub fn prune_backups(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let backups = list_backups(dir)?;
+    let excess = backups.len().saturating_sub(keep);
+    let pruned: Vec<PathBuf> = backups.into_iter().take(excess).collect();
+    for path in &pruned {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to delete old backup '{}'", path.display()))?;
+    }
+    Ok(pruned)
+}
@@ -0,0 +1,136 @@
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File extension of checkpoint files in the checkpoint directory
+pub const CHECKPOINT_EXTENSION: &str = "db";
+
+/// Maximum length of a checkpoint name
+pub const MAX_CHECKPOINT_NAME_LENGTH: usize = 64;
+
+/// A saved copy of the database
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct CheckpointInfo {
+    pub name: String,
+    /// When the checkpoint file was written (ISO-8601 UTC)
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    /// Size of the checkpoint file in bytes
+    pub size: u64,
+}
+
+/// Check that a checkpoint name is usable as a file name:
+/// 1-64 ASCII letters, digits, '-', '_' or '.', not starting with '.'
+pub fn validate_checkpoint_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("Checkpoint name cannot be empty");
+    }
+    if name.len() > MAX_CHECKPOINT_NAME_LENGTH {
+        bail!(
+            "Checkpoint name too long: {} characters (max {})",
+            name.len(),
+            MAX_CHECKPOINT_NAME_LENGTH
+        );
+    }
+    if name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        bail!(
+            "Invalid checkpoint name '{}': use letters, digits, '-', '_' or '.' (not leading)",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// File a checkpoint is stored in: `<dir>/<name>.db`
+pub fn checkpoint_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    validate_checkpoint_name(name)?;
+    Ok(dir.join(format!("{}.{}", name, CHECKPOINT_EXTENSION)))
+}
+
+/// Describe the checkpoint stored at path
+pub fn checkpoint_info(name: &str, path: &Path) -> Result<CheckpointInfo> {
+    let metadata = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read checkpoint '{}'", path.display()))?;
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+    Ok(CheckpointInfo {
+        name: name.to_string(),
+        created_at: format_timestamp(modified),
+        size: metadata.len(),
+    })
+}
+
+/// List checkpoints in the directory, oldest first (a missing directory has none)
+pub fn list_checkpoints(dir: &Path) -> Result<Vec<CheckpointInfo>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("Failed to read checkpoint directory '{}'", dir.display())
+            })
+        }
+    };
+
+    let mut checkpoints = Vec::new();
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("Failed to read checkpoint directory '{}'", dir.display()))?
+            .path();
+        if path.extension().and_then(|e| e.to_str()) != Some(CHECKPOINT_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        // Skip files that were not written by create_checkpoint
+        if validate_checkpoint_name(name).is_err() || !path.is_file() {
+            continue;
+        }
+        checkpoints.push(checkpoint_info(name, &path)?);
+    }
+    checkpoints.sort_by(|a, b| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(checkpoints)
+}
+
+/// Format a file time like the entity timestamps: `2024-05-01T12:34:56.789Z`
+pub fn format_timestamp(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = elapsed.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let seconds_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+/// Convert days since 1970-01-01 into a (year, month, day) date of the proleptic
+/// Gregorian calendar (Howard Hinnant's civil_from_days)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
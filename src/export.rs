@@ -0,0 +1,271 @@
+use crate::graph::{Entity, GraphRecord, KnowledgeGraph};
+use crate::path::resolve_nearest_existing;
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// File format for graph exports
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Same shape as read_graph: { "entities": [...], "relations": [...] }
+    #[default]
+    Json,
+    /// Graphviz DOT digraph
+    Dot,
+    /// Newline-delimited JSON: one {"type": "entity" | "relation", ...} object per line
+    /// (the JSONL format --seed reads)
+    Ndjson,
+}
+
+impl ExportFormat {
+    /// File extension expected for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Dot => "dot",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// Layout choices of the DOT serializer (ignored by the other formats)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DotOptions {
+    /// Group entities of the same type into labeled `cluster_<type>` subgraphs
+    pub cluster_by_type: bool,
+}
+
+/// Serialize graph in the given format
+pub fn render(graph: &KnowledgeGraph, format: ExportFormat) -> Result<String> {
+    render_with(graph, format, DotOptions::default())
+}
+
+/// Serialize graph in the given format, laying out DOT output per options
+pub fn render_with(
+    graph: &KnowledgeGraph,
+    format: ExportFormat,
+    dot: DotOptions,
+) -> Result<String> {
+    match format {
+        ExportFormat::Json => to_json(graph),
+        ExportFormat::Dot => Ok(to_dot_with(graph, dot)),
+        ExportFormat::Ndjson => to_ndjson(graph),
+    }
+}
+
+/// Serialize graph as pretty-printed JSON
+pub fn to_json(graph: &KnowledgeGraph) -> Result<String> {
+    serde_json::to_string_pretty(graph).context("Failed to serialize graph as JSON")
+}
+
+/// Serialize graph as NDJSON: entities first, then relations, one record per line
+pub fn to_ndjson(graph: &KnowledgeGraph) -> Result<String> {
+    let mut out = String::new();
+    for entity in &graph.entities {
+        out.push_str(&ndjson_line(&GraphRecord::Entity(entity.clone()))?);
+    }
+    for relation in &graph.relations {
+        out.push_str(&ndjson_line(&GraphRecord::Relation(relation.clone()))?);
+    }
+    Ok(out)
+}
+
+/// Serialize one record as an NDJSON line (including the trailing newline)
+pub fn ndjson_line(record: &GraphRecord) -> Result<String> {
+    let mut line = serde_json::to_string(record).context("Failed to serialize graph record")?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Serialize graph as a Graphviz digraph (nodes labeled "name\n(type)", edges by relation type)
+pub fn to_dot(graph: &KnowledgeGraph) -> String {
+    to_dot_with(graph, DotOptions::default())
+}
+
+/// Serialize graph as a Graphviz digraph; with cluster_by_type, entities are grouped into one
+/// labeled cluster per type (types sorted), bordered and filled in a color derived from the type
+pub fn to_dot_with(graph: &KnowledgeGraph, options: DotOptions) -> String {
+    let mut out = String::from("digraph memory {\n");
+    if options.cluster_by_type {
+        let mut by_type: BTreeMap<&str, Vec<&Entity>> = BTreeMap::new();
+        for entity in &graph.entities {
+            by_type.entry(&entity.entity_type).or_default().push(entity);
+        }
+        for (entity_type, entities) in by_type {
+            let hue = type_hue(entity_type);
+            let _ = writeln!(out, "  subgraph \"cluster_{}\" {{", escape_dot(entity_type));
+            let _ = writeln!(out, "    label=\"{}\";", escape_dot(entity_type));
+            let _ = writeln!(out, "    color=\"{:.3} 0.600 0.700\";", hue);
+            let _ = writeln!(
+                out,
+                "    node [style=filled, fillcolor=\"{:.3} 0.250 1.000\"];",
+                hue
+            );
+            for entity in entities {
+                out.push_str("  ");
+                write_dot_node(&mut out, entity);
+            }
+            out.push_str("  }\n");
+        }
+    } else {
+        for entity in &graph.entities {
+            write_dot_node(&mut out, entity);
+        }
+    }
+    for rel in &graph.relations {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [label=\"{}\"];",
+            escape_dot(&rel.from),
+            escape_dot(&rel.to),
+            escape_dot(&rel.relation_type)
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Append one node statement labeled "name\n(type)"
+fn write_dot_node(out: &mut String, entity: &Entity) {
+    let _ = writeln!(
+        out,
+        "  \"{}\" [label=\"{}\\n({})\"];",
+        escape_dot(&entity.name),
+        escape_dot(&entity.name),
+        escape_dot(&entity.entity_type)
+    );
+}
+
+/// Hue (0..1) of an entity type's cluster color: FNV-1a of the type, so the same type gets
+/// the same color in every render and on every platform
+fn type_hue(entity_type: &str) -> f64 {
+    let hash = entity_type
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    (hash % 1000) as f64 / 1000.0
+}
+
+/// Serialize graph as a Mermaid flowchart (left to right): one node per entity labeled
+/// "name (type)", one arrow per relation labeled with its type
+/// Node ids are the names reduced to [A-Za-z0-9_] behind an "e_" prefix (so they never clash
+/// with Mermaid keywords such as `end`), numbered when two names reduce to the same id
+pub fn to_mermaid(graph: &KnowledgeGraph) -> String {
+    let mut ids: HashMap<&str, String> = HashMap::new();
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut out = String::from("flowchart LR\n");
+
+    for entity in &graph.entities {
+        let id = mermaid_id(&entity.name, &mut taken);
+        let _ = writeln!(
+            out,
+            "  {}[\"{} ({})\"]",
+            id,
+            escape_mermaid(&entity.name),
+            escape_mermaid(&entity.entity_type)
+        );
+        ids.insert(&entity.name, id);
+    }
+    for rel in &graph.relations {
+        // Endpoints outside the entity list still get a node, labeled with the bare name
+        for name in [&rel.from, &rel.to] {
+            if !ids.contains_key(name.as_str()) {
+                let id = mermaid_id(name, &mut taken);
+                let _ = writeln!(out, "  {}[\"{}\"]", id, escape_mermaid(name));
+                ids.insert(name, id);
+            }
+        }
+        let _ = writeln!(
+            out,
+            "  {} -->|\"{}\"| {}",
+            ids[rel.from.as_str()],
+            escape_mermaid(&rel.relation_type),
+            ids[rel.to.as_str()]
+        );
+    }
+    out
+}
+
+/// Unused Mermaid node id for an entity name
+fn mermaid_id(name: &str, taken: &mut HashSet<String>) -> String {
+    let mut base = String::from("e_");
+    base.extend(name.chars().map(|c| {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            c
+        } else {
+            '_'
+        }
+    }));
+    let mut id = base.clone();
+    let mut n = 2;
+    while !taken.insert(id.clone()) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+/// Escape a string for a double-quoted Mermaid label (entity codes for '#' and '"')
+fn escape_mermaid(s: &str) -> String {
+    s.replace('#', "#35;").replace('"', "#quot;")
+}
+
+/// Escape a string for use inside a double-quoted DOT identifier
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Resolve a client-supplied export destination inside the export directory
+/// The destination must be a relative path without '..' and carry the format's extension;
+/// the resolved file may not escape the export directory (e.g. through symlinks)
+pub fn resolve_export_path(
+    export_dir: &Path,
+    destination: &str,
+    format: ExportFormat,
+) -> Result<PathBuf> {
+    let relative = Path::new(destination);
+    if destination.trim().is_empty() {
+        bail!("Export destination cannot be empty");
+    }
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        bail!(
+            "Export destination must be a relative path inside the export directory (got '{}')",
+            destination
+        );
+    }
+    if relative.extension().and_then(|e| e.to_str()) != Some(format.extension()) {
+        bail!(
+            "Export destination must have .{} extension for {:?} format",
+            format.extension(),
+            format
+        );
+    }
+
+    // Resolve and check containment before creating anything, so a symlink inside the
+    // export directory cannot get directories (or the file) created outside of it
+    let canonical_dir =
+        resolve_nearest_existing(export_dir).context("Failed to resolve export directory")?;
+    let resolved = resolve_nearest_existing(&export_dir.join(relative))
+        .context("Failed to resolve export destination")?;
+    if !resolved.starts_with(&canonical_dir) || resolved == canonical_dir {
+        bail!("Export destination escapes the export directory");
+    }
+
+    let parent = resolved
+        .parent()
+        .context("Export destination has no parent directory")?;
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create export directory '{}'", parent.display()))?;
+    if parent.canonicalize().ok().as_deref() != Some(parent) {
+        bail!("Export destination escapes the export directory");
+    }
+    Ok(resolved)
+}
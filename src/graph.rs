@@ -1,10 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 /// Entity in the knowledge graph
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Entity {
-    /// Unique name of the entity (serves as ID)
+    /// Unique name of the entity (primary key; see `id` for a handle that is not the name)
     pub name: String,
 
     /// Type of entity (person, organization, concept, etc.)
@@ -13,10 +14,29 @@ pub struct Entity {
 
     /// Array of observations (facts) about the entity
     pub observations: Vec<String>,
+
+    /// Optional canonical summary, kept separate from the observation log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Stable id assigned by the server on creation; unlike the name it never changes
+    /// Read-only: ignored when creating entities, so it is left out of input schemas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    pub id: Option<String>,
+
+    /// Optional confidence in the entity (0.0-1.0), e.g. from a probabilistic extractor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+
+    /// Optional free-form JSON for app-specific data (URLs, coordinates, external ids)
+    /// Stored as given and never searched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
 }
 
 /// Relation between two entities
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
 pub struct Relation {
     /// Source entity name
     pub from: String,
@@ -36,6 +56,367 @@ pub struct KnowledgeGraph {
     pub relations: Vec<Relation>,
 }
 
+impl KnowledgeGraph {
+    /// Drop entities scored below `min` and the relations touching them
+    /// Entities without a confidence score are kept
+    pub fn retain_min_confidence(&mut self, min: f64) {
+        let mut dropped = HashSet::new();
+        self.entities.retain(|entity| match entity.confidence {
+            Some(confidence) if confidence < min => {
+                dropped.insert(entity.name.clone());
+                false
+            }
+            _ => true,
+        });
+        if !dropped.is_empty() {
+            self.relations
+                .retain(|r| !dropped.contains(&r.from) && !dropped.contains(&r.to));
+        }
+    }
+}
+
+/// What merge_graph does with an incoming entity whose name already exists
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the existing entity as it is
+    Skip,
+    /// Replace the existing entity's type and observations with the incoming ones
+    Overwrite,
+    /// Append the incoming observations the existing entity does not have yet
+    #[default]
+    MergeObservations,
+}
+
+/// Outcome of merging an imported graph into the existing one
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MergeGraphResult {
+    /// Newly created entities
+    pub entities: Vec<Entity>,
+    /// Newly created relations
+    pub relations: Vec<Relation>,
+    /// Observations appended to entities that already existed
+    #[serde(rename = "mergedObservations")]
+    pub merged_observations: Vec<ObservationResult>,
+    /// Existing entities the conflict policy changed (overwritten or given new observations)
+    pub updated: Vec<String>,
+    /// Existing entities left as they were (skip policy, or nothing new to merge)
+    pub skipped: Vec<String>,
+    /// Batch the created entities and relations were tagged with (see get_batch)
+    #[serde(rename = "batchId")]
+    pub batch_id: String,
+}
+
+/// What a successful mutation changed in the graph
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraphEvent {
+    EntitiesCreated {
+        names: Vec<String>,
+    },
+    /// Observations or description changed
+    EntitiesUpdated {
+        names: Vec<String>,
+    },
+    /// Relations touching these entities were removed with them
+    EntitiesDeleted {
+        names: Vec<String>,
+    },
+    RelationsCreated {
+        relations: Vec<Relation>,
+    },
+    RelationsDeleted {
+        relations: Vec<Relation>,
+    },
+    /// The whole graph was replaced by a saved checkpoint
+    CheckpointRestored {
+        name: String,
+    },
+}
+
+/// Change notification published to subscribers after a mutation commits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GraphChange {
+    /// Graph revision read right after the change (None if it could not be read)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
+    #[serde(flatten)]
+    pub event: GraphEvent,
+}
+
+/// A write staged in an open transaction, applied in order on commit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum GraphOperation {
+    CreateEntities { entities: Vec<Entity> },
+    CreateRelations { relations: Vec<Relation> },
+    AddObservations { observations: Vec<ObservationInput> },
+    DeleteEntities { names: Vec<String> },
+    DeleteObservations { deletions: Vec<ObservationDeletion> },
+    DeleteRelations { relations: Vec<Relation> },
+}
+
+impl GraphOperation {
+    /// Name of the tool this operation was staged by
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphOperation::CreateEntities { .. } => "create_entities",
+            GraphOperation::CreateRelations { .. } => "create_relations",
+            GraphOperation::AddObservations { .. } => "add_observations",
+            GraphOperation::DeleteEntities { .. } => "delete_entities",
+            GraphOperation::DeleteObservations { .. } => "delete_observations",
+            GraphOperation::DeleteRelations { .. } => "delete_relations",
+        }
+    }
+
+    /// Number of items in the operation's batch
+    pub fn len(&self) -> usize {
+        match self {
+            GraphOperation::CreateEntities { entities } => entities.len(),
+            GraphOperation::CreateRelations { relations }
+            | GraphOperation::DeleteRelations { relations } => relations.len(),
+            GraphOperation::AddObservations { observations } => observations.len(),
+            GraphOperation::DeleteEntities { names } => names.len(),
+            GraphOperation::DeleteObservations { deletions } => deletions.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Outcome of one committed operation, shaped like the result of the matching tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum OperationResult {
+    CreateEntities(CreateEntitiesResult),
+    CreateRelations { relations: Vec<Relation> },
+    AddObservations { results: Vec<ObservationResult> },
+    DeleteEntities { deleted: usize },
+    DeleteObservations { entities: Vec<String> },
+    DeleteRelations { deleted: usize },
+}
+
+/// Outcome of reversing a relation's direction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReversedRelation {
+    /// The relation as it is stored now (old 'to' -> old 'from')
+    pub relation: Relation,
+    /// True if the reversed relation already existed, so the original was only removed
+    pub merged: bool,
+}
+
+/// Relations whose endpoints name no entity, and those missing names
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DanglingRelations {
+    pub relations: Vec<Relation>,
+    /// Endpoint names without an entity (sorted, each once)
+    pub missing: Vec<String>,
+}
+
+/// Rows of a table next to the documents its full-text index holds
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct SearchIndexCount {
+    /// Base table: "entities" or "relations"
+    pub table: String,
+    pub rows: u64,
+    pub indexed: u64,
+}
+
+impl SearchIndexCount {
+    /// Index and table agree (a mismatch means the FTS triggers or shadow tables drifted)
+    pub fn is_consistent(&self) -> bool {
+        self.rows == self.indexed
+    }
+}
+
+/// One line of a JSONL (NDJSON) graph file: an entity or a relation, tagged by "type"
+/// {"type": "entity", "name": ..., "entityType": ..., "observations": [...]}
+/// {"type": "relation", "from": ..., "to": ..., "relationType": ...}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GraphRecord {
+    Entity(Entity),
+    Relation(Relation),
+}
+
+/// Entity with its creation and last-update times
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentEntity {
+    #[serde(flatten)]
+    pub entity: Entity,
+
+    /// When the entity was created (ISO-8601 UTC; null if created before timestamps existed)
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<String>,
+
+    /// When the entity was last changed (ISO-8601 UTC; null if unchanged since before timestamps)
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<String>,
+}
+
+/// How often an entity was returned by open_nodes / search_nodes (with --track-access)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct EntityAccess {
+    pub name: String,
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    #[serde(rename = "accessCount")]
+    pub access_count: u64,
+    /// Last time the entity was returned (ISO-8601 UTC; null if never)
+    #[serde(rename = "lastAccessed")]
+    pub last_accessed: Option<String>,
+}
+
+/// Entity with the number of observations it holds
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ObservationCount {
+    pub name: String,
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    #[serde(rename = "observationCount")]
+    pub observation_count: u64,
+}
+
+/// Distinct observation text with the number of entities holding it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ObservationValue {
+    pub observation: String,
+    #[serde(rename = "entityCount")]
+    pub entity_count: u64,
+}
+
+/// One page of distinct observation values (see list_observations)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ObservationValuePage {
+    /// Values on this page (most widely held first, then by text)
+    pub observations: Vec<ObservationValue>,
+    /// Total number of distinct matching values across all pages
+    pub total: usize,
+    /// Offset of the first value on this page
+    pub offset: usize,
+    /// Maximum page size that was requested
+    pub limit: usize,
+}
+
+/// Entity with its cached PageRank importance (see compute_importance)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ImportantEntity {
+    pub name: String,
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    /// Share of the total rank (all scores of one run add up to 1)
+    pub importance: f64,
+}
+
+/// Outcome of compute_importance
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ImportanceRun {
+    /// Entities scored
+    pub entities: usize,
+    /// Relations followed as links
+    pub relations: usize,
+    /// Iterations run (fewer than requested once the scores stopped changing)
+    pub iterations: u32,
+    /// Whether the scores settled before the iteration limit
+    pub converged: bool,
+}
+
+/// Incoming entity that was mapped onto an existing entity instead of being created
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DeduplicatedEntity {
+    /// Name as given in the request
+    pub name: String,
+
+    /// Name of the existing entity it was deduplicated into
+    #[serde(rename = "existingName")]
+    pub existing_name: String,
+
+    /// Observations merged into the existing entity (empty unless merging is enabled)
+    #[serde(rename = "mergedObservations")]
+    pub merged_observations: Vec<String>,
+}
+
+/// Outcome of create_entities: new entities plus any deduplicated ones
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CreateEntitiesResult {
+    pub created: Vec<Entity>,
+    pub deduplicated: Vec<DeduplicatedEntity>,
+    /// Batch the created entities were tagged with (see get_batch); None if nothing was sent
+    #[serde(rename = "batchId", default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+/// Outcome of create_relations: the new relations and the batch they were tagged with
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CreateRelationsResult {
+    pub created: Vec<Relation>,
+    /// None if no relations were sent
+    #[serde(rename = "batchId", default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Endpoints that named an alias rather than an entity
+    #[serde(
+        rename = "resolvedAliases",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub resolved_aliases: Vec<ResolvedAlias>,
+}
+
+/// Outcome of delete_relations: how many of the relations existed (and are gone now)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct DeleteRelationsResult {
+    pub deleted: usize,
+    /// Endpoints that named an alias rather than an entity
+    #[serde(
+        rename = "resolvedAliases",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub resolved_aliases: Vec<ResolvedAlias>,
+}
+
+/// A relation endpoint given as an alias, and the entity it was resolved to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResolvedAlias {
+    /// Position of the relation in the request
+    pub index: usize,
+    /// "from" or "to"
+    pub endpoint: String,
+    pub alias: String,
+    pub entity: String,
+}
+
+impl ResolvedAlias {
+    /// Put the entity name in place of the alias in the requested relations
+    pub fn apply(&self, relations: &mut [Relation]) {
+        if let Some(relation) = relations.get_mut(self.index) {
+            let name = if self.endpoint == "from" {
+                &mut relation.from
+            } else {
+                &mut relation.to
+            };
+            *name = self.entity.clone();
+        }
+    }
+}
+
+/// Aliases of an entity: alternative names relation endpoints may use for it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityAliases {
+    #[serde(rename = "entityName")]
+    pub entity_name: String,
+    pub aliases: Vec<String>,
+}
+
+/// Outcome of replace_entity: the entity as stored, and whether it had to be created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacedEntity {
+    pub entity: Entity,
+    pub created: bool,
+}
+
 /// Input for adding observations to an entity
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ObservationInput {
@@ -53,10 +434,537 @@ pub struct ObservationResult {
     pub added_observations: Vec<String>,
 }
 
+/// Result of appending one observation to many entities
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FanOutObservationResult {
+    /// The observation as stored (normalized per the observation format)
+    pub observation: String,
+    /// Entities the observation was appended to
+    pub added: Vec<String>,
+    /// Entities that already had the observation
+    #[serde(rename = "alreadyPresent")]
+    pub already_present: Vec<String>,
+    /// Names that matched no entity (skipped)
+    pub missing: Vec<String>,
+}
+
+/// Whether an entity already has an observation
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ObservationCheck {
+    #[serde(rename = "entityExists")]
+    pub entity_exists: bool,
+    pub found: bool,
+    /// The stored observation that matched (may differ from the query in case or whitespace)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched: Option<String>,
+}
+
 /// Input for deleting observations from an entity
+/// Observations can be targeted by exact text, by zero-based index, or both
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ObservationDeletion {
+    #[serde(rename = "entityName")]
+    pub entity_name: String,
+    #[serde(default)]
+    pub observations: Vec<String>,
+    /// Zero-based positions in the entity's observation array
+    #[serde(default)]
+    pub indices: Vec<usize>,
+}
+
+/// Input for reordering observations of an entity
+/// Listed indices move to the front in the given order, the rest keep their relative order
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObservationReorder {
+    #[serde(rename = "entityName")]
+    pub entity_name: String,
+    /// Zero-based indices (a full permutation or a prefix to move to the front)
+    pub order: Vec<usize>,
+}
+
+/// Observations of an entity in stored order
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityObservations {
     #[serde(rename = "entityName")]
     pub entity_name: String,
     pub observations: Vec<String>,
 }
+
+/// Verdict of validate_entities / validate_relations for one item of the payload
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ItemCheck {
+    /// Zero-based position of the item in the payload
+    pub index: usize,
+    /// Entity name, or "from -> to (type)" for relations
+    pub name: String,
+    pub valid: bool,
+    /// First problem found (absent when valid)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ItemCheck {
+    pub fn new<T>(index: usize, name: String, result: anyhow::Result<T>) -> Self {
+        let error = result.err().map(|e| format!("{:#}", e));
+        Self {
+            index,
+            name,
+            valid: error.is_none(),
+            error,
+        }
+    }
+}
+
+/// One observation with the time it was added
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TimedObservation {
+    pub text: String,
+    /// ISO-8601 UTC; null for observations stored before times were recorded
+    #[serde(rename = "addedAt")]
+    pub added_at: Option<String>,
+}
+
+/// Observations of an entity with the time each was added
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ObservationTimes {
+    #[serde(rename = "entityName")]
+    pub entity_name: String,
+    pub observations: Vec<TimedObservation>,
+}
+
+/// Number of relations of one type between two entity types
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct RelationTypeCount {
+    #[serde(rename = "fromEntityType")]
+    pub from_entity_type: String,
+    #[serde(rename = "relationType")]
+    pub relation_type: String,
+    #[serde(rename = "toEntityType")]
+    pub to_entity_type: String,
+    pub count: usize,
+}
+
+/// Implicit schema of one entity type: the relations its entities take part in
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TypeOntology {
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    /// Entities of this type
+    pub entities: usize,
+    /// Relations starting at entities of this type (most frequent first)
+    pub outgoing: Vec<TypeLink>,
+    /// Relations ending at entities of this type (most frequent first)
+    pub incoming: Vec<TypeLink>,
+}
+
+/// Relation type connecting an entity type with another one
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TypeLink {
+    #[serde(rename = "relationType")]
+    pub relation_type: String,
+    /// Type at the other end: the target of outgoing, the source of incoming relations
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    pub count: usize,
+}
+
+/// Aggregate connectivity metrics of the whole graph
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
+pub struct GraphMetrics {
+    #[serde(rename = "entityCount")]
+    pub entity_count: usize,
+
+    #[serde(rename = "relationCount")]
+    pub relation_count: usize,
+
+    /// Relations / possible directed edges between distinct entities, n * (n - 1)
+    pub density: f64,
+
+    /// Average number of relations touching an entity (in + out), 2 * relations / entities
+    #[serde(rename = "averageDegree")]
+    pub average_degree: f64,
+
+    /// Entities that take part in no relation at all
+    #[serde(rename = "orphanCount")]
+    pub orphan_count: usize,
+}
+
+/// Entity listed as a possible duplicate
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DuplicateCandidate {
+    pub name: String,
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+}
+
+/// Group of entities whose names look like the same thing (ordered by name)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DuplicateCluster {
+    pub entities: Vec<DuplicateCandidate>,
+}
+
+/// Relation type with the number of relations using it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct RelationTypeUsage {
+    #[serde(rename = "relationType")]
+    pub relation_type: String,
+    pub count: u64,
+}
+
+/// Relation types that look like spellings of the same thing, with a suggested canonical form
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct RelationTypeSynonyms {
+    /// Suggested type to keep: the most used one (ties: the one already in snake_case, then
+    /// by name)
+    pub canonical: String,
+    /// Every type of the group, most used first
+    pub types: Vec<RelationTypeUsage>,
+    /// Relations using any type of the group
+    pub relations: u64,
+}
+
+/// Outgoing edge in an adjacency list
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct AdjacencyEdge {
+    pub to: String,
+    #[serde(rename = "relationType")]
+    pub relation_type: String,
+}
+
+/// Entity metadata plus its outgoing edges
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct AdjacencyEntry {
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    pub observations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub edges: Vec<AdjacencyEdge>,
+}
+
+/// Graph as adjacency list: entity name -> entry (sorted by name)
+pub type AdjacencyList = BTreeMap<String, AdjacencyEntry>;
+
+/// Which relations to follow from an entity
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Relations where the entity is the source ('from')
+    #[serde(alias = "out")]
+    Outgoing,
+    /// Relations where the entity is the target ('to')
+    #[serde(alias = "in")]
+    Incoming,
+    /// Both outgoing and incoming relations
+    #[default]
+    Both,
+}
+
+/// How the terms of a search query are combined
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// Every term must match (FTS5 implicit AND)
+    #[default]
+    All,
+    /// Any term may match (terms joined with FTS5 OR)
+    Any,
+}
+
+/// Direction of a ranked listing
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Smallest first
+    Asc,
+    /// Largest first
+    #[default]
+    Desc,
+}
+
+/// Entity columns a search selects and returns
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchProjection {
+    /// Whole entities plus the relations among them
+    #[default]
+    Full,
+    /// Entity names only
+    Names,
+    /// Entity names and types
+    NamesTypes,
+}
+
+/// A matched entity with its relevance score in scored search results
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScoredEntity {
+    pub entity: Entity,
+    /// Negated FTS5 bm25() rank: higher is more relevant, only comparable within one search
+    pub score: f64,
+}
+
+/// A matched entity in the names / names_types search projections
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct SearchHit {
+    pub name: String,
+    #[serde(
+        rename = "entityType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub entity_type: Option<String>,
+}
+
+/// Direction of a single relation relative to a center entity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeDirection {
+    #[serde(alias = "out")]
+    Outgoing,
+    #[serde(alias = "in")]
+    Incoming,
+}
+
+/// Relation annotated with its direction relative to a center entity
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DirectedRelation {
+    #[serde(flatten)]
+    pub relation: Relation,
+    pub direction: EdgeDirection,
+}
+
+/// Direct neighbors of an entity
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Neighborhood {
+    /// Name of the entity whose neighbors were requested
+    pub center: String,
+    /// Neighbor entities (the center itself is not included)
+    pub entities: Vec<Entity>,
+    /// Relations connecting the center to its neighbors
+    pub relations: Vec<DirectedRelation>,
+}
+
+/// Relations of one entity grouped by direction and relation type
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RelationSummary {
+    pub name: String,
+    /// Relation type -> targets of the entity's outgoing relations, ordered by name
+    pub outgoing: BTreeMap<String, Vec<String>>,
+    /// Relation type -> sources of the entity's incoming relations, ordered by name
+    /// (self-loops are only listed under outgoing)
+    pub incoming: BTreeMap<String, Vec<String>>,
+}
+
+/// One entity flattened into a prompt-ready list of facts
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EntityFacts {
+    pub name: String,
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Observations in stored order, then "<from> <relationType> <to>" for every relation
+    /// touching the entity (outgoing first)
+    pub facts: Vec<String>,
+}
+
+/// Entities exactly two hops from an entity that are not its direct neighbors
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SecondDegree {
+    pub center: String,
+    /// Second-degree entities, ordered by name
+    pub entities: Vec<Entity>,
+    /// Name of each second-degree entity -> the direct neighbors it is reached through
+    pub via: BTreeMap<String, Vec<String>>,
+}
+
+/// How many relations link two entities, and of which types
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RelationCountBetween {
+    pub a: String,
+    pub b: String,
+    /// Only relations from a to b were counted (else both directions)
+    pub directed: bool,
+    pub count: usize,
+    /// Distinct types of the counted relations, sorted
+    #[serde(rename = "relationTypes")]
+    pub relation_types: Vec<String>,
+}
+
+/// Entities connected to both of two entities
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommonNeighbors {
+    pub a: String,
+    pub b: String,
+    /// Shared neighbors, ordered by name (a and b themselves are never included)
+    pub entities: Vec<Entity>,
+    /// Relations linking a or b to a shared neighbor
+    pub relations: Vec<Relation>,
+}
+
+/// Densely connected group of entities grown from a seed entity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Community {
+    pub seed: String,
+    /// Members in the order they joined (the seed first)
+    pub entities: Vec<Entity>,
+    /// Relations between members
+    pub relations: Vec<Relation>,
+    /// Linked member pairs over all member pairs (1.0 for the seed alone)
+    pub density: f64,
+}
+
+/// Entity reached by a traversal, with how it was reached
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraversalNode {
+    #[serde(flatten)]
+    pub entity: Entity,
+    /// Hops from the root (0 = the root itself)
+    pub distance: u32,
+    /// Entity this one was first reached from (None for the root)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+/// Breadth-first traversal from a root entity
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Traversal {
+    /// Name of the entity the traversal started from
+    pub root: String,
+    /// Entities in visit order: by distance, then by parent's visit order, then by name
+    pub entities: Vec<TraversalNode>,
+}
+
+/// Chain of relations connecting two entities
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityPath {
+    /// Entity names from start to end
+    pub entities: Vec<String>,
+    /// relations[i] connects entities[i] and entities[i + 1] (as stored, so it may point backwards)
+    pub relations: Vec<Relation>,
+}
+
+impl EntityPath {
+    /// Readable form, e.g. "Alice -works_at-> BetaCorp <-owns- Acme"
+    pub fn describe(&self) -> String {
+        let mut text = self.entities.first().cloned().unwrap_or_default();
+        for (relation, next) in self.relations.iter().zip(self.entities.iter().skip(1)) {
+            if relation.to == *next {
+                text.push_str(&format!(" -{}-> {}", relation.relation_type, next));
+            } else {
+                text.push_str(&format!(" <-{}- {}", relation.relation_type, next));
+            }
+        }
+        text
+    }
+}
+
+/// Shortest paths between two entities (empty when they are not connected)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PathResult {
+    pub from: String,
+    pub to: String,
+    pub paths: Vec<EntityPath>,
+}
+
+/// Longest acyclic chain of relations from an entity (see longest_chain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainResult {
+    pub start: String,
+    /// The chain itself; just the start entity when no relation leads anywhere
+    pub chain: EntityPath,
+    /// False when the depth cap or step budget cut the search short: a longer chain may exist
+    pub exhaustive: bool,
+    /// True when a followed relation led back into the chain, i.e. the relations are not a DAG
+    pub cyclic: bool,
+}
+
+/// Overview of one node requested from inspect_nodes
+/// Degrees count stored relations, so a missing entity can still have dangling ones
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NodeInspection {
+    /// The entity, or None when no entity has the requested name
+    pub entity: Option<Entity>,
+    /// Relations pointing at the node
+    #[serde(rename = "inDegree")]
+    pub in_degree: u64,
+    /// Relations starting at the node
+    #[serde(rename = "outDegree")]
+    pub out_degree: u64,
+    #[serde(rename = "observationCount")]
+    pub observation_count: u64,
+}
+
+/// Result of inspect_nodes: one inspection per distinct requested name
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NodeInspections {
+    pub nodes: BTreeMap<String, NodeInspection>,
+    /// Requested names without an entity (sorted)
+    pub missing: Vec<String>,
+}
+
+/// One page of entities from a paginated listing
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EntityPage {
+    /// Entities on this page (ordered by name)
+    pub entities: Vec<Entity>,
+    /// Total number of matching entities across all pages
+    pub total: usize,
+    /// Offset of the first entity on this page
+    pub offset: usize,
+    /// Maximum page size that was requested
+    pub limit: usize,
+}
+
+/// One page of the whole graph: entities by name, with the relations touching them
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GraphPage {
+    /// Entities on this page (ordered by name)
+    pub entities: Vec<Entity>,
+    /// Relations with at least one endpoint on this page (ordered by from, to, type)
+    pub relations: Vec<Relation>,
+    /// Total number of entities across all pages
+    pub total: usize,
+    /// Offset of the first entity on this page
+    pub offset: usize,
+    /// Maximum page size that was requested
+    pub limit: usize,
+}
+
+/// Entities of one type in a multi-type listing
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EntityTypeGroup {
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    /// Entities of this type on this page (ordered by name)
+    pub entities: Vec<Entity>,
+    /// Total number of entities of this type across all pages
+    pub total: usize,
+}
+
+/// One page per type of a multi-type listing (offset and limit apply to each type)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EntityGroupPage {
+    /// Groups in the order the types were requested (repeats dropped)
+    pub groups: Vec<EntityTypeGroup>,
+    /// Offset of the first entity of each group
+    pub offset: usize,
+    /// Maximum entities per group that was requested
+    pub limit: usize,
+}
+
+/// One page of relations of a single type
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RelationPage {
+    /// Relations on this page (ordered by from, then to)
+    pub relations: Vec<Relation>,
+    /// Endpoint entities of the relations on this page (only when requested, ordered by name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<Entity>>,
+    /// Total number of matching relations across all pages
+    pub total: usize,
+    /// Offset of the first relation on this page
+    pub offset: usize,
+    /// Maximum page size that was requested
+    pub limit: usize,
+}
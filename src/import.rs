@@ -0,0 +1,186 @@
+use crate::graph::{Entity, GraphRecord, KnowledgeGraph, Relation};
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Load a graph file, picking the format from the extension:
+/// `.json` = read_graph/export shape `{ "entities": [...], "relations": [...] }`,
+/// `.jsonl` = one entity or relation record per line
+pub fn load_graph_file(path: &Path) -> Result<KnowledgeGraph> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read '{}'", path.display()))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => parse_json(&content),
+        Some("jsonl") => parse_jsonl(&content),
+        _ => bail!(
+            "Unsupported graph file '{}' (expected .json or .jsonl)",
+            path.display()
+        ),
+    }
+    .with_context(|| format!("Invalid graph file '{}'", path.display()))
+}
+
+/// Parse a graph in read_graph shape; missing 'entities'/'relations' count as empty
+pub fn parse_json(content: &str) -> Result<KnowledgeGraph> {
+    #[derive(Deserialize)]
+    struct GraphFile {
+        #[serde(default)]
+        entities: Vec<Entity>,
+        #[serde(default)]
+        relations: Vec<Relation>,
+    }
+
+    let file: GraphFile = serde_json::from_str(content).context("Malformed JSON graph")?;
+    Ok(KnowledgeGraph {
+        entities: file.entities,
+        relations: file.relations,
+    })
+}
+
+/// Parse a JSONL graph (format of the TypeScript memory server, and of NDJSON exports);
+/// blank lines are skipped, errors name the offending line
+pub fn parse_jsonl(content: &str) -> Result<KnowledgeGraph> {
+    let mut graph = KnowledgeGraph::default();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: GraphRecord = serde_json::from_str(line)
+            .with_context(|| format!("Malformed record on line {}", i + 1))?;
+        match record {
+            GraphRecord::Entity(entity) => graph.entities.push(entity),
+            GraphRecord::Relation(relation) => graph.relations.push(relation),
+        }
+    }
+    Ok(graph)
+}
+
+/// External memory export formats that import_foreign can map onto the graph
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForeignFormat {
+    /// mem0 `get_all` output: { "results": [...], "relations": [...] } or a bare memory array
+    Mem0,
+}
+
+/// Map an export of another memory system onto entities and relations
+pub fn parse_foreign(content: &str, format: ForeignFormat) -> Result<KnowledgeGraph> {
+    match format {
+        ForeignFormat::Mem0 => parse_mem0(content),
+    }
+}
+
+/// Entity type of mem0 graph nodes (the export does not carry their type)
+pub const MEM0_NODE_TYPE: &str = "entity";
+
+/// Owner entity for memories that carry no user, agent or run id
+pub const MEM0_DEFAULT_OWNER: &str = "mem0";
+
+/// Parse a mem0 export
+/// Each memory string becomes an observation on the entity it belongs to: the user
+/// (type "user"), else the agent ("agent"), else the run ("session"), else "mem0" ("memory").
+/// Graph-memory relations {source, relationship, target|destination} become relations
+/// between entities of type "entity".
+pub fn parse_mem0(content: &str) -> Result<KnowledgeGraph> {
+    #[derive(Deserialize)]
+    struct Memory {
+        memory: String,
+        user_id: Option<String>,
+        agent_id: Option<String>,
+        run_id: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct GraphRelation {
+        source: String,
+        relationship: String,
+        #[serde(alias = "destination")]
+        target: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Export {
+        Wrapped {
+            #[serde(default)]
+            results: Vec<Memory>,
+            #[serde(default)]
+            relations: Vec<GraphRelation>,
+        },
+        Memories(Vec<Memory>),
+    }
+
+    let (memories, relations) = match serde_json::from_str(content)
+        .context("Malformed mem0 export (expected {\"results\": [...]} or a memory array)")?
+    {
+        Export::Wrapped { results, relations } => (results, relations),
+        Export::Memories(memories) => (memories, Vec::new()),
+    };
+
+    let mut graph = GraphBuilder::default();
+    for memory in memories {
+        let (owner, owner_type) = if let Some(user) = memory.user_id {
+            (user, "user")
+        } else if let Some(agent) = memory.agent_id {
+            (agent, "agent")
+        } else if let Some(run) = memory.run_id {
+            (run, "session")
+        } else {
+            (MEM0_DEFAULT_OWNER.to_string(), "memory")
+        };
+        let text = memory.memory.trim();
+        let entity = graph.entity(&owner, owner_type);
+        if !text.is_empty() && !entity.observations.iter().any(|o| o == text) {
+            entity.observations.push(text.to_string());
+        }
+    }
+    for relation in relations {
+        graph.entity(&relation.source, MEM0_NODE_TYPE);
+        graph.entity(&relation.target, MEM0_NODE_TYPE);
+        graph.relations.push(Relation {
+            from: relation.source,
+            to: relation.target,
+            relation_type: relation.relationship,
+        });
+    }
+    Ok(graph.build())
+}
+
+/// Collects entities by name (first type wins) in first-seen order
+#[derive(Default)]
+struct GraphBuilder {
+    entities: Vec<Entity>,
+    index: HashMap<String, usize>,
+    relations: Vec<Relation>,
+}
+
+impl GraphBuilder {
+    fn entity(&mut self, name: &str, entity_type: &str) -> &mut Entity {
+        let i = match self.index.get(name) {
+            Some(&i) => i,
+            None => {
+                self.entities.push(Entity {
+                    name: name.to_string(),
+                    entity_type: entity_type.to_string(),
+                    observations: Vec::new(),
+                    description: None,
+                    id: None,
+                    confidence: None,
+                    metadata: None,
+                });
+                self.index.insert(name.to_string(), self.entities.len() - 1);
+                self.entities.len() - 1
+            }
+        };
+        &mut self.entities[i]
+    }
+
+    fn build(self) -> KnowledgeGraph {
+        KnowledgeGraph {
+            entities: self.entities,
+            relations: self.relations,
+        }
+    }
+}
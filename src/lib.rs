@@ -1,4 +1,10 @@
 // Public exports for testing and library usage
+pub mod backup;
+pub mod checkpoint;
+pub mod export;
 pub mod graph;
+pub mod import;
 pub mod manager;
+pub mod path;
+pub mod similarity;
 pub mod storage;
@@ -1,26 +1,60 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::convert::Infallible;
+use std::hash::{BuildHasher, RandomState};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
+use anyhow::Context as _;
+use axum::extract::{FromRef, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use clap::Parser;
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
-    model::{CallToolResult, Content, Implementation, ServerCapabilities, ServerInfo},
-    tool, tool_handler, tool_router,
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters},
+    model::{
+        AnnotateAble, CallToolRequestParam, CallToolResult, Content, ErrorCode,
+        GetPromptRequestParam, GetPromptResult, Implementation, JsonObject, ListPromptsResult,
+        ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+        Prompt, PromptArgument, PromptMessage, PromptMessageRole, RawResource, RawResourceTemplate,
+        ReadResourceRequestParam, ReadResourceResult, ResourceContents, ServerCapabilities,
+        ServerInfo,
+    },
+    service::RequestContext,
+    tool, tool_router,
     transport::stdio,
-    ErrorData as McpError, ServerHandler, ServiceExt,
+    ErrorData as McpError, RoleServer, ServerHandler, ServiceExt,
 };
 use schemars::JsonSchema;
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
 
-mod graph;
+mod audit;
 mod logging;
-mod manager;
-mod storage;
+mod rate_limit;
+mod strict_args;
 
-use graph::{Entity, ObservationDeletion, ObservationInput, Relation};
+use audit::ToolAuditLog;
 use logging::{init_logging, TransportMode};
-use manager::KnowledgeGraphManager;
+use memory_mcp_rs::checkpoint;
+use memory_mcp_rs::export::{self, DotOptions, ExportFormat};
+use memory_mcp_rs::graph::{
+    ConflictPolicy, Direction, EdgeDirection, Entity, GraphOperation, ItemCheck, KnowledgeGraph,
+    MatchMode, ObservationDeletion, ObservationInput, ObservationReorder, PathResult, Relation,
+    SearchProjection, SortOrder, TypeLink,
+};
+use memory_mcp_rs::import::{self, ForeignFormat};
+use memory_mcp_rs::manager::KnowledgeGraphManager;
+use memory_mcp_rs::path::{resolve_db_path, DbPathPolicy};
+use memory_mcp_rs::storage::{
+    is_pool_exhausted, is_query_timeout, validate_confidence, validation_failures, CacheSize,
+    Database, DatabaseOptions, DedupOptions, LengthUnit, ObservationFormat, ReadWindow,
+    MAX_PAGE_SIZE,
+};
 
 /// Command-line arguments
 #[derive(Parser, Debug)]
@@ -30,6 +64,10 @@ struct Args {
     #[arg(long)]
     db_path: Option<PathBuf>,
 
+    /// Read-only replica serving read_graph/search_nodes/open_nodes (kept in sync externally, e.g. Litestream)
+    #[arg(long, value_name = "PATH")]
+    read_db_path: Option<PathBuf>,
+
     /// Enable streamable HTTP mode (default: stdio)
     #[arg(short = 's', long = "stream")]
     stream_mode: bool,
@@ -42,29 +80,525 @@ struct Args {
     #[arg(short = 'b', long, default_value = "127.0.0.1")]
     bind: String,
 
+    /// Compress HTTP responses (gzip/brotli) when the client sends Accept-Encoding
+    #[arg(long)]
+    compress: bool,
+
+    /// Maximum HTTP request body size in bytes for stream mode (larger requests get 413)
+    #[arg(long, value_name = "BYTES", default_value = "4194304")]
+    max_body_size: usize,
+
+    /// Seconds to wait for in-flight HTTP requests after a shutdown signal before exiting
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    shutdown_timeout: u64,
+
+    /// Limit each HTTP client IP to N requests per second; excess gets 429
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit: Option<u32>,
+
     /// Enable file logging. Optionally specify log file name (default: memory-mcp-rs.log)
     #[arg(short = 'l', long, value_name = "FILE", num_args = 0..=1, default_missing_value = "memory-mcp-rs.log")]
     log: Option<String>,
+
+    /// Check database health (integrity check) and exit: 0 = healthy, 1 = problem
+    #[arg(long)]
+    check: bool,
+
+    /// Restrict entity types to a comma-separated allowlist (default: any valid type)
+    #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+    entity_types: Option<Vec<String>>,
+
+    /// Restrict relation types to a comma-separated allowlist (default: any valid type)
+    #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+    relation_types: Option<Vec<String>>,
+
+    /// Reject relations from an entity to itself (default: allowed)
+    #[arg(long)]
+    disallow_self_relations: bool,
+
+    /// Abort database operations running longer than this many milliseconds (default: no limit)
+    #[arg(long, value_name = "MS")]
+    query_timeout_ms: Option<u64>,
+
+    /// Wait this many milliseconds for a free database connection before answering busy (default: 5000)
+    #[arg(long, value_name = "MS")]
+    pool_timeout_ms: Option<u64>,
+
+    /// Idle database connections to keep open, at most 15 (default: all 15)
+    #[arg(long, value_name = "N")]
+    pool_min_idle: Option<u32>,
+
+    /// Close database connections idle this many milliseconds, down to --pool-min-idle (default: 600000)
+    #[arg(long, value_name = "MS")]
+    pool_idle_timeout_ms: Option<u64>,
+
+    /// Page cache per connection: pages (2000) or memory (65536k, 64m); default ~2 MiB
+    #[arg(long, value_name = "SIZE")]
+    cache_size: Option<CacheSize>,
+
+    /// Bytes of the database file to memory-map per connection (default: no mmap)
+    #[arg(long, value_name = "BYTES")]
+    mmap_size: Option<u64>,
+
+    /// Reject search queries shorter than this many characters (0 = no minimum)
+    #[arg(long, value_name = "CHARS", default_value_t = 2)]
+    min_query_length: usize,
+
+    /// Reject new entity names longer than this (at most 256, counted in --length-unit)
+    #[arg(long, value_name = "CHARS")]
+    max_entity_name_length: Option<usize>,
+
+    /// Unit length limits are counted in: bytes (UTF-8) or chars (Unicode scalar values)
+    #[arg(long, value_name = "UNIT", default_value = "bytes")]
+    length_unit: LengthUnit,
+
+    /// Compile hot SQL statements on every pooled connection at startup (avoids first-request latency)
+    #[arg(long)]
+    prewarm: bool,
+
+    /// Count how often open_nodes/search_nodes return each entity (for least_accessed)
+    #[arg(long)]
+    track_access: bool,
+
+    /// Hold add_observations calls this many milliseconds and write them together (1-60000)
+    #[arg(long, value_name = "MS")]
+    buffer_observations_ms: Option<u64>,
+
+    /// Store observations zstd-compressed (needs the compress-observations cargo feature)
+    #[arg(long)]
+    compress_observations: bool,
+
+    /// Key entities by name and type ("Mercury#planet"), re-keying the database for good
+    #[arg(long)]
+    name_type_keys: bool,
+
+    /// Drop the full-text search indexes so writes skip indexing; searches fall back to LIKE scans
+    #[arg(long)]
+    no_fts: bool,
+
+    /// Fail if the database's parent directory does not exist instead of creating it
+    #[arg(long)]
+    no_create_dirs: bool,
+
+    /// Refuse database paths (and --read-db-path) that resolve outside this directory
+    #[arg(long, value_name = "DIR")]
+    allowed_db_dir: Option<PathBuf>,
+
+    /// Directory that export tools write into (default: "exports" next to the database)
+    #[arg(long, value_name = "DIR")]
+    export_dir: Option<PathBuf>,
+
+    /// Directory checkpoint tools save database copies into (default: "checkpoints" next to the database)
+    #[arg(long, value_name = "DIR")]
+    checkpoint_dir: Option<PathBuf>,
+
+    /// Before serving, copy the database into a timestamped file in the backup directory
+    #[arg(long)]
+    backup_on_start: bool,
+
+    /// Directory --backup-on-start writes into (default: "backups" next to the database)
+    #[arg(long, value_name = "DIR", requires = "backup_on_start")]
+    backup_dir: Option<PathBuf>,
+
+    /// With --backup-on-start: keep this many newest backups, deleting older ones
+    #[arg(long, value_name = "N", default_value_t = 5, requires = "backup_on_start",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    backup_keep: u64,
+
+    /// Return at most this many entities from a read tool; the rest is fetched with next_page
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_response_entities: Option<u64>,
+
+    /// Append one JSON line per tool call (tool, time, argument summary, outcome) to this file
+    #[arg(long, value_name = "PATH")]
+    tool_audit_file: Option<PathBuf>,
+
+    /// Reject tool calls whose arguments contain fields the tool's input schema does not declare
+    #[arg(long)]
+    strict_args: bool,
+
+    /// Map near-duplicate entity names (trimmed, case-folded) onto existing entities on create
+    #[arg(long)]
+    dedup_on_create: bool,
+
+    /// With --dedup-on-create: compare names case-sensitively (only trim)
+    #[arg(long, requires = "dedup_on_create")]
+    dedup_case_sensitive: bool,
+
+    /// With --dedup-on-create: merge observations of duplicates into the existing entity
+    #[arg(long, requires = "dedup_on_create")]
+    dedup_merge_observations: bool,
+
+    /// Observation format to enforce: plain (any text), markdown (raw HTML stripped), json (must parse)
+    #[arg(long, value_name = "FORMAT", default_value = "plain")]
+    observation_format: ObservationFormat,
+
+    /// Import this .json/.jsonl graph file before serving, only if the database is empty
+    #[arg(long, value_name = "PATH")]
+    seed: Option<PathBuf>,
 }
 
+/// Writes a session may stage in one transaction before it has to commit or roll back
+const MAX_STAGED_OPERATIONS: usize = 1000;
+
+/// Write tools an open transaction stages instead of applying
+const STAGED_TOOLS: [&str; 7] = [
+    "create_entities",
+    "create_relations",
+    "create_star_relations",
+    "add_observations",
+    "delete_entities",
+    "delete_observations",
+    "delete_relations",
+];
+
+/// Other tools without the read-only hint that may run while a transaction is open:
+/// transaction control, and tools that leave entities and relations alone (files, caches,
+/// the search index, observations held from before the transaction)
+const TRANSACTION_SAFE_TOOLS: [&str; 11] = [
+    "begin_transaction",
+    "commit_transaction",
+    "rollback_transaction",
+    "flush_observations",
+    "check_search_index",
+    "compute_importance",
+    "export_subgraph",
+    "export_mermaid",
+    "export_search_results",
+    "export_ndjson",
+    "create_checkpoint",
+];
+
+/// Tools that only read unless the named flag is set; --max-response-entities caps their reads
+/// like those of the tools annotated read-only
+const READ_MODE_TOOLS: [(&str, &str); 1] = [("find_empty_entities", "delete")];
+
+/// Pageable read tools whose pages are relations rather than entities
+const RELATION_PAGED_TOOLS: [&str; 2] = ["search_relations", "list_relations_by_type"];
+
 #[derive(Clone)]
 struct MemoryServer {
     manager: Arc<KnowledgeGraphManager>,
+    export_dir: Arc<PathBuf>,
+    checkpoint_dir: Arc<PathBuf>,
+    /// Record of every tool call (--tool-audit-file), shared by all sessions
+    audit: Option<Arc<ToolAuditLog>>,
+    /// Most entities a read tool returns at once (--max-response-entities)
+    max_response_entities: Option<usize>,
+    /// Reject unknown argument fields instead of ignoring them (--strict-args)
+    strict_args: bool,
+    /// Writes staged since begin_transaction (None when no transaction is open)
+    /// Per session: every HTTP session gets its own server via for_session
+    transaction: Arc<Mutex<Option<Vec<GraphOperation>>>>,
     tool_router: ToolRouter<Self>,
 }
 
 impl MemoryServer {
-    fn new(manager: Arc<KnowledgeGraphManager>) -> Self {
+    fn new(
+        manager: Arc<KnowledgeGraphManager>,
+        export_dir: PathBuf,
+        checkpoint_dir: PathBuf,
+        audit: Option<ToolAuditLog>,
+        max_response_entities: Option<usize>,
+        strict_args: bool,
+    ) -> Self {
         Self {
             manager,
+            export_dir: Arc::new(export_dir),
+            checkpoint_dir: Arc::new(checkpoint_dir),
+            audit: audit.map(Arc::new),
+            max_response_entities,
+            strict_args,
+            transaction: Arc::default(),
             tool_router: Self::tool_router(),
         }
     }
 
+    /// Server for a new MCP session: same database, no open transaction
+    fn for_session(&self) -> Self {
+        Self {
+            transaction: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    /// With --strict-args, fail a call whose arguments hold fields the tool's input schema
+    /// does not declare (serde would silently ignore them), naming each with a likely fix
+    fn check_strict_args(&self, request: &CallToolRequestParam) -> Result<(), McpError> {
+        if !self.strict_args {
+            return Ok(());
+        }
+        let (Some(route), Some(arguments)) = (
+            self.tool_router.map.get(request.name.as_ref()),
+            request.arguments.as_ref(),
+        ) else {
+            return Ok(());
+        };
+        let unknown = strict_args::unknown_fields(&route.attr.input_schema, arguments);
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        let fields: Vec<String> = unknown
+            .iter()
+            .map(|field| match &field.suggestion {
+                Some(suggestion) => format!("'{}' (did you mean '{}'?)", field.path, suggestion),
+                None => format!("'{}'", field.path),
+            })
+            .collect();
+        Err(McpError::invalid_params(
+            format!(
+                "Unknown argument field(s) for '{}': {}",
+                request.name,
+                fields.join(", ")
+            ),
+            Some(json!({"unknownFields": unknown})),
+        ))
+    }
+
+    /// Lock the session's transaction (a panic while staging leaves the list usable)
+    fn transaction(&self) -> MutexGuard<'_, Option<Vec<GraphOperation>>> {
+        self.transaction
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.transaction().is_some()
+    }
+
+    /// Whether the tool is annotated read-only (`readOnlyHint`)
+    fn is_read_only(&self, tool: &str) -> bool {
+        self.tool_router
+            .map
+            .get(tool)
+            .and_then(|route| route.attr.annotations.as_ref())
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false)
+    }
+
+    /// Whether --max-response-entities applies to a call: every tool annotated read-only
+    /// except next_page itself, and the READ_MODE_TOOLS unless they are asked to write
+    fn is_capped(&self, tool: &str, arguments: Option<&JsonObject>) -> bool {
+        if let Some((_, flag)) = READ_MODE_TOOLS.iter().find(|(name, _)| *name == tool) {
+            return !arguments
+                .and_then(|arguments| arguments.get(*flag))
+                .is_some_and(|value| value == &Value::Bool(true));
+        }
+        tool != "next_page" && self.is_read_only(tool)
+    }
+
+    /// Declared argument fields of a tool (`properties` of its input schema)
+    fn argument_schema(&self, tool: &str) -> Option<&JsonObject> {
+        self.tool_router
+            .map
+            .get(tool)?
+            .attr
+            .input_schema
+            .get("properties")?
+            .as_object()
+    }
+
+    /// Size field of a pageable read tool (one taking 'offset' and 'limit' or 'count') with
+    /// its default; a null default means no limit
+    fn page_size_field(&self, tool: &str) -> Option<(&'static str, Option<usize>)> {
+        let properties = self.argument_schema(tool)?;
+        properties.get("offset")?;
+        ["limit", "count"].into_iter().find_map(|field| {
+            let default = properties
+                .get(field)?
+                .get("default")
+                .and_then(Value::as_u64)
+                .and_then(|n| usize::try_from(n).ok());
+            Some((field, default))
+        })
+    }
+
+    /// Run a read capped by --max-response-entities; a result cut short gets "hasMore" and a
+    /// next_page cursor. Pageable tools are asked for just the page, with one row extra to
+    /// tell whether more follow, and the cursor repeats the call from the next offset; other
+    /// tools run in full and are cut from `skip` afterwards (see cap_response)
+    async fn call_capped(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+        cap: usize,
+        skip: usize,
+    ) -> Result<CallToolResult, McpError> {
+        let tool = request.name.to_string();
+        let arguments = request.arguments.clone().unwrap_or_default();
+        let Some((field, default)) = self.page_size_field(&tool) else {
+            let mut result = self
+                .tool_router
+                .call(ToolCallContext::new(self, request, context))
+                .await?;
+            cap_response(&mut result, &tool, &arguments, skip, cap);
+            return Ok(result);
+        };
+
+        // list_entities_by_types pages every type at once, so the cap is shared between them
+        let groups = arguments
+            .get("types")
+            .and_then(Value::as_array)
+            .map_or(1, |types| types.len().max(1));
+        let per_call = (cap / groups).clamp(1, MAX_PAGE_SIZE - 1);
+        let requested = match arguments.get(field) {
+            None => Some(default),
+            Some(Value::Null) => Some(None),
+            Some(value) => value
+                .as_u64()
+                .and_then(|n| usize::try_from(n).ok())
+                .filter(|&n| n <= MAX_PAGE_SIZE)
+                .map(Some),
+        };
+        // Sizes within the cap go through as asked; invalid ones are left for the tool to reject
+        let Some(requested) = requested.filter(|size| size.is_none_or(|size| size > per_call))
+        else {
+            return self
+                .tool_router
+                .call(ToolCallContext::new(self, request, context))
+                .await;
+        };
+        let offset = arguments
+            .get("offset")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(0);
+
+        // An unseeded sample would reshuffle between pages
+        let mut next = arguments;
+        let seeded = self
+            .argument_schema(&tool)
+            .is_some_and(|properties| properties.contains_key("seed"));
+        if seeded && next.get("seed").is_none_or(Value::is_null) {
+            next.insert("seed".into(), json!(RandomState::new().hash_one(&tool)));
+        }
+        let mut paged = next.clone();
+        paged.insert(field.into(), json!(per_call + 1));
+        let request = CallToolRequestParam {
+            name: request.name,
+            arguments: Some(paged),
+        };
+        let mut result = self
+            .tool_router
+            .call(ToolCallContext::new(self, request, context))
+            .await?;
+
+        let Some(data) = result
+            .structured_content
+            .as_mut()
+            .and_then(|content| content.get_mut("data"))
+        else {
+            return Ok(result);
+        };
+        let key = if RELATION_PAGED_TOOLS.contains(&tool.as_str()) {
+            "relations"
+        } else if data.get("results").is_some() {
+            "results"
+        } else {
+            "entities"
+        };
+        let Some((shown, cut)) = trim_page(data, key, per_call) else {
+            return Ok(result);
+        };
+        if !cut {
+            if data.get("limit").is_some() {
+                data["limit"] = json!(requested);
+            }
+            return Ok(result);
+        }
+
+        if data.get("limit").is_some() {
+            data["limit"] = json!(per_call);
+        }
+        data["hasMore"] = json!(true);
+        next.insert("offset".into(), json!(offset + per_call));
+        next.insert(field.into(), json!(requested.map(|size| size - per_call)));
+        data["cursor"] = json!(encode_cursor(&tool, &next, 0));
+        let noun = if key == "relations" {
+            "relations"
+        } else {
+            "entities"
+        };
+        let mut note = format!("Showing {} {}-{}", noun, offset + 1, offset + shown);
+        if let Some(total) = data.get("total").and_then(Value::as_u64) {
+            note.push_str(&format!(" of {}", total));
+        }
+        note.push_str("; call next_page with the cursor for more");
+        if let Some(content) = result.structured_content.take() {
+            result.structured_content = Some(canonical(content));
+        }
+        result.content.push(Content::text(note));
+        Ok(result)
+    }
+
+    /// Reject graph writes that cannot be staged while the session has a transaction open:
+    /// applied right away, they would slip past the transaction's all-or-nothing commit
+    fn check_transaction_write(&self, tool: &str) -> Result<(), McpError> {
+        if self.is_read_only(tool)
+            || STAGED_TOOLS.contains(&tool)
+            || TRANSACTION_SAFE_TOOLS.contains(&tool)
+            || !self.in_transaction()
+        {
+            return Ok(());
+        }
+        Err(McpError::invalid_params(
+            format!(
+                "'{}' cannot be staged and would bypass the open transaction; \
+                 commit_transaction or rollback_transaction first",
+                tool
+            ),
+            Some(json!({"tool": tool, "stageable": STAGED_TOOLS})),
+        ))
+    }
+
+    /// Validate a write and stage it in the open transaction instead of applying it
+    fn stage(&self, operation: GraphOperation) -> Result<CallToolResult, McpError> {
+        let name = operation.name();
+        self.manager
+            .validate_operation(&operation)
+            .map_err(internal_err("Failed to stage operation"))?;
+
+        let mut transaction = self.transaction();
+        let staged = transaction.as_mut().ok_or_else(no_transaction)?;
+        if staged.len() >= MAX_STAGED_OPERATIONS {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Transaction already holds {} operations; commit or roll it back first",
+                    MAX_STAGED_OPERATIONS
+                ),
+                None,
+            ));
+        }
+        let items = operation.len();
+        staged.push(operation);
+
+        let summary = format!(
+            "Staged {} ({} items) as operation #{} of the open transaction; nothing is written until commit_transaction",
+            name,
+            items,
+            staged.len() - 1
+        );
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "staged": name,
+                "operation": staged.len() - 1,
+                "pending": staged.len(),
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
     fn server_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: Default::default(),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
             server_info: Implementation {
                 name: "memory-mcp-rs".to_string(),
                 version: env!("CARGO_PKG_VERSION").to_string(),
@@ -90,7 +624,10 @@ Input schema:
     {
       \"name\": \"entity-unique-id\",
       \"entityType\": \"person|organization|project|concept|...\",
-      \"observations\": [\"fact 1 about entity\", \"fact 2 about entity\"]
+      \"observations\": [\"fact 1 about entity\", \"fact 2 about entity\"],
+      \"description\": \"optional one-line summary\",
+      \"confidence\": 0.8,
+      \"metadata\": {\"any\": \"json\"}
     }
   ]
 }
@@ -111,23 +648,69 @@ Example - create a person and a company:
   ]
 }
 
-IMPORTANT: Use 'entityType' (camelCase), NOT 'entity_type'."
+IMPORTANT: Use 'entityType' (camelCase), NOT 'entity_type'.
+'description' is optional: a stable headline for the entity, separate from the observation
+log. It is full-text searchable and can be changed later with set_entity_description.
+'confidence' is optional: how sure you are of the entity, from 0.0 to 1.0 (e.g. the score of
+an extraction pipeline). It can be changed later with set_entity_confidence.
+'metadata' is optional free-form JSON for app-specific data (URLs, coordinates, external ids).
+It is stored as given and returned on reads but never searched; at most 8192 bytes of JSON.
+It can be changed later with set_entity_metadata.
+If the server was started with an entity type allowlist, other types are rejected.
+If the server was started with --dedup-on-create, names matching an existing entity after
+trimming (and case-folding) are not created; they are listed under 'deduplicated' with the
+existing name they mapped to.
+Soft issues (blank or very short observations, names near the length limit, a new entityType
+resembling an existing one) do not block creation; they are listed under 'warnings'.
+
+For large batches, set \"verbose\": false to get only {\"count\": n, \"names\": [...]} for the
+created entities instead of the full objects (default true).
+
+The result carries a 'batchId' tagging the entities this call created; get_batch returns them
+later."
     )]
     async fn create_entities(
         &self,
         Parameters(args): Parameters<CreateEntitiesArgs>,
     ) -> Result<CallToolResult, McpError> {
-        let created = self
+        if self.in_transaction() {
+            return self.stage(GraphOperation::CreateEntities {
+                entities: args.entities,
+            });
+        }
+        let warnings = self
             .manager
-            .create_entities(args.entities)
+            .entity_warnings(args.entities.clone())
+            .await
+            .map_err(internal_err("Failed to check entities"))?;
+        let result = self
+            .manager
+            .create_entities_detailed(args.entities)
             .await
             .map_err(internal_err("Failed to create entities"))?;
 
-        let summary = format!("{} entities created successfully", created.len());
+        let mut summary = format!("{} entities created successfully", result.created.len());
+        let mut structured = if args.verbose {
+            json!({"entities": result.created})
+        } else {
+            let names: Vec<_> = result.created.iter().map(|e| &e.name).collect();
+            json!({"count": names.len(), "names": names})
+        };
+        if !result.deduplicated.is_empty() {
+            summary.push_str(&format!(
+                ", {} deduplicated into existing entities",
+                result.deduplicated.len()
+            ));
+            structured["deduplicated"] = json!(result.deduplicated);
+        }
+        if let Some(batch_id) = &result.batch_id {
+            structured["batchId"] = json!(batch_id);
+        }
+        add_warnings(&mut summary, &mut structured, warnings);
 
         Ok(CallToolResult {
             content: vec![Content::text(&summary)],
-            structured_content: Some(json!({"entities": created})),
+            structured_content: Some(versioned(structured)),
             is_error: Some(false),
             meta: None,
         })
@@ -165,23 +748,239 @@ Example - create employment and friendship relations:
   ]
 }
 
-IMPORTANT: Use 'relationType' (camelCase), NOT 'relation_type'. Both 'from' and 'to' entities must exist."
+IMPORTANT: Use 'relationType' (camelCase), NOT 'relation_type'. Both 'from' and 'to' entities must exist.
+An endpoint may also name an alias of an entity (see add_aliases): the relation is stored under
+the entity's name, and 'resolvedAliases' lists each such endpoint as
+{\"index\": 0, \"endpoint\": \"from\", \"alias\": \"US\", \"entity\": \"United States\"}.
+If the server was started with a relation type allowlist, other types are rejected.
+A new relationType resembling an existing one is still created, with a note under 'warnings'.
+
+Set \"includeEndpoints\": true to also get the current state of every 'from'/'to' entity under
+'endpoints' (saves a follow-up open_nodes call).
+
+For large batches, set \"verbose\": false to get only {\"count\": n, \"names\": [...]} (the
+distinct entity names the created relations connect) instead of the relations (default true);
+it cannot be combined with includeEndpoints.
+
+The result carries a 'batchId' tagging the relations this call created; get_batch returns them
+later."
     )]
     async fn create_relations(
         &self,
         Parameters(args): Parameters<CreateRelationsArgs>,
     ) -> Result<CallToolResult, McpError> {
-        let created = self
+        if self.in_transaction() {
+            return self.stage(GraphOperation::CreateRelations {
+                relations: args.relations,
+            });
+        }
+        let warnings = self
+            .manager
+            .relation_warnings(args.relations.clone())
+            .await
+            .map_err(internal_err("Failed to check relations"))?;
+        if args.include_endpoints && !args.verbose {
+            return Err(McpError::invalid_params(
+                "includeEndpoints returns full entities, it cannot be combined with verbose: false",
+                None,
+            ));
+        }
+        let mut requested = args.relations.clone();
+        let result = self
             .manager
-            .create_relations(args.relations)
+            .create_relations_detailed(args.relations)
             .await
             .map_err(internal_err("Failed to create relations"))?;
 
-        let summary = format!("{} relations created successfully", created.len());
+        let mut summary = format!("{} relations created successfully", result.created.len());
+        let mut structured = relations_result(&result.created, args.verbose);
+        if let Some(batch_id) = &result.batch_id {
+            structured["batchId"] = json!(batch_id);
+        }
+        if !result.resolved_aliases.is_empty() {
+            summary.push_str(&format!(
+                " ({} endpoints resolved via aliases)",
+                result.resolved_aliases.len()
+            ));
+            structured["resolvedAliases"] = json!(result.resolved_aliases);
+        }
+        if args.include_endpoints {
+            for alias in &result.resolved_aliases {
+                alias.apply(&mut requested);
+            }
+            let endpoints = endpoint_names(&requested);
+            let entities = self
+                .manager
+                .entities_by_names(endpoints)
+                .await
+                .map_err(internal_err("Failed to read endpoint entities"))?;
+            structured["endpoints"] = json!(entities);
+        }
+        add_warnings(&mut summary, &mut structured, warnings);
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(structured)),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Dry-run validation of entities
+    #[tool(
+        name = "validate_entities",
+        annotations(read_only_hint = true),
+        description = "Check entities against every rule create_entities applies, without writing anything, and get a pass/fail verdict per entity.
+
+Input schema: same as create_entities
+{
+  \"entities\": [
+    {\"name\": \"entity-name\", \"entityType\": \"type\", \"observations\": [\"...\"]}
+  ]
+}
+
+Returns:
+{
+  \"valid\": false,
+  \"invalidCount\": 1,
+  \"items\": [
+    {\"index\": 0, \"name\": \"John_Smith\", \"valid\": true},
+    {\"index\": 1, \"name\": \"\", \"valid\": false, \"error\": \"Entity name cannot be empty\"}
+  ]
+}
+
+Checks names, types (including an entity type allowlist), observations, descriptions and
+confidence. Names that already exist pass: create_entities skips them rather than failing.
+Soft issues create_entities would report are listed under 'warnings'. Use it to fix a large
+payload in one go before sending it."
+    )]
+    async fn validate_entities(
+        &self,
+        Parameters(args): Parameters<ValidateEntitiesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let warnings = self
+            .manager
+            .entity_warnings(args.entities.clone())
+            .await
+            .map_err(internal_err("Failed to check entities"))?;
+        let checks = self
+            .manager
+            .check_entities(args.entities)
+            .await
+            .map_err(internal_err("Failed to validate entities"))?;
+        Ok(check_result("entities", &checks, warnings))
+    }
+
+    /// Dry-run validation of relations
+    #[tool(
+        name = "validate_relations",
+        annotations(read_only_hint = true),
+        description = "Check relations against every rule create_relations applies, including that both endpoints exist, without writing anything, and get a pass/fail verdict per relation.
+
+Input schema:
+{
+  \"relations\": [
+    {\"from\": \"source-entity-name\", \"to\": \"target-entity-name\", \"relationType\": \"works_at\"}
+  ],
+  \"pendingEntities\": [\"New_Entity\"]
+}
+
+'pendingEntities' is optional: names to treat as existing, e.g. entities you are about to
+create in the same batch.
+
+Returns:
+{
+  \"valid\": false,
+  \"invalidCount\": 1,
+  \"items\": [
+    {\"index\": 0, \"name\": \"John_Smith -> TechCorp (works_at)\", \"valid\": false,
+     \"error\": \"Entity 'TechCorp' does not exist\"}
+  ]
+}
+
+Checks endpoint names, relation types (including a relation type allowlist), the
+self-relation policy and endpoint existence. Soft issues create_relations would report are
+listed under 'warnings'."
+    )]
+    async fn validate_relations(
+        &self,
+        Parameters(args): Parameters<ValidateRelationsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let warnings = self
+            .manager
+            .relation_warnings(args.relations.clone())
+            .await
+            .map_err(internal_err("Failed to check relations"))?;
+        let checks = self
+            .manager
+            .check_relations(args.relations, args.pending_entities)
+            .await
+            .map_err(internal_err("Failed to validate relations"))?;
+        Ok(check_result("relations", &checks, warnings))
+    }
+
+    /// Create relations between one center entity and many others
+    #[tool(
+        name = "create_star_relations",
+        description = "Create the same relation between one center entity and many other entities (fan-out/fan-in).
+
+Input schema:
+{
+  \"center\": \"hub-entity-name\",
+  \"others\": [\"entity-1\", \"entity-2\"],
+  \"relationType\": \"belongs_to|...\",
+  \"direction\": \"out\" | \"in\"
+}
+
+'direction' is relative to the center: \"out\" creates center -> other, \"in\" creates other -> center
+(\"outgoing\"/\"incoming\" are accepted too).
+
+Example - three files belong to one project:
+{
+  \"center\": \"Project_Alpha\",
+  \"others\": [\"main.rs\", \"lib.rs\", \"storage.rs\"],
+  \"relationType\": \"belongs_to\",
+  \"direction\": \"in\"
+}
+
+Equivalent to create_relations with one relation per entry in 'others', in a single transaction
+(all or nothing). Returns only newly created relations; existing ones are skipped.
+
+IMPORTANT: Use 'relationType' (camelCase), NOT 'relation_type'. All entities must exist.
+A new relationType resembling an existing one is still created, with a note under 'warnings'.
+Set \"verbose\": false to get only {\"count\": n, \"names\": [...]} instead of the relations."
+    )]
+    async fn create_star_relations(
+        &self,
+        Parameters(args): Parameters<CreateStarRelationsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let relations = KnowledgeGraphManager::star_relations(
+            args.center,
+            args.others,
+            args.relation_type,
+            args.direction,
+        );
+        if self.in_transaction() {
+            return self.stage(GraphOperation::CreateRelations { relations });
+        }
+        let warnings = self
+            .manager
+            .relation_warnings(relations.clone())
+            .await
+            .map_err(internal_err("Failed to check star relations"))?;
+        let created = self
+            .manager
+            .create_relations(relations)
+            .await
+            .map_err(internal_err("Failed to create star relations"))?;
+
+        let mut summary = format!("{} relations created successfully", created.len());
+        let mut structured = relations_result(&created, args.verbose);
+        add_warnings(&mut summary, &mut structured, warnings);
 
         Ok(CallToolResult {
             content: vec![Content::text(&summary)],
-            structured_content: Some(json!({"relations": created})),
+            structured_content: Some(versioned(structured)),
             is_error: Some(false),
             meta: None,
         })
@@ -219,64 +1018,287 @@ Example - add observations to multiple entities:
 CRITICAL field names:
 - Use 'entityName' (camelCase), NOT 'entity_name' or 'entity'
 - Use 'contents' (array of strings), NOT 'observation' or 'content'
-- The entity specified by 'entityName' must already exist"
+- The entity specified by 'entityName' must already exist
+
+Blank or very short observations are still added, with a note under 'warnings'.
+
+When the server runs with --buffer-observations-ms, the observations are validated and held
+('buffered': true) and written together shortly after; any other tool call, and
+flush_observations, writes them first. A buffered reply means held, not yet stored: if
+writing held observations fails they stay held and are retried, and the next
+add_observations or flush_observations call reports the failure."
     )]
     async fn add_observations(
         &self,
         Parameters(args): Parameters<AddObservationsArgs>,
     ) -> Result<CallToolResult, McpError> {
+        if self.in_transaction() {
+            return self.stage(GraphOperation::AddObservations {
+                observations: args.observations,
+            });
+        }
+        let warnings = self.manager.observation_warnings(&args.observations);
         let results = self
             .manager
             .add_observations(args.observations)
             .await
             .map_err(internal_err("Failed to add observations"))?;
 
-        let summary = format!("Added observations to {} entities", results.len());
+        let buffered = self.manager.buffers_observations();
+        let mut summary = if buffered {
+            format!("Buffered observations for {} entities", results.len())
+        } else {
+            format!("Added observations to {} entities", results.len())
+        };
+        let mut structured = json!({"results": results});
+        if buffered {
+            structured["buffered"] = json!(true);
+        }
+        add_warnings(&mut summary, &mut structured, warnings);
 
         Ok(CallToolResult {
             content: vec![Content::text(&summary)],
-            structured_content: Some(json!({"results": results})),
+            structured_content: Some(versioned(structured)),
             is_error: Some(false),
             meta: None,
         })
     }
 
-    /// Delete entities and their relations
+    /// Write buffered observations now
     #[tool(
-        name = "delete_entities",
-        description = "Delete entities and their associated relations from the knowledge graph.
+        name = "flush_observations",
+        description = "Write the observations add_observations is holding back, right away.
+
+No input required - call with empty object: {}
+
+Returns {\"written\": 3}: the number of observations written. Only relevant when the server
+runs with --buffer-observations-ms; otherwise add_observations writes at once and this
+returns 0. Reads never need it: every other tool call writes held observations first."
+    )]
+    async fn flush_observations(&self) -> Result<CallToolResult, McpError> {
+        let written = self
+            .manager
+            .flush_observations()
+            .await
+            .map_err(internal_err("Failed to write buffered observations"))?;
+
+        Ok(CallToolResult {
+            content: vec![Content::text(format!(
+                "Wrote {} buffered observations",
+                written
+            ))],
+            structured_content: Some(versioned(json!({"written": written}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Append one observation to many entities
+    #[tool(
+        name = "add_observation_to_many",
+        description = "Append the same observation to many existing entities at once, in one transaction.
 
 Input schema:
 {
-  \"entity_names\": [\"entity-name-1\", \"entity-name-2\"]
+  \"entity_names\": [\"entity-name-1\", \"entity-name-2\"],
+  \"observation\": \"shared fact\"
 }
 
-Example - delete multiple entities:
+Example - mark a set of documents as reviewed:
 {
-  \"entity_names\": [\"John_Smith\", \"Old_Project\"]
+  \"entity_names\": [\"Design_Doc\", \"API_Spec\", \"Runbook\"],
+  \"observation\": \"Reviewed in Q1\"
 }
 
-Note: All relations involving deleted entities are automatically removed (cascade delete)."
+Returns:
+{
+  \"observation\": \"Reviewed in Q1\",
+  \"added\": [\"Design_Doc\", \"API_Spec\"],
+  \"alreadyPresent\": [\"Runbook\"],
+  \"missing\": []
+}
+
+Unlike add_observations, names that match no entity do not fail the call: they are
+skipped and listed under 'missing'. Entities that already have the observation are left
+unchanged. Applies immediately, even inside a transaction."
     )]
-    async fn delete_entities(
+    async fn add_observation_to_many(
         &self,
-        Parameters(args): Parameters<DeleteEntitiesArgs>,
+        Parameters(args): Parameters<AddObservationToManyArgs>,
     ) -> Result<CallToolResult, McpError> {
-        let count = self
+        let result = self
             .manager
-            .delete_entities(args.entity_names)
+            .add_observation_to_many(args.entity_names, args.observation)
             .await
-            .map_err(internal_err("Failed to delete entities"))?;
+            .map_err(internal_err("Failed to add observation"))?;
 
-        Ok(CallToolResult::success(vec![Content::text(format!(
-            "{} entities deleted successfully",
-            count
-        ))]))
+        let mut summary = format!(
+            "Added observation to {} entities ({} already had it)",
+            result.added.len(),
+            result.already_present.len()
+        );
+        if !result.missing.is_empty() {
+            summary.push_str(&format!(
+                "\nSkipped missing entities: {}",
+                result.missing.join(", ")
+            ));
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
     }
 
-    /// Delete observations from entities
+    /// Copy observations from one entity to another
     #[tool(
-        name = "delete_observations",
+        name = "copy_observations",
+        description = "Copy all observations of one entity onto another, keeping both entities.
+
+Input schema:
+{
+  \"source\": \"entity-to-copy-from\",
+  \"target\": \"entity-to-copy-to\"
+}
+
+Example - propagate known facts from a product to its successor:
+{
+  \"source\": \"Widget_v1\",
+  \"target\": \"Widget_v2\"
+}
+
+Returns:
+{
+  \"entityName\": \"Widget_v2\",
+  \"addedObservations\": [\"Ships in blue\"]
+}
+
+Observations the target already has are skipped, so 'addedObservations' lists only what was
+actually copied. Unlike merging, the source entity and its relations are left untouched.
+Both entities must exist. Applies immediately, even inside a transaction."
+    )]
+    async fn copy_observations(
+        &self,
+        Parameters(args): Parameters<CopyObservationsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .copy_observations(args.source.clone(), args.target)
+            .await
+            .map_err(internal_err("Failed to copy observations"))?;
+
+        let summary = format!(
+            "Copied {} observations from '{}' to '{}'",
+            result.added_observations.len(),
+            args.source,
+            result.entity_name
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Check whether an entity has an observation
+    #[tool(
+        name = "has_observation",
+        annotations(read_only_hint = true),
+        description = "Check whether an entity already has an observation, e.g. before adding it again.
+
+Input schema:
+{
+  \"entityName\": \"entity-name\",
+  \"observation\": \"fact to look for\"
+}
+
+Example - do we already know that Alice lives in Paris?
+{
+  \"entityName\": \"Alice\",
+  \"observation\": \"Lives in Paris\"
+}
+
+Returns:
+{
+  \"entityExists\": true,
+  \"found\": true,
+  \"matched\": \"lives in  Paris\"
+}
+
+The text matches exactly or ignoring case and extra whitespace; 'matched' is the stored
+observation. A missing entity is not an error: \"entityExists\" and \"found\" are false."
+    )]
+    async fn has_observation(
+        &self,
+        Parameters(args): Parameters<HasObservationArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let check = self
+            .manager
+            .has_observation(args.entity_name.clone(), args.observation)
+            .await
+            .map_err(internal_err("Failed to check observation"))?;
+
+        let summary = match (&check.matched, check.entity_exists) {
+            (Some(matched), _) => {
+                format!("'{}' has the observation: {}", args.entity_name, matched)
+            }
+            (None, true) => format!("'{}' does not have that observation", args.entity_name),
+            (None, false) => format!("Entity '{}' does not exist", args.entity_name),
+        };
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(check))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Delete entities and their relations
+    #[tool(
+        name = "delete_entities",
+        description = "Delete entities and their associated relations from the knowledge graph.
+
+Input schema:
+{
+  \"entity_names\": [\"entity-name-1\", \"entity-name-2\"]
+}
+
+Example - delete multiple entities:
+{
+  \"entity_names\": [\"John_Smith\", \"Old_Project\"]
+}
+
+Note: All relations involving deleted entities are automatically removed (cascade delete)."
+    )]
+    async fn delete_entities(
+        &self,
+        Parameters(args): Parameters<DeleteEntitiesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.in_transaction() {
+            return self.stage(GraphOperation::DeleteEntities {
+                names: args.entity_names,
+            });
+        }
+        let count = self
+            .manager
+            .delete_entities(args.entity_names)
+            .await
+            .map_err(internal_err("Failed to delete entities"))?;
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{} entities deleted successfully",
+            count
+        ))]))
+    }
+
+    /// Delete observations from entities
+    #[tool(
+        name = "delete_observations",
         description = "Delete specific observations from entities in the knowledge graph (batch operation).
 
 Input schema:
@@ -284,11 +1306,15 @@ Input schema:
   \"deletions\": [
     {
       \"entityName\": \"existing-entity-name\",
-      \"observations\": [\"exact observation text to delete\"]
+      \"observations\": [\"exact observation text to delete\"],
+      \"indices\": [0, 2]
     }
   ]
 }
 
+Observations can be targeted by exact text ('observations'), by zero-based position in the
+entity's observation array ('indices'), or both. Either field may be omitted.
+
 Example - remove outdated observations:
 {
   \"deletions\": [
@@ -303,12 +1329,18 @@ Example - remove outdated observations:
   ]
 }
 
-IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'. Observation text must match exactly."
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'. Observation text must match exactly.
+Out-of-range indices are rejected."
     )]
     async fn delete_observations(
         &self,
         Parameters(args): Parameters<DeleteObservationsArgs>,
     ) -> Result<CallToolResult, McpError> {
+        if self.in_transaction() {
+            return self.stage(GraphOperation::DeleteObservations {
+                deletions: args.deletions,
+            });
+        }
         self.manager
             .delete_observations(args.deletions)
             .await
@@ -319,246 +1351,4845 @@ IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'. Observation text mus
         )]))
     }
 
-    /// Delete relations
+    /// Reorder observations of entities
     #[tool(
-        name = "delete_relations",
-        description = "Delete specific relations from the knowledge graph.
+        name = "reorder_observations",
+        description = "Change the order of observations on entities (batch operation).
 
 Input schema:
 {
-  \"relations\": [
+  \"reorders\": [
     {
-      \"from\": \"source-entity-name\",
-      \"to\": \"target-entity-name\",
-      \"relationType\": \"relation-type-to-delete\"
+      \"entityName\": \"existing-entity-name\",
+      \"order\": [2, 0]
     }
   ]
 }
 
-Example - remove outdated relations:
+'order' lists zero-based indices into the entity's current observation array (the order in
+which read_graph/open_nodes return them). Listed observations move to the front in the given
+order; unlisted ones keep their relative order after them. Pass a full permutation to reorder
+everything, or a single index to move one observation to the front.
+
+Example - move the third observation to the front:
 {
-  \"relations\": [
+  \"reorders\": [
     {
-      \"from\": \"John_Smith\",
-      \"to\": \"OldCompany\",
-      \"relationType\": \"works_at\"
+      \"entityName\": \"John_Smith\",
+      \"order\": [2]
     }
   ]
 }
 
-IMPORTANT: Use 'relationType' (camelCase), NOT 'relation_type'. All three fields must match exactly."
+Returns the resulting observation order for each entity.
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'. Indices must be unique and in range."
     )]
-    async fn delete_relations(
+    async fn reorder_observations(
         &self,
-        Parameters(args): Parameters<DeleteRelationsArgs>,
+        Parameters(args): Parameters<ReorderObservationsArgs>,
     ) -> Result<CallToolResult, McpError> {
-        let count = self
+        let results = self
             .manager
-            .delete_relations(args.relations)
+            .reorder_observations(args.reorders)
             .await
-            .map_err(internal_err("Failed to delete relations"))?;
+            .map_err(internal_err("Failed to reorder observations"))?;
 
-        Ok(CallToolResult::success(vec![Content::text(format!(
-            "{} relations deleted successfully",
-            count
-        ))]))
+        let summary = format!("Reordered observations of {} entities", results.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"results": results}))),
+            is_error: Some(false),
+            meta: None,
+        })
     }
 
-    /// Read entire knowledge graph
+    /// Set or clear an entity's description
     #[tool(
-        name = "read_graph",
-        description = "Read the entire knowledge graph.
+        name = "set_entity_description",
+        description = "Set or clear the description of an existing entity.
 
-No input required - call with empty object: {}
+Input schema:
+{
+  \"entityName\": \"existing-entity-name\",
+  \"description\": \"new description\" | null
+}
 
-Returns:
+The description is a single canonical summary of the entity, kept separate from the
+append-only observation log. It replaces any previous description; pass null (or omit it)
+to clear it. Descriptions are indexed for full-text search by search_nodes.
+
+Example:
 {
-  \"entities\": [
-    {\"name\": \"...\", \"entityType\": \"...\", \"observations\": [\"...\"]}
-  ],
-  \"relations\": [
-    {\"from\": \"...\", \"to\": \"...\", \"relationType\": \"...\"}
-  ]
+  \"entityName\": \"John_Smith\",
+  \"description\": \"Staff engineer leading the TechCorp storage team\"
 }
 
-Use this to get a complete snapshot of all stored knowledge."
+Returns the updated entity. Fails if the entity does not exist.
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'."
     )]
-    async fn read_graph(&self) -> Result<CallToolResult, McpError> {
-        let graph = self
+    async fn set_entity_description(
+        &self,
+        Parameters(args): Parameters<SetEntityDescriptionArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let entity = self
             .manager
-            .read_graph()
+            .set_entity_description(args.entity_name, args.description)
             .await
-            .map_err(internal_err("Failed to read graph"))?;
+            .map_err(internal_err("Failed to set entity description"))?;
 
-        let summary = format!(
-            "Knowledge graph contains {} entities and {} relations",
-            graph.entities.len(),
-            graph.relations.len()
-        );
+        let summary = match entity.description {
+            Some(_) => format!("Description of '{}' updated", entity.name),
+            None => format!("Description of '{}' cleared", entity.name),
+        };
 
         Ok(CallToolResult {
             content: vec![Content::text(&summary)],
-            structured_content: Some(json!(graph)),
+            structured_content: Some(versioned(json!({"entity": entity}))),
             is_error: Some(false),
             meta: None,
         })
     }
 
-    /// Search nodes by query
+    /// Set or clear an entity's confidence score
     #[tool(
-        name = "search_nodes",
-        description = "Search for nodes in the knowledge graph using full-text search. Searches across entity names, types, and observations.
+        name = "set_entity_confidence",
+        description = "Set or clear the confidence score of an existing entity.
 
 Input schema:
 {
-  \"query\": \"search terms\" | null
+  \"entityName\": \"existing-entity-name\",
+  \"confidence\": 0.0-1.0 | null
 }
 
-Example - find entities related to 'Rust':
-{
-  \"query\": \"Rust programming\"
-}
+The confidence says how sure you are of the entity, e.g. the score a probabilistic extractor
+gave it. It must be between 0.0 and 1.0 and replaces any previous score; pass null (or omit
+it) to clear it. read_graph and search_nodes can skip low-confidence entities with
+'minConfidence'.
 
-Example - get all entities (empty/null query):
+Example:
 {
-  \"query\": null
+  \"entityName\": \"John_Smith\",
+  \"confidence\": 0.65
 }
 
-Returns matching entities and their relations. Uses SQLite FTS5 for efficient full-text search."
+Returns the updated entity. Fails if the entity does not exist.
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'."
     )]
-    async fn search_nodes(
+    async fn set_entity_confidence(
         &self,
-        Parameters(args): Parameters<SearchNodesArgs>,
+        Parameters(args): Parameters<SetEntityConfidenceArgs>,
     ) -> Result<CallToolResult, McpError> {
-        let result = self
+        let entity = self
             .manager
-            .search_nodes(args.query)
+            .set_entity_confidence(args.entity_name, args.confidence)
             .await
-            .map_err(internal_err("Failed to search nodes"))?;
+            .map_err(internal_err("Failed to set entity confidence"))?;
 
-        let summary = format!(
-            "Found {} entities and {} relations",
-            result.entities.len(),
-            result.relations.len()
-        );
+        let summary = match entity.confidence {
+            Some(confidence) => format!("Confidence of '{}' set to {}", entity.name, confidence),
+            None => format!("Confidence of '{}' cleared", entity.name),
+        };
 
         Ok(CallToolResult {
             content: vec![Content::text(&summary)],
-            structured_content: Some(json!(result)),
+            structured_content: Some(versioned(json!({"entity": entity}))),
             is_error: Some(false),
             meta: None,
         })
     }
 
-    /// Open specific nodes by names
+    /// Set or clear an entity's free-form metadata
     #[tool(
-        name = "open_nodes",
-        description = "Open specific nodes in the knowledge graph by their names.
+        name = "set_entity_metadata",
+        description = "Set or clear the free-form JSON metadata of an existing entity.
 
 Input schema:
 {
-  \"names\": [\"entity-name-1\", \"entity-name-2\"]
+  \"entityName\": \"existing-entity-name\",
+  \"metadata\": any JSON value | null
 }
 
-Example - retrieve specific entities:
+Metadata holds app-specific data that is not a fact about the entity: URLs, coordinates,
+external ids. It is stored as given, returned on reads and never searched. It replaces any
+previous metadata as a whole; pass null (or omit it) to clear it. At most 8192 bytes of JSON.
+
+Example:
 {
-  \"names\": [\"John_Smith\", \"TechCorp\", \"Project_Alpha\"]
+  \"entityName\": \"TechCorp\",
+  \"metadata\": {\"url\": \"https://techcorp.example\", \"crmId\": 4411}
 }
 
-Returns the requested entities with all their observations, plus any relations between them."
+Returns the updated entity. Fails if the entity does not exist.
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'."
     )]
-    async fn open_nodes(
+    async fn set_entity_metadata(
         &self,
-        Parameters(args): Parameters<OpenNodesArgs>,
+        Parameters(args): Parameters<SetEntityMetadataArgs>,
     ) -> Result<CallToolResult, McpError> {
-        let result = self
+        let entity = self
             .manager
-            .open_nodes(args.names)
+            .set_entity_metadata(args.entity_name, args.metadata)
             .await
-            .map_err(internal_err("Failed to open nodes"))?;
+            .map_err(internal_err("Failed to set entity metadata"))?;
 
-        let summary = format!(
-            "Retrieved {} entities and {} relations",
-            result.entities.len(),
-            result.relations.len()
-        );
+        let summary = match entity.metadata {
+            Some(_) => format!("Metadata of '{}' set", entity.name),
+            None => format!("Metadata of '{}' cleared", entity.name),
+        };
 
         Ok(CallToolResult {
             content: vec![Content::text(&summary)],
-            structured_content: Some(json!(result)),
+            structured_content: Some(versioned(json!({"entity": entity}))),
             is_error: Some(false),
             meta: None,
         })
     }
-}
 
-#[tool_handler]
-impl ServerHandler for MemoryServer {
-    fn get_info(&self) -> ServerInfo {
-        self.server_info()
-    }
+    /// Give an entity alternative names for relation endpoints
+    #[tool(
+        name = "add_aliases",
+        description = "Give an existing entity aliases: other names relation endpoints may use for it.
+
+Input schema:
+{
+  \"entityName\": \"existing-entity-name\",
+  \"aliases\": [\"other-name\"]
 }
 
-// Tool argument schemas
+Once \"US\" is an alias of \"United States\", create_relations and delete_relations accept
+\"US\" as 'from' or 'to' and store or match the relation under \"United States\"; their result
+lists each endpoint resolved that way in 'resolvedAliases'. An entity actually named like an
+alias takes precedence over it. Aliases are only resolved in relation endpoints, not in other
+tools or searches.
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct CreateEntitiesArgs {
-    entities: Vec<Entity>,
-}
+'aliases' takes 1-100 names. An alias cannot be the name of an existing entity or an alias of
+another entity; aliases the entity already has are skipped. Aliases are deleted with their
+entity.
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct CreateRelationsArgs {
-    relations: Vec<Relation>,
+Example:
+{
+  \"entityName\": \"United States\",
+  \"aliases\": [\"US\", \"USA\"]
 }
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct AddObservationsArgs {
-    observations: Vec<ObservationInput>,
-}
+Returns { \"entityName\": \"...\", \"added\": [\"...\"] }.
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'."
+    )]
+    async fn add_aliases(
+        &self,
+        Parameters(args): Parameters<AddAliasesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let added = self
+            .manager
+            .add_aliases(args.entity_name.clone(), args.aliases)
+            .await
+            .map_err(internal_err("Failed to add aliases"))?;
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct DeleteEntitiesArgs {
-    entity_names: Vec<String>,
-}
+        let summary = format!("Added {} aliases to '{}'", added.len(), args.entity_name);
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct DeleteObservationsArgs {
-    deletions: Vec<ObservationDeletion>,
-}
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(
+                json!({"entityName": args.entity_name, "added": added}),
+            )),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct DeleteRelationsArgs {
-    relations: Vec<Relation>,
-}
+    /// Remove entity aliases
+    #[tool(
+        name = "remove_aliases",
+        description = "Remove aliases added with add_aliases, whichever entities they belong to.
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct SearchNodesArgs {
-    query: Option<String>,
+Input schema:
+{
+  \"aliases\": [\"alias\"]
 }
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct OpenNodesArgs {
-    names: Vec<String>,
-}
+'aliases' takes 1-100 names; names that are not aliases are ignored. Relations created
+through an alias are stored under the entity's name, so they stay.
 
-// Helper for error conversion
-fn internal_err<T: ToString>(msg: &'static str) -> impl Fn(T) -> McpError {
-    move |err| McpError::internal_error(msg, Some(json!({ "error": err.to_string() })))
-}
+Returns { \"removed\": [\"...\"] }: the aliases that existed."
+    )]
+    async fn remove_aliases(
+        &self,
+        Parameters(args): Parameters<RemoveAliasesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let removed = self
+            .manager
+            .remove_aliases(args.aliases)
+            .await
+            .map_err(internal_err("Failed to remove aliases"))?;
 
-/// Run server in stdio mode (default)
-async fn run_stdio_mode(server: MemoryServer) -> Result<(), Box<dyn std::error::Error>> {
-    let transport = stdio();
-    let svc = server.serve(transport).await?;
-    svc.waiting().await?;
-    Ok(())
-}
+        let summary = format!("Removed {} aliases", removed.len());
 
-/// Run server in streamable HTTP mode
-async fn run_stream_mode(
-    server: MemoryServer,
-    bind: &str,
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"removed": removed}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Aliases of one entity
+    #[tool(
+        name = "list_aliases",
+        annotations(read_only_hint = true),
+        description = "List the aliases of an existing entity (see add_aliases).
+
+Input schema:
+{
+  \"entityName\": \"existing-entity-name\"
+}
+
+Returns { \"entityName\": \"...\", \"aliases\": [\"...\"] }, aliases sorted. Fails if the
+entity does not exist.
+IMPORTANT: Use 'entityName' (camelCase), NOT 'entity_name'."
+    )]
+    async fn list_aliases(
+        &self,
+        Parameters(args): Parameters<ListAliasesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let aliases = self
+            .manager
+            .entity_aliases(args.entity_name)
+            .await
+            .map_err(internal_err("Failed to list aliases"))?;
+
+        let summary = format!(
+            "'{}' has {} aliases",
+            aliases.entity_name,
+            aliases.aliases.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(aliases))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Replace an entity's type and observations (create it if missing)
+    #[tool(
+        name = "replace_entity",
+        description = "Replace an entity's type and observations in one step, creating the entity if it does not exist.
+
+Input schema:
+{
+  \"entity\": {
+    \"name\": \"entity-name\",
+    \"entityType\": \"new-type\",
+    \"observations\": [\"the complete new observation list\"]
+  }
+}
+
+PUT semantics: unlike add_observations / delete_observations, the stored type and observations
+are overwritten with exactly what is given (an empty list clears them). Relations, the entity
+id and an existing description and confidence are kept; a description or confidence given
+here is only used when the entity is created (use set_entity_description or
+set_entity_confidence to change them).
+
+Returns {\"entity\": ..., \"created\": true|false}: created is false when an existing entity
+was replaced.
+IMPORTANT: Use 'entityType' (camelCase), NOT 'entity_type'."
+    )]
+    async fn replace_entity(
+        &self,
+        Parameters(args): Parameters<ReplaceEntityArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .replace_entity(args.entity)
+            .await
+            .map_err(internal_err("Failed to replace entity"))?;
+
+        let summary = format!(
+            "{} entity '{}' ({}, {} observations)",
+            if result.created {
+                "Created"
+            } else {
+                "Replaced"
+            },
+            result.entity.name,
+            result.entity.entity_type,
+            result.entity.observations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Delete relations
+    #[tool(
+        name = "delete_relations",
+        description = "Delete specific relations from the knowledge graph.
+
+Input schema:
+{
+  \"relations\": [
+    {
+      \"from\": \"source-entity-name\",
+      \"to\": \"target-entity-name\",
+      \"relationType\": \"relation-type-to-delete\"
+    }
+  ]
+}
+
+Example - remove outdated relations:
+{
+  \"relations\": [
+    {
+      \"from\": \"John_Smith\",
+      \"to\": \"OldCompany\",
+      \"relationType\": \"works_at\"
+    }
+  ]
+}
+
+IMPORTANT: Use 'relationType' (camelCase), NOT 'relation_type'. All three fields must match exactly,
+except that 'from' and 'to' may name an alias of the entity (see add_aliases).
+
+Returns { \"deleted\": n } (relations that did not exist are not counted), with
+'resolvedAliases' listing the endpoints given as aliases, like create_relations."
+    )]
+    async fn delete_relations(
+        &self,
+        Parameters(args): Parameters<DeleteRelationsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.in_transaction() {
+            return self.stage(GraphOperation::DeleteRelations {
+                relations: args.relations,
+            });
+        }
+        let result = self
+            .manager
+            .delete_relations_detailed(args.relations)
+            .await
+            .map_err(internal_err("Failed to delete relations"))?;
+
+        let mut summary = format!("{} relations deleted successfully", result.deleted);
+        if !result.resolved_aliases.is_empty() {
+            summary.push_str(&format!(
+                " ({} endpoints resolved via aliases)",
+                result.resolved_aliases.len()
+            ));
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Start staging writes for an atomic commit
+    #[tool(
+        name = "begin_transaction",
+        description = "Start a transaction for this session: later writes are staged instead of applied.
+
+No input required - call with empty object: {}
+
+While the transaction is open, create_entities, create_relations, create_star_relations,
+add_observations, delete_entities, delete_observations and delete_relations are validated
+and staged; they return {\"staged\": \"tool_name\", \"operation\": 0, \"pending\": 1} instead of
+their usual result. Call commit_transaction to apply everything at once (all or nothing), or
+rollback_transaction to discard it.
+
+Reads (read_graph, search_nodes, ...) still see only committed data, and other tools write
+immediately. Only one transaction can be open per session; it is discarded when the session
+ends."
+    )]
+    async fn begin_transaction(&self) -> Result<CallToolResult, McpError> {
+        let mut transaction = self.transaction();
+        if let Some(staged) = transaction.as_ref() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "A transaction is already open ({} operations staged); commit or roll it back first",
+                    staged.len()
+                ),
+                None,
+            ));
+        }
+        *transaction = Some(Vec::new());
+
+        Ok(CallToolResult::success(vec![Content::text(
+            "Transaction started; writes are staged until commit_transaction or rollback_transaction",
+        )]))
+    }
+
+    /// Apply all staged writes atomically
+    #[tool(
+        name = "commit_transaction",
+        description = "Apply every write staged since begin_transaction, in order, in one database transaction.
+
+No input required - call with empty object: {}
+
+Returns:
+{
+  \"operations\": [
+    {\"op\": \"create_entities\", \"created\": [...], \"deduplicated\": []},
+    {\"op\": \"create_relations\", \"relations\": [...]},
+    {\"op\": \"delete_entities\", \"deleted\": 1}
+  ]
+}
+
+All or nothing: if any operation fails (e.g. a relation to an entity that does not exist), none
+are written and the error names the failing operation as 'Operation #index (tool_name)'.
+The transaction is closed either way."
+    )]
+    async fn commit_transaction(&self) -> Result<CallToolResult, McpError> {
+        let staged = self.transaction().take().ok_or_else(no_transaction)?;
+        let count = staged.len();
+        let results = self
+            .manager
+            .apply_operations(staged)
+            .await
+            .map_err(internal_err("Failed to commit transaction"))?;
+
+        let summary = format!("Transaction committed: {} operations applied", count);
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"operations": results}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Discard all staged writes
+    #[tool(
+        name = "rollback_transaction",
+        description = "Discard every write staged since begin_transaction and close the transaction.
+
+No input required - call with empty object: {}
+
+Returns {\"discarded\": 3}: the number of staged operations dropped. Nothing was written, so
+there is nothing to undo in the database."
+    )]
+    async fn rollback_transaction(&self) -> Result<CallToolResult, McpError> {
+        let staged = self.transaction().take().ok_or_else(no_transaction)?;
+
+        let summary = format!(
+            "Transaction rolled back: {} operations discarded",
+            staged.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"discarded": staged.len()}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Swap the direction of a single relation
+    #[tool(
+        name = "reverse_relation",
+        description = "Reverse the direction of one existing relation in place, e.g. fix 'Bob manages Alice' into 'Alice manages Bob'.
+
+Input schema:
+{
+  \"from\": \"current-source-entity\",
+  \"to\": \"current-target-entity\",
+  \"relationType\": \"relation-type\"
+}
+
+Example - the relation was recorded backwards:
+{
+  \"from\": \"Bob\",
+  \"to\": \"Alice\",
+  \"relationType\": \"manages\"
+}
+
+Returns:
+{
+  \"relation\": {\"from\": \"Alice\", \"to\": \"Bob\", \"relationType\": \"manages\"},
+  \"merged\": false
+}
+
+The relation is updated in place rather than deleted and recreated. If the reversed relation
+already exists, the original is removed and \"merged\" is true. Fails if the relation does not
+exist.
+
+IMPORTANT: Use 'relationType' (camelCase), NOT 'relation_type'."
+    )]
+    async fn reverse_relation(
+        &self,
+        Parameters(args): Parameters<ReverseRelationArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let relation = Relation {
+            from: args.from,
+            to: args.to,
+            relation_type: args.relation_type,
+        };
+        let result = self
+            .manager
+            .reverse_relation(relation)
+            .await
+            .map_err(internal_err("Failed to reverse relation"))?;
+
+        let summary = if result.merged {
+            format!(
+                "Relation '{}' -> '{}' ({}) already existed; removed the backwards one",
+                result.relation.from, result.relation.to, result.relation.relation_type
+            )
+        } else {
+            format!(
+                "Relation is now '{}' -> '{}' ({})",
+                result.relation.from, result.relation.to, result.relation.relation_type
+            )
+        };
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Change the type of a single relation
+    #[tool(
+        name = "change_relation_type",
+        description = "Change the type of one existing relation in place, e.g. fix a mislabeled edge.
+
+Input schema:
+{
+  \"from\": \"source-entity-name\",
+  \"to\": \"target-entity-name\",
+  \"oldType\": \"current-relation-type\",
+  \"newType\": \"new-relation-type\"
+}
+
+Example - Alice manages Bob rather than employs him:
+{
+  \"from\": \"Alice\",
+  \"to\": \"Bob\",
+  \"oldType\": \"employs\",
+  \"newType\": \"manages\"
+}
+
+Fails if the relation does not exist, or if a relation with the new type already exists
+between the same entities (delete one of them instead). The new type is subject to the
+relation type allowlist, if configured.
+
+IMPORTANT: Use 'oldType'/'newType' (camelCase), NOT 'old_type'/'new_type'."
+    )]
+    async fn change_relation_type(
+        &self,
+        Parameters(args): Parameters<ChangeRelationTypeArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let relation = Relation {
+            from: args.from,
+            to: args.to,
+            relation_type: args.old_type,
+        };
+        let updated = self
+            .manager
+            .change_relation_type(relation, args.new_type)
+            .await
+            .map_err(internal_err("Failed to change relation type"))?;
+
+        let summary = format!(
+            "Relation '{}' -> '{}' is now '{}'",
+            updated.from, updated.to, updated.relation_type
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"relation": updated}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Read entire knowledge graph
+    #[tool(
+        name = "read_graph",
+        annotations(read_only_hint = true),
+        description = "Read the entire knowledge graph.
+
+Input schema (optional):
+{
+  \"sorted\": false,
+  \"maxObservationsReturned\": 20,
+  \"minConfidence\": 0.5,
+  \"limit\": 100,
+  \"offset\": 0
+}
+
+Call with an empty object {} for the default (storage) order. With \"sorted\": true, entities
+are ordered by name and relations by (from, to, relationType), so repeated exports of the
+same graph are identical and diff cleanly.
+
+With 'limit' (1-1000) or 'offset', only that page of entities is read, ordered by name, with
+the relations touching them, plus \"total\", \"offset\" and \"limit\" like list_entities_by_type.
+
+Returns:
+{
+  \"entities\": [
+    {\"name\": \"...\", \"entityType\": \"...\", \"observations\": [\"...\"]}
+  ],
+  \"relations\": [
+    {\"from\": \"...\", \"to\": \"...\", \"relationType\": \"...\"}
+  ]
+}
+
+Observations are returned in stored order; an observation's zero-based position is the index
+used by delete_observations ('indices') and reorder_observations ('order').
+
+With 'maxObservationsReturned', each entity carries at most that many observations (the first
+ones); entities that had more are marked \"truncated\": true - use open_nodes to fetch them.
+
+With 'minConfidence' (0.0-1.0), entities whose confidence is below it are left out, together
+with their relations. Entities without a confidence score are always kept.
+
+Use this to get a complete snapshot of all stored knowledge."
+    )]
+    async fn read_graph(
+        &self,
+        Parameters(args): Parameters<ReadGraphArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        check_min_confidence(args.min_confidence)?;
+        if let Some(window) = read_window(args.limit, args.offset, args.min_confidence) {
+            let page = self
+                .manager
+                .read_graph_page(window)
+                .await
+                .map_err(internal_err("Failed to read graph"))?;
+
+            let summary = format!(
+                "Listed {} of {} entities (offset {}) with {} relations",
+                page.entities.len(),
+                page.total,
+                page.offset,
+                page.relations.len()
+            );
+
+            return Ok(CallToolResult {
+                content: vec![Content::text(&summary)],
+                structured_content: Some(versioned(limit_observations(
+                    json!(page),
+                    args.max_observations_returned,
+                ))),
+                is_error: Some(false),
+                meta: None,
+            });
+        }
+        let mut graph = if args.sorted {
+            self.manager.read_graph_sorted().await
+        } else {
+            self.manager.read_graph().await
+        }
+        .map_err(internal_err("Failed to read graph"))?;
+        if let Some(min) = args.min_confidence {
+            graph.retain_min_confidence(min);
+        }
+
+        let summary = format!(
+            "Knowledge graph contains {} entities and {} relations",
+            graph.entities.len(),
+            graph.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(limit_observations(
+                json!(graph),
+                args.max_observations_returned,
+            ))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Continue a response cut by --max-response-entities
+    #[tool(
+        name = "next_page",
+        annotations(read_only_hint = true),
+        description = "Fetch the next entities of a read result cut short by the server's response cap.
+
+Input schema:
+{
+  \"cursor\": \"<cursor from the previous result>\"
+}
+
+When the server runs with --max-response-entities, every read tool returns at most that
+many entities. A cut result carries \"hasMore\": true and a 'cursor'; pass it here to get the
+next entities in the same shape, until 'hasMore' is false. Tools taking 'offset' continue
+from the next offset, others run the original call again, so writes in between can shift
+or change what the next page holds."
+    )]
+    async fn next_page(
+        &self,
+        Parameters(args): Parameters<NextPageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let (tool, arguments, offset) = decode_cursor(&args.cursor)
+            .filter(|(tool, arguments, _)| self.is_capped(tool, Some(arguments)))
+            .ok_or_else(|| McpError::invalid_params("Invalid cursor", None))?;
+        let cap = self.max_response_entities.ok_or_else(|| {
+            McpError::invalid_params(
+                "Responses are not capped; start the server with --max-response-entities",
+                None,
+            )
+        })?;
+
+        let request = CallToolRequestParam {
+            name: tool.into(),
+            arguments: Some(arguments),
+        };
+        let mut result = self.call_capped(request, context, cap, offset).await?;
+        // The last page of a paged read comes back uncut, without "hasMore"
+        if let Some(mut content) = result.structured_content.take() {
+            if let Some(data) = content.get_mut("data").and_then(Value::as_object_mut) {
+                data.entry("hasMore").or_insert(json!(false));
+            }
+            result.structured_content = Some(canonical(content));
+        }
+        Ok(result)
+    }
+
+    /// Read graph as adjacency list
+    #[tool(
+        name = "export_adjacency",
+        annotations(read_only_hint = true),
+        description = "Read the entire knowledge graph as an adjacency list (read-only).
+
+No input required - call with empty object: {}
+
+Returns a map keyed by entity name:
+{
+  \"adjacency\": {
+    \"John_Smith\": {
+      \"entityType\": \"person\",
+      \"observations\": [\"...\"],
+      \"edges\": [
+        {\"to\": \"TechCorp\", \"relationType\": \"works_at\"}
+      ]
+    },
+    \"TechCorp\": {\"entityType\": \"organization\", \"observations\": [], \"edges\": []}
+  }
+}
+
+Only outgoing edges are listed per entity. Same data as read_graph, in a shape that is more
+convenient for graph algorithms."
+    )]
+    async fn export_adjacency(&self) -> Result<CallToolResult, McpError> {
+        let adjacency = self
+            .manager
+            .read_adjacency()
+            .await
+            .map_err(internal_err("Failed to export adjacency list"))?;
+
+        let edges: usize = adjacency.values().map(|e| e.edges.len()).sum();
+        let summary = format!(
+            "Adjacency list with {} entities and {} edges",
+            adjacency.len(),
+            edges
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"adjacency": adjacency}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Aggregate relations by entity types
+    #[tool(
+        name = "relation_matrix",
+        annotations(read_only_hint = true),
+        description = "Summarize the structure of the knowledge graph: how many relations of each type exist between each pair of entity types.
+
+No input required - call with empty object: {}
+
+Returns:
+{
+  \"matrix\": [
+    {\"fromEntityType\": \"person\", \"relationType\": \"works_at\", \"toEntityType\": \"organization\", \"count\": 42}
+  ]
+}
+
+Most frequent patterns come first. Use this to understand or validate the implicit ontology
+(e.g. spot a 'works_at' relation pointing at a 'person')."
+    )]
+    async fn relation_matrix(&self) -> Result<CallToolResult, McpError> {
+        let matrix = self
+            .manager
+            .relation_matrix()
+            .await
+            .map_err(internal_err("Failed to build relation matrix"))?;
+
+        let total: usize = matrix.iter().map(|c| c.count).sum();
+        let summary = format!(
+            "{} relation patterns across {} relations",
+            matrix.len(),
+            total
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"matrix": matrix}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Implicit schema of the graph, per entity type
+    #[tool(
+        name = "infer_ontology",
+        annotations(read_only_hint = true),
+        description = "Describe the implicit schema of the knowledge graph: for every entity type, which relation types lead from and to it, and the entity type at the other end.
+
+No input required - call with empty object: {}
+
+Returns:
+{
+  \"ontology\": [
+    {
+      \"entityType\": \"person\",
+      \"entities\": 12,
+      \"outgoing\": [
+        {\"relationType\": \"works_at\", \"entityType\": \"organization\", \"count\": 10},
+        {\"relationType\": \"knows\", \"entityType\": \"person\", \"count\": 4}
+      ],
+      \"incoming\": [
+        {\"relationType\": \"knows\", \"entityType\": \"person\", \"count\": 4}
+      ]
+    }
+  ]
+}
+
+Entity types are sorted by name, links by frequency. Check it before adding entries, so new
+entities and relations follow the patterns already established (same relation names, same
+target types)."
+    )]
+    async fn infer_ontology(&self) -> Result<CallToolResult, McpError> {
+        let ontology = self
+            .manager
+            .infer_ontology()
+            .await
+            .map_err(internal_err("Failed to infer ontology"))?;
+
+        let link = |l: &TypeLink, arrow: &str| {
+            format!(
+                "{}{}{} ({})",
+                l.relation_type, arrow, l.entity_type, l.count
+            )
+        };
+        let summary = ontology
+            .iter()
+            .map(|t| {
+                let mut line = format!("{} ({})", t.entity_type, t.entities);
+                if !t.outgoing.is_empty() {
+                    let links: Vec<_> = t.outgoing.iter().map(|l| link(l, "→")).collect();
+                    line.push_str(&format!(": outgoing {}", links.join(", ")));
+                }
+                if !t.incoming.is_empty() {
+                    let links: Vec<_> = t.incoming.iter().map(|l| link(l, "←")).collect();
+                    line.push_str(&format!("; incoming {}", links.join(", ")));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(CallToolResult {
+            content: vec![Content::text(if summary.is_empty() {
+                "The graph has no entities"
+            } else {
+                &summary
+            })],
+            structured_content: Some(versioned(json!({"ontology": ontology}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Relations pointing at entities that do not exist
+    #[tool(
+        name = "find_dangling_relations",
+        annotations(read_only_hint = true),
+        description = "List relations whose 'from' or 'to' entity does not exist.
+
+Input schema: {} (no parameters)
+
+Returns:
+{
+  \"relations\": [
+    {\"from\": \"Alice\", \"to\": \"Ghost_Project\", \"relationType\": \"works_on\"}
+  ],
+  \"missing\": [\"Ghost_Project\"]
+}
+
+The server keeps relations and entities consistent, but databases written with foreign keys
+off (bulk imports by other tools, very old databases) can hold such relations. 'missing' lists
+the endpoint names with no entity. Repair with materialize_dangling, which creates placeholder
+entities for them, or remove the relations with delete_relations."
+    )]
+    async fn find_dangling_relations(&self) -> Result<CallToolResult, McpError> {
+        let dangling = self
+            .manager
+            .dangling_relations()
+            .await
+            .map_err(internal_err("Failed to find dangling relations"))?;
+
+        let summary = format!(
+            "{} dangling relations referencing {} missing entities",
+            dangling.relations.len(),
+            dangling.missing.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(dangling))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Create placeholder entities for missing relation endpoints
+    #[tool(
+        name = "materialize_dangling",
+        description = "Repair dangling relations by creating a bare placeholder entity for every missing endpoint.
+
+Input schema (optional):
+{
+  \"entityType\": \"placeholder\"
+}
+
+'entityType' (default \"placeholder\") is the type given to the new entities; it must pass the
+server's entity type allowlist, if any. The placeholders have no observations, and the
+relations are kept, so nothing is lost: fill in or merge the placeholders afterwards.
+
+Returns:
+{
+  \"created\": [
+    {\"name\": \"Ghost_Project\", \"entityType\": \"placeholder\", \"observations\": []}
+  ]
+}"
+    )]
+    async fn materialize_dangling(
+        &self,
+        Parameters(args): Parameters<MaterializeDanglingArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let created = self
+            .manager
+            .materialize_dangling(args.entity_type)
+            .await
+            .map_err(internal_err("Failed to materialize dangling endpoints"))?;
+
+        let summary = format!("Created {} placeholder entities", created.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"created": created}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Compare table and full-text index sizes, optionally rebuilding the index
+    #[tool(
+        name = "check_search_index",
+        description = "Check that the full-text search index covers every entity and relation, and optionally rebuild it.
+
+Input schema:
+{
+  \"rebuild\": false
+}
+
+Returns:
+{
+  \"consistent\": false,
+  \"counts\": [
+    {\"table\": \"entities\", \"rows\": 120, \"indexed\": 118},
+    {\"table\": \"relations\", \"rows\": 300, \"indexed\": 300}
+  ],
+  \"rebuilt\": false
+}
+
+A mismatch means search_nodes can miss (or phantom-match) entities. With \"rebuild\": true an
+inconsistent index is rebuilt from the tables and 'counts' shows the state afterwards.
+A consistent index is never rebuilt."
+    )]
+    async fn check_search_index(
+        &self,
+        Parameters(args): Parameters<CheckSearchIndexArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut counts = self
+            .manager
+            .search_index_counts()
+            .await
+            .map_err(internal_err("Failed to check search index"))?;
+        let consistent = counts.iter().all(|c| c.is_consistent());
+
+        let rebuilt = args.rebuild && !consistent;
+        if rebuilt {
+            self.manager
+                .rebuild_search_index()
+                .await
+                .map_err(internal_err("Failed to rebuild search index"))?;
+            counts = self
+                .manager
+                .search_index_counts()
+                .await
+                .map_err(internal_err("Failed to check search index"))?;
+        }
+
+        let mut summary = counts
+            .iter()
+            .map(|c| format!("{}: {} rows, {} indexed", c.table, c.rows, c.indexed))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if rebuilt {
+            summary.push_str("\nSearch index rebuilt");
+        } else if !consistent {
+            summary.push_str("\nSearch index is out of sync; call again with \"rebuild\": true");
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "consistent": counts.iter().all(|c| c.is_consistent()),
+                "counts": counts,
+                "rebuilt": rebuilt,
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Graph connectivity metrics
+    #[tool(
+        name = "graph_metrics",
+        annotations(read_only_hint = true),
+        description = "Get aggregate connectivity metrics of the knowledge graph (read-only, cheap).
+
+No input required - call with empty object: {}
+
+Returns:
+{
+  \"entityCount\": 120,
+  \"relationCount\": 340,
+  \"density\": 0.0238,
+  \"averageDegree\": 5.67,
+  \"orphanCount\": 7
+}
+
+density = relations / (entities * (entities - 1)), i.e. the fraction of possible directed edges
+that exist. averageDegree counts both incoming and outgoing relations. orphanCount is the
+number of entities without any relation. Call periodically to trend how connected memory is."
+    )]
+    async fn graph_metrics(&self) -> Result<CallToolResult, McpError> {
+        let metrics = self
+            .manager
+            .graph_metrics()
+            .await
+            .map_err(internal_err("Failed to compute graph metrics"))?;
+
+        let summary = format!(
+            "{} entities, {} relations, density {:.4}, average degree {:.2}, {} orphans",
+            metrics.entity_count,
+            metrics.relation_count,
+            metrics.density,
+            metrics.average_degree,
+            metrics.orphan_count
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(metrics))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Find likely duplicate entities
+    #[tool(
+        name = "find_duplicates",
+        annotations(read_only_hint = true),
+        description = "Find groups of entities that are probably the same thing (read-only).
+
+Input schema (optional):
+{
+  \"threshold\": 0.6
+}
+
+Without a threshold, entities are grouped when their names are equal after trimming and
+lowercasing (\"Acme Corp\" and \" acme corp\"). With a threshold between 0 (exclusive) and 1,
+names whose trigram similarity reaches it are grouped too (\"Acme Corp\" and \"Acme Corp.\");
+grouping is transitive. Lower thresholds find more, looser candidates.
+
+Returns:
+{
+  \"clusters\": [
+    {\"entities\": [{\"name\": \"Acme Corp\", \"entityType\": \"organization\"}, ...]}
+  ]
+}
+
+Only groups with two or more entities are returned. Review the candidates before merging:
+move observations onto the entity to keep, then delete the others."
+    )]
+    async fn find_duplicates(
+        &self,
+        Parameters(args): Parameters<FindDuplicatesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let clusters = self
+            .manager
+            .find_duplicates(args.threshold)
+            .await
+            .map_err(internal_err("Failed to find duplicates"))?;
+
+        let summary = format!(
+            "Found {} duplicate clusters covering {} entities",
+            clusters.len(),
+            clusters.iter().map(|c| c.entities.len()).sum::<usize>()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"clusters": clusters}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Find relation types spelled differently but meaning the same
+    #[tool(
+        name = "find_relation_type_synonyms",
+        annotations(read_only_hint = true),
+        description = "Find groups of relation types that are probably the same relation spelled differently (read-only).
+
+Input schema (optional):
+{
+  \"threshold\": 0.6
+}
+
+Without a threshold, types are grouped when they are equal after normalizing case and
+separators (\"works_at\", \"works-at\", \"worksAt\" and \"Works At\" all become works_at).
+With a threshold between 0 (exclusive) and 1, types whose trigram similarity reaches it are
+grouped too (\"works_at\" and \"work_at\"); grouping is transitive.
+
+Returns:
+{
+  \"groups\": [
+    {
+      \"canonical\": \"works_at\",
+      \"relations\": 14,
+      \"types\": [
+        {\"relationType\": \"works_at\", \"count\": 11},
+        {\"relationType\": \"worksAt\", \"count\": 3}
+      ]
+    }
+  ]
+}
+
+Only groups with two or more types are returned, the most used groups first. 'canonical'
+suggests the type to keep: the most used one (ties go to the snake_case spelling). Review
+the groups, then retype the other relations with change_relation_type."
+    )]
+    async fn find_relation_type_synonyms(
+        &self,
+        Parameters(args): Parameters<FindDuplicatesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let groups = self
+            .manager
+            .find_relation_type_synonyms(args.threshold)
+            .await
+            .map_err(internal_err("Failed to find relation type synonyms"))?;
+
+        let summary = format!(
+            "Found {} relation type synonym groups covering {} types",
+            groups.len(),
+            groups.iter().map(|g| g.types.len()).sum::<usize>()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"groups": groups}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Search nodes by query
+    #[tool(
+        name = "search_nodes",
+        annotations(read_only_hint = true),
+        description = "Search for nodes in the knowledge graph using full-text search. Searches across entity names, types, and observations.
+
+Input schema:
+{
+  \"query\": \"search terms\" | null,
+  \"includeRelationMatches\": false,
+  \"matchMode\": \"all\" | \"any\",
+  \"maxObservationsReturned\": 20,
+  \"minConfidence\": 0.5,
+  \"projection\": \"full\" | \"names\" | \"names_types\",
+  \"scored\": false,
+  \"limit\": 100,
+  \"offset\": 0
+}
+
+By default (\"matchMode\": \"all\") an entity must match every term. With \"matchMode\": \"any\" it
+matches if any term does, e.g. \"paris london\" finds entities mentioning either city.
+
+With \"includeRelationMatches\": true, relations whose own text (endpoint names or relation type)
+matches the query are returned too, even if their endpoints did not match.
+
+With 'maxObservationsReturned', each entity carries at most that many observations (the first
+ones); entities that had more are marked \"truncated\": true.
+
+With 'minConfidence' (0.0-1.0), matching entities whose confidence is below it are left out,
+together with their relations. Entities without a confidence score are always kept.
+
+To just locate candidates, use \"projection\": \"names\" (or \"names_types\"): 'entities' then lists
+only {\"name\"} (or {\"name\", \"entityType\"}) and no relations are returned. Open the entities you
+need with open_nodes afterwards. \"includeRelationMatches\" and \"minConfidence\" require the default
+\"full\" projection.
+
+For \"top N most relevant\" retrieval use \"scored\": true: instead of a graph the result is
+{\"results\": [{\"entity\": {...}, \"score\": 3.2}]}, ranked by FTS5 bm25 relevance, highest score
+first, with no relations. Scores only compare within one search. Needs a non-empty query and
+the \"full\" projection; \"includeRelationMatches\" is not supported.
+
+With 'limit' (1-1000) or 'offset', only that page of the matches is read: entities by name
+(scored results by rank) and the relations of the page's entities.
+
+Example - find entities related to 'Rust':
+{
+  \"query\": \"Rust programming\"
+}
+
+Example - get all entities (empty/null query):
+{
+  \"query\": null
+}
+
+Returns matching entities and their relations. Uses SQLite FTS5 for efficient full-text search.
+Queries shorter than the server's minimum (default 2 characters, quotes and spaces not counted)
+are rejected; search for something more specific instead."
+    )]
+    async fn search_nodes(
+        &self,
+        Parameters(args): Parameters<SearchNodesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        check_min_confidence(args.min_confidence)?;
+        // Paged searches slice (and filter by confidence) in the query itself
+        let window = read_window(args.limit, args.offset, args.min_confidence);
+        if args.scored {
+            if args.projection != SearchProjection::Full {
+                return Err(McpError::invalid_params(
+                    "scored requires the \"full\" projection",
+                    None,
+                ));
+            }
+            if args.include_relation_matches {
+                return Err(McpError::invalid_params(
+                    "scored results do not include relations; drop includeRelationMatches",
+                    None,
+                ));
+            }
+            let Some(query) = args.query.filter(|q| !q.trim().is_empty()) else {
+                return Err(McpError::invalid_params("scored requires a query", None));
+            };
+            let mut results = self
+                .manager
+                .search_nodes_scored(query, args.match_mode, window)
+                .await
+                .map_err(internal_err("Failed to search nodes"))?;
+            if let Some(min) = args.min_confidence.filter(|_| window.is_none()) {
+                results.retain(|r| r.entity.confidence.is_none_or(|c| c >= min));
+            }
+            let summary = format!("Found {} entities", results.len());
+            return Ok(CallToolResult {
+                content: vec![Content::text(&summary)],
+                structured_content: Some(versioned(limit_observations(
+                    json!({"results": results}),
+                    args.max_observations_returned,
+                ))),
+                is_error: Some(false),
+                meta: None,
+            });
+        }
+        if args.projection != SearchProjection::Full {
+            if args.include_relation_matches {
+                return Err(McpError::invalid_params(
+                    "includeRelationMatches requires the \"full\" projection",
+                    None,
+                ));
+            }
+            if args.min_confidence.is_some() {
+                return Err(McpError::invalid_params(
+                    "minConfidence requires the \"full\" projection",
+                    None,
+                ));
+            }
+            let hits = self
+                .manager
+                .search_entity_hits(args.query, args.match_mode, args.projection, window)
+                .await
+                .map_err(internal_err("Failed to search nodes"))?;
+            let summary = format!("Found {} entities", hits.len());
+            return Ok(CallToolResult {
+                content: vec![Content::text(&summary)],
+                structured_content: Some(versioned(json!({"entities": hits}))),
+                is_error: Some(false),
+                meta: None,
+            });
+        }
+
+        let mut result = if let Some(window) = window {
+            self.manager
+                .search_nodes_window(
+                    args.query,
+                    args.match_mode,
+                    args.include_relation_matches,
+                    window,
+                )
+                .await
+        } else if args.include_relation_matches {
+            self.manager
+                .search_nodes_and_relations(args.query, args.match_mode)
+                .await
+        } else {
+            self.manager
+                .search_nodes_matching(args.query, args.match_mode)
+                .await
+        }
+        .map_err(internal_err("Failed to search nodes"))?;
+        if let Some(min) = args.min_confidence.filter(|_| window.is_none()) {
+            result.retain_min_confidence(min);
+        }
+
+        let summary = format!(
+            "Found {} entities and {} relations",
+            result.entities.len(),
+            result.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(limit_observations(
+                json!(result),
+                args.max_observations_returned,
+            ))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Full-text search over relations
+    #[tool(
+        name = "search_relations",
+        annotations(read_only_hint = true),
+        description = "Search relations only, using full-text search over their source name, target name and type.
+
+Input schema:
+{
+  \"query\": \"search terms\",
+  \"limit\": 100,
+  \"offset\": 0
+}
+
+'limit' (1-1000) and 'offset' are optional; with either, only that page of the matches is
+read, ordered by (from, to, relationType).
+
+Example - find employment edges:
+{
+  \"query\": \"works_at\"
+}
+
+Returns { \"relations\": [{\"from\": \"...\", \"to\": \"...\", \"relationType\": \"...\"}] }.
+All query words must match (AND); FTS operators are not supported. Query must not be empty
+or shorter than the server's minimum query length (default 2 characters)."
+    )]
+    async fn search_relations(
+        &self,
+        Parameters(args): Parameters<SearchRelationsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let relations = self
+            .manager
+            .search_relations(args.query, read_window(args.limit, args.offset, None))
+            .await
+            .map_err(internal_err("Failed to search relations"))?;
+
+        let summary = format!("Found {} relations", relations.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"relations": relations}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// List entities of an exact type with pagination
+    #[tool(
+        name = "list_entities_by_type",
+        annotations(read_only_hint = true),
+        description = "List entities whose type matches exactly, one page at a time.
+
+Input schema:
+{
+  \"entityType\": \"type-name\",
+  \"limit\": 100,
+  \"offset\": 0
+}
+
+'limit' (1-1000, default 100) and 'offset' (default 0) are optional.
+
+Example - first page of all people:
+{
+  \"entityType\": \"person\"
+}
+
+Example - next page:
+{
+  \"entityType\": \"person\",
+  \"limit\": 100,
+  \"offset\": 100
+}
+
+Returns { \"entities\": [...], \"total\": N, \"offset\": 0, \"limit\": 100 }, entities ordered by name.
+Unlike search_nodes, this does not match the type name inside observations or other fields.
+
+IMPORTANT: Use 'entityType' (camelCase), not 'entity_type'."
+    )]
+    async fn list_entities_by_type(
+        &self,
+        Parameters(args): Parameters<ListEntitiesByTypeArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let page = self
+            .manager
+            .list_entities_by_type(args.entity_type, args.limit, args.offset)
+            .await
+            .map_err(internal_err("Failed to list entities by type"))?;
+
+        let summary = format!(
+            "Listed {} of {} entities (offset {})",
+            page.entities.len(),
+            page.total,
+            page.offset
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(page))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// List entities of several exact types, grouped by type
+    #[tool(
+        name = "list_entities_by_types",
+        annotations(read_only_hint = true),
+        description = "List entities of several exact types at once, grouped by type with per-type counts.
+
+Input schema:
+{
+  \"types\": [\"type-a\", \"type-b\"],
+  \"limit\": 100,
+  \"offset\": 0
+}
+
+'types' takes 1-100 entity types (repeats are ignored). 'limit' (1-1000, default 100) and
+'offset' (default 0) are optional and apply to each type separately, so every group can be
+paged side by side.
+
+Example - people and organizations:
+{
+  \"types\": [\"person\", \"organization\"]
+}
+
+Returns { \"groups\": [{ \"entityType\": \"person\", \"entities\": [...], \"total\": N }, ...],
+\"offset\": 0, \"limit\": 100 }: one group per requested type, in request order (also for
+types without entities), entities ordered by name. A group is complete once
+offset + entities.length reaches its total."
+    )]
+    async fn list_entities_by_types(
+        &self,
+        Parameters(args): Parameters<ListEntitiesByTypesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let page = self
+            .manager
+            .list_entities_by_types(args.types, args.limit, args.offset)
+            .await
+            .map_err(internal_err("Failed to list entities by types"))?;
+
+        let summary = page
+            .groups
+            .iter()
+            .map(|group| {
+                format!(
+                    "{}: {} of {}",
+                    group.entity_type,
+                    group.entities.len(),
+                    group.total
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let summary = format!("Listed entities (offset {}): {}", page.offset, summary);
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(page))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// List relations of an exact type with pagination
+    #[tool(
+        name = "list_relations_by_type",
+        annotations(read_only_hint = true),
+        description = "List relations whose type matches exactly, one page at a time.
+
+Input schema:
+{
+  \"relationType\": \"type-name\",
+  \"limit\": 100,
+  \"offset\": 0,
+  \"includeEntities\": false
+}
+
+'limit' (1-1000, default 100), 'offset' (default 0) and 'includeEntities' (default false)
+are optional. With 'includeEntities', the endpoint entities of the relations on the page are
+returned as well.
+
+Example - everyone who works somewhere, with the people and companies involved:
+{
+  \"relationType\": \"works_at\",
+  \"includeEntities\": true
+}
+
+Returns { \"relations\": [...], \"entities\": [...], \"total\": N, \"offset\": 0, \"limit\": 100 },
+relations ordered by (from, to); 'entities' is present only with includeEntities.
+Unlike search_relations, this is an exact, index-backed match on the relation type.
+
+IMPORTANT: Use 'relationType' (camelCase), not 'relation_type'."
+    )]
+    async fn list_relations_by_type(
+        &self,
+        Parameters(args): Parameters<ListRelationsByTypeArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let page = self
+            .manager
+            .list_relations_by_type(
+                args.relation_type,
+                args.limit,
+                args.offset,
+                args.include_entities,
+            )
+            .await
+            .map_err(internal_err("Failed to list relations by type"))?;
+
+        let summary = format!(
+            "Listed {} of {} relations (offset {})",
+            page.relations.len(),
+            page.total,
+            page.offset
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(page))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Most recently created or updated entities
+    #[tool(
+        name = "recent_entities",
+        annotations(read_only_hint = true),
+        description = "List the entities that were created or changed most recently, newest first.
+
+Input schema (optional):
+{
+  \"limit\": 20,
+  \"offset\": 0
+}
+
+'limit' (1-1000, default 20) caps the number of entities; 'offset' skips the newest ones. Adding or deleting observations,
+reordering them and setting the description all count as changes; relations do not.
+
+Returns:
+{
+  \"entities\": [
+    {\"name\": \"...\", \"entityType\": \"...\", \"observations\": [\"...\"],
+     \"createdAt\": \"2025-01-31T12:00:00.000Z\", \"updatedAt\": \"2025-02-01T08:30:12.345Z\"}
+  ]
+}
+
+Timestamps are ISO-8601 UTC. Entities stored before timestamps were recorded have null
+times and are listed last. Use this for a 'what was I working on' view without reading the
+whole graph."
+    )]
+    async fn recent_entities(
+        &self,
+        Parameters(args): Parameters<RecentEntitiesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let entities = self
+            .manager
+            .recent_entities(args.limit, args.offset)
+            .await
+            .map_err(internal_err("Failed to list recent entities"))?;
+
+        let summary = format!("{} recently changed entities", entities.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"entities": entities}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Random sample of entities
+    #[tool(
+        name = "sample_entities",
+        annotations(read_only_hint = true),
+        description = "Return a random sample of entities, to spot-check what has been stored.
+
+Input schema (all optional):
+{
+  \"count\": 10,
+  \"entityType\": \"person\",
+  \"seed\": 42,
+  \"offset\": 0
+}
+
+'count' (1-1000, default 10) caps the sample size; 'entityType' samples only entities of that
+exact type. Every call returns a different sample unless 'seed' is given: the same seed on an
+unchanged graph returns the same entities in the same order, so 'offset' pages through a
+seeded sample.
+
+Returns { \"entities\": [...] } (fewer than 'count' if the graph or type has fewer)."
+    )]
+    async fn sample_entities(
+        &self,
+        Parameters(args): Parameters<SampleEntitiesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let entities = self
+            .manager
+            .sample_entities(args.count, args.entity_type, args.seed, args.offset)
+            .await
+            .map_err(internal_err("Failed to sample entities"))?;
+
+        let summary = format!("Sampled {} entities", entities.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"entities": entities}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Entities without observations
+    #[tool(
+        name = "find_empty_entities",
+        description = "Find entities that have no observations, for data-quality sweeps. These are usually
+placeholders, often created automatically as relation endpoints, that need enrichment or
+cleanup.
+
+Input schema (all optional):
+{
+  \"entityType\": \"person\",
+  \"limit\": 100,
+  \"offset\": 0,
+  \"delete\": false,
+  \"confirm\": false
+}
+
+'entityType' only considers entities of that exact type. 'limit' (1-1000, default 100) caps
+the number of entities listed, by name, from 'offset'. With \"delete\": true ALL matching entities (not just
+'limit' of them) are deleted together with their relations; this also needs
+\"confirm\": true, otherwise the call is rejected and nothing changes.
+
+Returns { \"entities\": [...] }, or { \"deleted\": [\"name\", ...] } when deleting."
+    )]
+    async fn find_empty_entities(
+        &self,
+        Parameters(args): Parameters<FindEmptyEntitiesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if args.delete {
+            if !args.confirm {
+                return Err(McpError::invalid_params(
+                    "Deleting empty entities also removes their relations; pass \"confirm\": true to proceed",
+                    None,
+                ));
+            }
+            let deleted = self
+                .manager
+                .delete_empty_entities(args.entity_type)
+                .await
+                .map_err(internal_err("Failed to delete empty entities"))?;
+
+            let summary = format!("Deleted {} entities without observations", deleted.len());
+
+            return Ok(CallToolResult {
+                content: vec![Content::text(&summary)],
+                structured_content: Some(versioned(json!({"deleted": deleted}))),
+                is_error: Some(false),
+                meta: None,
+            });
+        }
+
+        let entities = self
+            .manager
+            .find_empty_entities(args.entity_type, args.limit, args.offset)
+            .await
+            .map_err(internal_err("Failed to find empty entities"))?;
+
+        let summary = format!("{} entities without observations", entities.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"entities": entities}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Bulk delete of search results
+    #[tool(
+        name = "delete_by_query",
+        description = "Delete every entity matching a full-text search, with its relations, to prune a topic wholesale.
+
+Input schema:
+{
+  \"query\": \"search terms\",
+  \"matchMode\": \"all\" | \"any\",
+  \"confirm\": true,
+  \"dryRun\": true
+}
+
+The query is matched like in search_nodes (an empty query is rejected, it does not mean
+everything). By default (\"dryRun\": true) nothing is deleted: the call only reports which
+entities would be. With \"dryRun\": false ALL matching entities are deleted in one
+transaction together with their relations; this also needs \"confirm\": true, otherwise the
+call is rejected and nothing changes. Run a dry run first and check the names.
+
+Example - preview, then delete, everything about a cancelled project:
+{
+  \"query\": \"Project_Phoenix\",
+  \"confirm\": false
+}
+{
+  \"query\": \"Project_Phoenix\",
+  \"confirm\": true,
+  \"dryRun\": false
+}
+
+Returns { \"count\": n, \"names\": [...], \"dryRun\": true|false }."
+    )]
+    async fn delete_by_query(
+        &self,
+        Parameters(args): Parameters<DeleteByQueryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if !args.dry_run && !args.confirm {
+            return Err(McpError::invalid_params(
+                "Deleting by query removes every matching entity and its relations; pass \"confirm\": true to proceed",
+                None,
+            ));
+        }
+        let names = self
+            .manager
+            .delete_by_query(args.query, args.match_mode, args.dry_run)
+            .await
+            .map_err(internal_err("Failed to delete by query"))?;
+
+        let summary = if args.dry_run {
+            format!(
+                "Dry run: {} matching entities would be deleted",
+                names.len()
+            )
+        } else {
+            format!("Deleted {} matching entities", names.len())
+        };
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "count": names.len(),
+                "names": names,
+                "dryRun": args.dry_run,
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Entities ranked by observation count
+    #[tool(
+        name = "entities_by_observation_count",
+        annotations(read_only_hint = true),
+        description = "Rank entities by how many observations they hold: the richest ones (candidates
+for splitting) or the emptiest ones (candidates for enrichment).
+
+Input schema (optional):
+{
+  \"order\": \"desc\",
+  \"limit\": 20
+}
+
+Example - entities with the fewest observations:
+{
+  \"order\": \"asc\",
+  \"limit\": 10
+}
+
+Returns:
+{
+  \"order\": \"asc\",
+  \"entities\": [
+    {\"name\": \"Stub_Project\", \"entityType\": \"project\", \"observationCount\": 0},
+    {\"name\": \"John_Smith\", \"entityType\": \"person\", \"observationCount\": 2}
+  ]
+}
+
+'order' is \"desc\" (most observations first, the default) or \"asc\" (fewest first); ties are
+ordered by name. 'limit' (1-1000, default 20) caps the number of entities."
+    )]
+    async fn entities_by_observation_count(
+        &self,
+        Parameters(args): Parameters<ObservationCountArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let entities = self
+            .manager
+            .entities_by_observation_count(args.order, args.limit)
+            .await
+            .map_err(internal_err("Failed to rank entities by observation count"))?;
+
+        let summary = format!("{} entities ranked by observation count", entities.len());
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "order": args.order,
+                "entities": entities,
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Distinct observation values across the graph
+    #[tool(
+        name = "list_observations",
+        annotations(read_only_hint = true),
+        description = "List the distinct observation texts across all entities, with how many entities
+hold each: for tag clouds or spotting facts shared by many entities.
+
+Input schema (optional):
+{
+  \"limit\": 100,
+  \"offset\": 0,
+  \"contains\": \"substring\"
+}
+
+'limit' (1-1000, default 100) and 'offset' (default 0) page through the values. 'contains'
+keeps only values containing it (case-insensitive for ASCII letters).
+
+Example - facts mentioning Rust:
+{
+  \"contains\": \"rust\"
+}
+
+Returns:
+{
+  \"observations\": [
+    {\"observation\": \"Expert in Rust programming\", \"entityCount\": 3},
+    {\"observation\": \"Learning Rust\", \"entityCount\": 1}
+  ],
+  \"total\": 2,
+  \"offset\": 0,
+  \"limit\": 100
+}
+
+Values held by the most entities come first; ties are ordered by text."
+    )]
+    async fn list_observations(
+        &self,
+        Parameters(args): Parameters<ListObservationsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let page = self
+            .manager
+            .list_observations(args.limit, args.offset, args.contains)
+            .await
+            .map_err(internal_err("Failed to list observations"))?;
+
+        let summary = format!(
+            "Listed {} of {} distinct observations (offset {})",
+            page.observations.len(),
+            page.total,
+            page.offset
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(page))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Least often read entities
+    #[tool(
+        name = "least_accessed",
+        annotations(read_only_hint = true),
+        description = "List the entities read least often, as candidates for pruning.
+
+Input schema (optional):
+{
+  \"limit\": 20
+}
+
+Returns:
+{
+  \"tracking\": true,
+  \"entities\": [
+    {\"name\": \"Old_Note\", \"entityType\": \"note\", \"accessCount\": 0, \"lastAccessed\": null},
+    {\"name\": \"Draft_Plan\", \"entityType\": \"plan\", \"accessCount\": 2,
+     \"lastAccessed\": \"2025-02-01T08:30:12.345Z\"}
+  ]
+}
+
+'limit' (1-1000, default 20) caps the number of entities. Reads are only counted when the
+server runs with --track-access ('tracking' says whether it does): each entity returned by
+open_nodes, open_nodes_by_id or a non-empty search_nodes query counts once. Never-read
+entities come first, then the least and least recently read ones."
+    )]
+    async fn least_accessed(
+        &self,
+        Parameters(args): Parameters<LeastAccessedArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let entities = self
+            .manager
+            .least_accessed(args.limit)
+            .await
+            .map_err(internal_err("Failed to list least accessed entities"))?;
+        let tracking = self.manager.tracks_access();
+
+        let mut summary = format!("{} least accessed entities", entities.len());
+        if !tracking {
+            summary.push_str(" (access tracking is off; start the server with --track-access)");
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "tracking": tracking,
+                "entities": entities,
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Compute and cache PageRank importance scores
+    #[tool(
+        name = "compute_importance",
+        description = "Rank every entity by how central it is in the relation graph (PageRank) and
+cache the scores for top_important. Run it again after larger changes to the graph.
+
+Input schema (optional):
+{
+  \"iterations\": 20
+}
+
+Returns:
+{
+  \"entities\": 120,
+  \"relations\": 340,
+  \"iterations\": 20,
+  \"converged\": false
+}
+
+Each relation is a link from its source to its target, so entities that many (important)
+entities point to rank highest. 'iterations' (1-100, default 20) bounds the work; it stops
+early once the scores settle ('converged'). Graphs above 100000 entities or 1000000 relations
+are rejected. Scores replace the previous run; entities created since have none."
+    )]
+    async fn compute_importance(
+        &self,
+        Parameters(args): Parameters<ComputeImportanceArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let run = self
+            .manager
+            .compute_importance(args.iterations)
+            .await
+            .map_err(internal_err("Failed to compute importance"))?;
+
+        let summary = format!(
+            "Scored {} entities over {} relations in {} iterations{}",
+            run.entities,
+            run.relations,
+            run.iterations,
+            if run.converged { " (converged)" } else { "" }
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(run))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Most important entities by cached PageRank score
+    #[tool(
+        name = "top_important",
+        annotations(read_only_hint = true),
+        description = "List the entities with the highest importance score from the last
+compute_importance run.
+
+Input schema (optional):
+{
+  \"limit\": 20
+}
+
+Returns:
+{
+  \"entities\": [
+    {\"name\": \"Acme_Corp\", \"entityType\": \"organization\", \"importance\": 0.082},
+    {\"name\": \"John_Smith\", \"entityType\": \"person\", \"importance\": 0.041}
+  ]
+}
+
+'limit' (1-1000, default 20) caps the number of entities. Scores of one run add up to 1.
+Empty until compute_importance has run; entities created since are not listed."
+    )]
+    async fn top_important(
+        &self,
+        Parameters(args): Parameters<TopImportantArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let entities = self
+            .manager
+            .top_important(args.limit)
+            .await
+            .map_err(internal_err("Failed to list important entities"))?;
+
+        let mut summary = format!("{} most important entities", entities.len());
+        if entities.is_empty() {
+            summary.push_str(" (run compute_importance first)");
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({ "entities": entities }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Open specific nodes by names
+    #[tool(
+        name = "open_nodes",
+        annotations(read_only_hint = true),
+        description = "Open specific nodes in the knowledge graph by their names.
+
+Input schema:
+{
+  \"names\": [\"entity-name-1\", \"entity-name-2\"],
+  \"maxObservationsReturned\": 20
+}
+
+Example - retrieve specific entities:
+{
+  \"names\": [\"John_Smith\", \"TechCorp\", \"Project_Alpha\"]
+}
+
+Returns the requested entities with all their observations (in stored order, see
+reorder_observations), plus any relations between them. With 'maxObservationsReturned' (optional),
+only the first N observations are returned and entities that had more are marked
+\"truncated\": true."
+    )]
+    async fn open_nodes(
+        &self,
+        Parameters(args): Parameters<OpenNodesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .open_nodes(args.names)
+            .await
+            .map_err(internal_err("Failed to open nodes"))?;
+
+        let summary = format!(
+            "Retrieved {} entities and {} relations",
+            result.entities.len(),
+            result.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(limit_observations(
+                json!(result),
+                args.max_observations_returned,
+            ))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Overview of specific nodes: entity, degrees and observation count per name
+    #[tool(
+        name = "inspect_nodes",
+        annotations(read_only_hint = true),
+        description = "Inspect nodes by name in one call: for each requested name, the entity (or null if
+it does not exist), how many relations point at it and start from it, and how many
+observations it holds.
+
+Input schema:
+{
+  \"names\": [\"entity-name-1\", \"entity-name-2\"]
+}
+
+Example:
+{
+  \"names\": [\"John_Smith\", \"Jane_Doe\"]
+}
+
+Returns:
+{
+  \"nodes\": {
+    \"John_Smith\": {
+      \"entity\": {\"name\": \"John_Smith\", \"entityType\": \"person\", \"observations\": [...]},
+      \"inDegree\": 2,
+      \"outDegree\": 3,
+      \"observationCount\": 4
+    },
+    \"Jane_Doe\": {\"entity\": null, \"inDegree\": 0, \"outDegree\": 0, \"observationCount\": 0}
+  },
+  \"missing\": [\"Jane_Doe\"]
+}
+
+Unlike open_nodes, missing names are reported instead of skipped, and relations are counted
+rather than returned. A missing name can still have degrees from dangling relations
+(see find_dangling_relations)."
+    )]
+    async fn inspect_nodes(
+        &self,
+        Parameters(args): Parameters<InspectNodesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .inspect_nodes(args.names)
+            .await
+            .map_err(internal_err("Failed to inspect nodes"))?;
+
+        let mut summary = format!("Inspected {} nodes", result.nodes.len());
+        if !result.missing.is_empty() {
+            summary.push_str(&format!(
+                " ({} missing: {})",
+                result.missing.len(),
+                result.missing.join(", ")
+            ));
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Open specific nodes by stable ids
+    #[tool(
+        name = "open_nodes_by_id",
+        annotations(read_only_hint = true),
+        description = "Open specific nodes in the knowledge graph by their stable ids.
+
+Input schema:
+{
+  \"ids\": [\"3f2a9c...\", \"b71e04...\"],
+  \"maxObservationsReturned\": 20
+}
+
+Every entity carries an 'id' (32 lowercase hex characters) in read results. Unlike the name it
+is assigned once by the server and never changes, so it is a stable handle to keep between
+sessions. Unknown ids are skipped. Returns the same shape as open_nodes: the entities plus
+any relations between them."
+    )]
+    async fn open_nodes_by_id(
+        &self,
+        Parameters(args): Parameters<OpenNodesByIdArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .open_nodes_by_id(args.ids)
+            .await
+            .map_err(internal_err("Failed to open nodes by id"))?;
+
+        let summary = format!(
+            "Retrieved {} entities and {} relations",
+            result.entities.len(),
+            result.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(limit_observations(
+                json!(result),
+                args.max_observations_returned,
+            ))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Everything one create call inserted
+    #[tool(
+        name = "get_batch",
+        annotations(read_only_hint = true),
+        description = "Recall the entities and relations created together by one call, using the 'batchId' that call returned.
+
+Input schema:
+{
+  \"batchId\": \"3f9a0c2e8b1d4a67\"
+}
+
+create_entities, create_relations, import_foreign and committed transactions (begin_transaction
+... commit_transaction) tag everything they create with a fresh batch id and return it. To keep
+entities and the relations between them in one batch, create them inside a transaction.
+
+Returns { \"entities\": [...], \"relations\": [...] } like read_graph, sorted by name (relations by
+from, to, type). Things deleted since are gone; observations added later are included. An
+unknown batch id returns an empty graph."
+    )]
+    async fn get_batch(
+        &self,
+        Parameters(args): Parameters<GetBatchArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let graph = self
+            .manager
+            .get_batch(args.batch_id)
+            .await
+            .map_err(internal_err("Failed to read batch"))?;
+
+        let summary = format!(
+            "Batch holds {} entities and {} relations",
+            graph.entities.len(),
+            graph.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(graph))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Import an export of another memory system
+    #[tool(
+        name = "import_foreign",
+        description = "Import memories exported from another memory system into the knowledge graph.
+
+Input schema:
+{
+  \"format\": \"mem0\",
+  \"content\": \"<the export as a JSON string>\"
+}
+
+Supported formats:
+- \"mem0\": output of mem0's get_all, either {\"results\": [...], \"relations\": [...]} or a bare
+  array of memories. Each memory string becomes an observation on the entity it belongs to:
+  its user_id (type \"user\"), else agent_id (\"agent\"), else run_id (\"session\"), else an
+  entity named \"mem0\" (\"memory\"). Graph relations {source, relationship, target} become
+  relations between entities of type \"entity\".
+
+Example:
+{
+  \"format\": \"mem0\",
+  \"content\": \"{\\\"results\\\": [{\\\"memory\\\": \\\"Likes tea\\\", \\\"user_id\\\": \\\"alice\\\"}]}\"
+}
+
+The import runs in one transaction. Entities that already exist keep their type; new
+observations are appended to them. Returns created entities and relations plus the
+observations merged into existing entities."
+    )]
+    async fn import_foreign(
+        &self,
+        Parameters(args): Parameters<ImportForeignArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let graph = import::parse_foreign(&args.content, args.format)
+            .map_err(|e| McpError::invalid_params(format!("{:#}", e), None))?;
+        let result = self
+            .manager
+            .merge_graph(graph, ConflictPolicy::MergeObservations)
+            .await
+            .map_err(internal_err("Failed to import memories"))?;
+
+        let merged: usize = result
+            .merged_observations
+            .iter()
+            .map(|r| r.added_observations.len())
+            .sum();
+        let summary = format!(
+            "Imported {} entities and {} relations; {} observations merged into existing entities",
+            result.entities.len(),
+            result.relations.len(),
+            merged
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Merge a graph into the existing one with a conflict policy
+    #[tool(
+        name = "merge_graph",
+        description = "Merge a whole graph (e.g. from another source or an export) into the knowledge graph,
+choosing what happens to entities that already exist.
+
+Input schema:
+{
+  \"entities\": [{\"name\": \"...\", \"entityType\": \"...\", \"observations\": [...]}],
+  \"relations\": [{\"from\": \"...\", \"to\": \"...\", \"relationType\": \"...\"}],
+  \"conflictPolicy\": \"skip\" | \"overwrite\" | \"merge_observations\"
+}
+
+'entities' and 'relations' have the read_graph shape, so an export can be passed as is
+(without entity ids under --strict-args). 'conflictPolicy' applies to incoming entities whose
+name already exists (default \"merge_observations\"):
+- \"skip\": keep the existing entity unchanged
+- \"overwrite\": replace its type and observations (relations, description, confidence and
+  metadata are kept)
+- \"merge_observations\": append the incoming observations it does not have yet
+
+Example - reconcile a teammate's graph, theirs wins:
+{
+  \"entities\": [{\"name\": \"John_Smith\", \"entityType\": \"person\", \"observations\": [\"Moved to Berlin\"]}],
+  \"relations\": [],
+  \"conflictPolicy\": \"overwrite\"
+}
+
+Everything is validated first and written in one transaction: an invalid item fails the
+whole merge. New entities are created as with create_entities (type allowlist,
+--dedup-on-create); relations that already exist are ignored. Returns the created
+'entities' and 'relations', the names of existing entities that were 'updated' or 'skipped'
+(nothing new to merge counts as skipped), the 'mergedObservations' and the 'batchId'."
+    )]
+    async fn merge_graph(
+        &self,
+        Parameters(args): Parameters<MergeGraphArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let graph = KnowledgeGraph {
+            entities: args.entities,
+            relations: args.relations,
+        };
+        let result = self
+            .manager
+            .merge_graph(graph, args.conflict_policy)
+            .await
+            .map_err(internal_err("Failed to merge graph"))?;
+
+        let summary = format!(
+            "Merged graph: {} entities created, {} updated, {} skipped; {} relations created",
+            result.entities.len(),
+            result.updated.len(),
+            result.skipped.len(),
+            result.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Export the neighborhood of an entity to a file
+    #[tool(
+        name = "export_subgraph",
+        description = "Extract the n-hop neighborhood around an entity and write it to a file.
+
+Input schema:
+{
+  \"root\": \"entity-name\",
+  \"depth\": 2,
+  \"destination\": \"relative/file.json\",
+  \"format\": \"json\" | \"dot\" | \"ndjson\",
+  \"clusterByType\": false
+}
+
+'depth' (0-10, default 1) counts hops, following relations in both directions. 'format' is
+optional (default \"json\", same shape as read_graph; \"dot\" writes a Graphviz digraph,
+\"ndjson\" one entity or relation per line). 'destination' is relative to the server's export
+directory; it must not contain '..' and must end in .json, .dot or .ndjson to match the format.
+Existing files are overwritten. With \"dot\", \"clusterByType\": true groups the entities of each
+type into a labeled, consistently colored cluster.
+
+Example - snapshot everything within two hops of Project_Alpha:
+{
+  \"root\": \"Project_Alpha\",
+  \"depth\": 2,
+  \"destination\": \"project_alpha.dot\",
+  \"format\": \"dot\"
+}
+
+Returns the written path and how many entities and relations were exported."
+    )]
+    async fn export_subgraph(
+        &self,
+        Parameters(args): Parameters<ExportSubgraphArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let graph = self
+            .manager
+            .get_subgraph(args.root, args.depth)
+            .await
+            .map_err(internal_err("Failed to extract subgraph"))?;
+
+        let dot = dot_options(args.format, args.cluster_by_type)?;
+        let contents = export::render_with(&graph, args.format, dot)
+            .map_err(internal_err("Failed to render subgraph"))?;
+        let path = export::resolve_export_path(&self.export_dir, &args.destination, args.format)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        tokio::fs::write(&path, contents)
+            .await
+            .with_context(|| format!("Failed to write '{}'", path.display()))
+            .map_err(internal_err("Failed to write export file"))?;
+
+        let summary = format!(
+            "Exported {} entities and {} relations to {}",
+            graph.entities.len(),
+            graph.relations.len(),
+            path.display()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "path": path,
+                "format": args.format,
+                "entities": graph.entities.len(),
+                "relations": graph.relations.len(),
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Render the graph or a neighborhood as a Mermaid diagram
+    #[tool(
+        name = "export_mermaid",
+        description = "Render the knowledge graph, or the neighborhood of one entity, as a Mermaid flowchart that chat UIs and markdown viewers draw inline.
+
+Input schema:
+{
+  \"root\": \"entity-name\",
+  \"depth\": 1
+}
+
+Both fields are optional. Without 'root' the whole graph is drawn (keep that for small
+graphs). With 'root', only entities within 'depth' hops (0-10, default 1, following relations
+in both directions) are included, like export_subgraph.
+
+Example - draw Project_Alpha and everything within two hops:
+{
+  \"root\": \"Project_Alpha\",
+  \"depth\": 2
+}
+
+Returns the diagram as a ```mermaid block in the text content, and as { \"mermaid\": \"flowchart LR ...\",
+\"entities\": 3, \"relations\": 2 }. Nodes are labeled \"name (type)\" and arrows with the relation
+type; node ids are derived from the names and only meant for Mermaid."
+    )]
+    async fn export_mermaid(
+        &self,
+        Parameters(args): Parameters<ExportMermaidArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let graph = match args.root {
+            Some(root) => self.manager.get_subgraph(root, args.depth).await,
+            None => self.manager.read_graph_sorted().await,
+        }
+        .map_err(internal_err("Failed to read graph"))?;
+
+        let mermaid = export::to_mermaid(&graph);
+        let text = format!("```mermaid\n{}```", mermaid);
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&text)],
+            structured_content: Some(versioned(json!({
+                "mermaid": mermaid,
+                "entities": graph.entities.len(),
+                "relations": graph.relations.len(),
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Export the entities matching a search to a file
+    #[tool(
+        name = "export_search_results",
+        description = "Run a full-text search and write the matching entities, with the relations between them, to a file.
+
+Input schema:
+{
+  \"query\": \"search terms\",
+  \"matchMode\": \"all\" | \"any\",
+  \"destination\": \"relative/file.json\",
+  \"format\": \"json\" | \"dot\" | \"ndjson\",
+  \"clusterByType\": false
+}
+
+The search works like search_nodes (\"matchMode\" defaults to \"all\": every term must match), but
+'query' may not be empty. 'format' is optional (default \"json\", same shape as read_graph; \"dot\"
+writes a Graphviz digraph, \"ndjson\" one entity or relation per line). 'destination' is relative
+to the server's export directory; it must not contain '..' and must end in .json, .dot or .ndjson
+to match the format. Existing files are overwritten. With \"dot\", \"clusterByType\": true groups
+the entities of each type into a labeled, consistently colored cluster.
+
+Example - everything about Project_Alpha as a shareable file:
+{
+  \"query\": \"Project_Alpha\",
+  \"destination\": \"project_alpha.json\"
+}
+
+Returns the written path and how many entities and relations were exported."
+    )]
+    async fn export_search_results(
+        &self,
+        Parameters(args): Parameters<ExportSearchResultsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if args.query.trim().is_empty() {
+            return Err(McpError::invalid_params(
+                "Search query cannot be empty; use a query that selects what to export",
+                None,
+            ));
+        }
+        let graph = self
+            .manager
+            .search_nodes_matching(Some(args.query.clone()), args.match_mode)
+            .await
+            .map_err(internal_err("Failed to search nodes"))?;
+
+        let dot = dot_options(args.format, args.cluster_by_type)?;
+        let contents = export::render_with(&graph, args.format, dot)
+            .map_err(internal_err("Failed to render search results"))?;
+        let path = export::resolve_export_path(&self.export_dir, &args.destination, args.format)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        tokio::fs::write(&path, contents)
+            .await
+            .with_context(|| format!("Failed to write '{}'", path.display()))
+            .map_err(internal_err("Failed to write export file"))?;
+
+        let summary = format!(
+            "Exported {} entities and {} relations matching '{}' to {}",
+            graph.entities.len(),
+            graph.relations.len(),
+            args.query,
+            path.display()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "path": path,
+                "format": args.format,
+                "query": args.query,
+                "entities": graph.entities.len(),
+                "relations": graph.relations.len(),
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Export the whole graph as NDJSON
+    #[tool(
+        name = "export_ndjson",
+        description = "Write the whole knowledge graph to a file as newline-delimited JSON, for graphs too large for read_graph.
+
+Input schema:
+{
+  \"destination\": \"relative/file.ndjson\"
+}
+
+Each line is one record: entities first (by name), then relations:
+{\"type\": \"entity\", \"name\": \"Alice\", \"entityType\": \"person\", \"observations\": [...]}
+{\"type\": \"relation\", \"from\": \"Alice\", \"to\": \"Acme\", \"relationType\": \"works_at\"}
+
+Records are written one at a time as they are read, so the graph is never held in memory.
+The file can be loaded back with --seed. 'destination' is relative to the server's export
+directory; it must not contain '..' and must end in .ndjson. Existing files are overwritten.
+
+Returns the written path and how many entities and relations were exported."
+    )]
+    async fn export_ndjson(
+        &self,
+        Parameters(args): Parameters<ExportNdjsonArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let path =
+            export::resolve_export_path(&self.export_dir, &args.destination, ExportFormat::Ndjson)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let (entities, relations) = self
+            .manager
+            .export_ndjson(path.clone())
+            .await
+            .map_err(internal_err("Failed to export graph"))?;
+
+        let summary = format!(
+            "Exported {} entities and {} relations to {}",
+            entities,
+            relations,
+            path.display()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "path": path,
+                "format": ExportFormat::Ndjson,
+                "entities": entities,
+                "relations": relations,
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Save the database as a named checkpoint
+    #[tool(
+        name = "create_checkpoint",
+        description = "Save the current state of the whole knowledge graph under a name, to return to later with restore_checkpoint.
+
+Input schema:
+{
+  \"name\": \"checkpoint-name\",
+  \"overwrite\": false
+}
+
+'name' may use letters, digits, '-', '_' and '.' (max 64 characters, not starting with '.').
+Creating a checkpoint that already exists fails unless 'overwrite' is true.
+
+Example - save a state before an experiment:
+{
+  \"name\": \"before-reorg\"
+}
+
+Returns {\"name\", \"createdAt\", \"size\"} of the saved checkpoint. The copy is taken with SQLite's
+online backup, so other sessions keep reading and writing meanwhile."
+    )]
+    async fn create_checkpoint(
+        &self,
+        Parameters(args): Parameters<CreateCheckpointArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        checkpoint::validate_checkpoint_name(&args.name)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let info = self
+            .manager
+            .create_checkpoint(self.checkpoint_dir.to_path_buf(), args.name, args.overwrite)
+            .await
+            .map_err(internal_err("Failed to create checkpoint"))?;
+
+        let summary = format!("Checkpoint '{}' saved ({} bytes)", info.name, info.size);
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(info))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// List saved checkpoints
+    #[tool(
+        name = "list_checkpoints",
+        annotations(read_only_hint = true),
+        description = "List the saved checkpoints, oldest first.
+
+Returns:
+{
+  \"checkpoints\": [
+    {\"name\": \"before-reorg\", \"createdAt\": \"2024-05-01T12:34:56.789Z\", \"size\": 98304}
+  ]
+}
+
+'size' is in bytes."
+    )]
+    async fn list_checkpoints(&self) -> Result<CallToolResult, McpError> {
+        let checkpoints = self
+            .manager
+            .list_checkpoints(self.checkpoint_dir.to_path_buf())
+            .await
+            .map_err(internal_err("Failed to list checkpoints"))?;
+
+        let summary = if checkpoints.is_empty() {
+            "No checkpoints saved".to_string()
+        } else {
+            format!(
+                "{} checkpoints:\n{}",
+                checkpoints.len(),
+                checkpoints
+                    .iter()
+                    .map(|c| format!("- {} ({}, {} bytes)", c.name, c.created_at, c.size))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        };
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({"checkpoints": checkpoints}))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Replace the graph with a saved checkpoint
+    #[tool(
+        name = "restore_checkpoint",
+        description = "Replace the WHOLE knowledge graph with a saved checkpoint. Every change made since the
+checkpoint was created is lost, for all sessions.
+
+Input schema:
+{
+  \"name\": \"checkpoint-name\",
+  \"confirm\": true
+}
+
+'confirm' must be true; without it the call is rejected and nothing changes. Consider
+create_checkpoint first to keep the current state.
+
+Example - undo an experiment:
+{
+  \"name\": \"before-reorg\",
+  \"confirm\": true
+}
+
+Returns the restored checkpoint name and the new graph revision."
+    )]
+    async fn restore_checkpoint(
+        &self,
+        Parameters(args): Parameters<RestoreCheckpointArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        checkpoint::validate_checkpoint_name(&args.name)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        if !args.confirm {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Restoring checkpoint '{}' replaces the whole graph; pass \"confirm\": true to proceed",
+                    args.name
+                ),
+                None,
+            ));
+        }
+        let revision = self
+            .manager
+            .restore_checkpoint(self.checkpoint_dir.to_path_buf(), args.name.clone())
+            .await
+            .map_err(internal_err("Failed to restore checkpoint"))?;
+
+        let summary = format!(
+            "Restored checkpoint '{}' (graph revision {})",
+            args.name, revision
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!({
+                "restored": args.name,
+                "revision": revision,
+            }))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Get direct neighbors of an entity
+    #[tool(
+        name = "get_neighbors",
+        annotations(read_only_hint = true),
+        description = "Get the entities directly connected to an entity, with the connecting relations.
+
+Input schema:
+{
+  \"name\": \"entity-name\",
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\"
+}
+
+'direction' is optional (default \"both\"; \"out\"/\"in\" are accepted as shorthands).
+
+Example - who does John_Smith point to and who points to him:
+{
+  \"name\": \"John_Smith\",
+  \"direction\": \"both\"
+}
+
+Returns:
+{
+  \"center\": \"John_Smith\",
+  \"entities\": [{\"name\": \"TechCorp\", \"entityType\": \"...\", \"observations\": [\"...\"]}],
+  \"relations\": [
+    {\"from\": \"John_Smith\", \"to\": \"TechCorp\", \"relationType\": \"works_at\", \"direction\": \"outgoing\"},
+    {\"from\": \"Jane_Doe\", \"to\": \"John_Smith\", \"relationType\": \"manages\", \"direction\": \"incoming\"}
+  ]
+}
+
+Each relation carries 'direction' relative to the center, so there is no need to compare
+'from'/'to' against the center name. The center entity must exist."
+    )]
+    async fn get_neighbors(
+        &self,
+        Parameters(args): Parameters<GetNeighborsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .get_neighbors(args.name, args.direction)
+            .await
+            .map_err(internal_err("Failed to get neighbors"))?;
+
+        let summary = format!(
+            "Found {} neighbors of '{}' via {} relations",
+            result.entities.len(),
+            result.center,
+            result.relations.len()
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Relations of one entity grouped by direction and type
+    #[tool(
+        name = "relation_summary",
+        annotations(read_only_hint = true),
+        description = "Summarize how one entity is connected: its relations grouped by direction and relation type.
+
+Input schema:
+{
+  \"name\": \"entity-name\"
+}
+
+Example:
+{
+  \"name\": \"John_Smith\"
+}
+
+Returns:
+{
+  \"name\": \"John_Smith\",
+  \"outgoing\": {\"works_at\": [\"TechCorp\"], \"knows\": [\"Alice\", \"Bob\"]},
+  \"incoming\": {\"manages\": [\"Jane_Doe\"]}
+}
+
+'outgoing' maps each relation type to the entities John_Smith points to, 'incoming' to the
+entities pointing to it; names are sorted. A relation from the entity to itself is listed
+under 'outgoing' only. Only names are returned; use get_neighbors or open_nodes for the
+entities themselves. The entity must exist."
+    )]
+    async fn relation_summary(
+        &self,
+        Parameters(args): Parameters<RelationSummaryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .relation_summary(args.name)
+            .await
+            .map_err(internal_err("Failed to summarize relations"))?;
+
+        let count =
+            |groups: &BTreeMap<String, Vec<String>>| groups.values().map(Vec::len).sum::<usize>();
+        let summary = format!(
+            "'{}' has {} outgoing and {} incoming relations",
+            result.name,
+            count(&result.outgoing),
+            count(&result.incoming)
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// An entity as a flat, prompt-ready fact list
+    #[tool(
+        name = "get_facts",
+        annotations(read_only_hint = true),
+        description = "Get one entity as a flat list of facts, ready to paste into a prompt: its observations followed by one \"<from> <relationType> <to>\" line per relation.
+
+Input schema:
+{
+  \"name\": \"entity-name\",
+  \"includeRelations\": true
+}
+
+'includeRelations' is optional (default true); with false only the observations are listed.
+
+Example:
+{
+  \"name\": \"John_Smith\"
+}
+
+Returns:
+{
+  \"name\": \"John_Smith\",
+  \"entityType\": \"person\",
+  \"facts\": [\"Speaks fluent Spanish\", \"John_Smith works_at TechCorp\", \"Jane_Doe manages John_Smith\"]
+}
+
+Observations come first in stored order, then outgoing relations, then incoming ones, each
+sorted by relation type. 'description' is included when the entity has one. The entity
+must exist."
+    )]
+    async fn get_facts(
+        &self,
+        Parameters(args): Parameters<GetFactsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .get_facts(args.name, args.include_relations)
+            .await
+            .map_err(internal_err("Failed to get facts"))?;
+
+        let mut text = result.facts.join("\n");
+        if text.is_empty() {
+            text = format!("No facts known about '{}'", result.name);
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&text)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Observations of an entity with the time each was added
+    #[tool(
+        name = "get_observation_times",
+        annotations(read_only_hint = true),
+        description = "Get the observations of an entity with the time each one was added, e.g. to find the most recent fact about it.
+
+Input schema:
+{
+  \"name\": \"entity-name\",
+  \"newestFirst\": false
+}
+
+'newestFirst' is optional (default false: stored order); with true the most recently added
+observations come first.
+
+Example:
+{
+  \"name\": \"John_Smith\",
+  \"newestFirst\": true
+}
+
+Returns:
+{
+  \"entityName\": \"John_Smith\",
+  \"observations\": [
+    {\"text\": \"Moved to Berlin\", \"addedAt\": \"2025-03-02T10:15:00.123Z\"},
+    {\"text\": \"Speaks fluent Spanish\", \"addedAt\": \"2025-01-15T08:00:00.000Z\"}
+  ]
+}
+
+Times are recorded whenever an observation is stored, by any tool (create, add, update, merge,
+import); reordering keeps them. 'addedAt' is null for observations stored before the server
+recorded times (those sort last with newestFirst). The entity must exist."
+    )]
+    async fn get_observation_times(
+        &self,
+        Parameters(args): Parameters<GetObservationTimesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .observation_times(args.name, args.newest_first)
+            .await
+            .map_err(internal_err("Failed to get observation times"))?;
+
+        let summary = format!(
+            "{} observations of '{}'",
+            result.observations.len(),
+            result.entity_name
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Densely connected group around a seed entity
+    #[tool(
+        name = "community",
+        annotations(read_only_hint = true),
+        description = "Find the tightly knit group of entities around a seed entity, e.g. to pull out one topic from a dense graph.
+
+Input schema:
+{
+  \"seed\": \"entity-name\",
+  \"maxSize\": 10,
+  \"minDensity\": 0.3
+}
+
+Starting from the seed, the entity linked to the most members so far joins next (ties by
+name), until the community has 'maxSize' members (1-200, default 10) or the next entity
+would bring its density below 'minDensity' (0.0-1.0, default 0.3). Density is the share of
+member pairs that are linked; relation direction and type are ignored. Unlike a fixed-depth
+neighborhood, loosely attached entities stay out.
+
+Example:
+{
+  \"seed\": \"Rust\",
+  \"maxSize\": 5
+}
+
+Returns:
+{
+  \"seed\": \"Rust\",
+  \"entities\": [...],
+  \"relations\": [...],
+  \"density\": 0.8
+}
+
+'entities' are in joining order (the seed first); 'relations' are all relations between
+members. The seed must exist."
+    )]
+    async fn community(
+        &self,
+        Parameters(args): Parameters<CommunityArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .community(args.seed, args.max_size, args.min_density)
+            .await
+            .map_err(internal_err("Failed to find community"))?;
+
+        let names: Vec<&str> = result.entities.iter().map(|e| e.name.as_str()).collect();
+        let summary = format!(
+            "Community of '{}' ({} entities, density {:.2}): {}",
+            result.seed,
+            names.len(),
+            result.density,
+            names.join(", ")
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Friends of friends of an entity
+    #[tool(
+        name = "second_degree",
+        annotations(read_only_hint = true),
+        description = "Find entities exactly two hops from an entity that it is not directly connected to (\"friends of friends\").
+
+Input schema:
+{
+  \"name\": \"entity-name\",
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\"
+}
+
+'direction' is optional (default \"both\"; \"out\"/\"in\" are accepted as shorthands) and applies
+to both hops: with \"outgoing\", the entities that the entities Alice points to point to.
+Direct neighbors and the entity itself are never returned, so the result is a list of
+candidates Alice is related to only indirectly, e.g. for recommendations.
+
+Example:
+{
+  \"name\": \"Alice\"
+}
+
+Returns:
+{
+  \"center\": \"Alice\",
+  \"entities\": [{\"name\": \"Dave\", \"entityType\": \"...\", \"observations\": [\"...\"]}],
+  \"via\": {\"Dave\": [\"Bob\", \"Carol\"]}
+}
+
+'via' lists, for every returned entity, the direct neighbors it is reached through. The
+entity must exist."
+    )]
+    async fn second_degree(
+        &self,
+        Parameters(args): Parameters<GetNeighborsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .second_degree(args.name, args.direction)
+            .await
+            .map_err(internal_err("Failed to find second-degree neighbors"))?;
+
+        let summary = format!(
+            "Found {} second-degree neighbors of '{}'",
+            result.entities.len(),
+            result.center
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// How strongly two entities are linked
+    #[tool(
+        name = "relation_count_between",
+        annotations(read_only_hint = true),
+        description = "Count the relations between two entities and list their types, e.g. to judge whether two entities are near-duplicates (many links) or only loosely connected.
+
+Input schema:
+{
+  \"a\": \"first-entity-name\",
+  \"b\": \"second-entity-name\",
+  \"directed\": false
+}
+
+'directed' is optional (default false): with true only relations from a to b are counted,
+otherwise relations in either direction.
+
+Example:
+{
+  \"a\": \"Alice\",
+  \"b\": \"Bob\"
+}
+
+Returns:
+{
+  \"a\": \"Alice\",
+  \"b\": \"Bob\",
+  \"directed\": false,
+  \"count\": 3,
+  \"relationTypes\": [\"knows\", \"works_with\"]
+}
+
+'relationTypes' lists each type once, sorted. Both entities must exist."
+    )]
+    async fn relation_count_between(
+        &self,
+        Parameters(args): Parameters<RelationCountBetweenArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .relation_count_between(args.a, args.b, args.directed)
+            .await
+            .map_err(internal_err("Failed to count relations"))?;
+
+        let summary = if result.directed {
+            format!(
+                "{} relations from '{}' to '{}'",
+                result.count, result.a, result.b
+            )
+        } else {
+            format!(
+                "{} relations between '{}' and '{}'",
+                result.count, result.a, result.b
+            )
+        };
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Entities connected to both of two entities
+    #[tool(
+        name = "common_neighbors",
+        annotations(read_only_hint = true),
+        description = "Find what two entities have in common: the entities connected to both of them.
+
+Input schema:
+{
+  \"a\": \"first-entity-name\",
+  \"b\": \"second-entity-name\",
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\"
+}
+
+'direction' is optional (default \"both\"; \"out\"/\"in\" are accepted as shorthands):
+\"outgoing\" finds entities both a and b point to, \"incoming\" entities pointing to both.
+
+Example - what do Alice and Bob share:
+{
+  \"a\": \"Alice\",
+  \"b\": \"Bob\"
+}
+
+Returns:
+{
+  \"a\": \"Alice\",
+  \"b\": \"Bob\",
+  \"entities\": [{\"name\": \"TechCorp\", \"entityType\": \"organization\", \"observations\": [\"...\"]}],
+  \"relations\": [
+    {\"from\": \"Alice\", \"to\": \"TechCorp\", \"relationType\": \"works_at\"},
+    {\"from\": \"Bob\", \"to\": \"TechCorp\", \"relationType\": \"works_at\"}
+  ]
+}
+
+'relations' link a or b to a shared neighbor. Both entities must exist and be different."
+    )]
+    async fn common_neighbors(
+        &self,
+        Parameters(args): Parameters<CommonNeighborsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .common_neighbors(args.a, args.b, args.direction)
+            .await
+            .map_err(internal_err("Failed to find common neighbors"))?;
+
+        let summary = format!(
+            "Found {} common neighbors of '{}' and '{}'",
+            result.entities.len(),
+            result.a,
+            result.b
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Breadth-first traversal from a root entity
+    #[tool(
+        name = "traverse",
+        annotations(read_only_hint = true),
+        description = "Walk the graph breadth-first from a root entity and return entities in visit order with their distance.
+
+Input schema:
+{
+  \"root\": \"entity-name\",
+  \"depth\": 2,
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\"
+}
+
+'depth' (0-10, default 1) is the maximum number of hops. 'direction' is optional (default
+\"both\"; \"out\"/\"in\" are accepted as shorthands).
+
+Example - everything Project_Alpha depends on, up to three levels deep:
+{
+  \"root\": \"Project_Alpha\",
+  \"depth\": 3,
+  \"direction\": \"outgoing\"
+}
+
+Returns:
+{
+  \"root\": \"Project_Alpha\",
+  \"entities\": [
+    {\"name\": \"Project_Alpha\", \"entityType\": \"...\", \"observations\": [...], \"distance\": 0},
+    {\"name\": \"Library_X\", \"entityType\": \"...\", \"observations\": [...], \"distance\": 1, \"parent\": \"Project_Alpha\"}
+  ]
+}
+
+Each entity appears once, at its shortest distance, with the entity it was first reached
+from as 'parent' (a spanning tree). Within a level, entities are ordered by their parent's
+position, then by name. Unlike export_subgraph, relations are not returned."
+    )]
+    async fn traverse(
+        &self,
+        Parameters(args): Parameters<TraverseArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .traverse(args.root, args.depth, args.direction)
+            .await
+            .map_err(internal_err("Failed to traverse graph"))?;
+
+        let max_distance = result
+            .entities
+            .iter()
+            .map(|n| n.distance)
+            .max()
+            .unwrap_or(0);
+        let summary = format!(
+            "Visited {} entities from '{}' ({} levels deep)",
+            result.entities.len(),
+            result.root,
+            max_distance
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+
+    /// Shortest typed path between two entities
+    #[tool(
+        name = "find_path",
+        annotations(read_only_hint = true),
+        description = "Find how two entities are connected: the shortest chain of relations between them.
+
+Input schema:
+{
+  \"from\": \"entity-name\",
+  \"to\": \"entity-name\",
+  \"maxDepth\": 6,
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\"
+}
+
+'maxDepth' (0-10, default 6) limits the number of hops. 'direction' is optional (default
+\"both\": relations are followed either way; \"outgoing\" only follows from -> to).
+
+Example - how is Alice connected to Acme:
+{
+  \"from\": \"Alice\",
+  \"to\": \"Acme\"
+}
+
+Returns:
+{
+  \"from\": \"Alice\",
+  \"to\": \"Acme\",
+  \"paths\": [{
+    \"entities\": [\"Alice\", \"BetaCorp\", \"Acme\"],
+    \"relations\": [
+      {\"from\": \"Alice\", \"to\": \"BetaCorp\", \"relationType\": \"works_at\"},
+      {\"from\": \"BetaCorp\", \"to\": \"Acme\", \"relationType\": \"subsidiary_of\"}
+    ]
+  }]
+}
+
+relations[i] connects entities[i] and entities[i + 1] and keeps its stored orientation. The
+text result spells the path out, e.g. \"Alice -works_at-> BetaCorp -subsidiary_of-> Acme\".
+'paths' is empty when there is no connection within maxDepth. Both entities must exist.
+Use all_paths to get several equally short alternatives."
+    )]
+    async fn find_path(
+        &self,
+        Parameters(args): Parameters<FindPathArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .find_paths(args.from, args.to, args.max_depth, 1, args.direction)
+            .await
+            .map_err(internal_err("Failed to find path"))?;
+
+        Ok(path_result(&result))
+    }
+
+    /// Several shortest typed paths between two entities
+    #[tool(
+        name = "all_paths",
+        annotations(read_only_hint = true),
+        description = "Find up to maxPaths distinct shortest paths between two entities.
+
+Input schema:
+{
+  \"from\": \"entity-name\",
+  \"to\": \"entity-name\",
+  \"maxPaths\": 5,
+  \"maxDepth\": 6,
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\"
+}
+
+'maxPaths' (1-20, default 5) bounds the number of paths. All returned paths have the same,
+minimal length; they differ in at least one relation (entities or relation types). Other
+fields and the result shape are the same as for find_path.
+
+Example - every shortest way Alice reaches Acme:
+{
+  \"from\": \"Alice\",
+  \"to\": \"Acme\",
+  \"maxPaths\": 10
+}"
+    )]
+    async fn all_paths(
+        &self,
+        Parameters(args): Parameters<AllPathsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .find_paths(
+                args.from,
+                args.to,
+                args.max_depth,
+                args.max_paths,
+                args.direction,
+            )
+            .await
+            .map_err(internal_err("Failed to find paths"))?;
+
+        Ok(path_result(&result))
+    }
+
+    /// Longest acyclic relation chain from an entity
+    #[tool(
+        name = "longest_chain",
+        annotations(read_only_hint = true),
+        description = "Find the longest chain of relations starting at an entity, without visiting any
+entity twice: how deep a dependency, ancestry or reporting line goes.
+
+Input schema:
+{
+  \"name\": \"entity-name\",
+  \"relationType\": \"depends_on\",
+  \"direction\": \"outgoing\" | \"incoming\" | \"both\",
+  \"maxDepth\": 20
+}
+
+'relationType' is optional (default: relations of any type). 'direction' defaults to
+\"outgoing\" (follow from -> to); \"incoming\" walks the chain backwards. 'maxDepth' (1-50,
+default 20) caps the number of hops.
+
+Example - deepest dependency of a crate:
+{
+  \"name\": \"my_app\",
+  \"relationType\": \"depends_on\"
+}
+
+Returns:
+{
+  \"start\": \"my_app\",
+  \"chain\": {
+    \"entities\": [\"my_app\", \"http\", \"bytes\"],
+    \"relations\": [
+      {\"from\": \"my_app\", \"to\": \"http\", \"relationType\": \"depends_on\"},
+      {\"from\": \"http\", \"to\": \"bytes\", \"relationType\": \"depends_on\"}
+    ]
+  },
+  \"exhaustive\": true,
+  \"cyclic\": false
+}
+
+The chain has the same shape as a find_path path. Among equally long chains the first in
+name order is returned. Longest chains are expensive to find in large graphs, so the search
+stops at maxDepth or after 100000 followed relations; \"exhaustive\": false means it was cut
+short and a longer chain may exist. \"cyclic\": true means some relation led back into the
+chain (the relations do not form a DAG); such relations are skipped."
+    )]
+    async fn longest_chain(
+        &self,
+        Parameters(args): Parameters<LongestChainArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .manager
+            .longest_chain(
+                args.name,
+                args.relation_type,
+                args.direction,
+                args.max_depth,
+            )
+            .await
+            .map_err(internal_err("Failed to find longest chain"))?;
+
+        let mut summary = format!(
+            "Longest chain from '{}' ({} hops): {}",
+            result.start,
+            result.chain.relations.len(),
+            result.chain.describe()
+        );
+        if !result.exhaustive {
+            summary.push_str("\nSearch was cut short; a longer chain may exist");
+        }
+        if result.cyclic {
+            summary.push_str("\nCycle detected: some relations lead back into the chain");
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(&summary)],
+            structured_content: Some(versioned(json!(result))),
+            is_error: Some(false),
+            meta: None,
+        })
+    }
+}
+
+impl ServerHandler for MemoryServer {
+    fn get_info(&self) -> ServerInfo {
+        self.server_info()
+    }
+
+    /// Dispatch to the tool router, rejecting unknown argument fields (--strict-args),
+    /// capping read results (--max-response-entities) and recording the call (--tool-audit-file)
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let cap = self
+            .max_response_entities
+            .filter(|_| self.is_capped(&request.name, request.arguments.as_ref()));
+        let tool = request.name.clone();
+        let arguments = self
+            .audit
+            .is_some()
+            .then(|| request.arguments.clone())
+            .flatten();
+        let started = Instant::now();
+        let checked = self
+            .check_strict_args(&request)
+            .and_then(|()| self.check_transaction_write(&request.name));
+        let result = match (checked, cap) {
+            (Ok(()), Some(cap)) => self.call_capped(request, context, cap, 0).await,
+            (Ok(()), None) => {
+                self.tool_router
+                    .call(ToolCallContext::new(self, request, context))
+                    .await
+            }
+            (Err(e), _) => Err(e),
+        };
+        let Some(audit) = &self.audit else {
+            return result;
+        };
+
+        let error = match &result {
+            Ok(result) if result.is_error == Some(true) => Some(
+                result
+                    .content
+                    .iter()
+                    .find_map(|content| content.as_text())
+                    .map(|text| text.text.clone())
+                    .unwrap_or_else(|| "Tool reported an error".to_string()),
+            ),
+            Ok(_) => None,
+            Err(e) => Some(e.message.to_string()),
+        };
+        // Never on stdout/stderr: in stdio mode both belong to the MCP client
+        if let Err(e) = audit.record(
+            &tool,
+            arguments.as_ref(),
+            error.as_deref(),
+            started.elapsed(),
+        ) {
+            tracing::warn!("{:#}", e);
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut graph = RawResource::new(GRAPH_RESOURCE_URI, "graph");
+        graph.description = Some("The whole knowledge graph (entities and relations)".to_string());
+        graph.mime_type = Some(JSON_MIME_TYPE.to_string());
+        Ok(ListResourcesResult::with_all_items(vec![
+            graph.no_annotation()
+        ]))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        let entity = RawResourceTemplate {
+            uri_template: format!("{}{{name}}", ENTITY_RESOURCE_PREFIX),
+            name: "entity".to_string(),
+            title: None,
+            description: Some("A single entity by name (percent-encoded)".to_string()),
+            mime_type: Some(JSON_MIME_TYPE.to_string()),
+        };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            entity.no_annotation()
+        ]))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let uri = request.uri;
+        let body = if uri == GRAPH_RESOURCE_URI {
+            let graph = self
+                .manager
+                .read_graph_sorted()
+                .await
+                .map_err(internal_err("Failed to read graph"))?;
+            json!(graph)
+        } else if let Some(encoded) = uri.strip_prefix(ENTITY_RESOURCE_PREFIX) {
+            let name = percent_decode(encoded).ok_or_else(|| {
+                McpError::invalid_params(format!("Malformed resource URI '{}'", uri), None)
+            })?;
+            let graph = self
+                .manager
+                .open_nodes(vec![name.clone()])
+                .await
+                .map_err(internal_err("Failed to read entity"))?;
+            match graph.entities.into_iter().next() {
+                Some(entity) => json!(entity),
+                None => {
+                    return Err(McpError::resource_not_found(
+                        format!("Entity '{}' not found", name),
+                        Some(json!({ "uri": uri })),
+                    ))
+                }
+            }
+        } else {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource '{}'", uri),
+                Some(json!({ "uri": uri })),
+            ));
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri,
+                mime_type: Some(JSON_MIME_TYPE.to_string()),
+                text: canonical(body).to_string(),
+                meta: None,
+            }],
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult::with_all_items(vec![
+            Prompt::new(
+                SUMMARIZE_ENTITY_PROMPT,
+                Some("Summarize everything the graph knows about an entity"),
+                Some(vec![prompt_argument("name", "Name of the entity")]),
+            ),
+            Prompt::new(
+                EXTRACT_KNOWLEDGE_PROMPT,
+                Some("Extract entities, relations and observations from text into the graph"),
+                Some(vec![prompt_argument(
+                    "text",
+                    "Text to extract knowledge from",
+                )]),
+            ),
+        ]))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let arguments = request.arguments.unwrap_or_default();
+        let (description, text) = match request.name.as_str() {
+            SUMMARIZE_ENTITY_PROMPT => {
+                let name = required_prompt_argument(&arguments, "name")?;
+                (
+                    format!("Summarize what is known about '{}'", name),
+                    format!(
+                        "Summarize everything the knowledge graph knows about '{name}'.\n\n\
+                         1. Call `open_nodes` with names [\"{name}\"] to load the entity and its observations.\n\
+                         2. Call `get_neighbors` for '{name}' to see how it relates to other entities.\n\
+                         3. Write a short summary: what '{name}' is, the key facts from its observations, \
+                         and its most important relations.\n\n\
+                         If the entity does not exist, say so instead of guessing."
+                    ),
+                )
+            }
+            EXTRACT_KNOWLEDGE_PROMPT => {
+                let text = required_prompt_argument(&arguments, "text")?;
+                (
+                    "Extract entities and relations from text".to_string(),
+                    format!(
+                        "Extract knowledge from the text below and store it in the knowledge graph.\n\n\
+                         1. Call `search_nodes` for each candidate entity first and reuse existing names.\n\
+                         2. Create new entities with `create_entities` (short `entityType` such as person, \
+                         organization, concept).\n\
+                         3. Record facts about existing entities with `add_observations`, one atomic fact each.\n\
+                         4. Connect entities with `create_relations`, using relation types in active voice \
+                         (works_at, knows, depends_on).\n\n\
+                         Text:\n\n{text}"
+                    ),
+                )
+            }
+            other => {
+                return Err(McpError::invalid_params(
+                    format!("Unknown prompt '{}'", other),
+                    None,
+                ))
+            }
+        };
+
+        Ok(GetPromptResult {
+            description: Some(description),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+}
+
+// MCP prompts: ready-made instructions for using the memory tools well
+const SUMMARIZE_ENTITY_PROMPT: &str = "summarize_entity";
+const EXTRACT_KNOWLEDGE_PROMPT: &str = "extract_knowledge";
+
+fn prompt_argument(name: &str, description: &str) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        required: Some(true),
+    }
+}
+
+/// Non-empty string argument of a prompt, or invalid_params naming it
+fn required_prompt_argument<'a>(
+    arguments: &'a JsonObject,
+    name: &str,
+) -> Result<&'a str, McpError> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| {
+            McpError::invalid_params(format!("Missing required prompt argument '{}'", name), None)
+        })
+}
+
+// MCP resources: the graph as a whole and single entities by name
+const GRAPH_RESOURCE_URI: &str = "memory://graph";
+const ENTITY_RESOURCE_PREFIX: &str = "memory://entity/";
+const JSON_MIME_TYPE: &str = "application/json";
+
+/// Decode %XX escapes in a resource URI segment (None on bad escapes or invalid UTF-8)
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+// Tool argument schemas
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CreateEntitiesArgs {
+    entities: Vec<Entity>,
+    #[serde(default = "default_true")]
+    verbose: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CreateRelationsArgs {
+    relations: Vec<Relation>,
+    #[serde(default, rename = "includeEndpoints")]
+    include_endpoints: bool,
+    #[serde(default = "default_true")]
+    verbose: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ValidateEntitiesArgs {
+    entities: Vec<Entity>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ValidateRelationsArgs {
+    relations: Vec<Relation>,
+    #[serde(default, rename = "pendingEntities")]
+    pending_entities: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CreateStarRelationsArgs {
+    center: String,
+    others: Vec<String>,
+    #[serde(rename = "relationType")]
+    relation_type: String,
+    direction: EdgeDirection,
+    #[serde(default = "default_true")]
+    verbose: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AddObservationsArgs {
+    observations: Vec<ObservationInput>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AddObservationToManyArgs {
+    entity_names: Vec<String>,
+    observation: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CopyObservationsArgs {
+    source: String,
+    target: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct HasObservationArgs {
+    #[serde(rename = "entityName")]
+    entity_name: String,
+    observation: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DeleteEntitiesArgs {
+    entity_names: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DeleteObservationsArgs {
+    deletions: Vec<ObservationDeletion>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReorderObservationsArgs {
+    reorders: Vec<ObservationReorder>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SetEntityDescriptionArgs {
+    #[serde(rename = "entityName")]
+    entity_name: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SetEntityConfidenceArgs {
+    #[serde(rename = "entityName")]
+    entity_name: String,
+    #[serde(default)]
+    confidence: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SetEntityMetadataArgs {
+    #[serde(rename = "entityName")]
+    entity_name: String,
+    #[serde(default)]
+    metadata: Option<Value>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AddAliasesArgs {
+    #[serde(rename = "entityName")]
+    entity_name: String,
+    aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RemoveAliasesArgs {
+    aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListAliasesArgs {
+    #[serde(rename = "entityName")]
+    entity_name: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReplaceEntityArgs {
+    entity: Entity,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DeleteRelationsArgs {
+    relations: Vec<Relation>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ChangeRelationTypeArgs {
+    from: String,
+    to: String,
+    #[serde(rename = "oldType")]
+    old_type: String,
+    #[serde(rename = "newType")]
+    new_type: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CreateCheckpointArgs {
+    name: String,
+    #[serde(default)]
+    overwrite: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RestoreCheckpointArgs {
+    name: String,
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReverseRelationArgs {
+    from: String,
+    to: String,
+    #[serde(rename = "relationType")]
+    relation_type: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReadGraphArgs {
+    #[serde(default)]
+    sorted: bool,
+    #[serde(default, rename = "maxObservationsReturned")]
+    max_observations_returned: Option<usize>,
+    #[serde(default, rename = "minConfidence")]
+    min_confidence: Option<f64>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct FindDuplicatesArgs {
+    /// Trigram similarity (0 < threshold <= 1) for fuzzy grouping; omit for exact matches only
+    #[serde(default)]
+    threshold: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SearchNodesArgs {
+    query: Option<String>,
+    #[serde(default, rename = "includeRelationMatches")]
+    include_relation_matches: bool,
+    #[serde(default, rename = "matchMode")]
+    match_mode: MatchMode,
+    #[serde(default, rename = "maxObservationsReturned")]
+    max_observations_returned: Option<usize>,
+    #[serde(default, rename = "minConfidence")]
+    min_confidence: Option<f64>,
+    #[serde(default)]
+    projection: SearchProjection,
+    #[serde(default)]
+    scored: bool,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SearchRelationsArgs {
+    query: String,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListEntitiesByTypeArgs {
+    #[serde(rename = "entityType")]
+    entity_type: String,
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListEntitiesByTypesArgs {
+    types: Vec<String>,
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListRelationsByTypeArgs {
+    #[serde(rename = "relationType")]
+    relation_type: String,
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default, rename = "includeEntities")]
+    include_entities: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RecentEntitiesArgs {
+    #[serde(default = "default_recent_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_recent_limit() -> usize {
+    20
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SampleEntitiesArgs {
+    #[serde(default = "default_sample_count")]
+    count: usize,
+    #[serde(default, rename = "entityType")]
+    entity_type: Option<String>,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_sample_count() -> usize {
+    10
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct FindEmptyEntitiesArgs {
+    #[serde(default, rename = "entityType")]
+    entity_type: Option<String>,
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    delete: bool,
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DeleteByQueryArgs {
+    query: String,
+    #[serde(default, rename = "matchMode")]
+    match_mode: MatchMode,
+    confirm: bool,
+    #[serde(default = "default_true", rename = "dryRun")]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct NextPageArgs {
+    cursor: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ObservationCountArgs {
+    #[serde(default)]
+    order: SortOrder,
+    #[serde(default = "default_recent_limit")]
+    limit: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListObservationsArgs {
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    contains: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct LeastAccessedArgs {
+    #[serde(default = "default_recent_limit")]
+    limit: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ComputeImportanceArgs {
+    #[serde(default = "default_importance_iterations")]
+    iterations: u32,
+}
+
+fn default_importance_iterations() -> u32 {
+    20
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TopImportantArgs {
+    #[serde(default = "default_recent_limit")]
+    limit: usize,
+}
+
+fn default_page_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct OpenNodesArgs {
+    names: Vec<String>,
+    #[serde(default, rename = "maxObservationsReturned")]
+    max_observations_returned: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct InspectNodesArgs {
+    names: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct OpenNodesByIdArgs {
+    ids: Vec<String>,
+    #[serde(default, rename = "maxObservationsReturned")]
+    max_observations_returned: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ImportForeignArgs {
+    format: ForeignFormat,
+    content: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MergeGraphArgs {
+    #[serde(default)]
+    entities: Vec<Entity>,
+    #[serde(default)]
+    relations: Vec<Relation>,
+    #[serde(default, rename = "conflictPolicy")]
+    conflict_policy: ConflictPolicy,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ExportSubgraphArgs {
+    root: String,
+    #[serde(default = "default_subgraph_depth")]
+    depth: u32,
+    destination: String,
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default, rename = "clusterByType")]
+    cluster_by_type: bool,
+}
+
+fn default_subgraph_depth() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ExportMermaidArgs {
+    root: Option<String>,
+    #[serde(default = "default_subgraph_depth")]
+    depth: u32,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MaterializeDanglingArgs {
+    #[serde(default = "default_placeholder_type", rename = "entityType")]
+    entity_type: String,
+}
+
+fn default_placeholder_type() -> String {
+    "placeholder".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CheckSearchIndexArgs {
+    #[serde(default)]
+    rebuild: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ExportNdjsonArgs {
+    destination: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ExportSearchResultsArgs {
+    query: String,
+    #[serde(default, rename = "matchMode")]
+    match_mode: MatchMode,
+    destination: String,
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default, rename = "clusterByType")]
+    cluster_by_type: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetNeighborsArgs {
+    name: String,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetBatchArgs {
+    #[serde(rename = "batchId")]
+    batch_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RelationSummaryArgs {
+    name: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetFactsArgs {
+    name: String,
+    #[serde(rename = "includeRelations", default = "default_true")]
+    include_relations: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CommunityArgs {
+    seed: String,
+    #[serde(rename = "maxSize", default = "default_community_size")]
+    max_size: usize,
+    #[serde(rename = "minDensity", default = "default_community_density")]
+    min_density: f64,
+}
+
+fn default_community_size() -> usize {
+    10
+}
+
+fn default_community_density() -> f64 {
+    0.3
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GetObservationTimesArgs {
+    name: String,
+    #[serde(rename = "newestFirst", default)]
+    newest_first: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RelationCountBetweenArgs {
+    a: String,
+    b: String,
+    #[serde(default)]
+    directed: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CommonNeighborsArgs {
+    a: String,
+    b: String,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TraverseArgs {
+    root: String,
+    #[serde(default = "default_subgraph_depth")]
+    depth: u32,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct FindPathArgs {
+    from: String,
+    to: String,
+    #[serde(default = "default_path_depth", rename = "maxDepth")]
+    max_depth: u32,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AllPathsArgs {
+    from: String,
+    to: String,
+    #[serde(default = "default_max_paths", rename = "maxPaths")]
+    max_paths: usize,
+    #[serde(default = "default_path_depth", rename = "maxDepth")]
+    max_depth: u32,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct LongestChainArgs {
+    name: String,
+    #[serde(default, rename = "relationType")]
+    relation_type: Option<String>,
+    #[serde(default = "default_chain_direction")]
+    direction: Direction,
+    #[serde(default = "default_chain_depth", rename = "maxDepth")]
+    max_depth: u32,
+}
+
+fn default_chain_direction() -> Direction {
+    Direction::Outgoing
+}
+
+fn default_chain_depth() -> u32 {
+    20
+}
+
+fn default_path_depth() -> u32 {
+    6
+}
+
+fn default_max_paths() -> usize {
+    5
+}
+
+/// Version of the structured content shape; bump whenever entity/relation output changes
+const SCHEMA_VERSION: u32 = 6;
+
+/// Wrap a structured tool payload in the versioned envelope `{schema_version, data}`
+fn versioned(data: Value) -> Value {
+    canonical(json!({ "schema_version": SCHEMA_VERSION, "data": data }))
+}
+
+/// JSON with the keys of every object in lexicographic (byte) order, whatever map type
+/// serde_json was built with (its preserve_order feature keeps insertion order instead), so
+/// equal content always serializes to equal text
+fn canonical(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> =
+                map.into_iter().map(|(k, v)| (k, canonical(v))).collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(entries.into_iter().collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical).collect()),
+        other => other,
+    }
+}
+
+/// DOT layout requested by an export tool; clustering only makes sense for DOT output
+fn dot_options(format: ExportFormat, cluster_by_type: bool) -> Result<DotOptions, McpError> {
+    if cluster_by_type && format != ExportFormat::Dot {
+        return Err(McpError::invalid_params(
+            "clusterByType requires the \"dot\" format",
+            None,
+        ));
+    }
+    Ok(DotOptions { cluster_by_type })
+}
+
+/// Error for commit/rollback (or a racing write) without an open transaction
+fn no_transaction() -> McpError {
+    McpError::invalid_params("No open transaction; call begin_transaction first", None)
+}
+
+/// Distinct entity names the relations connect, in first-seen order
+fn endpoint_names(relations: &[Relation]) -> Vec<String> {
+    let mut names = Vec::new();
+    for relation in relations {
+        for name in [&relation.from, &relation.to] {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Structured payload of a relation create: the relations, or (verbose: false) their count
+/// and the names they connect
+fn relations_result(created: &[Relation], verbose: bool) -> Value {
+    if verbose {
+        json!({"relations": created})
+    } else {
+        json!({"count": created.len(), "names": endpoint_names(created)})
+    }
+}
+
+/// Attach non-fatal warnings to a write result: listed in the text, and as "warnings"
+/// in the structured payload (omitted when there are none)
+/// Tool result for validate_entities/validate_relations: failures spelled out as text, every
+/// verdict as structured content
+fn check_result(kind: &str, checks: &[ItemCheck], warnings: Vec<String>) -> CallToolResult {
+    let invalid: Vec<&ItemCheck> = checks.iter().filter(|check| !check.valid).collect();
+    let mut summary = format!(
+        "{} of {} {} valid",
+        checks.len() - invalid.len(),
+        checks.len(),
+        kind
+    );
+    for check in &invalid {
+        summary.push_str(&format!(
+            "\n- #{} '{}': {}",
+            check.index,
+            check.name,
+            check.error.as_deref().unwrap_or_default()
+        ));
+    }
+    let mut structured = json!({
+        "valid": invalid.is_empty(),
+        "invalidCount": invalid.len(),
+        "items": checks,
+    });
+    add_warnings(&mut summary, &mut structured, warnings);
+
+    CallToolResult {
+        content: vec![Content::text(&summary)],
+        structured_content: Some(versioned(structured)),
+        is_error: Some(false),
+        meta: None,
+    }
+}
+
+fn add_warnings(summary: &mut String, structured: &mut Value, warnings: Vec<String>) {
+    if warnings.is_empty() {
+        return;
+    }
+    summary.push_str(&format!("\nWarnings ({}):", warnings.len()));
+    for warning in &warnings {
+        summary.push_str(&format!("\n- {}", warning));
+    }
+    structured["warnings"] = json!(warnings);
+}
+
+/// Tool result for find_path/all_paths: paths spelled out as text, full result as structured content
+fn path_result(result: &PathResult) -> CallToolResult {
+    let summary = match result.paths.first() {
+        None => format!("No path from '{}' to '{}'", result.from, result.to),
+        Some(first) => {
+            let lines: Vec<String> = result.paths.iter().map(|p| p.describe()).collect();
+            format!(
+                "Found {} shortest path(s) from '{}' to '{}' ({} hops):\n{}",
+                result.paths.len(),
+                result.from,
+                result.to,
+                first.relations.len(),
+                lines.join("\n")
+            )
+        }
+    };
+
+    CallToolResult {
+        content: vec![Content::text(&summary)],
+        structured_content: Some(versioned(json!(result))),
+        is_error: Some(false),
+        meta: None,
+    }
+}
+
+/// Keep only the first `max` observations of each entity in a payload with an "entities"
+/// array (or scored "results"), marking entities that lost observations with "truncated": true
+fn limit_observations(mut data: Value, max: Option<usize>) -> Value {
+    let Some(max) = max else {
+        return data;
+    };
+    // Scored search results wrap each entity as {"entity", "score"}
+    let scored = data.get("results").is_some();
+    let list = data
+        .get_mut(if scored { "results" } else { "entities" })
+        .and_then(Value::as_array_mut);
+    for item in list.into_iter().flatten() {
+        let entity = if scored {
+            match item.get_mut("entity") {
+                Some(entity) => entity,
+                None => continue,
+            }
+        } else {
+            item
+        };
+        let truncated = match entity.get_mut("observations").and_then(Value::as_array_mut) {
+            Some(observations) if observations.len() > max => {
+                observations.truncate(max);
+                true
+            }
+            _ => false,
+        };
+        if truncated {
+            entity["truncated"] = json!(true);
+        }
+    }
+    data
+}
+
+/// Keep the entities of a capped read result from `offset`, at most `cap` of them, with the
+/// relations touching them; a result cut short gets "hasMore" and a next_page cursor
+/// Scored search results ("results") are cut the same way; uncut results are left alone
+fn cap_response(
+    result: &mut CallToolResult,
+    tool: &str,
+    arguments: &JsonObject,
+    offset: usize,
+    cap: usize,
+) {
+    let Some(data) = result
+        .structured_content
+        .as_mut()
+        .and_then(|content| content.get_mut("data"))
+    else {
+        return;
+    };
+    let key = if data.get("results").is_some() {
+        "results"
+    } else {
+        "entities"
+    };
+    let Some(items) = data.get_mut(key).and_then(Value::as_array_mut) else {
+        return;
+    };
+    let total = items.len();
+    if offset == 0 && total <= cap {
+        return;
+    }
+    let page: Vec<Value> = items.drain(..).skip(offset).take(cap).collect();
+    let shown = page.len();
+    *items = page;
+    narrow_to_page(data, key);
+
+    let has_more = offset + cap < total;
+    data["hasMore"] = json!(has_more);
+    let mut note = format!(
+        "Showing entities {}-{} of {}",
+        offset + 1,
+        offset + shown,
+        total
+    );
+    if has_more {
+        let cursor = encode_cursor(tool, arguments, offset + cap);
+        data["cursor"] = json!(cursor);
+        note.push_str("; call next_page with the cursor for more");
+    }
+    if let Some(content) = result.structured_content.take() {
+        result.structured_content = Some(canonical(content));
+    }
+    result.content.push(Content::text(note));
+}
+
+/// Cut the `key` list of a paged read, fetched with one row extra, to `size` items (in every
+/// group of a grouped page) and narrow the rest to it; returns the most items shown and whether
+/// any were cut, or None when the result holds no such list
+fn trim_page(data: &mut Value, key: &str, size: usize) -> Option<(usize, bool)> {
+    if let Some(groups) = data.get_mut("groups").and_then(Value::as_array_mut) {
+        let (mut shown, mut cut) = (0, false);
+        for entities in groups
+            .iter_mut()
+            .filter_map(|group| group.get_mut("entities").and_then(Value::as_array_mut))
+        {
+            cut |= entities.len() > size;
+            entities.truncate(size);
+            shown = shown.max(entities.len());
+        }
+        return Some((shown, cut));
+    }
+    let items = data.get_mut(key)?.as_array_mut()?;
+    let cut = items.len() > size;
+    items.truncate(size);
+    let shown = items.len();
+    narrow_to_page(data, key);
+    Some((shown, cut))
+}
+
+/// Drop what no longer belongs to the `key` list of a cut result: relations (and "via" entries)
+/// not touching a listed entity, or for a page of relations the entities that are not one of
+/// their endpoints
+fn narrow_to_page(data: &mut Value, key: &str) {
+    let Some(items) = data.get(key).and_then(Value::as_array) else {
+        return;
+    };
+    if key == "relations" {
+        let endpoints: HashSet<String> = items
+            .iter()
+            .flat_map(|relation| ["from", "to"].map(|end| relation.get(end)))
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect();
+        if let Some(entities) = data.get_mut("entities").and_then(Value::as_array_mut) {
+            entities.retain(|entity| {
+                entity
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| endpoints.contains(name))
+            });
+        }
+        return;
+    }
+
+    let names: HashSet<String> = items
+        .iter()
+        .filter_map(|item| item.get("entity").unwrap_or(item).get("name"))
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+    let touches_page = |relation: &Value| {
+        ["from", "to"].iter().any(|end| {
+            relation
+                .get(*end)
+                .and_then(Value::as_str)
+                .is_some_and(|name| names.contains(name))
+        })
+    };
+    if let Some(relations) = data.get_mut("relations").and_then(Value::as_array_mut) {
+        relations.retain(touches_page);
+    }
+    if let Some(via) = data.get_mut("via").and_then(Value::as_object_mut) {
+        via.retain(|name, _| names.contains(name));
+    }
+}
+
+/// Opaque next_page cursor: the hex-encoded JSON of the call to repeat and where to resume
+fn encode_cursor(tool: &str, arguments: &JsonObject, offset: usize) -> String {
+    let call = json!({"tool": tool, "arguments": arguments, "offset": offset}).to_string();
+    call.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Tool, arguments and offset of a next_page cursor (None when malformed)
+fn decode_cursor(cursor: &str) -> Option<(String, JsonObject, usize)> {
+    if !cursor.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let call: Value = serde_json::from_slice(&bytes).ok()?;
+    Some((
+        call.get("tool")?.as_str()?.to_string(),
+        call.get("arguments")?.as_object()?.clone(),
+        usize::try_from(call.get("offset")?.as_u64()?).ok()?,
+    ))
+}
+
+/// Slice of a paged read: given once 'limit' or 'offset' is (a bare offset reads up to
+/// MAX_PAGE_SIZE rows), None for a read of everything
+fn read_window(
+    limit: Option<usize>,
+    offset: usize,
+    min_confidence: Option<f64>,
+) -> Option<ReadWindow> {
+    (limit.is_some() || offset > 0).then(|| ReadWindow {
+        limit: limit.unwrap_or(MAX_PAGE_SIZE),
+        offset,
+        min_confidence,
+    })
+}
+
+/// Reject a minConfidence filter outside 0.0-1.0
+fn check_min_confidence(min: Option<f64>) -> Result<(), McpError> {
+    match min {
+        Some(min) => validate_confidence(min).map_err(|err| {
+            McpError::invalid_params(format!("Invalid minConfidence: {}", err), None)
+        }),
+        None => Ok(()),
+    }
+}
+
+// JSON-RPC server error code (implementation-defined range) for "all connections busy"
+const SERVER_BUSY: ErrorCode = ErrorCode(-32000);
+// Retry hint sent along with SERVER_BUSY
+const BUSY_RETRY_AFTER_MS: u64 = 1000;
+
+// Helper for error conversion
+fn internal_err(msg: &'static str) -> impl Fn(anyhow::Error) -> McpError {
+    move |err| {
+        // Bad input in a batch: report every offending item so the client can fix them
+        if let Some(invalid) = validation_failures(&err) {
+            return McpError::invalid_params(
+                format!("{}: {}", msg, invalid),
+                Some(json!({ "error": err.to_string(), "failures": invalid.failures })),
+            );
+        }
+
+        // Out of pooled connections: a distinct, retryable error instead of an internal one
+        if is_pool_exhausted(&err) {
+            return McpError::new(
+                SERVER_BUSY,
+                "Server busy, try again shortly",
+                Some(json!({ "error": err.to_string(), "retryAfterMs": BUSY_RETRY_AFTER_MS })),
+            );
+        }
+
+        // Report interrupted statements as a timeout rather than the generic failure
+        let msg = if is_query_timeout(&err) {
+            "Query timed out"
+        } else {
+            msg
+        };
+        McpError::internal_error(msg, Some(json!({ "error": err.to_string() })))
+    }
+}
+
+/// Import the seed file into an empty database; databases with data are left alone
+async fn seed_database(
+    manager: &KnowledgeGraphManager,
+    seed: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !manager.is_empty().await? {
+        tracing::info!(
+            "Database already has data, skipping seed '{}'",
+            seed.display()
+        );
+        return Ok(());
+    }
+
+    let graph = import::load_graph_file(seed)?;
+    let created = manager
+        .import_graph(graph)
+        .await
+        .with_context(|| format!("Failed to import seed '{}'", seed.display()))?;
+    tracing::info!(
+        "Seeded database from '{}': {} entities, {} relations",
+        seed.display(),
+        created.entities.len(),
+        created.relations.len()
+    );
+    Ok(())
+}
+
+/// Run server in stdio mode (default)
+async fn run_stdio_mode(server: MemoryServer) -> Result<(), Box<dyn std::error::Error>> {
+    let transport = stdio();
+    let svc = server.serve(transport).await?;
+    svc.waiting().await?;
+    Ok(())
+}
+
+/// Run server in streamable HTTP mode
+async fn run_stream_mode(
+    server: MemoryServer,
+    bind: &str,
     port: u16,
+    compress: bool,
+    max_body_size: usize,
+    shutdown_timeout: Duration,
+    rate_limit: Option<u32>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
     use rmcp::transport::StreamableHttpService;
@@ -566,54 +6197,363 @@ async fn run_stream_mode(
     let addr = format!("{}:{}", bind, port);
     tracing::info!("Starting MCP HTTP server on http://{}/mcp", addr);
 
+    let manager = server.manager.clone();
+    let checkpoint_manager = manager.clone();
+
     // Create service with session management
     let service = StreamableHttpService::new(
-        move || Ok(server.clone()),
+        move || Ok(server.for_session()),
         LocalSessionManager::default().into(),
         Default::default(),
     );
 
-    // Build router with MCP endpoint and health check
-    let router = axum::Router::new()
-        .nest_service("/mcp", service)
-        .route("/health", axum::routing::get(|| async { "OK" }));
+    // Flips to true on SIGTERM/Ctrl-C; also ends open /events streams so the drain can finish
+    let (signal_tx, mut signal_rx) = tokio::sync::watch::channel(false);
+
+    // Build router with MCP endpoint, health check, conditional graph read and change events
+    let state = HttpState {
+        manager,
+        shutdown: signal_rx.clone(),
+    };
+    let mut router = axum::Router::new()
+        .route("/graph", axum::routing::get(http_read_graph))
+        .route("/events", axum::routing::get(http_events))
+        .route("/graph.ndjson", axum::routing::get(http_graph_ndjson))
+        .route("/search", axum::routing::get(http_search))
+        .with_state(state.clone())
+        .nest_service("/mcp", service);
+
+    // Optional per-client rate limit. Added before /health, so probes are never throttled
+    if let Some(per_second) = rate_limit {
+        tracing::info!(
+            "Rate limiting clients to {} requests per second",
+            per_second
+        );
+        router = router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(rate_limit::RateLimiter::new(per_second)),
+            rate_limit::limit_requests,
+        ));
+    }
+    router = router.merge(
+        axum::Router::new()
+            .route("/health", axum::routing::get(http_health))
+            .with_state(state),
+    );
+
+    // Optional response compression. The default predicate skips text/event-stream,
+    // so the streaming MCP (SSE) responses are never buffered by the encoder
+    if compress {
+        router = router.layer(tower_http::compression::CompressionLayer::new());
+    }
+
+    // Cap request bodies (413 Payload Too Large). axum's DefaultBodyLimit only applies to
+    // axum extractors, and the nested MCP service reads the body itself, so limit at the
+    // tower level instead
+    router = router.layer(tower_http::limit::RequestBodyLimitLayer::new(max_body_size));
 
     let tcp_listener = tokio::net::TcpListener::bind(&addr).await?;
 
-    // Start server with graceful shutdown
-    axum::serve(tcp_listener, router)
-        .with_graceful_shutdown(async {
-            tokio::signal::ctrl_c().await.ok();
-        })
-        .await?;
+    // Start server with graceful shutdown; the drain after the signal is bounded below
+    // Peer addresses identify clients for the rate limit
+    let app = router.into_make_service_with_connect_info::<std::net::SocketAddr>();
+    let serve = axum::serve(tcp_listener, app).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = signal_tx.send(true);
+    });
+    let mut serve = std::pin::pin!(std::future::IntoFuture::into_future(serve));
+
+    tokio::select! {
+        result = &mut serve => result?,
+        _ = signal_rx.wait_for(|received| *received) => {
+            tracing::info!(
+                "Shutdown signal received, draining in-flight requests (up to {}s)",
+                shutdown_timeout.as_secs()
+            );
+            let drain = async {
+                // Flush the WAL while connections drain, so a hard kill afterwards loses nothing
+                match checkpoint_manager.checkpoint().await {
+                    Ok(true) => tracing::info!("WAL checkpoint complete"),
+                    Ok(false) => tracing::warn!("WAL checkpoint incomplete (database busy)"),
+                    Err(e) => tracing::warn!("WAL checkpoint failed: {:#}", e),
+                }
+                serve.await
+            };
+            match tokio::time::timeout(shutdown_timeout, drain).await {
+                Ok(result) => result?,
+                Err(_) => tracing::warn!(
+                    "Shutdown timeout of {}s expired, closing remaining connections",
+                    shutdown_timeout.as_secs()
+                ),
+            }
+        }
+    }
 
     Ok(())
 }
 
-/// Canonicalize database path to prevent path traversal attacks
-/// Extension validation is done in storage::Database::open()
-fn canonicalize_db_path(path: &std::path::Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // Canonicalize path to resolve .. and symlinks
-    let canonical =
-        path.canonicalize()
-            .or_else(|_| -> Result<PathBuf, Box<dyn std::error::Error>> {
-                // If file doesn't exist yet, canonicalize parent and append filename
-                if let Some(parent) = path.parent() {
-                    let filename = path.file_name().ok_or("Invalid path: no filename")?;
-                    std::fs::create_dir_all(parent)?;
-                    let canonical_parent = parent.canonicalize()?;
-                    Ok(canonical_parent.join(filename))
-                } else {
-                    Err("Invalid path: no parent directory".into())
+/// Resolve on Ctrl-C, or SIGTERM on Unix (what orchestrators send on rolling restarts)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
                 }
-            })?;
+            }
+            Err(_) => {
+                tokio::signal::ctrl_c().await.ok();
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.ok();
+    }
+}
+
+/// Strong ETag for a graph revision
+fn revision_etag(revision: u64) -> String {
+    format!("\"{}\"", revision)
+}
+
+/// True if an If-None-Match header matches the ETag (`*`, lists and weak tags allowed)
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Response carrying the revision ETag: 304 without body if the client already has it
+fn conditional_response(headers: &HeaderMap, revision: u64, body: impl IntoResponse) -> Response {
+    let etag = revision_etag(revision);
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+    ];
+    if etag_matches(headers, &etag) {
+        (StatusCode::NOT_MODIFIED, cache_headers).into_response()
+    } else {
+        (cache_headers, body).into_response()
+    }
+}
+
+/// Shared state of the plain HTTP routes (everything except /mcp)
+#[derive(Clone)]
+struct HttpState {
+    manager: Arc<KnowledgeGraphManager>,
+    shutdown: tokio::sync::watch::Receiver<bool>,
+}
+
+impl FromRef<HttpState> for Arc<KnowledgeGraphManager> {
+    fn from_ref(state: &HttpState) -> Self {
+        state.manager.clone()
+    }
+}
+
+/// GET /events: server-sent events, one "change" per committed mutation
+/// A subscriber that falls behind gets a "resync" event and should re-read /graph
+async fn http_events(State(state): State<HttpState>) -> Response {
+    let mut changes = state.manager.subscribe();
+    let mut shutdown = state.shutdown;
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(16);
+
+    tokio::spawn(async move {
+        loop {
+            let received = tokio::select! {
+                received = changes.recv() => received,
+                _ = shutdown.wait_for(|stopping| *stopping) => break,
+                _ = tx.closed() => break,
+            };
+            let event = match received {
+                Ok(change) => {
+                    let event = Event::default().event("change");
+                    let event = match change.revision {
+                        Some(revision) => event.id(revision.to_string()),
+                        None => event,
+                    };
+                    match event.json_data(&change) {
+                        Ok(event) => event,
+                        Err(e) => {
+                            tracing::error!("Failed to encode change event: {}", e);
+                            continue;
+                        }
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => Event::default()
+                    .event("resync")
+                    .data(json!({ "skipped": skipped }).to_string()),
+                Err(RecvError::Closed) => break,
+            };
+            if tx.send(Ok(event)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Sse::new(ReceiverStream::new(rx))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// GET /health: "OK" plus the current revision ETag, so pollers can detect changes cheaply
+async fn http_health(
+    State(manager): State<Arc<KnowledgeGraphManager>>,
+    headers: HeaderMap,
+) -> Response {
+    match manager.revision().await {
+        Ok(revision) => conditional_response(&headers, revision, "OK"),
+        Err(e) => {
+            tracing::error!("Health check failed: {:#}", e);
+            (StatusCode::SERVICE_UNAVAILABLE, "Database unavailable").into_response()
+        }
+    }
+}
+
+/// GET /graph: whole graph in sorted read_graph shape, with revision ETag (304 if unchanged)
+async fn http_read_graph(
+    State(manager): State<Arc<KnowledgeGraphManager>>,
+    headers: HeaderMap,
+) -> Response {
+    // Cheap revision check first, so unchanged graphs are never read
+    match manager.revision().await {
+        Ok(revision) if etag_matches(&headers, &revision_etag(revision)) => {
+            return conditional_response(&headers, revision, ());
+        }
+        Ok(_) => {}
+        Err(e) => return http_internal_error(e),
+    }
+
+    match manager.read_graph_with_revision().await {
+        Ok((revision, graph)) => {
+            conditional_response(&headers, revision, axum::Json(canonical(json!(graph))))
+        }
+        Err(e) => http_internal_error(e),
+    }
+}
+
+/// GET /graph.ndjson: the whole graph as newline-delimited JSON, streamed as it is read
+/// A read error after the first bytes can only abort the body; it is logged
+async fn http_graph_ndjson(State(manager): State<Arc<KnowledgeGraphManager>>) -> Response {
+    let lines = ReceiverStream::new(manager.stream_ndjson()).map(|line| {
+        line.map_err(|e| {
+            tracing::error!("NDJSON export failed: {:#}", e);
+            std::io::Error::other(format!("{:#}", e))
+        })
+    });
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+/// Query string of GET /search
+#[derive(Debug, Deserialize)]
+struct HttpSearchParams {
+    q: String,
+    #[serde(default)]
+    mode: MatchMode,
+}
+
+/// GET /search?q=...: server-sent events, one "entity" per match in name order as the
+/// pages are read, then "done" with the count (or "error" if the search fails part way)
+async fn http_search(
+    State(manager): State<Arc<KnowledgeGraphManager>>,
+    Query(params): Query<HttpSearchParams>,
+) -> Response {
+    if params.q.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing search query 'q'").into_response();
+    }
+    let mut matches = manager.stream_search(params.q, params.mode);
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(16);
 
-    // Ensure path is absolute
-    if !canonical.is_absolute() {
-        return Err("Database path must be absolute".into());
+    tokio::spawn(async move {
+        let mut count = 0usize;
+        let last = loop {
+            let event = match matches.recv().await {
+                Some(Ok(entity)) => match Event::default().event("entity").json_data(&entity) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::error!("Failed to encode search match: {}", e);
+                        continue;
+                    }
+                },
+                Some(Err(e)) => {
+                    tracing::warn!("Streaming search failed: {:#}", e);
+                    break Event::default()
+                        .event("error")
+                        .data(json!({ "message": format!("{:#}", e) }).to_string());
+                }
+                None => {
+                    break Event::default()
+                        .event("done")
+                        .data(json!({ "count": count }).to_string())
+                }
+            };
+            if tx.send(Ok(event)).await.is_err() {
+                return;
+            }
+            count += 1;
+        };
+        let _ = tx.send(Ok(last)).await;
+    });
+
+    Sse::new(ReceiverStream::new(rx))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// 500 response for HTTP routes (details go to the log only)
+fn http_internal_error(e: anyhow::Error) -> Response {
+    if is_pool_exhausted(&e) {
+        tracing::warn!("HTTP request rejected: {:#}", e);
+        let retry_after = BUSY_RETRY_AFTER_MS.div_ceil(1000).to_string();
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after)],
+            "Server busy, try again shortly",
+        )
+            .into_response();
+    }
+    tracing::error!("HTTP request failed: {:#}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+}
+
+/// Run database health check and exit (no server, no transport)
+/// Usable as a container HEALTHCHECK for stdio deployments
+/// Opens the file read-only, so probing never migrates or otherwise changes the database
+fn run_check(db_path: PathBuf, options: DatabaseOptions) -> ! {
+    let result = Database::check_file(&db_path, &options);
+
+    match result {
+        Ok(()) => {
+            println!("OK: {}", db_path.display());
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("FAIL: {}: {:#}", db_path.display(), e);
+            std::process::exit(1);
+        }
     }
+}
 
-    Ok(canonical)
+/// Build type allowlist from comma-separated CLI values (blank entries ignored)
+fn parse_allowlist(values: Option<Vec<String>>) -> Option<BTreeSet<String>> {
+    values.map(|values| {
+        values
+            .iter()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(String::from)
+            .collect()
+    })
 }
 
 #[tokio::main]
@@ -643,23 +6583,135 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             path
         });
 
-    // Create parent directories if needed
-    if let Some(parent) = db_path.parent() {
-        tokio::fs::create_dir_all(parent).await?;
+    // Resolve '..' and symlinks to prevent traversal attacks, keep the database inside
+    // --allowed-db-dir, and create parent directories (unless --no-create-dirs or --check:
+    // a health check only inspects, it never creates anything)
+    let policy = DbPathPolicy {
+        create_dirs: !args.no_create_dirs && !args.check,
+        allowed_dir: args.allowed_db_dir.clone(),
+    };
+    let db_path = match resolve_db_path(&db_path, &policy) {
+        Ok(resolved) => resolved,
+        Err(e) if args.check => {
+            eprintln!("FAIL: {}: {:#}", db_path.display(), e);
+            std::process::exit(1);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let options = DatabaseOptions {
+        entity_types: parse_allowlist(args.entity_types),
+        relation_types: parse_allowlist(args.relation_types),
+        disallow_self_relations: args.disallow_self_relations,
+        query_timeout: args.query_timeout_ms.map(Duration::from_millis),
+        pool_timeout: args.pool_timeout_ms.map(Duration::from_millis),
+        pool_min_idle: args.pool_min_idle,
+        pool_idle_timeout: args.pool_idle_timeout_ms.map(Duration::from_millis),
+        dedup_on_create: args.dedup_on_create.then_some(DedupOptions {
+            case_fold: !args.dedup_case_sensitive,
+            merge_observations: args.dedup_merge_observations,
+        }),
+        observation_format: args.observation_format,
+        prewarm: args.prewarm,
+        cache_size: args.cache_size,
+        mmap_size: args.mmap_size,
+        min_query_length: args.min_query_length,
+        max_entity_name_length: args.max_entity_name_length,
+        length_unit: args.length_unit,
+        track_access: args.track_access,
+        observation_buffer: args.buffer_observations_ms.map(Duration::from_millis),
+        compress_observations: args.compress_observations,
+        name_type_keys: args.name_type_keys,
+        read_replica: args.read_db_path,
+        no_fts: args.no_fts,
+        no_create_dirs: args.no_create_dirs,
+        allowed_db_dir: args.allowed_db_dir,
+    };
+
+    if args.check {
+        run_check(db_path, options);
     }
 
-    // Canonicalize path to prevent traversal attacks (extension validated in Database::open)
-    let db_path = canonicalize_db_path(&db_path)?;
+    // Export tools may only write below this directory
+    let export_dir = args.export_dir.unwrap_or_else(|| {
+        db_path
+            .parent()
+            .map(|p| p.join("exports"))
+            .unwrap_or_else(|| PathBuf::from("exports"))
+    });
+    let checkpoint_dir = args.checkpoint_dir.unwrap_or_else(|| {
+        db_path
+            .parent()
+            .map(|p| p.join("checkpoints"))
+            .unwrap_or_else(|| PathBuf::from("checkpoints"))
+    });
+
+    let backup_dir = args.backup_dir.unwrap_or_else(|| {
+        db_path
+            .parent()
+            .map(|p| p.join("backups"))
+            .unwrap_or_else(|| PathBuf::from("backups"))
+    });
+
+    let audit = args
+        .tool_audit_file
+        .as_deref()
+        .map(ToolAuditLog::open)
+        .transpose()?;
 
     // Initialize manager
-    let manager = Arc::new(KnowledgeGraphManager::new(db_path)?);
+    let manager = Arc::new(KnowledgeGraphManager::with_options(db_path, options)?);
+
+    // Rollback point for this session, taken before the seed or any client writes
+    if args.backup_on_start {
+        let run = manager
+            .create_backup(backup_dir, args.backup_keep as usize)
+            .await
+            .context("Failed to back up database on start")?;
+        tracing::info!(
+            "Backed up database to '{}' ({} old backups deleted)",
+            run.path.display(),
+            run.pruned.len()
+        );
+    }
+
+    if let Some(seed) = args.seed {
+        seed_database(&manager, &seed).await?;
+    }
 
     // Create server
-    let server = MemoryServer::new(manager);
+    let server = MemoryServer::new(
+        manager.clone(),
+        export_dir,
+        checkpoint_dir,
+        audit,
+        args.max_response_entities.map(|n| n as usize),
+        args.strict_args,
+    );
 
     // Run in selected mode
-    match mode {
+    let result = match mode {
         TransportMode::Stdio => run_stdio_mode(server).await,
-        TransportMode::Stream => run_stream_mode(server, &args.bind, args.port).await,
+        TransportMode::Stream => {
+            run_stream_mode(
+                server,
+                &args.bind,
+                args.port,
+                args.compress,
+                args.max_body_size,
+                Duration::from_secs(args.shutdown_timeout),
+                args.rate_limit,
+            )
+            .await
+        }
+    };
+
+    // Observations and read counts are buffered; write what the last requests left behind
+    if let Err(e) = manager.flush_observations().await {
+        tracing::warn!("{:#}", e);
+    }
+    if let Err(e) = manager.flush_access().await {
+        tracing::warn!("Failed to record entity access: {:#}", e);
     }
+    result
 }
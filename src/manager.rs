@@ -1,96 +1,1610 @@
+use crate::backup::{self, BackupRun};
+use crate::checkpoint::{self, CheckpointInfo};
+use crate::export;
 use crate::graph::{
-    Entity, KnowledgeGraph, ObservationDeletion, ObservationInput, ObservationResult, Relation,
+    AdjacencyList, ChainResult, CommonNeighbors, Community, ConflictPolicy, CreateEntitiesResult,
+    CreateRelationsResult, DanglingRelations, DeleteRelationsResult, Direction, DuplicateCluster,
+    EdgeDirection, Entity, EntityAccess, EntityAliases, EntityFacts, EntityGroupPage,
+    EntityObservations, EntityPage, FanOutObservationResult, GraphChange, GraphEvent, GraphMetrics,
+    GraphOperation, GraphPage, GraphRecord, ImportanceRun, ImportantEntity, ItemCheck,
+    KnowledgeGraph, MatchMode, MergeGraphResult, Neighborhood, NodeInspections, ObservationCheck,
+    ObservationCount, ObservationDeletion, ObservationInput, ObservationReorder, ObservationResult,
+    ObservationTimes, ObservationValuePage, OperationResult, PathResult, RecentEntity, Relation,
+    RelationCountBetween, RelationPage, RelationSummary, RelationTypeCount, RelationTypeSynonyms,
+    ReplacedEntity, ReversedRelation, ScoredEntity, SearchHit, SearchIndexCount, SearchProjection,
+    SecondDegree, SortOrder, Traversal, TypeOntology,
 };
-use crate::storage::Database;
-use anyhow::{Context, Result};
+use crate::storage::{CallBudget, Database, DatabaseOptions, OperationTimedOut, ReadWindow};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, mpsc};
+
+/// Change notifications buffered per subscriber before it starts missing some
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// NDJSON lines read ahead of a streaming consumer
+const NDJSON_STREAM_CAPACITY: usize = 64;
+
+/// Search matches read ahead of a streaming consumer
+const SEARCH_STREAM_CAPACITY: usize = 64;
+
+/// Rows a streaming reader takes per connection checkout (released before they are sent)
+const STREAM_PAGE_SIZE: usize = 64;
+
+/// Context of a call refused because held observations could not be written before it
+const HELD_OBSERVATIONS_UNWRITTEN: &str =
+    "Not run: buffered observations could not be written first, so it would miss them";
+
+/// Longest time buffered read counts wait before being written
+const ACCESS_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Distinct entities buffered before read counts are written regardless of the interval
+const ACCESS_FLUSH_BATCH: usize = 512;
+
+/// Read counts collected in memory so reads never wait on a write (--track-access)
+struct AccessTracker {
+    buffer: Mutex<AccessBuffer>,
+}
+
+struct AccessBuffer {
+    counts: HashMap<String, u64>,
+    flushed_at: Instant,
+}
+
+impl AccessTracker {
+    fn new() -> Self {
+        Self {
+            buffer: Mutex::new(AccessBuffer {
+                counts: HashMap::new(),
+                flushed_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Count one read of each name; returns the buffered batch once it is due for writing
+    fn record<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Option<Vec<(String, u64)>> {
+        let mut buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        for name in names {
+            *buffer.counts.entry(name.to_string()).or_default() += 1;
+        }
+        let due = buffer.counts.len() >= ACCESS_FLUSH_BATCH
+            || buffer.flushed_at.elapsed() >= ACCESS_FLUSH_INTERVAL;
+        (due && !buffer.counts.is_empty()).then(|| Self::take(&mut buffer))
+    }
+
+    /// Take everything buffered so far
+    fn drain(&self) -> Vec<(String, u64)> {
+        Self::take(&mut self.buffer.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn take(buffer: &mut AccessBuffer) -> Vec<(String, u64)> {
+        buffer.flushed_at = Instant::now();
+        buffer.counts.drain().collect()
+    }
+}
+
+/// add_observations calls held back and written together (--buffer-observations-ms)
+struct ObservationBuffer {
+    window: Duration,
+    pending: Mutex<Vec<ObservationInput>>,
+    /// Why the last write of held observations failed (cleared by the next one that works)
+    failure: Mutex<Option<String>>,
+}
+
+impl ObservationBuffer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::new(Vec::new()),
+            failure: Mutex::new(None),
+        }
+    }
+
+    /// Hold previewed observations, merged into the entity's pending entry (one per entity)
+    /// Returns what each input newly added, and whether the buffer was empty before, in which
+    /// case the caller schedules the flush
+    fn hold(&self, previews: Vec<ObservationResult>) -> (Vec<ObservationResult>, bool) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let was_empty = pending.is_empty();
+        let results = previews
+            .into_iter()
+            .map(|preview| {
+                let index = match pending
+                    .iter()
+                    .position(|p| p.entity_name == preview.entity_name)
+                {
+                    Some(index) => index,
+                    None => {
+                        pending.push(ObservationInput {
+                            entity_name: preview.entity_name.clone(),
+                            contents: Vec::new(),
+                        });
+                        pending.len() - 1
+                    }
+                };
+                let entry = &mut pending[index];
+                let added: Vec<String> = preview
+                    .added_observations
+                    .into_iter()
+                    .filter(|obs| !entry.contents.contains(obs))
+                    .collect();
+                entry.contents.extend(added.iter().cloned());
+                ObservationResult {
+                    entity_name: preview.entity_name,
+                    added_observations: added,
+                }
+            })
+            .collect();
+        pending.retain(|p| !p.contents.is_empty());
+        (results, was_empty && !pending.is_empty())
+    }
+
+    /// Take everything held so far
+    fn take(&self) -> Vec<ObservationInput> {
+        std::mem::take(&mut *self.pending.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Hold a batch that could not be written again, ahead of anything held meanwhile
+    fn restore(&self, batch: Vec<ObservationInput>, failure: String) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let newer = std::mem::replace(&mut *pending, batch);
+        for input in newer {
+            match pending
+                .iter_mut()
+                .find(|p| p.entity_name == input.entity_name)
+            {
+                Some(entry) => {
+                    for obs in input.contents {
+                        if !entry.contents.contains(&obs) {
+                            entry.contents.push(obs);
+                        }
+                    }
+                }
+                None => pending.push(input),
+            }
+        }
+        *self.failure.lock().unwrap_or_else(PoisonError::into_inner) = Some(failure);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    fn has_failed(&self) -> bool {
+        self.failure
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    fn clear_failure(&self) {
+        self.failure
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+/// Send every entity matching a search (by name), reading one page at a time so no
+/// connection is held while blocked on the receiver
+fn send_search_pages(
+    db: &Database,
+    query: &str,
+    mode: MatchMode,
+    tx: &mpsc::Sender<Result<Entity>>,
+) -> Result<()> {
+    let mut after: Option<String> = None;
+    loop {
+        let page = db.search_matches_after(query, mode, after.as_deref(), STREAM_PAGE_SIZE)?;
+        let last_page = page.len() < STREAM_PAGE_SIZE;
+        after = page.last().map(|e| e.name.clone());
+        for entity in page {
+            tx.blocking_send(Ok(entity))
+                .map_err(|_| anyhow!("Search stream receiver went away"))?;
+        }
+        if last_page {
+            return Ok(());
+        }
+    }
+}
+
+/// Send every entity (by name), then every relation (by from, to, type) as NDJSON lines,
+/// reading one page at a time so no connection is held while blocked on the receiver
+fn send_ndjson_pages(db: &Database, tx: &mpsc::Sender<Result<String>>) -> Result<()> {
+    let send = |record: GraphRecord| {
+        tx.blocking_send(Ok(export::ndjson_line(&record)?))
+            .map_err(|_| anyhow!("NDJSON stream receiver went away"))
+    };
+
+    let mut after: Option<String> = None;
+    loop {
+        let page = db.entities_after(after.as_deref(), STREAM_PAGE_SIZE)?;
+        let last_page = page.len() < STREAM_PAGE_SIZE;
+        after = page.last().map(|e| e.name.clone());
+        for entity in page {
+            send(GraphRecord::Entity(entity))?;
+        }
+        if last_page {
+            break;
+        }
+    }
+
+    let mut after: Option<Relation> = None;
+    loop {
+        let page = db.relations_after(after.as_ref(), STREAM_PAGE_SIZE)?;
+        let last_page = page.len() < STREAM_PAGE_SIZE;
+        after = page.last().cloned();
+        for relation in page {
+            send(GraphRecord::Relation(relation))?;
+        }
+        if last_page {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Write the held observations in one transaction and notify subscribers of the updated
+/// entities; returns how many observations were written
+/// Runs on the blocking pool. Entities deleted since their observations were held are
+/// dropped from the batch; any other failure holds the batch again (to be retried) and
+/// reports why
+fn flush_observations(
+    db: &Database,
+    buffer: &ObservationBuffer,
+    events: &broadcast::Sender<GraphChange>,
+) -> Result<usize> {
+    let mut pending = buffer.take();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    let mut written = db.add_observations(&pending);
+    if written.is_err() && drop_deleted_entities(db, &mut pending) {
+        written = if pending.is_empty() {
+            Ok(Vec::new())
+        } else {
+            db.add_observations(&pending)
+        };
+    }
+    let results = match written {
+        Ok(results) => results,
+        Err(e) => {
+            let e = e.context(format!(
+                "Failed to write buffered observations for {} entities (kept for a retry)",
+                pending.len()
+            ));
+            buffer.restore(pending, format!("{:#}", e));
+            return Err(e);
+        }
+    };
+    buffer.clear_failure();
+    let names: Vec<String> = results
+        .iter()
+        .filter(|r| !r.added_observations.is_empty())
+        .map(|r| r.entity_name.clone())
+        .collect();
+    if !names.is_empty() && events.receiver_count() > 0 {
+        let revision = db.revision().ok();
+        let _ = events.send(GraphChange {
+            revision,
+            event: GraphEvent::EntitiesUpdated { names },
+        });
+    }
+    Ok(results.iter().map(|r| r.added_observations.len()).sum())
+}
+
+/// Remove held observations of entities that no longer exist; true if any were removed
+fn drop_deleted_entities(db: &Database, pending: &mut Vec<ObservationInput>) -> bool {
+    let names: Vec<String> = pending.iter().map(|p| p.entity_name.clone()).collect();
+    let Ok(graph) = db.open_nodes(&names) else {
+        return false;
+    };
+    let existing: HashSet<&str> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    let before = pending.len();
+    pending.retain(|input| {
+        let exists = existing.contains(input.entity_name.as_str());
+        if !exists {
+            tracing::warn!(
+                "Dropped {} buffered observations of deleted entity '{}'",
+                input.contents.len(),
+                input.entity_name
+            );
+        }
+        exists
+    });
+    pending.len() != before
+}
 
 /// Manager for knowledge graph operations
 /// Provides async API wrapping SQLite database with proper blocking isolation
 pub struct KnowledgeGraphManager {
     db: Arc<Database>,
+    events: broadcast::Sender<GraphChange>,
+    access: Option<AccessTracker>,
+    observations: Option<Arc<ObservationBuffer>>,
+    timeout: Option<Duration>,
 }
 
 impl KnowledgeGraphManager {
     /// Create new manager with database at given path
     pub fn new(db_path: PathBuf) -> Result<Self> {
-        let db = Database::open(&db_path)?;
-        Ok(Self { db: Arc::new(db) })
+        Self::with_options(db_path, DatabaseOptions::default())
     }
 
-    /// Create entities (returns only newly created entities)
-    pub async fn create_entities(&self, entities: Vec<Entity>) -> Result<Vec<Entity>> {
+    /// Create new manager with database at given path and custom options
+    pub fn with_options(db_path: PathBuf, options: DatabaseOptions) -> Result<Self> {
+        let access = options.track_access.then(AccessTracker::new);
+        let observations = options
+            .observation_buffer
+            .map(|window| Arc::new(ObservationBuffer::new(window)));
+        let timeout = options.query_timeout;
+        let db = Database::open_with_options(&db_path, options)?;
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Ok(Self {
+            db: Arc::new(db),
+            events,
+            access,
+            observations,
+            timeout,
+        })
+    }
+
+    /// Receive a notification for every change committed through this manager
+    /// A subscriber that falls more than EVENT_CHANNEL_CAPACITY changes behind gets Lagged
+    pub fn subscribe(&self) -> broadcast::Receiver<GraphChange> {
+        self.events.subscribe()
+    }
+
+    /// Run a database call on the blocking pool, bounded by the query timeout (if configured)
+    /// The deadline is fixed before the call is queued and also interrupts its SQLite
+    /// statements, so a timed-out call frees its thread and connection shortly after instead
+    /// of occupying them indefinitely. A timeout is only reported while the call has
+    /// committed nothing (later commits are rolled back); once it has, its outcome is awaited
+    /// Buffered observations are written first, so the call sees them; if that fails they
+    /// stay held for a retry and the call returns that failure instead of running without them
+    async fn blocking<T, F>(&self, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
+        let buffer = self.observations.clone();
+        let events = self.events.clone();
+        self.blocking_unflushed(move |db| {
+            if let Some(buffer) = &buffer {
+                flush_observations(db, buffer, &events).context(HELD_OBSERVATIONS_UNWRITTEN)?;
+            }
+            call(db)
+        })
+        .await
+    }
+
+    /// Like blocking, but leaves buffered observations held
+    async fn blocking_unflushed<T, F>(&self, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
         let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.create_entities(&entities))
+        let Some(limit) = self.timeout else {
+            return tokio::task::spawn_blocking(move || call(&db))
+                .await
+                .context("Task panicked")?;
+        };
+
+        let budget = CallBudget::new(limit);
+        let deadline = tokio::time::Instant::from_std(budget.deadline());
+        let task_budget = budget.clone();
+        let mut task = tokio::task::spawn_blocking(move || task_budget.run(|| call(&db)));
+        let joined = match tokio::time::timeout_at(deadline, &mut task).await {
+            Ok(joined) => joined,
+            Err(_) if budget.abandon() => return Err(OperationTimedOut { after: limit }.into()),
+            // Already committed: report what actually happened, so the change gets published
+            Err(_) => task.await,
+        };
+        joined.context("Task panicked")?
+    }
+
+    /// Count a read of the given entities (with --track-access)
+    /// The counts are written in the background once a batch is due, never on the caller's path
+    fn note_access<'a>(&self, names: impl IntoIterator<Item = &'a str>) {
+        let Some(batch) = self
+            .access
+            .as_ref()
+            .and_then(|tracker| tracker.record(names))
+        else {
+            return;
+        };
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            match tokio::task::spawn_blocking(move || db.record_access(&batch)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!("Failed to record entity access: {:#}", e),
+                Err(e) => tracing::warn!("Entity access task panicked: {}", e),
+            }
+        });
+    }
+
+    /// Write read counts still buffered in memory (no-op without --track-access)
+    pub async fn flush_access(&self) -> Result<()> {
+        let Some(tracker) = &self.access else {
+            return Ok(());
+        };
+        let batch = tracker.drain();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.blocking(move |db| db.record_access(&batch)).await
+    }
+
+    /// Whether reads are being counted (--track-access)
+    pub fn tracks_access(&self) -> bool {
+        self.access.is_some()
+    }
+
+    /// Entities read least often: prune candidates (buffered counts are written first)
+    pub async fn least_accessed(&self, limit: usize) -> Result<Vec<EntityAccess>> {
+        self.flush_access().await?;
+        self.blocking(move |db| db.least_accessed(limit)).await
+    }
+
+    /// Entities ranked by observation count (asc: emptiest first, desc: richest first)
+    pub async fn entities_by_observation_count(
+        &self,
+        order: SortOrder,
+        limit: usize,
+    ) -> Result<Vec<ObservationCount>> {
+        self.blocking(move |db| db.entities_by_observation_count(order, limit))
+            .await
+    }
+
+    /// Distinct observation values with the number of entities holding each (paginated)
+    pub async fn list_observations(
+        &self,
+        limit: usize,
+        offset: usize,
+        contains: Option<String>,
+    ) -> Result<ObservationValuePage> {
+        self.blocking(move |db| db.list_observations(limit, offset, contains.as_deref()))
+            .await
+    }
+
+    /// Run PageRank over the relation graph and cache the scores
+    pub async fn compute_importance(&self, iterations: u32) -> Result<ImportanceRun> {
+        self.blocking(move |db| db.compute_importance(iterations))
+            .await
+    }
+
+    /// Entities with the highest cached importance
+    pub async fn top_important(&self, limit: usize) -> Result<Vec<ImportantEntity>> {
+        self.blocking(move |db| db.top_important(limit)).await
+    }
+
+    /// Notify subscribers of a change (skipped when nobody listens)
+    async fn publish(&self, event: GraphEvent) {
+        if self.events.receiver_count() == 0 {
+            return;
+        }
+        let revision = self.revision().await.ok();
+        // Sending only fails when every subscriber went away meanwhile
+        let _ = self.events.send(GraphChange { revision, event });
+    }
+
+    /// Publish created entities and relations, skipping empty events
+    async fn publish_created(&self, entities: &[Entity], relations: &[Relation]) {
+        if !entities.is_empty() {
+            let names = entities.iter().map(|e| e.name.clone()).collect();
+            self.publish(GraphEvent::EntitiesCreated { names }).await;
+        }
+        if !relations.is_empty() {
+            self.publish(GraphEvent::RelationsCreated {
+                relations: relations.to_vec(),
+            })
+            .await;
+        }
+    }
+
+    /// Publish entities whose observations or description changed, skipping empty events
+    async fn publish_updated(&self, names: Vec<String>) {
+        if !names.is_empty() {
+            self.publish(GraphEvent::EntitiesUpdated { names }).await;
+        }
+    }
+
+    /// Verify database health (SELECT 1 + integrity check)
+    pub async fn health_check(&self) -> Result<()> {
+        self.blocking(move |db| db.health_check()).await
+    }
+
+    /// Relations whose endpoints name no entity
+    pub async fn dangling_relations(&self) -> Result<DanglingRelations> {
+        self.blocking(move |db| db.dangling_relations()).await
+    }
+
+    /// Create placeholder entities for the missing endpoints of dangling relations
+    pub async fn materialize_dangling(&self, entity_type: String) -> Result<Vec<Entity>> {
+        let created = self
+            .blocking(move |db| db.materialize_dangling(&entity_type))
+            .await?;
+        self.publish_created(&created, &[]).await;
+        Ok(created)
+    }
+
+    /// Compare entity and relation counts with their full-text indexes
+    pub async fn search_index_counts(&self) -> Result<Vec<SearchIndexCount>> {
+        self.blocking(move |db| db.search_index_counts()).await
+    }
+
+    /// Rebuild the full-text indexes from the entities and relations tables
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        self.blocking(move |db| db.rebuild_search_index()).await
+    }
+
+    /// Checkpoint the WAL into the database file (false if it could not complete)
+    pub async fn checkpoint(&self) -> Result<bool> {
+        self.blocking(move |db| db.checkpoint()).await
+    }
+
+    /// Save a copy of the database as checkpoint `name` in dir (online backup)
+    /// An existing checkpoint of that name is only replaced with overwrite
+    pub async fn create_checkpoint(
+        &self,
+        dir: PathBuf,
+        name: String,
+        overwrite: bool,
+    ) -> Result<CheckpointInfo> {
+        self.blocking(move |db| {
+            let path = checkpoint::checkpoint_path(&dir, &name)?;
+            if path.exists() && !overwrite {
+                bail!(
+                    "Checkpoint '{}' already exists; pass overwrite to replace it",
+                    name
+                );
+            }
+            std::fs::create_dir_all(&dir).with_context(|| {
+                format!("Failed to create checkpoint directory '{}'", dir.display())
+            })?;
+
+            // Back up next to the target and rename, so a failed backup never
+            // leaves a truncated checkpoint behind
+            let partial = path.with_extension("db.partial");
+            let _ = std::fs::remove_file(&partial);
+            db.backup_to(&partial)?;
+            std::fs::rename(&partial, &path)
+                .with_context(|| format!("Failed to store checkpoint '{}'", name))?;
+            checkpoint::checkpoint_info(&name, &path)
+        })
+        .await
+    }
+
+    /// Copy the database into a new timestamped backup in dir (online backup), then delete
+    /// the oldest backups beyond `keep`
+    pub async fn create_backup(&self, dir: PathBuf, keep: usize) -> Result<BackupRun> {
+        if keep == 0 {
+            bail!("Backup retention must keep at least one backup");
+        }
+        self.blocking(move |db| {
+            std::fs::create_dir_all(&dir).with_context(|| {
+                format!("Failed to create backup directory '{}'", dir.display())
+            })?;
+
+            // Same partial-then-rename as checkpoints: a failed backup leaves no
+            // truncated file that rotation would count as a good one
+            let path = backup::backup_path(&dir, SystemTime::now());
+            let partial = path.with_extension("db.partial");
+            let _ = std::fs::remove_file(&partial);
+            db.backup_to(&partial)?;
+            std::fs::rename(&partial, &path)
+                .with_context(|| format!("Failed to store backup '{}'", path.display()))?;
+            let pruned = backup::prune_backups(&dir, keep)?;
+            Ok(BackupRun { path, pruned })
+        })
+        .await
+    }
+
+    /// List the checkpoints saved in dir, oldest first
+    pub async fn list_checkpoints(&self, dir: PathBuf) -> Result<Vec<CheckpointInfo>> {
+        tokio::task::spawn_blocking(move || checkpoint::list_checkpoints(&dir))
             .await
             .context("Task panicked")?
     }
 
+    /// Replace the whole graph with checkpoint `name` from dir, returning the new revision
+    pub async fn restore_checkpoint(&self, dir: PathBuf, name: String) -> Result<u64> {
+        let checkpoint_name = name.clone();
+        let revision = self
+            .blocking(move |db| {
+                let path = checkpoint::checkpoint_path(&dir, &checkpoint_name)?;
+                if !path.is_file() {
+                    bail!("Checkpoint '{}' does not exist", checkpoint_name);
+                }
+                db.restore_from(&path)
+            })
+            .await?;
+        self.publish(GraphEvent::CheckpointRestored { name }).await;
+        Ok(revision)
+    }
+
+    /// Create entities (returns only newly created entities)
+    pub async fn create_entities(&self, entities: Vec<Entity>) -> Result<Vec<Entity>> {
+        Ok(self.create_entities_detailed(entities).await?.created)
+    }
+
+    /// Create entities, also reporting which ones were deduplicated into existing entities
+    pub async fn create_entities_detailed(
+        &self,
+        entities: Vec<Entity>,
+    ) -> Result<CreateEntitiesResult> {
+        let result = self
+            .blocking(move |db| db.create_entities_detailed(&entities))
+            .await?;
+        self.publish_created(&result.created, &[]).await;
+        self.publish_updated(
+            result
+                .deduplicated
+                .iter()
+                .filter(|d| !d.merged_observations.is_empty())
+                .map(|d| d.existing_name.clone())
+                .collect(),
+        )
+        .await;
+        Ok(result)
+    }
+
     /// Create relations (returns only newly created relations)
     pub async fn create_relations(&self, relations: Vec<Relation>) -> Result<Vec<Relation>> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.create_relations(&relations))
-            .await
-            .context("Task panicked")?
+        Ok(self.create_relations_detailed(relations).await?.created)
+    }
+
+    /// Create relations, also reporting the batch id they were tagged with
+    pub async fn create_relations_detailed(
+        &self,
+        relations: Vec<Relation>,
+    ) -> Result<CreateRelationsResult> {
+        let result = self
+            .blocking(move |db| db.create_relations_detailed(&relations))
+            .await?;
+        self.publish_created(&[], &result.created).await;
+        Ok(result)
+    }
+
+    /// Create relations between a center entity and each of the others (one transaction)
+    /// Outgoing: center -> other; Incoming: other -> center. Returns only newly created relations
+    pub async fn create_star_relations(
+        &self,
+        center: String,
+        others: Vec<String>,
+        relation_type: String,
+        direction: EdgeDirection,
+    ) -> Result<Vec<Relation>> {
+        let relations = Self::star_relations(center, others, relation_type, direction);
+        self.create_relations(relations).await
+    }
+
+    /// Relations of a star: center -> each other (Outgoing) or each other -> center (Incoming)
+    pub fn star_relations(
+        center: String,
+        others: Vec<String>,
+        relation_type: String,
+        direction: EdgeDirection,
+    ) -> Vec<Relation> {
+        others
+            .into_iter()
+            .map(|other| {
+                let (from, to) = match direction {
+                    EdgeDirection::Outgoing => (center.clone(), other),
+                    EdgeDirection::Incoming => (other, center.clone()),
+                };
+                Relation {
+                    from,
+                    to,
+                    relation_type: relation_type.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// True if the database holds no entities and no relations
+    pub async fn is_empty(&self) -> Result<bool> {
+        self.blocking(move |db| db.is_empty()).await
+    }
+
+    /// Import a whole graph atomically, returning the newly created entities and relations
+    pub async fn import_graph(&self, graph: KnowledgeGraph) -> Result<KnowledgeGraph> {
+        let created = self.blocking(move |db| db.import_graph(&graph)).await?;
+        self.publish_created(&created.entities, &created.relations)
+            .await;
+        Ok(created)
+    }
+
+    /// Import a graph, resolving entities that already exist with the conflict policy
+    pub async fn merge_graph(
+        &self,
+        graph: KnowledgeGraph,
+        policy: ConflictPolicy,
+    ) -> Result<MergeGraphResult> {
+        let result = self
+            .blocking(move |db| db.merge_graph(&graph, policy))
+            .await?;
+        self.publish_created(&result.entities, &result.relations)
+            .await;
+        self.publish_updated(result.updated.clone()).await;
+        Ok(result)
     }
 
     /// Add observations to multiple entities (batch operation)
+    /// With an observation buffer the observations are validated and held instead, then written
+    /// together after the buffer window, on flush_observations or before the next operation;
+    /// success then means "held", not "stored". While earlier held observations fail to be
+    /// written, the call retries them first and returns that failure instead of holding more
     pub async fn add_observations(
         &self,
         inputs: Vec<ObservationInput>,
     ) -> Result<Vec<ObservationResult>> {
+        if let Some(buffer) = &self.observations {
+            if buffer.has_failed() {
+                self.flush_observations().await?;
+            }
+            let previews = self
+                .blocking_unflushed(move |db| db.preview_observations(&inputs))
+                .await?;
+            let (results, schedule) = buffer.hold(previews);
+            if schedule {
+                self.schedule_observation_flush(buffer.clone());
+            }
+            return Ok(results);
+        }
+        let results = self
+            .blocking(move |db| db.add_observations(&inputs))
+            .await?;
+        self.publish_updated(
+            results
+                .iter()
+                .filter(|r| !r.added_observations.is_empty())
+                .map(|r| r.entity_name.clone())
+                .collect(),
+        )
+        .await;
+        Ok(results)
+    }
+
+    /// Write the held observations once the buffer window has passed, retrying every window
+    /// while the write fails
+    fn schedule_observation_flush(&self, buffer: Arc<ObservationBuffer>) {
         let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.add_observations(&inputs))
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(buffer.window).await;
+                let (db, flushing, events) = (db.clone(), buffer.clone(), events.clone());
+                let flush = move || flush_observations(&db, &flushing, &events);
+                match tokio::task::spawn_blocking(flush).await {
+                    Ok(Ok(_)) => break,
+                    Ok(Err(e)) => tracing::warn!("{:#}", e),
+                    Err(e) => tracing::warn!("Observation flush task panicked: {}", e),
+                }
+                if buffer.is_empty() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Write observations held by the buffer now, returning how many were written
+    /// (no-op without --buffer-observations-ms)
+    pub async fn flush_observations(&self) -> Result<usize> {
+        let Some(buffer) = self.observations.clone() else {
+            return Ok(0);
+        };
+        let events = self.events.clone();
+        self.blocking_unflushed(move |db| flush_observations(db, &buffer, &events))
+            .await
+    }
+
+    /// Whether add_observations calls are buffered (--buffer-observations-ms)
+    pub fn buffers_observations(&self) -> bool {
+        self.observations.is_some()
+    }
+
+    /// Append one observation to many entities, skipping (and reporting) missing ones
+    pub async fn add_observation_to_many(
+        &self,
+        names: Vec<String>,
+        observation: String,
+    ) -> Result<FanOutObservationResult> {
+        let result = self
+            .blocking(move |db| db.add_observation_to_many(&names, &observation))
+            .await?;
+        self.publish_updated(result.added.clone()).await;
+        Ok(result)
+    }
+
+    /// Copy the observations of one entity onto another, keeping both
+    pub async fn copy_observations(
+        &self,
+        source: String,
+        target: String,
+    ) -> Result<ObservationResult> {
+        let result = self
+            .blocking(move |db| db.copy_observations(&source, &target))
+            .await?;
+        if !result.added_observations.is_empty() {
+            self.publish_updated(vec![result.entity_name.clone()]).await;
+        }
+        Ok(result)
+    }
+
+    /// Check whether an entity has an observation (exact or ignoring case and whitespace)
+    pub async fn has_observation(
+        &self,
+        entity_name: String,
+        observation: String,
+    ) -> Result<ObservationCheck> {
+        self.blocking(move |db| db.has_observation(&entity_name, &observation))
             .await
-            .context("Task panicked")?
     }
 
     /// Delete entities (cascade deletes relations via FOREIGN KEY)
     pub async fn delete_entities(&self, names: Vec<String>) -> Result<usize> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.delete_entities(&names))
-            .await
-            .context("Task panicked")?
+        let requested = names.clone();
+        let deleted = self.blocking(move |db| db.delete_entities(&names)).await?;
+        if deleted > 0 {
+            // Names that did not exist are included; deleting them was a no-op
+            self.publish(GraphEvent::EntitiesDeleted { names: requested })
+                .await;
+        }
+        Ok(deleted)
     }
 
     /// Delete observations from multiple entities (batch operation)
     pub async fn delete_observations(&self, deletions: Vec<ObservationDeletion>) -> Result<()> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.delete_observations(&deletions))
-            .await
-            .context("Task panicked")?
+        let names = deletions.iter().map(|d| d.entity_name.clone()).collect();
+        self.blocking(move |db| db.delete_observations(&deletions))
+            .await?;
+        self.publish_updated(names).await;
+        Ok(())
+    }
+
+    /// Reorder observations of multiple entities (batch operation)
+    pub async fn reorder_observations(
+        &self,
+        reorders: Vec<ObservationReorder>,
+    ) -> Result<Vec<EntityObservations>> {
+        let results = self
+            .blocking(move |db| db.reorder_observations(&reorders))
+            .await?;
+        self.publish_updated(results.iter().map(|r| r.entity_name.clone()).collect())
+            .await;
+        Ok(results)
     }
 
     /// Delete relations
     pub async fn delete_relations(&self, relations: Vec<Relation>) -> Result<usize> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.delete_relations(&relations))
+        Ok(self.delete_relations_detailed(relations).await?.deleted)
+    }
+
+    /// Delete relations, also reporting the endpoints that were given as aliases
+    pub async fn delete_relations_detailed(
+        &self,
+        relations: Vec<Relation>,
+    ) -> Result<DeleteRelationsResult> {
+        let mut requested = relations.clone();
+        let result = self
+            .blocking(move |db| db.delete_relations_detailed(&relations))
+            .await?;
+        if result.deleted > 0 {
+            for alias in &result.resolved_aliases {
+                alias.apply(&mut requested);
+            }
+            // Relations that did not exist are included; deleting them was a no-op
+            self.publish(GraphEvent::RelationsDeleted {
+                relations: requested,
+            })
+            .await;
+        }
+        Ok(result)
+    }
+
+    /// Apply operations staged in a transaction, all in one database transaction
+    /// Subscribers hear about each operation's changes only after everything committed
+    pub async fn apply_operations(
+        &self,
+        operations: Vec<GraphOperation>,
+    ) -> Result<Vec<OperationResult>> {
+        let (operations, results) = self
+            .blocking(move |db| {
+                db.apply_operations(&operations)
+                    .map(|results| (operations, results))
+            })
+            .await?;
+        for (operation, result) in operations.into_iter().zip(&results) {
+            self.publish_operation(operation, result).await;
+        }
+        Ok(results)
+    }
+
+    /// Check a staged operation the way its tool would (no database access)
+    pub fn validate_operation(&self, operation: &GraphOperation) -> Result<()> {
+        self.db.validate_operation(operation)
+    }
+
+    /// Publish the changes of one committed operation, like the matching direct call does
+    async fn publish_operation(&self, operation: GraphOperation, result: &OperationResult) {
+        match (operation, result) {
+            (_, OperationResult::CreateEntities(result)) => {
+                self.publish_created(&result.created, &[]).await;
+                self.publish_updated(
+                    result
+                        .deduplicated
+                        .iter()
+                        .filter(|d| !d.merged_observations.is_empty())
+                        .map(|d| d.existing_name.clone())
+                        .collect(),
+                )
+                .await;
+            }
+            (_, OperationResult::CreateRelations { relations }) => {
+                self.publish_created(&[], relations).await;
+            }
+            (_, OperationResult::AddObservations { results }) => {
+                self.publish_updated(
+                    results
+                        .iter()
+                        .filter(|r| !r.added_observations.is_empty())
+                        .map(|r| r.entity_name.clone())
+                        .collect(),
+                )
+                .await;
+            }
+            (
+                GraphOperation::DeleteEntities { names },
+                OperationResult::DeleteEntities { deleted },
+            ) if *deleted > 0 => {
+                self.publish(GraphEvent::EntitiesDeleted { names }).await;
+            }
+            (_, OperationResult::DeleteObservations { entities }) => {
+                self.publish_updated(entities.clone()).await;
+            }
+            (
+                GraphOperation::DeleteRelations { relations },
+                OperationResult::DeleteRelations { deleted },
+            ) if *deleted > 0 => {
+                self.publish(GraphEvent::RelationsDeleted { relations })
+                    .await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Change the type of a single relation, returning the updated relation
+    pub async fn change_relation_type(
+        &self,
+        relation: Relation,
+        new_type: String,
+    ) -> Result<Relation> {
+        let old = relation.clone();
+        let updated = self
+            .blocking(move |db| db.change_relation_type(&relation, &new_type))
+            .await?;
+        // A type change replaces one relation by another
+        self.publish(GraphEvent::RelationsDeleted {
+            relations: vec![old],
+        })
+        .await;
+        self.publish_created(&[], std::slice::from_ref(&updated))
+            .await;
+        Ok(updated)
+    }
+
+    /// Swap the direction of a single relation (merging into the reverse if it exists)
+    pub async fn reverse_relation(&self, relation: Relation) -> Result<ReversedRelation> {
+        let old = relation.clone();
+        let result = self
+            .blocking(move |db| db.reverse_relation(&relation))
+            .await?;
+        if old != result.relation {
+            // Like a type change, a reversal replaces one relation by another
+            self.publish(GraphEvent::RelationsDeleted {
+                relations: vec![old],
+            })
+            .await;
+            if !result.merged {
+                self.publish_created(&[], std::slice::from_ref(&result.relation))
+                    .await;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Set or clear the description of an entity, returning the updated entity
+    pub async fn set_entity_description(
+        &self,
+        entity_name: String,
+        description: Option<String>,
+    ) -> Result<Entity> {
+        let entity = self
+            .blocking(move |db| db.set_entity_description(&entity_name, description.as_deref()))
+            .await?;
+        self.publish_updated(vec![entity.name.clone()]).await;
+        Ok(entity)
+    }
+
+    /// Set or clear the confidence score of an entity, returning the updated entity
+    pub async fn set_entity_confidence(
+        &self,
+        entity_name: String,
+        confidence: Option<f64>,
+    ) -> Result<Entity> {
+        let entity = self
+            .blocking(move |db| db.set_entity_confidence(&entity_name, confidence))
+            .await?;
+        self.publish_updated(vec![entity.name.clone()]).await;
+        Ok(entity)
+    }
+
+    /// Give an entity aliases that relation endpoints may use, returning the new ones
+    pub async fn add_aliases(
+        &self,
+        entity_name: String,
+        aliases: Vec<String>,
+    ) -> Result<Vec<String>> {
+        self.blocking(move |db| db.add_aliases(&entity_name, &aliases))
             .await
-            .context("Task panicked")?
+    }
+
+    /// Remove aliases, returning the ones that existed
+    pub async fn remove_aliases(&self, aliases: Vec<String>) -> Result<Vec<String>> {
+        self.blocking(move |db| db.remove_aliases(&aliases)).await
+    }
+
+    /// Aliases of an entity
+    pub async fn entity_aliases(&self, entity_name: String) -> Result<EntityAliases> {
+        self.blocking(move |db| db.entity_aliases(&entity_name))
+            .await
+    }
+
+    /// Set or clear the free-form JSON metadata of an entity, returning the updated entity
+    pub async fn set_entity_metadata(
+        &self,
+        entity_name: String,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<Entity> {
+        let entity = self
+            .blocking(move |db| db.set_entity_metadata(&entity_name, metadata.as_ref()))
+            .await?;
+        self.publish_updated(vec![entity.name.clone()]).await;
+        Ok(entity)
+    }
+
+    /// Replace an entity's type and observations, creating it if missing
+    pub async fn replace_entity(&self, entity: Entity) -> Result<ReplacedEntity> {
+        let result = self.blocking(move |db| db.replace_entity(&entity)).await?;
+        if result.created {
+            self.publish_created(std::slice::from_ref(&result.entity), &[])
+                .await;
+        } else {
+            self.publish_updated(vec![result.entity.name.clone()]).await;
+        }
+        Ok(result)
+    }
+
+    /// Non-fatal warnings for entities about to be created
+    pub async fn entity_warnings(&self, entities: Vec<Entity>) -> Result<Vec<String>> {
+        self.blocking(move |db| db.entity_warnings(&entities)).await
+    }
+
+    /// Non-fatal warnings for relations about to be created
+    pub async fn relation_warnings(&self, relations: Vec<Relation>) -> Result<Vec<String>> {
+        self.blocking(move |db| db.relation_warnings(&relations))
+            .await
+    }
+
+    /// Non-fatal warnings for observations about to be added (no database access)
+    pub fn observation_warnings(&self, inputs: &[ObservationInput]) -> Vec<String> {
+        self.db.observation_input_warnings(inputs)
+    }
+
+    /// Find clusters of likely duplicate entities (exact normalized match, or trigram threshold)
+    pub async fn find_duplicates(&self, threshold: Option<f64>) -> Result<Vec<DuplicateCluster>> {
+        self.blocking(move |db| db.find_duplicates(threshold)).await
+    }
+
+    /// Relation types that look like spellings of the same relation
+    pub async fn find_relation_type_synonyms(
+        &self,
+        threshold: Option<f64>,
+    ) -> Result<Vec<RelationTypeSynonyms>> {
+        self.blocking(move |db| db.find_relation_type_synonyms(threshold))
+            .await
+    }
+
+    /// Write the whole graph to path as NDJSON, one record at a time (never materialized)
+    /// Returns how many entities and relations were written
+    pub async fn export_ndjson(&self, path: PathBuf) -> Result<(usize, usize)> {
+        self.blocking(move |db| {
+            let file = std::fs::File::create(&path)
+                .with_context(|| format!("Failed to create '{}'", path.display()))?;
+            let mut writer = BufWriter::new(file);
+            let (mut entities, mut relations) = (0, 0);
+            db.for_each_record(|record| {
+                match record {
+                    GraphRecord::Entity(_) => entities += 1,
+                    GraphRecord::Relation(_) => relations += 1,
+                }
+                writer
+                    .write_all(export::ndjson_line(&record)?.as_bytes())
+                    .with_context(|| format!("Failed to write '{}'", path.display()))
+            })?;
+            writer
+                .flush()
+                .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            Ok((entities, relations))
+        })
+        .await
+    }
+
+    /// Stream the whole graph as NDJSON lines, read in the background as the receiver
+    /// consumes them (at most NDJSON_STREAM_CAPACITY lines are buffered)
+    /// Rows are read in pages of STREAM_PAGE_SIZE, each on its own connection checkout, so a
+    /// slow receiver holds neither a connection nor a read transaction while it catches up;
+    /// records changed during the stream may show either state, but none is sent twice
+    /// A read error is sent as the last item; dropping the receiver stops the read
+    pub fn stream_ndjson(&self) -> mpsc::Receiver<Result<String>> {
+        let (tx, rx) = mpsc::channel(NDJSON_STREAM_CAPACITY);
+        let db = self.db.clone();
+        let buffer = self.observations.clone();
+        let events = self.events.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Some(buffer) = &buffer {
+                if let Err(e) = flush_observations(&db, buffer, &events) {
+                    let _ = tx.blocking_send(Err(e.context(HELD_OBSERVATIONS_UNWRITTEN)));
+                    return;
+                }
+            }
+            if let Err(e) = send_ndjson_pages(&db, &tx) {
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+        rx
+    }
+
+    /// Stream the entities matching a search by name, read in the background as the receiver
+    /// consumes them (at most SEARCH_STREAM_CAPACITY are buffered)
+    /// Matches are read in pages of STREAM_PAGE_SIZE like stream_ndjson, so a slow receiver
+    /// holds no connection; entities changed during the stream may show either state
+    /// A search error is sent as the last item; dropping the receiver stops the search
+    /// Matches are not counted by --track-access
+    pub fn stream_search(&self, query: String, mode: MatchMode) -> mpsc::Receiver<Result<Entity>> {
+        let (tx, rx) = mpsc::channel(SEARCH_STREAM_CAPACITY);
+        let db = self.db.clone();
+        let buffer = self.observations.clone();
+        let events = self.events.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Some(buffer) = &buffer {
+                if let Err(e) = flush_observations(&db, buffer, &events) {
+                    let _ = tx.blocking_send(Err(e.context(HELD_OBSERVATIONS_UNWRITTEN)));
+                    return;
+                }
+            }
+            if let Err(e) = send_search_pages(&db, &query, mode, &tx) {
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+        rx
     }
 
     /// Read entire knowledge graph
     pub async fn read_graph(&self) -> Result<KnowledgeGraph> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.read_graph())
-            .await
-            .context("Task panicked")?
+        self.blocking(move |db| db.read_graph()).await
+    }
+
+    /// Everything one create call inserted (by the batch id it reported)
+    pub async fn get_batch(&self, batch_id: String) -> Result<KnowledgeGraph> {
+        self.blocking(move |db| db.get_batch(&batch_id)).await
+    }
+
+    /// Read one page of the graph: entities by name with the relations touching them
+    pub async fn read_graph_page(&self, window: ReadWindow) -> Result<GraphPage> {
+        self.blocking(move |db| db.read_graph_page(window)).await
+    }
+
+    /// Read entire knowledge graph in deterministic order (entities by name, relations by from/to/type)
+    pub async fn read_graph_sorted(&self) -> Result<KnowledgeGraph> {
+        self.blocking(move |db| db.read_graph_sorted()).await
+    }
+
+    /// Current graph revision (increases with every change)
+    pub async fn revision(&self) -> Result<u64> {
+        self.blocking(move |db| db.revision()).await
+    }
+
+    /// Read the graph in deterministic order together with its revision (consistent snapshot)
+    pub async fn read_graph_with_revision(&self) -> Result<(u64, KnowledgeGraph)> {
+        self.blocking(move |db| db.read_graph_with_revision()).await
+    }
+
+    /// Read entire knowledge graph as adjacency list
+    pub async fn read_adjacency(&self) -> Result<AdjacencyList> {
+        self.blocking(move |db| db.read_adjacency()).await
+    }
+
+    /// Compute graph connectivity metrics (counts, density, average degree, orphans)
+    pub async fn graph_metrics(&self) -> Result<GraphMetrics> {
+        self.blocking(move |db| db.graph_metrics()).await
+    }
+
+    /// Aggregate relation counts by (from type, relation type, to type)
+    pub async fn relation_matrix(&self) -> Result<Vec<RelationTypeCount>> {
+        self.blocking(move |db| db.relation_matrix()).await
+    }
+
+    /// Implicit schema per entity type, inferred from the relations
+    pub async fn infer_ontology(&self) -> Result<Vec<TypeOntology>> {
+        self.blocking(move |db| db.infer_ontology()).await
     }
 
     /// Search nodes using FTS5 full-text search
     pub async fn search_nodes(&self, query: Option<String>) -> Result<KnowledgeGraph> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.search_nodes(query.as_deref()))
+        self.search_nodes_matching(query, MatchMode::All).await
+    }
+
+    /// Search nodes using FTS5, combining query terms per match mode
+    pub async fn search_nodes_matching(
+        &self,
+        query: Option<String>,
+        mode: MatchMode,
+    ) -> Result<KnowledgeGraph> {
+        let counted = is_query(&query);
+        let graph = self
+            .blocking(move |db| db.search_nodes_matching(query.as_deref(), mode))
+            .await?;
+        if counted {
+            self.note_access(graph.entities.iter().map(|e| e.name.as_str()));
+        }
+        Ok(graph)
+    }
+
+    /// Search entities ranked by relevance, most relevant first (optionally one window of them)
+    pub async fn search_nodes_scored(
+        &self,
+        query: String,
+        mode: MatchMode,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<ScoredEntity>> {
+        let scored = self
+            .blocking(move |db| db.search_nodes_scored(&query, mode, window))
+            .await?;
+        self.note_access(scored.iter().map(|s| s.entity.name.as_str()));
+        Ok(scored)
+    }
+
+    /// Search entities, returning only names (and types) per projection (optionally one window)
+    pub async fn search_entity_hits(
+        &self,
+        query: Option<String>,
+        mode: MatchMode,
+        projection: SearchProjection,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<SearchHit>> {
+        let counted = is_query(&query);
+        let hits = self
+            .blocking(move |db| db.search_entity_hits(query.as_deref(), mode, projection, window))
+            .await?;
+        if counted {
+            self.note_access(hits.iter().map(|h| h.name.as_str()));
+        }
+        Ok(hits)
+    }
+
+    /// Search nodes, also returning relations whose own text matches the query
+    pub async fn search_nodes_and_relations(
+        &self,
+        query: Option<String>,
+        mode: MatchMode,
+    ) -> Result<KnowledgeGraph> {
+        let counted = is_query(&query);
+        let graph = self
+            .blocking(move |db| db.search_nodes_and_relations(query.as_deref(), mode))
+            .await?;
+        if counted {
+            self.note_access(graph.entities.iter().map(|e| e.name.as_str()));
+        }
+        Ok(graph)
+    }
+
+    /// One window of search_nodes_matching, optionally with the matching relations touching it
+    pub async fn search_nodes_window(
+        &self,
+        query: Option<String>,
+        mode: MatchMode,
+        relation_matches: bool,
+        window: ReadWindow,
+    ) -> Result<KnowledgeGraph> {
+        let counted = is_query(&query);
+        let graph = self
+            .blocking(move |db| {
+                db.search_nodes_window(query.as_deref(), mode, relation_matches, window)
+            })
+            .await?;
+        if counted {
+            self.note_access(graph.entities.iter().map(|e| e.name.as_str()));
+        }
+        Ok(graph)
+    }
+
+    /// Search relations by text (from, to, relation type) using FTS5 (optionally one window)
+    pub async fn search_relations(
+        &self,
+        query: String,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<Relation>> {
+        self.blocking(move |db| db.search_relations(&query, window))
             .await
-            .context("Task panicked")?
+    }
+
+    /// Extract the n-hop neighborhood around an entity as a graph
+    pub async fn get_subgraph(&self, root: String, depth: u32) -> Result<KnowledgeGraph> {
+        self.blocking(move |db| db.get_subgraph(&root, depth)).await
+    }
+
+    /// Breadth-first traversal from root with distances, in visit order
+    pub async fn traverse(
+        &self,
+        root: String,
+        depth: u32,
+        direction: Direction,
+    ) -> Result<Traversal> {
+        self.blocking(move |db| db.traverse(&root, depth, direction))
+            .await
+    }
+
+    /// Up to max_paths shortest paths between two entities, with the relations on each hop
+    pub async fn find_paths(
+        &self,
+        from: String,
+        to: String,
+        max_depth: u32,
+        max_paths: usize,
+        direction: Direction,
+    ) -> Result<PathResult> {
+        self.blocking(move |db| db.find_paths(&from, &to, max_depth, max_paths, direction))
+            .await
+    }
+
+    /// Longest acyclic chain of relations from an entity (bounded search)
+    pub async fn longest_chain(
+        &self,
+        name: String,
+        relation_type: Option<String>,
+        direction: Direction,
+        max_depth: u32,
+    ) -> Result<ChainResult> {
+        self.blocking(move |db| {
+            db.longest_chain(&name, relation_type.as_deref(), direction, max_depth)
+        })
+        .await
+    }
+
+    /// Get direct neighbors of an entity with direction-annotated relations
+    pub async fn get_neighbors(&self, name: String, direction: Direction) -> Result<Neighborhood> {
+        self.blocking(move |db| db.get_neighbors(&name, direction))
+            .await
+    }
+
+    /// Relations of one entity grouped by direction and type
+    pub async fn relation_summary(&self, name: String) -> Result<RelationSummary> {
+        self.blocking(move |db| db.relation_summary(&name)).await
+    }
+
+    /// Validate entities as create_entities would, without writing
+    pub async fn check_entities(&self, entities: Vec<Entity>) -> Result<Vec<ItemCheck>> {
+        self.blocking(move |db| Ok(db.check_entities(&entities)))
+            .await
+    }
+
+    /// Validate relations as create_relations would (endpoints included), without writing
+    pub async fn check_relations(
+        &self,
+        relations: Vec<Relation>,
+        pending: Vec<String>,
+    ) -> Result<Vec<ItemCheck>> {
+        self.blocking(move |db| db.check_relations(&relations, &pending))
+            .await
+    }
+
+    /// Densely connected group of entities grown greedily from a seed
+    pub async fn community(
+        &self,
+        seed: String,
+        max_size: usize,
+        min_density: f64,
+    ) -> Result<Community> {
+        self.blocking(move |db| db.community(&seed, max_size, min_density))
+            .await
+    }
+
+    /// Observations of an entity with the time each was added
+    pub async fn observation_times(
+        &self,
+        name: String,
+        newest_first: bool,
+    ) -> Result<ObservationTimes> {
+        self.blocking(move |db| db.observation_times(&name, newest_first))
+            .await
+    }
+
+    /// An entity flattened into observations plus "<from> <type> <to>" relation facts
+    pub async fn get_facts(&self, name: String, include_relations: bool) -> Result<EntityFacts> {
+        self.blocking(move |db| db.get_facts(&name, include_relations))
+            .await
+    }
+
+    /// Entities two hops away that are not direct neighbors, with the neighbors between
+    pub async fn second_degree(&self, name: String, direction: Direction) -> Result<SecondDegree> {
+        self.blocking(move |db| db.second_degree(&name, direction))
+            .await
+    }
+
+    /// Number and types of the relations between two entities
+    pub async fn relation_count_between(
+        &self,
+        a: String,
+        b: String,
+        directed: bool,
+    ) -> Result<RelationCountBetween> {
+        self.blocking(move |db| db.relation_count_between(&a, &b, directed))
+            .await
+    }
+
+    /// Entities connected to both of two entities, with the linking relations
+    pub async fn common_neighbors(
+        &self,
+        a: String,
+        b: String,
+        direction: Direction,
+    ) -> Result<CommonNeighbors> {
+        self.blocking(move |db| db.common_neighbors(&a, &b, direction))
+            .await
+    }
+
+    /// List entities of several types, one page per type with per-type totals
+    pub async fn list_entities_by_types(
+        &self,
+        entity_types: Vec<String>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<EntityGroupPage> {
+        self.blocking(move |db| db.list_entities_by_types(&entity_types, limit, offset))
+            .await
+    }
+
+    /// List entities with exactly the given type (paginated, ordered by name)
+    pub async fn list_entities_by_type(
+        &self,
+        entity_type: String,
+        limit: usize,
+        offset: usize,
+    ) -> Result<EntityPage> {
+        self.blocking(move |db| db.list_entities_by_type(&entity_type, limit, offset))
+            .await
+    }
+
+    /// List relations of an exact type with pagination (optionally with endpoint entities)
+    pub async fn list_relations_by_type(
+        &self,
+        relation_type: String,
+        limit: usize,
+        offset: usize,
+        include_entities: bool,
+    ) -> Result<RelationPage> {
+        self.blocking(move |db| {
+            db.list_relations_by_type(&relation_type, limit, offset, include_entities)
+        })
+        .await
+    }
+
+    /// Most recently created or updated entities, newest first (paginated)
+    pub async fn recent_entities(&self, limit: usize, offset: usize) -> Result<Vec<RecentEntity>> {
+        self.blocking(move |db| db.recent_entities(limit, offset))
+            .await
+    }
+
+    /// Random sample of entities (reproducible with a seed, which also makes offset page it)
+    pub async fn sample_entities(
+        &self,
+        count: usize,
+        entity_type: Option<String>,
+        seed: Option<u64>,
+        offset: usize,
+    ) -> Result<Vec<Entity>> {
+        self.blocking(move |db| db.sample_entities(count, entity_type.as_deref(), seed, offset))
+            .await
+    }
+
+    /// Entities without observations, optionally of one type (paginated)
+    pub async fn find_empty_entities(
+        &self,
+        entity_type: Option<String>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Entity>> {
+        self.blocking(move |db| db.find_empty_entities(entity_type.as_deref(), limit, offset))
+            .await
+    }
+
+    /// Delete all entities without observations, optionally of one type
+    pub async fn delete_empty_entities(&self, entity_type: Option<String>) -> Result<Vec<String>> {
+        let deleted = self
+            .blocking(move |db| db.delete_empty_entities(entity_type.as_deref()))
+            .await?;
+        if !deleted.is_empty() {
+            self.publish(GraphEvent::EntitiesDeleted {
+                names: deleted.clone(),
+            })
+            .await;
+        }
+        Ok(deleted)
+    }
+
+    /// Delete all entities matching a full-text query (or just list them on a dry run)
+    pub async fn delete_by_query(
+        &self,
+        query: String,
+        mode: MatchMode,
+        dry_run: bool,
+    ) -> Result<Vec<String>> {
+        let names = self
+            .blocking(move |db| db.delete_by_query(&query, mode, dry_run))
+            .await?;
+        if !dry_run && !names.is_empty() {
+            self.publish(GraphEvent::EntitiesDeleted {
+                names: names.clone(),
+            })
+            .await;
+        }
+        Ok(names)
     }
 
     /// Open specific nodes by names
     pub async fn open_nodes(&self, names: Vec<String>) -> Result<KnowledgeGraph> {
-        let db = self.db.clone();
-        tokio::task::spawn_blocking(move || db.open_nodes(&names))
-            .await
-            .context("Task panicked")?
+        let graph = self.blocking(move |db| db.open_nodes(&names)).await?;
+        self.note_access(graph.entities.iter().map(|e| e.name.as_str()));
+        Ok(graph)
+    }
+
+    /// Inspect nodes by name: entity (or none), degrees and observation count per name
+    pub async fn inspect_nodes(&self, names: Vec<String>) -> Result<NodeInspections> {
+        let inspections = self.blocking(move |db| db.inspect_nodes(&names)).await?;
+        self.note_access(
+            inspections
+                .nodes
+                .values()
+                .filter_map(|node| node.entity.as_ref())
+                .map(|e| e.name.as_str()),
+        );
+        Ok(inspections)
+    }
+
+    /// Read entities by name without their relations
+    pub async fn entities_by_names(&self, names: Vec<String>) -> Result<Vec<Entity>> {
+        self.blocking(move |db| db.entities_by_names(&names)).await
+    }
+
+    /// Open specific nodes by their stable ids
+    pub async fn open_nodes_by_id(&self, ids: Vec<String>) -> Result<KnowledgeGraph> {
+        let graph = self.blocking(move |db| db.open_nodes_by_id(&ids)).await?;
+        self.note_access(graph.entities.iter().map(|e| e.name.as_str()));
+        Ok(graph)
     }
 }
+
+/// A blank search returns the whole graph: that is a dump, not a read of each entity
+fn is_query(query: &Option<String>) -> bool {
+    query.as_deref().is_some_and(|q| !q.trim().is_empty())
+}
@@ -0,0 +1,122 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// File extension every database file must carry
+pub const DB_EXTENSION: &str = "db";
+
+/// How a database path is checked and resolved
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbPathPolicy {
+    /// Create missing parent directories (otherwise the parent must already exist)
+    pub create_dirs: bool,
+    /// Directory the database must live under, compared after resolving '..' and symlinks
+    pub allowed_dir: Option<PathBuf>,
+}
+
+/// Check the .db extension of a database path (before any filesystem operation)
+pub fn validate_db_extension(path: &Path) -> Result<()> {
+    match path.extension() {
+        Some(ext) if ext == DB_EXTENSION => Ok(()),
+        Some(_) => bail!("Invalid database file extension (must be .db)"),
+        None => bail!("Database path must have .db extension"),
+    }
+}
+
+/// Resolve a path that may not exist yet to an absolute path without '..' or symlinks:
+/// the nearest existing ancestor is canonicalized and the missing rest is appended, with
+/// '.' and '..' applied lexically (the missing part cannot contain symlinks)
+/// Nothing is created, so the result can be checked before any directory is made
+pub fn resolve_nearest_existing(path: &Path) -> Result<PathBuf> {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .context("Failed to read the current directory")?
+            .join(path)
+    };
+    let components: Vec<Component> = absolute.components().collect();
+
+    // symlink_metadata so that a dangling symlink counts as existing (and then fails to
+    // resolve) instead of being treated as a missing name that could later be created
+    let existing = (1..=components.len())
+        .rev()
+        .find(|&len| {
+            components[..len]
+                .iter()
+                .collect::<PathBuf>()
+                .symlink_metadata()
+                .is_ok()
+        })
+        .unwrap_or(1);
+    let ancestor: PathBuf = components[..existing].iter().collect();
+    let mut resolved = ancestor
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve '{}'", ancestor.display()))?;
+
+    for component in &components[existing..] {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(resolved)
+}
+
+/// Validate a database path and resolve it to an absolute path without '..' or symlinks:
+/// checks the extension and, with an allowed directory, rejects paths that resolve outside
+/// of it; only then creates the parent directory (or requires it, per policy)
+/// The file itself does not need to exist yet
+pub fn resolve_db_path(path: &Path, policy: &DbPathPolicy) -> Result<PathBuf> {
+    validate_db_extension(path)?;
+    if path.file_name().is_none() {
+        bail!("Invalid database path: no file name");
+    }
+
+    // Resolves the file itself if it exists, so a symlinked database is followed too
+    let resolved = resolve_nearest_existing(path)?;
+
+    if let Some(allowed) = &policy.allowed_dir {
+        let allowed = allowed.canonicalize().with_context(|| {
+            format!(
+                "Allowed database directory '{}' is not usable",
+                allowed.display()
+            )
+        })?;
+        if !resolved.starts_with(&allowed) {
+            bail!(
+                "Database path '{}' is outside the allowed directory '{}'",
+                resolved.display(),
+                allowed.display()
+            );
+        }
+    }
+
+    let parent = resolved
+        .parent()
+        .context("Invalid database path: no parent directory")?;
+    if !parent.is_dir() {
+        if !policy.create_dirs {
+            let shown = match path.parent() {
+                Some(shown) if !shown.as_os_str().is_empty() => shown,
+                _ => parent,
+            };
+            bail!("Database directory '{}' does not exist", shown.display());
+        }
+        std::fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create database directory '{}'", parent.display())
+        })?;
+        // The directories were checked before they existed; make sure nothing swapped in
+        // a symlink meanwhile
+        if parent.canonicalize().ok().as_deref() != Some(parent) {
+            bail!(
+                "Database directory '{}' changed while it was created",
+                parent.display()
+            );
+        }
+    }
+
+    Ok(resolved)
+}
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+
+/// Normalized form of an entity name for duplicate detection (trimmed, lowercased)
+pub fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Normalized form of an identifier such as a relation type, so spelling conventions compare
+/// equal: camelCase humps and runs of non-alphanumerics become single underscores, lowercased
+/// ("worksAt", "works-at", "Works At" -> "works_at")
+pub fn normalize_identifier(identifier: &str) -> String {
+    let mut normalized = String::with_capacity(identifier.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in identifier.chars() {
+        if c.is_alphanumeric() {
+            let hump =
+                c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_numeric());
+            if hump && !normalized.is_empty() && !normalized.ends_with('_') {
+                normalized.push('_');
+            }
+            normalized.extend(c.to_lowercase());
+        } else if !normalized.is_empty() && !normalized.ends_with('_') {
+            normalized.push('_');
+        }
+        previous = Some(c);
+    }
+    normalized.truncate(normalized.trim_end_matches('_').len());
+    normalized
+}
+
+/// Normalized form of free text for equality checks (trimmed, whitespace runs collapsed,
+/// lowercased)
+pub fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Character trigrams of a normalized name, padded so short names still produce some
+/// ("ab" -> "  a", " ab", "ab ")
+pub fn trigrams(normalized: &str) -> HashSet<String> {
+    let padded: Vec<char> = format!("  {} ", normalized).chars().collect();
+    padded
+        .windows(3)
+        .map(|w| w.iter().collect::<String>())
+        .collect()
+}
+
+/// Jaccard similarity of two trigram sets (1.0 = identical sets)
+pub fn trigram_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let shared = a.intersection(b).count();
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        return 1.0;
+    }
+    shared as f64 / union as f64
+}
+
+/// Group names into clusters of likely duplicates, returning indices into `names`
+/// Names always cluster when their normalized forms are equal; with a threshold, names whose
+/// trigram similarity is at least `threshold` cluster too (transitively).
+/// Only clusters with two or more members are returned, each sorted, ordered by first index.
+pub fn cluster_names(names: &[String], threshold: Option<f64>) -> Vec<Vec<usize>> {
+    let normalized: Vec<String> = names.iter().map(|n| normalize_name(n)).collect();
+    cluster_normalized(&normalized, threshold)
+}
+
+/// cluster_names for identifiers, compared in their normalize_identifier form
+pub fn cluster_identifiers(identifiers: &[String], threshold: Option<f64>) -> Vec<Vec<usize>> {
+    let normalized: Vec<String> = identifiers
+        .iter()
+        .map(|i| normalize_identifier(i))
+        .collect();
+    cluster_normalized(&normalized, threshold)
+}
+
+/// Clusters of already normalized keys (see cluster_names)
+fn cluster_normalized(normalized: &[String], threshold: Option<f64>) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..normalized.len()).collect();
+
+    // Exact collisions of normalized names
+    let mut first_by_key: HashMap<&str, usize> = HashMap::new();
+    for (i, key) in normalized.iter().enumerate() {
+        match first_by_key.get(key.as_str()) {
+            Some(&first) => union(&mut parent, first, i),
+            None => {
+                first_by_key.insert(key, i);
+            }
+        }
+    }
+
+    // Fuzzy pairs: only compare names sharing at least one trigram (inverted index)
+    if let Some(threshold) = threshold {
+        let grams: Vec<HashSet<String>> = normalized.iter().map(|n| trigrams(n)).collect();
+        let mut index: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, set) in grams.iter().enumerate() {
+            for gram in set {
+                index.entry(gram.as_str()).or_default().push(i);
+            }
+        }
+
+        for (i, set) in grams.iter().enumerate() {
+            let mut shared: HashMap<usize, usize> = HashMap::new();
+            for gram in set {
+                for &j in &index[gram.as_str()] {
+                    if j > i {
+                        *shared.entry(j).or_default() += 1;
+                    }
+                }
+            }
+            for (j, count) in shared {
+                let union_size = set.len() + grams[j].len() - count;
+                if count as f64 / union_size as f64 >= threshold {
+                    union(&mut parent, i, j);
+                }
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..normalized.len() {
+        let root = find(&mut parent, i);
+        clusters.entry(root).or_default().push(i);
+    }
+    let mut clusters: Vec<Vec<usize>> = clusters
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .collect();
+    clusters.sort_by_key(|members| members[0]);
+    clusters
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (ra, rb) = (find(parent, a), find(parent, b));
+    if ra != rb {
+        // Keep the smaller index as root so cluster order is stable
+        parent[ra.max(rb)] = ra.min(rb);
+    }
+}
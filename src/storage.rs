@@ -1,38 +1,630 @@
 use crate::graph::{
-    Entity, KnowledgeGraph, ObservationDeletion, ObservationInput, ObservationResult, Relation,
+    AdjacencyEdge, AdjacencyEntry, AdjacencyList, ChainResult, CommonNeighbors, Community,
+    ConflictPolicy, CreateEntitiesResult, CreateRelationsResult, DanglingRelations,
+    DeduplicatedEntity, DeleteRelationsResult, DirectedRelation, Direction, DuplicateCandidate,
+    DuplicateCluster, EdgeDirection, Entity, EntityAccess, EntityAliases, EntityFacts,
+    EntityGroupPage, EntityObservations, EntityPage, EntityPath, EntityTypeGroup,
+    FanOutObservationResult, GraphMetrics, GraphOperation, GraphPage, GraphRecord, ImportanceRun,
+    ImportantEntity, ItemCheck, KnowledgeGraph, MatchMode, MergeGraphResult, Neighborhood,
+    NodeInspection, NodeInspections, ObservationCheck, ObservationCount, ObservationDeletion,
+    ObservationInput, ObservationReorder, ObservationResult, ObservationTimes, ObservationValue,
+    ObservationValuePage, OperationResult, PathResult, RecentEntity, Relation,
+    RelationCountBetween, RelationPage, RelationSummary, RelationTypeCount, RelationTypeSynonyms,
+    RelationTypeUsage, ReplacedEntity, ResolvedAlias, ReversedRelation, ScoredEntity, SearchHit,
+    SearchIndexCount, SearchProjection, SecondDegree, SortOrder, TimedObservation, Traversal,
+    TraversalNode, TypeLink, TypeOntology,
 };
+use crate::path::{resolve_db_path, validate_db_extension, DbPathPolicy};
+use crate::similarity;
 use anyhow::{bail, Context, Result};
-use r2d2::Pool;
+use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{params, Connection, OptionalExtension};
-use std::collections::HashSet;
-use std::path::Path;
+use rusqlite::backup::Progress;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{
+    params, params_from_iter, Connection, ErrorCode, OpenFlags, OptionalExtension, MAIN_DB,
+};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // Validation constants (chosen for practical limits while preventing abuse)
 const MAX_NAME_LENGTH: usize = 256; // Entity/relation names
 const MAX_TYPE_LENGTH: usize = 128; // Type identifiers
 const MAX_OBSERVATION_LENGTH: usize = 4096; // Individual observation text
+const MAX_DESCRIPTION_LENGTH: usize = 2048; // Entity description text
+const MAX_METADATA_LENGTH: usize = 8192; // Entity metadata, in bytes of compact JSON
+pub const MAX_PAGE_SIZE: usize = 1000; // Entities per page in paginated listings
+const MAX_LISTED_TYPES: usize = 100; // Types per list_entities_by_types call
+const MAX_ALIASES: usize = 100; // Aliases per add_aliases/remove_aliases call
+const MAX_SUBGRAPH_DEPTH: u32 = 10; // Hops for subgraph extraction
+const MAX_PATHS: usize = 20; // Shortest paths returned by find_paths
+const MAX_CHAIN_DEPTH: u32 = 50; // Hops of a longest_chain search
+const MAX_CHAIN_STEPS: usize = 100_000; // Relations a longest_chain search may follow
+const MAX_COMMUNITY_SIZE: usize = 200; // Members a community may grow to
+const MAX_IMPORTANCE_ITERATIONS: u32 = 100; // PageRank iterations per compute_importance
+const MAX_IMPORTANCE_ENTITIES: usize = 100_000; // Graph size compute_importance loads
+const MAX_IMPORTANCE_RELATIONS: usize = 1_000_000;
+const ENTITY_ID_LENGTH: usize = 32; // Hex chars of an entity id (16 random bytes)
+
+// PageRank: probability of following a link rather than jumping to a random entity, and the
+// total score change below which the scores count as settled
+const IMPORTANCE_DAMPING: f64 = 0.85;
+const IMPORTANCE_TOLERANCE: f64 = 1e-9;
+
+// Soft validation: accepted, but reported back as warnings
+const NEAR_LIMIT_NAME_PERCENT: usize = 90; // Names this long (percent of the limit) are near it
+const MIN_OBSERVATION_CHARS: usize = 3; // Shorter observations carry hardly any information
+const TYPE_SIMILARITY_THRESHOLD: f64 = 0.5; // New types this close to an existing one look like typos
+
+// Hot statements, run through each connection's statement cache (and primed by --prewarm)
+const SQL_SELECT_OBSERVATIONS: &str = "SELECT observations FROM entities WHERE name = ?1";
+const SQL_UPDATE_OBSERVATIONS: &str = "UPDATE entities
+     SET observations = observations_encode(?1), updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+     WHERE name = ?2";
+const SQL_INSERT_ENTITY: &str = "INSERT OR IGNORE INTO entities
+     (name, entity_type, observations, description, confidence, created_at, updated_at, id,
+      batch_id, metadata)
+     VALUES (?1, ?2, observations_encode(?3), ?4, ?5,
+             strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+             lower(hex(randomblob(16))), ?6, ?7)
+     RETURNING id";
+const SQL_INSERT_RELATION: &str = "INSERT OR IGNORE INTO relations
+     (from_entity, to_entity, relation_type, batch_id) VALUES (?1, ?2, ?3, ?4)";
+const SQL_DELETE_RELATION: &str =
+    "DELETE FROM relations WHERE from_entity = ?1 AND to_entity = ?2 AND relation_type = ?3";
+const SQL_READ_ENTITIES: &str =
+    "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities";
+const SQL_READ_RELATIONS: &str = "SELECT from_entity, to_entity, relation_type FROM relations";
+const SQL_SEARCH_ENTITIES: &str =
+    "SELECT e.name, e.entity_type, e.observations, e.description, e.id, e.confidence, e.metadata
+     FROM entities e
+     INNER JOIN entities_fts fts ON e.rowid = fts.rowid
+     WHERE entities_fts MATCH ?1";
+const SQL_SEARCH_ENTITIES_SCORED: &str =
+    "SELECT e.name, e.entity_type, e.observations, e.description, e.id, e.confidence, e.metadata,
+            -bm25(entities_fts)
+     FROM entities e
+     INNER JOIN entities_fts fts ON e.rowid = fts.rowid
+     WHERE entities_fts MATCH ?1";
+const SQL_SCORED_ORDER: &str = "ORDER BY bm25(entities_fts), e.name";
+const SQL_SEARCH_ENTITY_NAMES: &str = "SELECT e.name, NULL
+     FROM entities e
+     INNER JOIN entities_fts fts ON e.rowid = fts.rowid
+     WHERE entities_fts MATCH ?1";
+const SQL_SEARCH_ENTITY_NAMES_TYPES: &str = "SELECT e.name, e.entity_type
+     FROM entities e
+     INNER JOIN entities_fts fts ON e.rowid = fts.rowid
+     WHERE entities_fts MATCH ?1";
+const SQL_SEARCH_RELATIONS: &str = "SELECT r.from_entity, r.to_entity, r.relation_type
+     FROM relations r
+     INNER JOIN relations_fts fts ON r.id = fts.rowid
+     WHERE relations_fts MATCH ?1";
+
+const PREWARM_STATEMENTS: [&str; 9] = [
+    SQL_SELECT_OBSERVATIONS,
+    SQL_UPDATE_OBSERVATIONS,
+    SQL_INSERT_ENTITY,
+    SQL_INSERT_RELATION,
+    SQL_DELETE_RELATION,
+    SQL_READ_ENTITIES,
+    SQL_READ_RELATIONS,
+    SQL_SEARCH_ENTITIES,
+    SQL_SEARCH_RELATIONS,
+];
+
+// SQLite VM instructions between query timeout checks (cheap enough to keep latency low)
+const PROGRESS_HANDLER_OPS: i32 = 1000;
+
+const POOL_SIZE: u32 = 15; // Concurrent connections per pool
+const DEFAULT_POOL_TIMEOUT: Duration = Duration::from_secs(5); // Wait for a free connection
+const MAX_OBSERVATION_BUFFER: Duration = Duration::from_secs(60); // Longest add_observations delay
+
+// Stored observations starting with this marker are base64 of a zstd-compressed JSON array
+// (plain arrays always start with '[')
+const COMPRESSED_OBSERVATIONS_PREFIX: &str = "zstd:";
+#[cfg(feature = "compress-observations")]
+const OBSERVATION_COMPRESSION_LEVEL: i32 = 3;
 
 /// Connection customizer to set PRAGMAs on every new connection
 #[derive(Debug)]
-struct SqliteCustomizer;
+struct SqliteCustomizer {
+    cache_size: Option<CacheSize>,
+    mmap_size: Option<u64>,
+    compress_observations: bool,
+}
 
 impl r2d2::CustomizeConnection<Connection, rusqlite::Error> for SqliteCustomizer {
     fn on_acquire(&self, conn: &mut Connection) -> std::result::Result<(), rusqlite::Error> {
         // Enable FOREIGN KEY constraints (must be set per-connection, not persisted)
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+        // Cache and mmap sizes are per-connection too; unset keeps SQLite's defaults
+        if let Some(cache_size) = self.cache_size {
+            conn.pragma_update(None, "cache_size", cache_size.pragma_value())?;
+        }
+        if let Some(mmap_size) = self.mmap_size {
+            // mmap_size reports the applied value as a row, so it cannot go through execute
+            conn.pragma_update_and_check(None, "mmap_size", mmap_size as i64, |_| Ok(()))?;
+        }
+
+        // Writes go through observations_encode, so compression is decided per connection;
+        // observations_json is what the search index and set triggers see once it is on
+        register_observation_functions(conn, self.compress_observations)?;
+
+        // sample_key(rowid, seed): a reproducible shuffle order for seeded samples
+        conn.create_scalar_function(
+            "sample_key",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let key = sample_key(ctx.get::<i64>(0)? as u64, ctx.get::<i64>(1)? as u64);
+                Ok(key as i64)
+            },
+        )
+    }
+}
+
+/// Pseudo-random sort key of a row for a seed (SplitMix64 finalizer of rowid and seed mixed)
+fn sample_key(rowid: u64, seed: u64) -> u64 {
+    let mut z = rowid.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(seed);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Register observations_encode (stored form of a JSON array) and observations_json (JSON
+/// array of a stored value) on a connection
+fn register_observation_functions(
+    conn: &Connection,
+    compress: bool,
+) -> std::result::Result<(), rusqlite::Error> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    conn.create_scalar_function("observations_encode", 1, flags, move |ctx| {
+        let json = ctx.get::<String>(0)?;
+        if !compress {
+            return Ok(json);
+        }
+        encode_observations(&json).map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+    })?;
+    conn.create_scalar_function("observations_json", 1, flags, |ctx| {
+        let stored = ctx.get::<String>(0)?;
+        observations_json(&stored)
+            .map(|json| json.into_owned())
+            .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+    })
+}
+
+/// Compressed form of a JSON observation array, or the array itself when compressing does
+/// not make it shorter (short arrays grow from the zstd header and base64)
+#[cfg(feature = "compress-observations")]
+fn encode_observations(json: &str) -> Result<String> {
+    use base64::Engine as _;
+
+    let compressed = zstd::encode_all(json.as_bytes(), OBSERVATION_COMPRESSION_LEVEL)
+        .context("Failed to compress observations")?;
+    let encoded = format!(
+        "{}{}",
+        COMPRESSED_OBSERVATIONS_PREFIX,
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(compressed)
+    );
+    Ok(if encoded.len() < json.len() {
+        encoded
+    } else {
+        json.to_string()
+    })
+}
+
+#[cfg(not(feature = "compress-observations"))]
+fn encode_observations(json: &str) -> Result<String> {
+    Ok(json.to_string())
+}
+
+/// JSON observation array of a stored value, decompressing it if needed
+fn observations_json(stored: &str) -> Result<std::borrow::Cow<'_, str>> {
+    match stored.strip_prefix(COMPRESSED_OBSERVATIONS_PREFIX) {
+        Some(encoded) => decompress_observations(encoded).map(std::borrow::Cow::Owned),
+        None => Ok(std::borrow::Cow::Borrowed(stored)),
+    }
+}
+
+#[cfg(feature = "compress-observations")]
+fn decompress_observations(encoded: &str) -> Result<String> {
+    use base64::Engine as _;
+
+    let compressed = base64::engine::general_purpose::STANDARD_NO_PAD
+        .decode(encoded)
+        .context("Compressed observations are not valid base64")?;
+    let json =
+        zstd::decode_all(compressed.as_slice()).context("Failed to decompress observations")?;
+    String::from_utf8(json).context("Decompressed observations are not valid UTF-8")
+}
+
+#[cfg(not(feature = "compress-observations"))]
+fn decompress_observations(_encoded: &str) -> Result<String> {
+    bail!(
+        "Observations are compressed; rebuild with the compress-observations feature to read them"
+    )
+}
+
+/// Relations with an endpoint among the entities (ordered by from, to, type), leaving out those
+/// whose other endpoint fails the window's confidence filter
+fn relations_touching(
+    conn: &Connection,
+    entities: &[Entity],
+    window: &ReadWindow,
+) -> Result<Vec<Relation>> {
+    if entities.is_empty() {
+        return Ok(Vec::new());
+    }
+    let names = serde_json::to_string(&entities.iter().map(|e| &e.name).collect::<Vec<_>>())?;
+    let sql = format!(
+        "SELECT r.from_entity, r.to_entity, r.relation_type FROM relations r
+         WHERE (r.from_entity IN (SELECT value FROM json_each(?1))
+                OR r.to_entity IN (SELECT value FROM json_each(?1)))
+           AND NOT EXISTS (SELECT 1 FROM entities e
+                           WHERE e.name IN (r.from_entity, r.to_entity) AND NOT {})
+         ORDER BY r.from_entity, r.to_entity, r.relation_type",
+        window.confidence_filter("e.")
+    );
+    conn.prepare_cached(&sql)?
+        .query_map([names], |row| {
+            Ok(Relation {
+                from: row.get(0)?,
+                to: row.get(1)?,
+                relation_type: row.get(2)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to read relations of the page")
+}
+
+/// Decode a stored observations value (JSON array, possibly compressed)
+fn decode_observations(stored: &str) -> Result<Vec<String>> {
+    Ok(serde_json::from_str(&observations_json(stored)?)?)
+}
+
+/// A database call that did not finish within the query timeout (raised by the manager,
+/// which stops waiting for it; its statements are interrupted by the same deadline)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationTimedOut {
+    pub after: Duration,
+}
+
+impl std::fmt::Display for OperationTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation timed out after {} ms", self.after.as_millis())
+    }
+}
+
+impl std::error::Error for OperationTimedOut {}
+
+const BUDGET_PENDING: u8 = 0;
+const BUDGET_COMMITTED: u8 = 1;
+const BUDGET_ABANDONED: u8 = 2;
+
+thread_local! {
+    static CALL_BUDGET: RefCell<Option<Arc<CallBudget>>> = const { RefCell::new(None) };
+}
+
+/// Query timeout of one manager call, shared between the call and the task awaiting it
+/// The deadline is fixed when the call is issued, not when it gets a thread or a connection,
+/// and the caller may only report a timeout while nothing has been committed: from then on
+/// every commit of the call is rolled back instead
+#[derive(Debug)]
+pub struct CallBudget {
+    deadline: Instant,
+    state: AtomicU8,
+}
+
+impl CallBudget {
+    pub fn new(timeout: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            deadline: Instant::now() + timeout,
+            state: AtomicU8::new(BUDGET_PENDING),
+        })
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Give up on the call; false if it already committed (its outcome must then be awaited)
+    pub fn abandon(&self) -> bool {
+        self.state
+            .compare_exchange(
+                BUDGET_PENDING,
+                BUDGET_ABANDONED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map_or_else(|state| state == BUDGET_ABANDONED, |_| true)
+    }
+
+    fn is_abandoned(&self) -> bool {
+        self.state.load(Ordering::Acquire) == BUDGET_ABANDONED
+    }
+
+    /// Claim a commit for the call; false once the call was abandoned
+    fn begin_commit(&self) -> bool {
+        self.state
+            .compare_exchange(
+                BUDGET_PENDING,
+                BUDGET_COMMITTED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map_or_else(|state| state == BUDGET_COMMITTED, |_| true)
+    }
+
+    /// Run a database call on this thread under the budget (connections checked out by the
+    /// call arm its deadline and commit guard instead of a fresh query timeout)
+    pub fn run<T>(self: Arc<Self>, call: impl FnOnce() -> T) -> T {
+        struct Reset;
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                CALL_BUDGET.with(|budget| budget.borrow_mut().take());
+            }
+        }
+
+        CALL_BUDGET.with(|budget| *budget.borrow_mut() = Some(self));
+        let _reset = Reset;
+        call()
+    }
+}
+
+/// Every pooled connection stayed checked out for as long as a call may wait for one
+/// (the server is overloaded; retrying shortly after usually succeeds)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolExhausted {
+    pub connections: u32,
+    pub waited: Duration,
+}
+
+impl std::fmt::Display for PoolExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "All {} database connections are busy (waited {} ms)",
+            self.connections,
+            self.waited.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for PoolExhausted {}
+
+/// Check whether an error was caused by running out of pooled connections
+pub fn is_pool_exhausted(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.is::<PoolExhausted>())
+}
+
+/// Check whether an error was caused by the query timeout (an interrupted statement, or a
+/// call the manager stopped waiting for)
+pub fn is_query_timeout(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<OperationTimedOut>()
+            || matches!(
+                cause.downcast_ref::<rusqlite::Error>(),
+                Some(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::OperationInterrupted
+            )
+    })
+}
+
+/// One invalid item of a batch request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemError {
+    /// Zero-based position of the item in the request
+    pub index: usize,
+    /// Entity name, or "from -> to (type)" for relations
+    pub name: String,
+    /// First validation problem found for this item
+    pub message: String,
+}
+
+/// Validation failures of a batch request: every offending item, not only the first
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub failures: Vec<ItemError>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.failures.len();
+        write!(
+            f,
+            "{} invalid item{}:",
+            count,
+            if count == 1 { "" } else { "s" }
+        )?;
+        for (i, failure) in self.failures.iter().enumerate() {
+            let sep = if i == 0 { " " } else { "; " };
+            write!(
+                f,
+                "{}#{} '{}': {}",
+                sep, failure.index, failure.name, failure.message
+            )?;
+        }
         Ok(())
     }
 }
 
-/// Validate entity/relation name (alphanumeric, spaces, dashes, underscores, dots)
+impl std::error::Error for ValidationError {}
+
+/// Batch validation failures behind an error, if that is what it is
+pub fn validation_failures(err: &anyhow::Error) -> Option<&ValidationError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<ValidationError>())
+}
+
+/// Validate every item of a batch, collecting all failures instead of stopping at the first
+/// Returns the per-item results (e.g. normalized items) when everything is valid
+fn validate_batch<T, R>(
+    items: &[T],
+    name: impl Fn(&T) -> String,
+    mut check: impl FnMut(&T) -> Result<R>,
+) -> Result<Vec<R>> {
+    let mut valid = Vec::with_capacity(items.len());
+    let mut failures = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        match check(item) {
+            Ok(result) => valid.push(result),
+            Err(e) => failures.push(ItemError {
+                index,
+                name: name(item),
+                message: format!("{:#}", e),
+            }),
+        }
+    }
+    if !failures.is_empty() {
+        return Err(ValidationError { failures }.into());
+    }
+    Ok(valid)
+}
+
+/// Item label for a relation in batch validation errors
+fn relation_label(rel: &Relation) -> String {
+    format!("{} -> {} ({})", rel.from, rel.to, rel.relation_type)
+}
+
+/// Validate the aliases of an add_aliases/remove_aliases call (names, like entities)
+fn validate_aliases(aliases: &[String]) -> Result<()> {
+    if aliases.is_empty() || aliases.len() > MAX_ALIASES {
+        bail!(
+            "Aliases must list between 1 and {} names (got {})",
+            MAX_ALIASES,
+            aliases.len()
+        );
+    }
+    validate_batch(aliases, String::clone, |alias| {
+        validate_name(alias, "Alias")
+    })?;
+    Ok(())
+}
+
+/// Validate entity names of a delete batch
+fn validate_entity_names(names: &[String]) -> Result<()> {
+    validate_batch(names, String::clone, |name| {
+        validate_name(name, "Entity name")
+    })?;
+    Ok(())
+}
+
+/// Validate entity names of an observation delete batch
+fn validate_observation_deletions(deletions: &[ObservationDeletion]) -> Result<()> {
+    validate_batch(
+        deletions,
+        |deletion| deletion.entity_name.clone(),
+        |deletion| validate_name(&deletion.entity_name, "Entity name"),
+    )?;
+    Ok(())
+}
+
+/// Validate names and types of relations to delete (no allowlist: existing ones may predate it)
+fn validate_relation_keys(relations: &[Relation]) -> Result<()> {
+    validate_batch(relations, relation_label, |rel| {
+        validate_endpoint(&rel.from, "From entity")?;
+        validate_endpoint(&rel.to, "To entity")?;
+        validate_type(&rel.relation_type, "Relation type")
+    })?;
+    Ok(())
+}
+
+/// Warning line in the same "#index 'name': message" form as validation failures
+fn warning(index: usize, name: &str, message: impl fmt::Display) -> String {
+    format!("#{} '{}': {}", index, name, message)
+}
+
+/// Warnings for observations that are blank or too short to say anything
+fn observation_warnings(index: usize, name: &str, observations: &[String]) -> Vec<String> {
+    observations
+        .iter()
+        .filter_map(|obs| {
+            let chars = obs.trim().chars().count();
+            if chars == 0 {
+                Some(warning(index, name, "observation is blank"))
+            } else if chars < MIN_OBSERVATION_CHARS {
+                Some(warning(
+                    index,
+                    name,
+                    format!("observation '{}' is very short", obs.trim()),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Warning for a type not yet in use that looks like a variant of types that are
+/// (same after trim + lowercase, or trigram-similar)
+fn type_warning(kind: &str, new_type: &str, vocabulary: &BTreeSet<String>) -> Option<String> {
+    if vocabulary.contains(new_type) {
+        return None;
+    }
+    let normalized = similarity::normalize_name(new_type);
+    let grams = similarity::trigrams(&normalized);
+    let similar: Vec<&str> = vocabulary
+        .iter()
+        .filter(|existing| {
+            let other = similarity::normalize_name(existing);
+            other == normalized
+                || similarity::trigram_similarity(&grams, &similarity::trigrams(&other))
+                    >= TYPE_SIMILARITY_THRESHOLD
+        })
+        .map(String::as_str)
+        .collect();
+    if similar.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{} type '{}' is new but resembles existing {}",
+        kind,
+        new_type,
+        similar
+            .iter()
+            .map(|t| format!("'{}'", t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// Distinct values of a type column (entity_type or relation_type)
+fn type_vocabulary(conn: &Connection, table: &str, column: &str) -> Result<BTreeSet<String>> {
+    let mut stmt = conn.prepare(&format!("SELECT DISTINCT {} FROM {}", column, table))?;
+    let types = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<BTreeSet<String>>>()
+        .with_context(|| format!("Failed to read {} vocabulary", column))?;
+    Ok(types)
+}
+
+/// Validate a stable entity id (32 lowercase hex characters, as assigned on insert)
+fn validate_entity_id(id: &str) -> Result<()> {
+    if id.len() != ENTITY_ID_LENGTH
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        bail!(
+            "Entity id must be {} lowercase hex characters",
+            ENTITY_ID_LENGTH
+        );
+    }
+    Ok(())
+}
+
+/// Validate an entity name (no control characters, at most MAX_NAME_LENGTH bytes)
 fn validate_name(name: &str, field: &str) -> Result<()> {
     if name.is_empty() {
         bail!("{} cannot be empty", field);
     }
-    if name.len() > MAX_NAME_LENGTH {
-        bail!("{} too long (max {} chars)", field, MAX_NAME_LENGTH);
-    }
+    check_lookup_length(name, MAX_NAME_LENGTH, field)?;
     // Check for control characters and null bytes
     if name.chars().any(|c| c.is_control() || c == '\0') {
         bail!("{} contains invalid characters", field);
@@ -40,14 +632,46 @@ fn validate_name(name: &str, field: &str) -> Result<()> {
     Ok(())
 }
 
+/// Fail if text is longer than max, measured in the given unit (named in the error)
+fn check_length(text: &str, max: usize, unit: LengthUnit, field: &str) -> Result<()> {
+    let length = unit.measure(text);
+    if length > max {
+        bail!(
+            "{} too long ({} {}, max {} {})",
+            field,
+            length,
+            unit,
+            max,
+            unit
+        );
+    }
+    Ok(())
+}
+
+/// Length check for names and types that only have to reference stored ones: counted in
+/// characters, so whatever either length unit let through on write is accepted (text never
+/// has more characters than bytes)
+fn check_lookup_length(text: &str, max: usize, field: &str) -> Result<()> {
+    check_length(text, max, LengthUnit::Chars, field)
+}
+
+/// Validate a relation endpoint: only that it can reference an entity name at all
+/// Whether the entity exists is decided by the lookup (or FOREIGN KEY) that follows,
+/// so the naming policy for new entities is not applied a second time here
+fn validate_endpoint(name: &str, field: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("{} cannot be empty", field);
+    }
+    check_lookup_length(name, MAX_NAME_LENGTH, field)?;
+    Ok(())
+}
+
 /// Validate type (alphanumeric, dashes, underscores)
 fn validate_type(type_str: &str, field: &str) -> Result<()> {
     if type_str.is_empty() {
         bail!("{} cannot be empty", field);
     }
-    if type_str.len() > MAX_TYPE_LENGTH {
-        bail!("{} too long (max {} chars)", field, MAX_TYPE_LENGTH);
-    }
+    check_lookup_length(type_str, MAX_TYPE_LENGTH, field)?;
     // Only allow alphanumeric, dash, underscore, dot, colon (for namespaced types)
     if !type_str
         .chars()
@@ -61,19 +685,180 @@ fn validate_type(type_str: &str, field: &str) -> Result<()> {
     Ok(())
 }
 
-/// Validate observation content
-fn validate_observation(obs: &str) -> Result<()> {
-    if obs.len() > MAX_OBSERVATION_LENGTH {
+/// Validate type against an optional allowlist (None = any valid type)
+fn validate_allowed_type(
+    type_str: &str,
+    allowed: Option<&BTreeSet<String>>,
+    field: &str,
+) -> Result<()> {
+    if let Some(allowed) = allowed {
+        if !allowed.contains(type_str) {
+            bail!(
+                "{} '{}' is not allowed (permitted: {})",
+                field,
+                type_str,
+                allowed.iter().cloned().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Validate a page size for paginated listings
+fn validate_page_limit(limit: usize) -> Result<()> {
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        bail!(
+            "Limit must be between 1 and {} (got {})",
+            MAX_PAGE_SIZE,
+            limit
+        );
+    }
+    Ok(())
+}
+
+/// Slice of a read: at most `limit` rows from `offset`, in a stable order (by name unless the
+/// read ranks its rows), optionally skipping entities with a confidence below `min_confidence`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReadWindow {
+    pub limit: usize,
+    pub offset: usize,
+    pub min_confidence: Option<f64>,
+}
+
+impl ReadWindow {
+    fn validate(&self) -> Result<()> {
+        validate_page_limit(self.limit)?;
+        if let Some(min) = self.min_confidence {
+            validate_confidence(min)?;
+        }
+        Ok(())
+    }
+
+    /// SQL condition keeping entities (table alias `alias`) that pass the confidence filter;
+    /// entities without a confidence always pass
+    fn confidence_filter(&self, alias: &str) -> String {
+        match self.min_confidence {
+            // Validated to 0.0-1.0, so the literal cannot carry anything but a number
+            Some(min) => format!(
+                "({0}confidence IS NULL OR {0}confidence >= {1:?})",
+                alias, min
+            ),
+            None => "1".to_string(),
+        }
+    }
+
+    /// LIMIT/OFFSET clause of the window
+    fn slice(&self) -> String {
+        format!("LIMIT {} OFFSET {}", self.limit, self.offset)
+    }
+}
+
+/// Validate entity description (length and null bytes)
+fn validate_description(description: &str, unit: LengthUnit) -> Result<()> {
+    check_length(description, MAX_DESCRIPTION_LENGTH, unit, "Description")?;
+    if description.contains('\0') {
+        bail!("Description contains null bytes");
+    }
+    Ok(())
+}
+
+/// Validate a confidence score (0.0-1.0 inclusive)
+pub fn validate_confidence(confidence: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&confidence) {
+        bail!("Confidence must be between 0.0 and 1.0, got {}", confidence);
+    }
+    Ok(())
+}
+
+/// Validate entity metadata (size of its compact JSON form)
+fn validate_metadata(metadata: &serde_json::Value) -> Result<()> {
+    let length = metadata.to_string().len();
+    if length > MAX_METADATA_LENGTH {
         bail!(
-            "Observation too long (max {} chars)",
-            MAX_OBSERVATION_LENGTH
+            "Metadata too long: {} bytes of JSON (max {})",
+            length,
+            MAX_METADATA_LENGTH
         );
     }
+    Ok(())
+}
+
+/// Validate observation content against the configured format
+/// Returns the observation to store (normalized for markdown, unchanged otherwise)
+fn validate_observation(obs: &str, format: ObservationFormat, unit: LengthUnit) -> Result<String> {
+    check_length(obs, MAX_OBSERVATION_LENGTH, unit, "Observation")?;
     // Check for null bytes (control characters in observations might be valid)
     if obs.contains('\0') {
         bail!("Observation contains null bytes");
     }
-    Ok(())
+    match format {
+        ObservationFormat::Plain => Ok(obs.to_string()),
+        ObservationFormat::Json => {
+            serde_json::from_str::<serde_json::Value>(obs)
+                .with_context(|| format!("Observation is not valid JSON: '{}'", obs))?;
+            Ok(obs.to_string())
+        }
+        ObservationFormat::Markdown => Ok(strip_html(obs)),
+    }
+}
+
+/// Remove raw HTML from markdown: tags and comments are dropped, script/style elements
+/// lose their content too. Autolinks (`<https://...>`) survive unless they use a
+/// scriptable scheme. A '<' not starting a tag (e.g. "a < b") is kept as text.
+fn strip_html(text: &str) -> String {
+    const DROP_CONTENT: [&str; 2] = ["script", "style"];
+    const UNSAFE_SCHEMES: [&str; 3] = ["javascript:", "vbscript:", "data:"];
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let starts_tag = candidate[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let end = if starts_tag {
+            candidate.find('>')
+        } else {
+            None
+        };
+        let Some(end) = end else {
+            out.push('<');
+            rest = &candidate[1..];
+            continue;
+        };
+
+        let inner = &candidate[1..end];
+        let lower = inner.to_ascii_lowercase();
+        rest = &candidate[end + 1..];
+
+        // Markdown autolink: keep unless it is a scriptable URL
+        if !inner.contains(char::is_whitespace) && inner.contains(':') && !inner.starts_with('/') {
+            if !UNSAFE_SCHEMES
+                .iter()
+                .any(|scheme| lower.starts_with(scheme))
+            {
+                out.push_str(&candidate[..=end]);
+            }
+            continue;
+        }
+
+        let name: String = lower
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if DROP_CONTENT.contains(&name.as_str()) {
+            // Skip everything up to and including the closing tag (or to the end)
+            let closing = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(pos) => rest[pos..].find('>').map_or("", |gt| &rest[pos + gt + 1..]),
+                None => "",
+            };
+        }
+    }
+    out.push_str(rest);
+    out
 }
 
 /// Build SQL placeholders for IN queries (?1, ?2, ?3, ...)
@@ -88,10 +873,17 @@ fn build_placeholders(count: usize, offset: usize) -> String {
 /// Escape FTS5 special characters in user query.
 /// NOTE: This intentionally disables FTS5 operators (OR/NEAR/*) by quoting each term,
 /// yielding a simple AND-of-words search to avoid syntax errors and injection.
-fn sanitize_fts5_query(query: &str) -> String {
-    // Split on whitespace, quote each term, rejoin with space (implicit AND)
-    query
+/// Terms without a letter or digit (`*`, `()`) hold no token and are dropped; a query left
+/// without terms becomes the empty phrase `""`, which matches nothing.
+fn sanitize_fts5_query(query: &str, mode: MatchMode) -> String {
+    // Split on whitespace, quote each term, rejoin with space (implicit AND) or OR
+    let separator = match mode {
+        MatchMode::All => " ",
+        MatchMode::Any => " OR ",
+    };
+    let sanitized = query
         .split_whitespace()
+        .filter(|term| term.chars().any(char::is_alphanumeric))
         .map(|term| {
             // Strip existing quotes to avoid triple-quoting issues
             let stripped = term.trim_matches('"');
@@ -100,657 +892,5738 @@ fn sanitize_fts5_query(query: &str) -> String {
             format!("\"{}\"", escaped)
         })
         .collect::<Vec<_>>()
-        .join(" ")
+        .join(separator);
+    if sanitized.is_empty() {
+        "\"\"".to_string()
+    } else {
+        sanitized
+    }
 }
 
-/// Validate database file path
-fn validate_db_path(path: &Path) -> Result<()> {
-    // Check file extension FIRST (before any filesystem operations)
-    if let Some(ext) = path.extension() {
-        if ext != "db" {
-            bail!("Invalid database file extension (must be .db)");
-        }
+/// Error of a search statement, with FTS5 query syntax errors (which sanitization should
+/// prevent) reported as an unparsable query rather than a raw SQLite message
+fn search_error(err: rusqlite::Error) -> anyhow::Error {
+    if err.to_string().contains("fts5") {
+        anyhow::anyhow!("Could not parse search query")
     } else {
-        bail!("Database path must have .db extension");
+        anyhow::Error::new(err)
     }
-    Ok(())
 }
 
-const SCHEMA: &str = r#"
--- Entities table
-CREATE TABLE IF NOT EXISTS entities (
-    name TEXT PRIMARY KEY NOT NULL,
-    entity_type TEXT NOT NULL,
-    observations TEXT NOT NULL
-) STRICT;
+/// WHERE clause matching every term of a search query (or any, per mode) with LIKE, used
+/// instead of FTS5 when full-text search is disabled; `term_matches` builds the condition
+/// for one term from its placeholder. Returns the clause and one pattern per term.
+fn like_filter(
+    query: &str,
+    mode: MatchMode,
+    term_matches: impl Fn(&str) -> String,
+) -> (String, Vec<String>) {
+    let separator = match mode {
+        MatchMode::All => " AND ",
+        MatchMode::Any => " OR ",
+    };
+    let patterns: Vec<String> = query
+        .split_whitespace()
+        .map(|term| term.trim_matches('"'))
+        .filter(|term| !term.is_empty())
+        .map(|term| {
+            let escaped = term
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+        .collect();
+    if patterns.is_empty() {
+        return ("0".to_string(), patterns);
+    }
+    let clause = (1..=patterns.len())
+        .map(|i| term_matches(&format!("?{}", i)))
+        .collect::<Vec<_>>()
+        .join(separator);
+    (clause, patterns)
+}
 
--- Relations table with FOREIGN KEY for cascade delete
-CREATE TABLE IF NOT EXISTS relations (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    from_entity TEXT NOT NULL,
-    to_entity TEXT NOT NULL,
-    relation_type TEXT NOT NULL,
-    UNIQUE(from_entity, to_entity, relation_type),
-    FOREIGN KEY(from_entity) REFERENCES entities(name) ON DELETE CASCADE,
-    FOREIGN KEY(to_entity) REFERENCES entities(name) ON DELETE CASCADE
-) STRICT;
+/// LIKE stand-in for the entity FTS5 index: the name, type, description or one observation
+/// contains the term (case-insensitive for ASCII only)
+fn like_entity_term(placeholder: &str) -> String {
+    format!(
+        "(e.name LIKE {p} ESCAPE '\\' OR e.entity_type LIKE {p} ESCAPE '\\'
+          OR e.description LIKE {p} ESCAPE '\\'
+          OR EXISTS (SELECT 1 FROM json_each(observations_json(e.observations))
+                     WHERE value LIKE {p} ESCAPE '\\'))",
+        p = placeholder
+    )
+}
 
--- Indexes for performance
-CREATE INDEX IF NOT EXISTS idx_entity_type ON entities(entity_type);
-CREATE INDEX IF NOT EXISTS idx_from ON relations(from_entity);
-CREATE INDEX IF NOT EXISTS idx_to ON relations(to_entity);
-CREATE INDEX IF NOT EXISTS idx_relation_type ON relations(relation_type);
+/// LIKE stand-in for the relation FTS5 index: an endpoint or the type contains the term
+fn like_relation_term(placeholder: &str) -> String {
+    format!(
+        "(r.from_entity LIKE {p} ESCAPE '\\' OR r.to_entity LIKE {p} ESCAPE '\\'
+          OR r.relation_type LIKE {p} ESCAPE '\\')",
+        p = placeholder
+    )
+}
 
--- Compound indexes for complex queries
-CREATE INDEX IF NOT EXISTS idx_relations_from_type ON relations(from_entity, relation_type);
-CREATE INDEX IF NOT EXISTS idx_relations_to_type ON relations(to_entity, relation_type);
+/// Characters of a search query that reach FTS5 (whitespace and quotes do not count)
+fn query_length(query: &str) -> usize {
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '"')
+        .count()
+}
 
--- FTS5 virtual table for full-text search
-CREATE VIRTUAL TABLE IF NOT EXISTS entities_fts USING fts5(
-    name,
-    entity_type,
-    observations,
-    content='entities',
-    content_rowid='rowid'
-);
+/// Load observations array of an entity (errors if the entity does not exist)
+/// action: what the caller is doing, used in the error message
+fn load_observations(conn: &Connection, entity_name: &str, action: &str) -> Result<Vec<String>> {
+    let current: Option<String> = conn
+        .prepare_cached(SQL_SELECT_OBSERVATIONS)
+        .and_then(|mut stmt| stmt.query_row(params![entity_name], |row| row.get(0)))
+        .optional()
+        .with_context(|| format!("Database error querying entity '{}'", entity_name))?;
 
--- Triggers to keep FTS5 in sync with entities table
-CREATE TRIGGER IF NOT EXISTS entities_ai AFTER INSERT ON entities BEGIN
-    INSERT INTO entities_fts(rowid, name, entity_type, observations)
-    VALUES (new.rowid, new.name, new.entity_type, new.observations);
-END;
+    let current = current.with_context(|| {
+        format!(
+            "Cannot {}: entity '{}' does not exist{}",
+            action,
+            entity_name,
+            typed_key_hint(conn, entity_name)
+        )
+    })?;
 
-CREATE TRIGGER IF NOT EXISTS entities_ad AFTER DELETE ON entities BEGIN
-    INSERT INTO entities_fts(entities_fts, rowid, name, entity_type, observations)
-    VALUES ('delete', old.rowid, old.name, old.entity_type, old.observations);
-END;
+    decode_observations(&current)
+        .with_context(|| format!("Corrupted observations data for entity '{}'", entity_name))
+}
 
-CREATE TRIGGER IF NOT EXISTS entities_au AFTER UPDATE ON entities BEGIN
-    INSERT INTO entities_fts(entities_fts, rowid, name, entity_type, observations)
-    VALUES ('delete', old.rowid, old.name, old.entity_type, old.observations);
-    INSERT INTO entities_fts(rowid, name, entity_type, observations)
-    VALUES (new.rowid, new.name, new.entity_type, new.observations);
-END;
-"#;
+/// Store observations array of an entity
+fn store_observations(conn: &Connection, entity_name: &str, observations: &[String]) -> Result<()> {
+    let obs_json = serde_json::to_string(observations).with_context(|| {
+        format!(
+            "Failed to serialize observations for entity '{}'",
+            entity_name
+        )
+    })?;
+    conn.prepare_cached(SQL_UPDATE_OBSERVATIONS)
+        .and_then(|mut stmt| stmt.execute(params![&obs_json, entity_name]))
+        .with_context(|| format!("Failed to update observations for entity '{}'", entity_name))?;
+    Ok(())
+}
 
-pub struct Database {
-    pool: Pool<SqliteConnectionManager>,
+/// Map normalized entity names to stored names for create-time deduplication
+fn load_normalized_names(
+    conn: &Connection,
+    dedup: &DedupOptions,
+) -> Result<HashMap<String, String>> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM entities")
+        .context("Failed to prepare entity name query")?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to read entity names")?;
+
+    let mut known = HashMap::with_capacity(names.len());
+    for name in names {
+        // Keep the first stored name if the table already holds near-duplicates
+        known.entry(dedup.normalize(&name)).or_insert(name);
+    }
+    Ok(known)
 }
 
-impl Database {
-    /// Open or create database with connection pool
-    pub fn open(path: &Path) -> Result<Self> {
-        // Validate path first
-        validate_db_path(path)?;
+/// Replace the type and observations of an entity, returning false if it does not exist
+/// Relations, id, description, confidence and metadata are kept
+fn overwrite_entity(conn: &Connection, entity: &Entity) -> Result<bool> {
+    let obs_json = serde_json::to_string(&entity.observations).with_context(|| {
+        format!(
+            "Failed to serialize observations for entity '{}'",
+            entity.name
+        )
+    })?;
+    let updated = conn
+        .execute(
+            "UPDATE entities
+             SET entity_type = ?1, observations = observations_encode(?2),
+                 updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+             WHERE name = ?3",
+            params![&entity.entity_type, &obs_json, &entity.name],
+        )
+        .with_context(|| format!("Failed to replace entity '{}'", entity.name))?;
+    Ok(updated > 0)
+}
 
-        // Create parent directory if needed
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+/// Append observations not yet present on an entity, returning the ones added
+fn merge_observations(
+    conn: &Connection,
+    entity_name: &str,
+    incoming: &[String],
+) -> Result<Vec<String>> {
+    let mut observations = load_observations(conn, entity_name, "merge observations")?;
+    let mut added = Vec::new();
+    for obs in incoming {
+        if !observations.contains(obs) {
+            observations.push(obs.clone());
+            added.push(obs.clone());
         }
+    }
+    if !added.is_empty() {
+        store_observations(conn, entity_name, &observations)?;
+    }
+    Ok(added)
+}
 
-        let manager = SqliteConnectionManager::file(path);
-        let pool = Pool::builder()
-            .max_size(15) // Allow up to 15 concurrent connections
-            .connection_customizer(Box::new(SqliteCustomizer)) // Apply PRAGMAs per-connection
-            .build(manager)
-            .context("Failed to create connection pool")?;
-
-        // Initialize schema on first connection (WAL mode persists in DB file)
-        {
-            let conn = pool.get().context("Failed to get connection from pool")?;
+/// Walk BFS predecessor edges back from the last entity in `entities` to `start`,
+/// pushing each complete path (reversed into start -> end order) until `limit` is reached
+fn collect_paths(
+    predecessors: &HashMap<String, Vec<(String, Relation)>>,
+    start: &str,
+    entities: &mut Vec<String>,
+    relations: &mut Vec<Relation>,
+    paths: &mut Vec<EntityPath>,
+    limit: usize,
+) {
+    let current = entities.last().cloned().unwrap_or_default();
+    if current == start {
+        paths.push(EntityPath {
+            entities: entities.iter().rev().cloned().collect(),
+            relations: relations.iter().rev().cloned().collect(),
+        });
+        return;
+    }
+    for (previous, relation) in predecessors.get(&current).into_iter().flatten() {
+        if paths.len() >= limit {
+            return;
+        }
+        entities.push(previous.clone());
+        relations.push(relation.clone());
+        collect_paths(predecessors, start, entities, relations, paths, limit);
+        entities.pop();
+        relations.pop();
+    }
+}
 
-            // WAL mode for concurrent reads (persisted in DB, only need to set once)
-            conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+/// Depth-first search state of longest_chain: relations of each visited entity are loaded
+/// once, and every relation followed counts against the step budget
+struct ChainSearch<'a> {
+    conn: &'a Connection,
+    relation_type: Option<&'a str>,
+    direction: Direction,
+    max_depth: usize,
+    steps_left: usize,
+    edges: HashMap<String, Vec<(String, Relation)>>,
+    best: EntityPath,
+    exhaustive: bool,
+    cyclic: bool,
+}
 
-            // Create schema
-            conn.execute_batch(SCHEMA)?;
+impl ChainSearch<'_> {
+    /// Relations leading on from an entity in the search direction, with the entity at the
+    /// other end, ordered by (other entity, relation type) so results are deterministic
+    fn edges(&mut self, name: &str) -> Result<Vec<(String, Relation)>> {
+        if let Some(edges) = self.edges.get(name) {
+            return Ok(edges.clone());
         }
+        let filter = match self.direction {
+            Direction::Outgoing => "from_entity = ?1",
+            Direction::Incoming => "to_entity = ?1",
+            Direction::Both => "(from_entity = ?1 OR to_entity = ?1)",
+        };
+        let query = format!(
+            "SELECT from_entity, to_entity, relation_type FROM relations
+             WHERE {} AND (?2 IS NULL OR relation_type = ?2)",
+            filter
+        );
+        let relations = self
+            .conn
+            .prepare_cached(&query)?
+            .query_map(params![name, self.relation_type], |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read chain relations")?;
 
-        Ok(Self { pool })
+        let mut edges = Vec::with_capacity(relations.len());
+        for relation in relations {
+            if self.direction != Direction::Incoming && relation.from == name {
+                edges.push((relation.to.clone(), relation.clone()));
+            }
+            if self.direction != Direction::Outgoing && relation.to == name {
+                edges.push((relation.from.clone(), relation));
+            }
+        }
+        edges.sort_by(|a, b| (&a.0, &a.1.relation_type).cmp(&(&b.0, &b.1.relation_type)));
+        self.edges.insert(name.to_string(), edges.clone());
+        Ok(edges)
     }
 
-    /// Create entities (returns only newly created entities)
-    /// Optimized: Uses INSERT OR IGNORE with tracking, no full table scan
-    /// Wrapped in transaction for atomicity
-    pub fn create_entities(&self, entities: &[Entity]) -> Result<Vec<Entity>> {
-        if entities.is_empty() {
-            return Ok(Vec::new());
+    /// Extend the chain ending at the last entity in every possible way, keeping the longest
+    fn extend(&mut self, entities: &mut Vec<String>, relations: &mut Vec<Relation>) -> Result<()> {
+        if relations.len() > self.best.relations.len() {
+            self.best = EntityPath {
+                entities: entities.clone(),
+                relations: relations.clone(),
+            };
+        }
+        let current = entities.last().cloned().unwrap_or_default();
+        for (next, relation) in self.edges(&current)? {
+            // Following "both" ways, the relation just taken leads straight back: not a cycle
+            if relations.last() == Some(&relation) {
+                continue;
+            }
+            if entities.contains(&next) {
+                self.cyclic = true;
+                continue;
+            }
+            if relations.len() >= self.max_depth || self.steps_left == 0 {
+                self.exhaustive = false;
+                return Ok(());
+            }
+            self.steps_left -= 1;
+            entities.push(next);
+            relations.push(relation);
+            self.extend(entities, relations)?;
+            entities.pop();
+            relations.pop();
         }
+        Ok(())
+    }
+}
 
-        // Validate all entities before starting transaction
-        for entity in entities {
-            validate_name(&entity.name, "Entity name")?;
-            validate_type(&entity.entity_type, "Entity type")?;
-            for obs in &entity.observations {
-                validate_observation(obs)?;
+/// Compile the hot statements into the statement cache of every pooled connection
+/// All connections are checked out at once so each one is primed exactly once
+/// Without full-text indexes (no_fts) the FTS5 searches cannot be compiled and are skipped
+fn prewarm_pool(pool: &Pool<SqliteConnectionManager>, no_fts: bool) -> Result<()> {
+    let conns = (0..pool.max_size())
+        .map(|_| pool.get())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to get connection from pool for prewarm")?;
+
+    for conn in &conns {
+        for sql in PREWARM_STATEMENTS {
+            if no_fts && sql.contains(" MATCH ") {
+                continue;
             }
+            conn.prepare_cached(sql)
+                .with_context(|| format!("Failed to prewarm statement: {}", sql))?;
         }
+    }
+    Ok(())
+}
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
-        let tx = conn
-            .unchecked_transaction()
-            .context("Failed to start transaction for creating entities")?;
-        let mut new_entities = Vec::new();
+/// Check whether an entity with given name exists
+fn entity_exists(conn: &Connection, name: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM entities WHERE name = ?1)",
+        params![name],
+        |row| row.get(0),
+    )
+    .with_context(|| format!("Database error querying entity '{}'", name))
+}
 
-        {
-            let mut stmt = tx.prepare_cached(
-                "INSERT OR IGNORE INTO entities (name, entity_type, observations) VALUES (?1, ?2, ?3)"
-            )
-            .context("Failed to prepare insert statement for entities")?;
+/// Entity an alias stands for; None when there is no such alias, or an entity has that name
+fn alias_target(conn: &Connection, name: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT entity_name FROM entity_aliases
+         WHERE alias = ?1 AND NOT EXISTS(SELECT 1 FROM entities WHERE name = ?1)",
+        params![name],
+        |row| row.get(0),
+    )
+    .optional()
+    .with_context(|| format!("Database error resolving alias '{}'", name))
+}
 
-            // INSERT OR IGNORE returns 0 if row already exists, 1 if inserted
-            for entity in entities {
-                let obs_json = serde_json::to_string(&entity.observations).context(format!(
-                    "Failed to serialize observations for entity '{}'",
-                    entity.name
-                ))?;
-                let rows_affected = stmt
-                    .execute(params![&entity.name, &entity.entity_type, &obs_json,])
-                    .with_context(|| format!("Failed to insert entity '{}'", entity.name))?;
-
-                // Track only newly inserted entities
-                if rows_affected > 0 {
-                    new_entities.push(entity.clone());
-                }
+/// The relations with every endpoint that names an alias replaced by its entity, and a note
+/// of each endpoint resolved that way
+fn resolve_aliases(
+    conn: &Connection,
+    relations: &[Relation],
+) -> Result<(Vec<Relation>, Vec<ResolvedAlias>)> {
+    let mut resolved = Vec::new();
+    for (index, rel) in relations.iter().enumerate() {
+        for (endpoint, name) in [("from", &rel.from), ("to", &rel.to)] {
+            if let Some(entity) = alias_target(conn, name)? {
+                resolved.push(ResolvedAlias {
+                    index,
+                    endpoint: endpoint.to_string(),
+                    alias: name.clone(),
+                    entity,
+                });
             }
         }
+    }
+    let mut relations = relations.to_vec();
+    for alias in &resolved {
+        alias.apply(&mut relations);
+    }
+    Ok((relations, resolved))
+}
 
-        tx.commit()
-            .context("Failed to commit transaction for creating entities")?;
-        Ok(new_entities)
+/// Check that every index points into an observation array of given length
+fn validate_indices(indices: &[usize], len: usize, entity_name: &str) -> Result<()> {
+    if let Some(&bad) = indices.iter().find(|&&i| i >= len) {
+        bail!(
+            "Observation index {} out of range: entity '{}' has {} observations",
+            bad,
+            entity_name,
+            len
+        );
     }
+    Ok(())
+}
 
-    /// Create relations (returns only newly created relations)
-    /// Optimized: Uses INSERT OR IGNORE with tracking, no full table scan
-    /// Wrapped in transaction for atomicity
-    pub fn create_relations(&self, relations: &[Relation]) -> Result<Vec<Relation>> {
-        if relations.is_empty() {
-            return Ok(Vec::new());
-        }
+/// Insert already validated relations inside the caller's transaction, tagged with batch_id
+/// Returns only newly inserted relations; missing endpoints fail the whole batch
+fn insert_relations(
+    tx: &Connection,
+    relations: &[Relation],
+    batch_id: &str,
+) -> Result<Vec<Relation>> {
+    let mut stmt = tx
+        .prepare_cached(SQL_INSERT_RELATION)
+        .context("Failed to prepare insert statement for relations")?;
+    let mut new_relations = Vec::new();
 
-        // Validate all relations before starting transaction
-        for rel in relations {
-            validate_name(&rel.from, "From entity")?;
-            validate_name(&rel.to, "To entity")?;
-            validate_type(&rel.relation_type, "Relation type")?;
+    // INSERT OR IGNORE returns 0 if duplicate, 1 if inserted
+    for rel in relations {
+        // FOREIGN KEY constraint validates entity existence
+        match stmt.execute(params![&rel.from, &rel.to, &rel.relation_type, batch_id]) {
+            Ok(rows_affected) => {
+                // Track only newly inserted relations
+                if rows_affected > 0 {
+                    new_relations.push(rel.clone());
+                }
+            }
+            Err(rusqlite::Error::SqliteFailure(err, _)) => {
+                if err.code == rusqlite::ErrorCode::ConstraintViolation {
+                    anyhow::bail!(
+                        "Cannot create relation '{}' -> '{}' (type: '{}'): one or both entities do not exist{}{}",
+                        rel.from, rel.to, rel.relation_type,
+                        typed_key_hint(tx, &rel.from), typed_key_hint(tx, &rel.to)
+                    );
+                }
+                return Err(err).with_context(|| {
+                    format!(
+                        "Database error creating relation '{}' -> '{}'",
+                        rel.from, rel.to
+                    )
+                });
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!(
+                        "Failed to insert relation '{}' -> '{}' (type: '{}')",
+                        rel.from, rel.to, rel.relation_type
+                    )
+                })
+            }
         }
+    }
+    Ok(new_relations)
+}
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
-        let tx = conn
-            .unchecked_transaction()
-            .context("Failed to start transaction for creating relations")?;
-        let mut new_relations = Vec::new();
+/// Append already validated observations inside the caller's transaction
+/// Observations the entity already has are skipped; a missing entity fails the whole batch
+fn insert_observations(
+    tx: &Connection,
+    inputs: &[ObservationInput],
+) -> Result<Vec<ObservationResult>> {
+    let mut results = Vec::new();
+    for input in inputs {
+        let mut observations = load_observations(tx, &input.entity_name, "add observations")?;
 
-        {
-            let mut stmt = tx.prepare_cached(
-                "INSERT OR IGNORE INTO relations (from_entity, to_entity, relation_type) VALUES (?1, ?2, ?3)"
-            )
-            .context("Failed to prepare insert statement for relations")?;
-
-            // INSERT OR IGNORE returns 0 if duplicate, 1 if inserted
-            for rel in relations {
-                // FOREIGN KEY constraint validates entity existence
-                match stmt.execute(params![&rel.from, &rel.to, &rel.relation_type]) {
-                    Ok(rows_affected) => {
-                        // Track only newly inserted relations
-                        if rows_affected > 0 {
-                            new_relations.push(rel.clone());
-                        }
-                    }
-                    Err(rusqlite::Error::SqliteFailure(err, _)) => {
-                        if err.code == rusqlite::ErrorCode::ConstraintViolation {
-                            anyhow::bail!(
-                                "Cannot create relation '{}' -> '{}' (type: '{}'): one or both entities do not exist",
-                                rel.from, rel.to, rel.relation_type
-                            );
-                        }
-                        return Err(err).with_context(|| {
-                            format!(
-                                "Database error creating relation '{}' -> '{}'",
-                                rel.from, rel.to
-                            )
-                        });
-                    }
-                    Err(e) => {
-                        return Err(e).with_context(|| {
-                            format!(
-                                "Failed to insert relation '{}' -> '{}' (type: '{}')",
-                                rel.from, rel.to, rel.relation_type
-                            )
-                        })
-                    }
-                }
+        // Track which observations are actually added
+        let mut added = Vec::new();
+        for obs in &input.contents {
+            if !observations.contains(obs) {
+                observations.push(obs.clone());
+                added.push(obs.clone());
             }
         }
 
-        tx.commit()
-            .context("Failed to commit transaction for creating relations")?;
-        Ok(new_relations)
+        // Update only if something was added
+        if !added.is_empty() {
+            store_observations(tx, &input.entity_name, &observations)?;
+        }
+
+        results.push(ObservationResult {
+            entity_name: input.entity_name.clone(),
+            added_observations: added,
+        });
+    }
+    Ok(results)
+}
+
+/// Delete entities by name inside the caller's transaction, returning how many existed
+/// Relations go with them via FOREIGN KEY CASCADE
+fn remove_entities(tx: &Connection, names: &[String]) -> Result<usize> {
+    if names.is_empty() {
+        return Ok(0);
     }
+    let placeholders = build_placeholders(names.len(), 1);
+    let query = format!("DELETE FROM entities WHERE name IN ({})", placeholders);
 
-    /// Add observations to multiple entities (batch operation)
-    /// Wrapped in transaction for atomicity
-    pub fn add_observations(&self, inputs: &[ObservationInput]) -> Result<Vec<ObservationResult>> {
-        // Validate all inputs before starting transaction
-        for input in inputs {
-            validate_name(&input.entity_name, "Entity name")?;
-            for obs in &input.contents {
-                validate_observation(obs)?;
-            }
-        }
+    let params: Vec<&dyn rusqlite::ToSql> =
+        names.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
-        let tx = conn
-            .unchecked_transaction()
-            .context("Failed to start transaction for adding observations")?;
-        let mut results = Vec::new();
+    tx.execute(&query, params.as_slice())
+        .context(format!("Failed to delete {} entities", names.len()))
+}
 
-        for input in inputs {
-            // Get current observations
-            let current: Option<String> = tx
-                .query_row(
-                    "SELECT observations FROM entities WHERE name = ?1",
-                    params![&input.entity_name],
-                    |row| row.get(0),
-                )
-                .optional()
-                .with_context(|| {
-                    format!("Database error querying entity '{}'", input.entity_name)
-                })?;
+/// Delete observations (by index or content) inside the caller's transaction
+fn remove_observations(tx: &Connection, deletions: &[ObservationDeletion]) -> Result<()> {
+    for deletion in deletions {
+        let observations = load_observations(tx, &deletion.entity_name, "delete observations")?;
+        validate_indices(&deletion.indices, observations.len(), &deletion.entity_name)?;
 
-            let current = current.with_context(|| {
+        let remaining: Vec<String> = observations
+            .into_iter()
+            .enumerate()
+            .filter(|(i, obs)| {
+                !deletion.indices.contains(i) && !deletion.observations.contains(obs)
+            })
+            .map(|(_, obs)| obs)
+            .collect();
+
+        store_observations(tx, &deletion.entity_name, &remaining)?;
+    }
+    Ok(())
+}
+
+/// Delete relations inside the caller's transaction, returning how many existed
+fn remove_relations(tx: &Connection, relations: &[Relation]) -> Result<usize> {
+    let mut stmt = tx
+        .prepare_cached(SQL_DELETE_RELATION)
+        .context("Failed to prepare delete statement for relations")?;
+
+    let mut count = 0;
+    for rel in relations {
+        count += stmt
+            .execute(params![&rel.from, &rel.to, &rel.relation_type])
+            .with_context(|| {
                 format!(
-                    "Cannot add observations: entity '{}' does not exist",
-                    input.entity_name
+                    "Failed to delete relation '{}' -> '{}' (type: '{}')",
+                    rel.from, rel.to, rel.relation_type
                 )
             })?;
+    }
+    Ok(count)
+}
 
-            // Parse JSON array
-            let mut observations: Vec<String> =
-                serde_json::from_str(&current).with_context(|| {
-                    format!(
-                        "Corrupted observations data for entity '{}'",
-                        input.entity_name
-                    )
-                })?;
-
-            // Track which observations are actually added
-            let mut added = Vec::new();
-            for obs in &input.contents {
-                if !observations.contains(obs) {
-                    observations.push(obs.clone());
-                    added.push(obs.clone());
-                }
-            }
+/// Read the graph revision counter
+fn read_revision(conn: &Connection) -> Result<u64> {
+    let revision: i64 = conn
+        .query_row(
+            "SELECT revision FROM graph_revision WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to read graph revision")?;
+    Ok(revision as u64)
+}
 
-            // Update only if something was added
-            if !added.is_empty() {
-                let obs_json = serde_json::to_string(&observations).with_context(|| {
-                    format!(
-                        "Failed to serialize observations for entity '{}'",
-                        input.entity_name
-                    )
-                })?;
-                tx.execute(
-                    "UPDATE entities SET observations = ?1 WHERE name = ?2",
-                    params![&obs_json, &input.entity_name],
-                )
-                .with_context(|| {
-                    format!(
-                        "Failed to update observations for entity '{}'",
-                        input.entity_name
-                    )
-                })?;
-            }
+/// True if a table, index or trigger with this name exists
+fn schema_object_exists(conn: &Connection, name: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = ?1)",
+        params![name],
+        |row| row.get(0),
+    )
+    .with_context(|| format!("Failed to look up schema object '{}'", name))
+}
 
-            results.push(ObservationResult {
-                entity_name: input.entity_name.clone(),
-                added_observations: added,
-            });
-        }
+/// Relations with an endpoint that names no entity (sorted), with the missing names
+fn dangling_relations(conn: &Connection) -> Result<DanglingRelations> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT r.from_entity, r.to_entity, r.relation_type
+         FROM relations r
+         WHERE NOT EXISTS (SELECT 1 FROM entities e WHERE e.name = r.from_entity)
+            OR NOT EXISTS (SELECT 1 FROM entities e WHERE e.name = r.to_entity)
+         ORDER BY r.from_entity, r.to_entity, r.relation_type",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Relation {
+            from: row.get(0)?,
+            to: row.get(1)?,
+            relation_type: row.get(2)?,
+        })
+    })?;
 
-        tx.commit()
-            .context("Failed to commit transaction for adding observations")?;
-        Ok(results)
+    let mut relations = Vec::new();
+    for row in rows {
+        relations.push(row.context("Failed to read dangling relations")?);
     }
 
-    /// Delete entities (cascade delete via FOREIGN KEY)
-    /// Wrapped in transaction for atomicity when deleting multiple entities
-    pub fn delete_entities(&self, names: &[String]) -> Result<usize> {
-        if names.is_empty() {
-            return Ok(0);
+    let mut missing = BTreeSet::new();
+    for relation in &relations {
+        for name in [&relation.from, &relation.to] {
+            if !missing.contains(name) && !entity_exists(conn, name)? {
+                missing.insert(name.clone());
+            }
         }
+    }
+    Ok(DanglingRelations {
+        relations,
+        missing: missing.into_iter().collect(),
+    })
+}
 
-        // Validate all entity names before starting transaction
-        for name in names {
-            validate_name(name, "Entity name")?;
-        }
+/// Row counts of entities and relations next to their full-text indexes
+/// The FTS tables read content from the base tables, so COUNT(*) on them would just count
+/// the base table again; indexed documents are counted in the `_docsize` shadow tables
+fn search_index_counts(conn: &Connection) -> Result<Vec<SearchIndexCount>> {
+    [
+        ("entities", "entities_fts_docsize"),
+        ("relations", "relations_fts_docsize"),
+    ]
+    .into_iter()
+    .map(|(table, index)| {
+        let (rows, indexed): (i64, i64) = conn
+            .query_row(
+                &format!(
+                    "SELECT (SELECT COUNT(*) FROM {}), (SELECT COUNT(*) FROM {})",
+                    table, index
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .with_context(|| format!("Failed to count full-text index rows of {}", table))?;
+        Ok(SearchIndexCount {
+            table: table.to_string(),
+            rows: rows as u64,
+            indexed: indexed as u64,
+        })
+    })
+    .collect()
+}
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
+/// `SELECT 1`, `PRAGMA integrity_check` and (unless full-text search is off) the index counts
+fn check_health(conn: &Connection, no_fts: bool) -> Result<()> {
+    conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+        .context("Database did not answer SELECT 1")?;
 
-        let tx = conn
-            .unchecked_transaction()
-            .context("Failed to start transaction for deleting entities")?;
+    let mut stmt = conn
+        .prepare("PRAGMA integrity_check")
+        .context("Failed to prepare integrity check")?;
+    let problems = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to run integrity check")?;
 
-        let placeholders = build_placeholders(names.len(), 1);
-        let query = format!("DELETE FROM entities WHERE name IN ({})", placeholders);
+    if problems != ["ok"] {
+        bail!("Integrity check failed: {}", problems.join("; "));
+    }
 
-        let params: Vec<&dyn rusqlite::ToSql> =
-            names.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+    if no_fts {
+        return Ok(());
+    }
+    if let Some(drift) = search_index_counts(conn)?
+        .into_iter()
+        .find(|count| !count.is_consistent())
+    {
+        bail!(
+            "Full-text index of {} holds {} rows for {} table rows; \
+             rebuild it (check_search_index with rebuild)",
+            drift.table,
+            drift.indexed,
+            drift.rows
+        );
+    }
+    Ok(())
+}
 
-        let count = tx
-            .execute(&query, params.as_slice())
-            .context(format!("Failed to delete {} entities", names.len()))?;
+/// Remove repeated observations (keeping the first occurrence) from every entity that has them
+/// One-time cleanup before the set constraint applies to existing rows
+fn dedup_stored_observations(conn: &Connection) -> Result<()> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start transaction for observation cleanup")?;
+    let duplicated = {
+        let mut stmt = tx.prepare(
+            "SELECT name, observations FROM entities
+             WHERE (SELECT COUNT(*) FROM json_each(observations))
+                != (SELECT COUNT(DISTINCT value) FROM json_each(observations))",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to find duplicate observations")?;
+        rows
+    };
+    for (name, obs_json) in duplicated {
+        let observations = decode_observations(&obs_json)
+            .with_context(|| format!("Corrupted observations for entity '{}'", name))?;
+        store_observations(&tx, &name, &unique_observations(observations))?;
+    }
+    tx.commit()
+        .context("Failed to commit observation cleanup")?;
+    Ok(())
+}
 
-        // FOREIGN KEY CASCADE auto-deletes relations!
+/// Drop repeated observations, keeping the first occurrence of each
+fn unique_observations(observations: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::with_capacity(observations.len());
+    observations
+        .into_iter()
+        .filter(|obs| seen.insert(obs.clone()))
+        .collect()
+}
 
-        tx.commit()
-            .context("Failed to commit transaction for deleting entities")?;
+/// True if an entities table exists but predates the given column
+fn entities_lack_column(conn: &Connection, column: &str) -> Result<bool> {
+    table_lacks_column(conn, "entities", column)
+}
 
-        Ok(count)
-    }
+/// True if the table exists but predates the given column
+fn table_lacks_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt
+        .query_map([table], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to inspect {} table", table))?;
+    Ok(!columns.is_empty() && !columns.iter().any(|c| c == column))
+}
 
-    /// Delete observations from multiple entities (batch operation)
-    /// Wrapped in transaction for atomicity
-    pub fn delete_observations(&self, deletions: &[ObservationDeletion]) -> Result<()> {
-        // Validate all deletions before starting transaction
-        for deletion in deletions {
-            validate_name(&deletion.entity_name, "Entity name")?;
-        }
+/// Fresh id tagging everything one create call inserts (16 hex chars)
+fn new_batch_id(tx: &Connection) -> Result<String> {
+    tx.query_row("SELECT lower(hex(randomblob(8)))", [], |row| row.get(0))
+        .context("Failed to generate batch id")
+}
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
-        let tx = conn
-            .unchecked_transaction()
-            .context("Failed to start transaction for deleting observations")?;
+/// Raw entity row: (name, entity_type, observations JSON, description, id, confidence,
+/// metadata JSON)
+type EntityRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<f64>,
+    Option<String>,
+);
 
-        for deletion in deletions {
-            let current: Option<String> = tx
-                .query_row(
-                    "SELECT observations FROM entities WHERE name = ?1",
-                    params![&deletion.entity_name],
-                    |row| row.get(0),
-                )
-                .optional()
-                .with_context(|| {
-                    format!("Database error querying entity '{}'", deletion.entity_name)
-                })?;
+/// Row mapper for queries selecting name, entity_type, observations, description, id,
+/// confidence, metadata
+fn entity_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<EntityRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+    ))
+}
 
-            let current = current.with_context(|| {
-                format!(
-                    "Cannot delete observations: entity '{}' does not exist",
-                    deletion.entity_name
-                )
-            })?;
+/// Read a (name, entity type or NULL) search row
+fn search_hit(row: &rusqlite::Row<'_>) -> rusqlite::Result<SearchHit> {
+    Ok(SearchHit {
+        name: row.get(0)?,
+        entity_type: row.get(1)?,
+    })
+}
 
-            let mut observations: Vec<String> =
-                serde_json::from_str(&current).with_context(|| {
-                    format!(
-                        "Corrupted observations data for entity '{}'",
-                        deletion.entity_name
-                    )
-                })?;
-            observations.retain(|obs| !deletion.observations.contains(obs));
+/// Decode an entity row into an Entity (observations are stored as a JSON array)
+fn decode_entity(
+    (name, entity_type, obs_json, description, id, confidence, metadata): EntityRow,
+) -> Result<Entity> {
+    let observations = decode_observations(&obs_json)
+        .with_context(|| format!("Corrupted observations for entity '{}'", name))?;
+    let metadata = metadata
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .with_context(|| format!("Corrupted metadata for entity '{}'", name))?;
+    Ok(Entity {
+        name,
+        entity_type,
+        observations,
+        description,
+        id,
+        confidence,
+        metadata,
+    })
+}
+
+const SCHEMA: &str = r#"
+-- Entities table
+CREATE TABLE IF NOT EXISTS entities (
+    name TEXT PRIMARY KEY NOT NULL,
+    entity_type TEXT NOT NULL,
+    observations TEXT NOT NULL,
+    description TEXT,
+    created_at TEXT, -- ISO-8601 UTC, set on insert
+    updated_at TEXT, -- ISO-8601 UTC, set on insert and every update
+    id TEXT,         -- Stable random handle (32 hex chars), set on insert, independent of name
+    confidence REAL CHECK (confidence BETWEEN 0.0 AND 1.0), -- Optional extraction confidence
+    batch_id TEXT,   -- Create call that inserted the entity (see get_batch)
+    metadata TEXT CHECK (metadata IS NULL OR json_valid(metadata)) -- Free-form JSON, not indexed
+) STRICT;
+
+-- Relations table with FOREIGN KEY for cascade delete
+CREATE TABLE IF NOT EXISTS relations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    from_entity TEXT NOT NULL,
+    to_entity TEXT NOT NULL,
+    relation_type TEXT NOT NULL,
+    batch_id TEXT, -- Create call that inserted the relation (see get_batch)
+    UNIQUE(from_entity, to_entity, relation_type),
+    FOREIGN KEY(from_entity) REFERENCES entities(name) ON DELETE CASCADE,
+    FOREIGN KEY(to_entity) REFERENCES entities(name) ON DELETE CASCADE
+) STRICT;
+
+-- Indexes for performance
+CREATE INDEX IF NOT EXISTS idx_entity_type ON entities(entity_type);
+CREATE INDEX IF NOT EXISTS idx_entities_updated_at ON entities(updated_at);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_entities_id ON entities(id);
+CREATE INDEX IF NOT EXISTS idx_from ON relations(from_entity);
+CREATE INDEX IF NOT EXISTS idx_to ON relations(to_entity);
+CREATE INDEX IF NOT EXISTS idx_relation_type ON relations(relation_type);
+CREATE INDEX IF NOT EXISTS idx_entities_batch ON entities(batch_id) WHERE batch_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_relations_batch ON relations(batch_id) WHERE batch_id IS NOT NULL;
+
+-- Compound indexes for complex queries
+CREATE INDEX IF NOT EXISTS idx_relations_from_type ON relations(from_entity, relation_type);
+CREATE INDEX IF NOT EXISTS idx_relations_to_type ON relations(to_entity, relation_type);
+
+-- Observations are a set: reject any write that would store the same observation twice,
+-- whatever code path it comes from (JSON blob, so enforced by trigger instead of UNIQUE)
+CREATE TRIGGER IF NOT EXISTS entities_observations_unique_ai BEFORE INSERT ON entities
+WHEN (SELECT COUNT(*) FROM json_each(new.observations))
+   != (SELECT COUNT(DISTINCT value) FROM json_each(new.observations))
+BEGIN
+    SELECT RAISE(ABORT, 'Duplicate observation: observations of an entity must be unique');
+END;
+
+CREATE TRIGGER IF NOT EXISTS entities_observations_unique_au BEFORE UPDATE OF observations ON entities
+WHEN (SELECT COUNT(*) FROM json_each(new.observations))
+   != (SELECT COUNT(DISTINCT value) FROM json_each(new.observations))
+BEGIN
+    SELECT RAISE(ABORT, 'Duplicate observation: observations of an entity must be unique');
+END;
+
+-- Read statistics (--track-access), kept out of the entities table so counting a read
+-- neither reindexes the entity nor bumps the graph revision
+CREATE TABLE IF NOT EXISTS entity_access (
+    name TEXT PRIMARY KEY NOT NULL,
+    access_count INTEGER NOT NULL,
+    last_accessed TEXT NOT NULL, -- ISO-8601 UTC
+    FOREIGN KEY(name) REFERENCES entities(name) ON DELETE CASCADE ON UPDATE CASCADE
+) STRICT;
+
+-- When each observation was added, maintained by triggers so every write path records it:
+-- new observations get the current time, removed ones lose their row, and a reorder keeps
+-- the times. Observations stored before this table existed have no row
+CREATE TABLE IF NOT EXISTS observation_times (
+    entity_name TEXT NOT NULL,
+    observation TEXT NOT NULL,
+    added_at TEXT NOT NULL, -- ISO-8601 UTC
+    PRIMARY KEY(entity_name, observation),
+    FOREIGN KEY(entity_name) REFERENCES entities(name) ON DELETE CASCADE ON UPDATE CASCADE
+) STRICT;
+
+CREATE TRIGGER IF NOT EXISTS observation_times_ai AFTER INSERT ON entities BEGIN
+    INSERT OR IGNORE INTO observation_times (entity_name, observation, added_at)
+    SELECT new.name, value, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    FROM json_each(new.observations);
+END;
+
+CREATE TRIGGER IF NOT EXISTS observation_times_au AFTER UPDATE OF observations ON entities BEGIN
+    DELETE FROM observation_times
+    WHERE entity_name = new.name
+      AND observation NOT IN (SELECT value FROM json_each(new.observations));
+    INSERT OR IGNORE INTO observation_times (entity_name, observation, added_at)
+    SELECT new.name, value, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    FROM json_each(new.observations);
+END;
+
+-- Cached PageRank scores (compute_importance), kept out of the entities table like the read
+-- statistics; entities created after the last run have no row
+CREATE TABLE IF NOT EXISTS entity_importance (
+    name TEXT PRIMARY KEY NOT NULL,
+    importance REAL NOT NULL,
+    FOREIGN KEY(name) REFERENCES entities(name) ON DELETE CASCADE ON UPDATE CASCADE
+) STRICT;
+
+-- Alternative names of entities (add_aliases): a relation endpoint naming an alias is resolved
+-- to its entity. An entity called like an alias takes precedence over it
+CREATE TABLE IF NOT EXISTS entity_aliases (
+    alias TEXT PRIMARY KEY NOT NULL,
+    entity_name TEXT NOT NULL,
+    FOREIGN KEY(entity_name) REFERENCES entities(name) ON DELETE CASCADE ON UPDATE CASCADE
+) STRICT;
+CREATE INDEX IF NOT EXISTS idx_entity_aliases_entity ON entity_aliases(entity_name);
+
+-- Graph revision: bumped by every write to entities or relations (drives HTTP ETags)
+CREATE TABLE IF NOT EXISTS graph_revision (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    revision INTEGER NOT NULL
+) STRICT;
+INSERT OR IGNORE INTO graph_revision (id, revision) VALUES (1, 0);
+
+CREATE TRIGGER IF NOT EXISTS revision_entities_ai AFTER INSERT ON entities BEGIN
+    UPDATE graph_revision SET revision = revision + 1 WHERE id = 1;
+END;
+CREATE TRIGGER IF NOT EXISTS revision_entities_ad AFTER DELETE ON entities BEGIN
+    UPDATE graph_revision SET revision = revision + 1 WHERE id = 1;
+END;
+CREATE TRIGGER IF NOT EXISTS revision_entities_au AFTER UPDATE ON entities BEGIN
+    UPDATE graph_revision SET revision = revision + 1 WHERE id = 1;
+END;
+CREATE TRIGGER IF NOT EXISTS revision_relations_ai AFTER INSERT ON relations BEGIN
+    UPDATE graph_revision SET revision = revision + 1 WHERE id = 1;
+END;
+CREATE TRIGGER IF NOT EXISTS revision_relations_ad AFTER DELETE ON relations BEGIN
+    UPDATE graph_revision SET revision = revision + 1 WHERE id = 1;
+END;
+CREATE TRIGGER IF NOT EXISTS revision_relations_au AFTER UPDATE ON relations BEGIN
+    UPDATE graph_revision SET revision = revision + 1 WHERE id = 1;
+END;
+"#;
+
+/// Full-text search index tables and the triggers keeping them in sync (left out with --no-fts)
+const FTS_SCHEMA: &str = r#"
+-- FTS5 virtual table for full-text search
+CREATE VIRTUAL TABLE IF NOT EXISTS entities_fts USING fts5(
+    name,
+    entity_type,
+    observations,
+    description,
+    content='entities',
+    content_rowid='rowid'
+);
+
+-- Triggers to keep FTS5 in sync with entities table
+-- Observations are indexed as their space-joined text, not as the stored JSON array (whose
+-- quotes and escapes would be tokenized too), so the index must never be rebuilt from the
+-- table with 'rebuild'; see SQL_REINDEX_ENTITIES
+CREATE TRIGGER IF NOT EXISTS entities_ai AFTER INSERT ON entities BEGIN
+    INSERT INTO entities_fts(rowid, name, entity_type, observations, description)
+    VALUES (new.rowid, new.name, new.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(new.observations)),
+            new.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS entities_ad AFTER DELETE ON entities BEGIN
+    INSERT INTO entities_fts(entities_fts, rowid, name, entity_type, observations, description)
+    VALUES ('delete', old.rowid, old.name, old.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(old.observations)),
+            old.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS entities_au AFTER UPDATE ON entities BEGIN
+    INSERT INTO entities_fts(entities_fts, rowid, name, entity_type, observations, description)
+    VALUES ('delete', old.rowid, old.name, old.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(old.observations)),
+            old.description);
+    INSERT INTO entities_fts(rowid, name, entity_type, observations, description)
+    VALUES (new.rowid, new.name, new.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(new.observations)),
+            new.description);
+END;
+
+-- FTS5 virtual table for relation text (endpoints + type)
+CREATE VIRTUAL TABLE IF NOT EXISTS relations_fts USING fts5(
+    from_entity,
+    to_entity,
+    relation_type,
+    content='relations',
+    content_rowid='id'
+);
+
+-- Triggers to keep FTS5 in sync with relations table (also fire on cascade deletes)
+CREATE TRIGGER IF NOT EXISTS relations_ai AFTER INSERT ON relations BEGIN
+    INSERT INTO relations_fts(rowid, from_entity, to_entity, relation_type)
+    VALUES (new.id, new.from_entity, new.to_entity, new.relation_type);
+END;
+
+CREATE TRIGGER IF NOT EXISTS relations_ad AFTER DELETE ON relations BEGIN
+    INSERT INTO relations_fts(relations_fts, rowid, from_entity, to_entity, relation_type)
+    VALUES ('delete', old.id, old.from_entity, old.to_entity, old.relation_type);
+END;
+
+CREATE TRIGGER IF NOT EXISTS relations_au AFTER UPDATE ON relations BEGIN
+    INSERT INTO relations_fts(relations_fts, rowid, from_entity, to_entity, relation_type)
+    VALUES ('delete', old.id, old.from_entity, old.to_entity, old.relation_type);
+    INSERT INTO relations_fts(rowid, from_entity, to_entity, relation_type)
+    VALUES (new.id, new.from_entity, new.to_entity, new.relation_type);
+END;
+"#;
+
+/// Drop the full-text search index and its triggers (--no-fts); writes stop paying for it
+const DROP_FTS: &str = "
+BEGIN;
+DROP TRIGGER IF EXISTS entities_ai;
+DROP TRIGGER IF EXISTS entities_ad;
+DROP TRIGGER IF EXISTS entities_au;
+DROP TRIGGER IF EXISTS relations_ai;
+DROP TRIGGER IF EXISTS relations_ad;
+DROP TRIGGER IF EXISTS relations_au;
+DROP TABLE IF EXISTS entities_fts;
+DROP TABLE IF EXISTS relations_fts;
+COMMIT;";
+
+/// Entity triggers of a database with compressed observations: the search index is fed the
+/// text of decoded arrays
+const COMPRESSED_ENTITY_TRIGGERS: &str = r#"
+CREATE TRIGGER entities_ai AFTER INSERT ON entities BEGIN
+    INSERT INTO entities_fts(rowid, name, entity_type, observations, description)
+    VALUES (new.rowid, new.name, new.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(observations_json(new.observations))),
+            new.description);
+END;
+
+CREATE TRIGGER entities_ad AFTER DELETE ON entities BEGIN
+    INSERT INTO entities_fts(entities_fts, rowid, name, entity_type, observations, description)
+    VALUES ('delete', old.rowid, old.name, old.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(observations_json(old.observations))),
+            old.description);
+END;
+
+CREATE TRIGGER entities_au AFTER UPDATE ON entities BEGIN
+    INSERT INTO entities_fts(entities_fts, rowid, name, entity_type, observations, description)
+    VALUES ('delete', old.rowid, old.name, old.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(observations_json(old.observations))),
+            old.description);
+    INSERT INTO entities_fts(rowid, name, entity_type, observations, description)
+    VALUES (new.rowid, new.name, new.entity_type,
+            (SELECT group_concat(value, ' ') FROM json_each(observations_json(new.observations))),
+            new.description);
+END;
+"#;
+
+/// Switch a database to compressed observations: the entity triggers feed the search index
+/// and the set check with decoded arrays (COMPRESSED_ENTITY_TRIGGERS completes the set),
+/// then every stored array is re-encoded
+const COMPRESS_OBSERVATIONS: &str = r#"
+DROP TRIGGER IF EXISTS entities_ai;
+DROP TRIGGER IF EXISTS entities_ad;
+DROP TRIGGER IF EXISTS entities_au;
+DROP TRIGGER IF EXISTS entities_observations_unique_ai;
+DROP TRIGGER IF EXISTS entities_observations_unique_au;
+
+CREATE TRIGGER entities_observations_unique_ai BEFORE INSERT ON entities
+WHEN (SELECT COUNT(*) FROM json_each(observations_json(new.observations)))
+   != (SELECT COUNT(DISTINCT value) FROM json_each(observations_json(new.observations)))
+BEGIN
+    SELECT RAISE(ABORT, 'Duplicate observation: observations of an entity must be unique');
+END;
+
+CREATE TRIGGER entities_observations_unique_au BEFORE UPDATE OF observations ON entities
+WHEN (SELECT COUNT(*) FROM json_each(observations_json(new.observations)))
+   != (SELECT COUNT(DISTINCT value) FROM json_each(observations_json(new.observations)))
+BEGIN
+    SELECT RAISE(ABORT, 'Duplicate observation: observations of an entity must be unique');
+END;
+"#;
+
+/// Observation time triggers of a database with compressed observations (replacing the ones
+/// of SCHEMA, which read the stored arrays directly)
+const COMPRESSED_OBSERVATION_TIME_TRIGGERS: &str = r#"
+DROP TRIGGER IF EXISTS observation_times_ai;
+DROP TRIGGER IF EXISTS observation_times_au;
+
+CREATE TRIGGER observation_times_ai AFTER INSERT ON entities BEGIN
+    INSERT OR IGNORE INTO observation_times (entity_name, observation, added_at)
+    SELECT new.name, value, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    FROM json_each(observations_json(new.observations));
+END;
+
+CREATE TRIGGER observation_times_au AFTER UPDATE OF observations ON entities BEGIN
+    DELETE FROM observation_times
+    WHERE entity_name = new.name
+      AND observation NOT IN (SELECT value FROM json_each(observations_json(new.observations)));
+    INSERT OR IGNORE INTO observation_times (entity_name, observation, added_at)
+    SELECT new.name, value, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    FROM json_each(observations_json(new.observations));
+END;
+"#;
+
+/// Key an entity's row by name and type (--name-type-keys): the name column then holds
+/// "name#type" and these triggers reject rows whose key does not end in their own type.
+/// Types cannot contain '#', so a key always splits at its last '#'
+const NAME_TYPE_KEY_TRIGGERS: &str = r#"
+CREATE TRIGGER entities_name_type_key_ai BEFORE INSERT ON entities
+WHEN length(new.name) <= length(new.entity_type) + 1
+  OR substr(new.name, -length(new.entity_type) - 1) != '#' || new.entity_type
+BEGIN
+    SELECT RAISE(ABORT, 'Entity key must be the name followed by #<entity type>');
+END;
+
+CREATE TRIGGER entities_name_type_key_au BEFORE UPDATE OF name, entity_type ON entities
+WHEN length(new.name) <= length(new.entity_type) + 1
+  OR substr(new.name, -length(new.entity_type) - 1) != '#' || new.entity_type
+BEGIN
+    SELECT RAISE(ABORT, 'Entity key must be the name followed by #<entity type>');
+END;
+"#;
+
+/// Rename every entity to its name#type key, relation endpoints included (they have no
+/// ON UPDATE CASCADE, the other tables do). Each table is renamed via a control-character
+/// prefix that no stored name has, so no intermediate key can collide with an old name;
+/// endpoints of dangling relations are left alone
+const KEY_ENTITIES_BY_TYPE: &str = r#"
+PRAGMA defer_foreign_keys = ON;
+UPDATE relations SET
+    from_entity = COALESCE(char(1) || from_entity || '#'
+        || (SELECT entity_type FROM entities WHERE name = relations.from_entity), from_entity),
+    to_entity = COALESCE(char(1) || to_entity || '#'
+        || (SELECT entity_type FROM entities WHERE name = relations.to_entity), to_entity);
+UPDATE relations SET
+    from_entity = CASE WHEN from_entity GLOB char(1) || '*'
+                       THEN substr(from_entity, 2) ELSE from_entity END,
+    to_entity = CASE WHEN to_entity GLOB char(1) || '*'
+                     THEN substr(to_entity, 2) ELSE to_entity END;
+UPDATE entities SET name = char(1) || name || '#' || entity_type;
+UPDATE entities SET name = substr(name, 2);
+"#;
+
+/// Reindex every entity by hand: FTS5 'rebuild' would index the stored observations (a JSON
+/// array, possibly compressed) instead of the plain text the triggers index
+const SQL_REINDEX_ENTITIES: &str = "
+INSERT INTO entities_fts(entities_fts) VALUES ('delete-all');
+INSERT INTO entities_fts(rowid, name, entity_type, observations, description)
+    SELECT rowid, name, entity_type,
+           (SELECT group_concat(value, ' ') FROM json_each(observations_json(observations))),
+           description
+    FROM entities;";
+
+/// Name normalization used to detect near-duplicate entities on create
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DedupOptions {
+    /// Compare names case-insensitively ("Acme" == "ACME")
+    pub case_fold: bool,
+
+    /// Append observations of a deduplicated entity to the existing one
+    pub merge_observations: bool,
+}
+
+impl DedupOptions {
+    /// Normalized key for the uniqueness check (trimmed, optionally case-folded)
+    fn normalize(&self, name: &str) -> String {
+        let trimmed = name.trim();
+        if self.case_fold {
+            trimmed.to_lowercase()
+        } else {
+            trimmed.to_string()
+        }
+    }
+}
+
+/// Expected content format of observations, checked on write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObservationFormat {
+    /// Any text (no extra checks)
+    #[default]
+    Plain,
+    /// Raw HTML is stripped before storing
+    Markdown,
+    /// Each observation must parse as JSON
+    Json,
+}
+
+/// How the length limits on names, types, observations and descriptions are measured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// UTF-8 bytes: bounds the stored size; multibyte text reaches the limit with fewer characters
+    #[default]
+    Bytes,
+    /// Unicode scalar values (what most people count as characters)
+    Chars,
+}
+
+impl LengthUnit {
+    /// Length of text in this unit
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            Self::Bytes => text.len(),
+            Self::Chars => text.chars().count(),
+        }
+    }
+}
+
+impl fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bytes => "bytes",
+            Self::Chars => "chars",
+        })
+    }
+}
+
+impl std::str::FromStr for LengthUnit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bytes" => Ok(Self::Bytes),
+            "chars" | "characters" => Ok(Self::Chars),
+            other => bail!("Unknown length unit '{}' (expected bytes or chars)", other),
+        }
+    }
+}
+
+impl std::str::FromStr for ObservationFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "plain" => Ok(Self::Plain),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            other => bail!(
+                "Unknown observation format '{}' (expected plain, markdown or json)",
+                other
+            ),
+        }
+    }
+}
+
+/// Size of each connection's page cache (SQLite `PRAGMA cache_size`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheSize {
+    /// Number of database pages (memory use depends on the page size)
+    Pages(u32),
+    /// Memory budget in KiB, independent of the page size
+    Kibibytes(u32),
+}
+
+impl CacheSize {
+    /// Value for PRAGMA cache_size (negative = KiB)
+    fn pragma_value(self) -> i64 {
+        match self {
+            Self::Pages(pages) => i64::from(pages),
+            Self::Kibibytes(kib) => -i64::from(kib),
+        }
+    }
+}
+
+impl std::str::FromStr for CacheSize {
+    type Err = anyhow::Error;
+
+    /// "2000" = pages, "65536k" / "65536KiB" = KiB, "64m" / "64MiB" = MiB
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.trim().to_ascii_lowercase();
+        let (digits, multiplier) = if let Some(n) = lower
+            .strip_suffix("kib")
+            .or_else(|| lower.strip_suffix('k'))
+        {
+            (n, Some(1))
+        } else if let Some(n) = lower
+            .strip_suffix("mib")
+            .or_else(|| lower.strip_suffix('m'))
+        {
+            (n, Some(1024))
+        } else {
+            (lower.as_str(), None)
+        };
+
+        let value: u32 = digits.trim().parse().map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid cache size '{}' (expected pages like 2000, or 65536k / 64m)",
+                s
+            )
+        })?;
+        if value == 0 {
+            bail!("Cache size must be greater than zero");
+        }
+        match multiplier {
+            None => Ok(Self::Pages(value)),
+            Some(multiplier) => value
+                .checked_mul(multiplier)
+                .map(Self::Kibibytes)
+                .ok_or_else(|| anyhow::anyhow!("Cache size '{}' is too large", s)),
+        }
+    }
+}
+
+/// Runtime options for the database (all optional, defaults keep permissive behavior)
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseOptions {
+    /// Allowlist of entity types accepted by create_entities (None = any valid type)
+    pub entity_types: Option<BTreeSet<String>>,
+
+    /// Allowlist of relation types accepted by create_relations (None = any valid type)
+    pub relation_types: Option<BTreeSet<String>>,
+
+    /// Reject new relations from an entity to itself (default: allowed)
+    pub disallow_self_relations: bool,
+
+    /// Time budget for a single operation; statements running past it are interrupted
+    pub query_timeout: Option<Duration>,
+
+    /// How long a call waits for a free pooled connection before failing with PoolExhausted
+    /// (None = 5 s)
+    pub pool_timeout: Option<Duration>,
+
+    /// Idle connections each pool keeps open, at most 15 (None = all 15 stay open)
+    pub pool_min_idle: Option<u32>,
+
+    /// Close connections idle this long, down to pool_min_idle (None = 10 minutes)
+    pub pool_idle_timeout: Option<Duration>,
+
+    /// Map near-duplicate names onto existing entities in create_entities (None = exact names only)
+    pub dedup_on_create: Option<DedupOptions>,
+
+    /// Compile hot statements on every pooled connection at open (smooths cold-start latency)
+    pub prewarm: bool,
+
+    /// Format observations must follow (default plain = any text)
+    pub observation_format: ObservationFormat,
+
+    /// Page cache size of every pooled connection (None = SQLite default, about 2 MiB)
+    pub cache_size: Option<CacheSize>,
+
+    /// Bytes of the database file to memory-map per connection (None = no mmap)
+    pub mmap_size: Option<u64>,
+
+    /// Shortest search query accepted, in characters after sanitization (0 = no minimum)
+    /// Empty queries are not affected: they explicitly ask for the whole graph
+    pub min_query_length: usize,
+
+    /// Count how often open_nodes / search_nodes return each entity (see least_accessed)
+    /// Counts are buffered by the manager and written in batches, off the read path
+    pub track_access: bool,
+
+    /// Hold add_observations calls this long and write them together, coalesced per entity
+    /// (None = every call writes at once); held observations are written before any other
+    /// operation runs, so reads never miss them. At most 60 s
+    pub observation_buffer: Option<Duration>,
+
+    /// Unit the length limits of new names, types, observations and descriptions count in
+    pub length_unit: LengthUnit,
+
+    /// Longest name accepted for new entities, at most 256 (None = 256)
+    /// Lookups and relation endpoints still accept names up to 256, so entities created
+    /// under a higher limit stay reachable after lowering it
+    pub max_entity_name_length: Option<usize>,
+
+    /// Store observation arrays zstd-compressed (needs the compress-observations feature)
+    /// Switches the database over for good: the search index and set triggers then decode
+    /// stored values, and existing rows are compressed on the spot
+    pub compress_observations: bool,
+
+    /// Key entities by name and type, so "Mercury" the planet and "Mercury" the person can
+    /// both exist: stored names, lookups and relation endpoints become "name#type"
+    /// Switches the database over for good: existing entities and relations are re-keyed
+    /// on the spot, and it stays keyed this way when later opened without the option
+    pub name_type_keys: bool,
+
+    /// Replica database serving read_graph, search_nodes and open_nodes (read-only)
+    /// Keeping it in sync with the primary is up to an external tool such as Litestream
+    pub read_replica: Option<PathBuf>,
+
+    /// Drop the full-text indexes and their triggers so writes skip indexing; searches then
+    /// scan the tables with LIKE (substring match instead of FTS5 tokens)
+    /// Opening without it again rebuilds the indexes
+    pub no_fts: bool,
+
+    /// Require the database's parent directory to exist instead of creating it
+    pub no_create_dirs: bool,
+
+    /// Directory the database (and read replica) must resolve into (None = anywhere)
+    pub allowed_db_dir: Option<PathBuf>,
+}
+
+/// Pool builder with the size, checkout timeout and idle reaping from the options
+fn pool_builder(options: &DatabaseOptions) -> r2d2::Builder<SqliteConnectionManager> {
+    let mut builder = Pool::builder()
+        .max_size(POOL_SIZE)
+        .connection_timeout(options.pool_timeout.unwrap_or(DEFAULT_POOL_TIMEOUT))
+        .min_idle(options.pool_min_idle);
+    if let Some(idle_timeout) = options.pool_idle_timeout {
+        builder = builder.idle_timeout(Some(idle_timeout));
+    }
+    builder
+}
+
+/// Open the read-only pool on a replica database kept up to date by an external tool
+/// The replica must already exist and hold a memory database; its schema is never migrated
+fn open_read_replica(
+    path: &Path,
+    options: &DatabaseOptions,
+) -> Result<Pool<SqliteConnectionManager>> {
+    validate_db_extension(path)?;
+    if !path.is_file() {
+        bail!("Read replica '{}' does not exist", path.display());
+    }
+    let path = &resolve_db_path(
+        path,
+        &DbPathPolicy {
+            create_dirs: false,
+            allowed_dir: options.allowed_db_dir.clone(),
+        },
+    )?;
+
+    let manager = SqliteConnectionManager::file(path).with_flags(
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    );
+    let pool = pool_builder(options)
+        .connection_customizer(Box::new(SqliteCustomizer {
+            cache_size: options.cache_size,
+            mmap_size: options.mmap_size,
+            compress_observations: false,
+        }))
+        .build(manager)
+        .with_context(|| format!("Failed to open read replica '{}'", path.display()))?;
+
+    let conn = pool
+        .get()
+        .context("Failed to get connection from replica pool")?;
+    if !schema_object_exists(&conn, "entities")? {
+        bail!("Read replica '{}' is not a memory database", path.display());
+    }
+    Ok(pool)
+}
+
+/// Migrate tables created by older versions and create whatever the schema still lacks
+/// Safe to run on every open (and on a restored checkpoint)
+fn prepare_schema(conn: &Connection, options: &DatabaseOptions) -> Result<()> {
+    // Databases created before relations_fts existed, or run with --no-fts until now, need
+    // their entities and relations indexed once
+    let had_entities_fts = schema_object_exists(conn, "entities_fts")?;
+    let had_relations_fts = schema_object_exists(conn, "relations_fts")?;
+
+    // Databases created before observations were enforced as a set may hold duplicates
+    let had_unique_observations = schema_object_exists(conn, "entities_observations_unique_ai")?;
+
+    // Databases created before entities had a description column: add it and drop the
+    // entity FTS table + triggers so FTS_SCHEMA recreates them with the new column
+    let needs_description = entities_lack_column(conn, "description")?;
+    if needs_description {
+        conn.execute_batch(
+            "BEGIN;
+             ALTER TABLE entities ADD COLUMN description TEXT;
+             DROP TRIGGER IF EXISTS entities_ai;
+             DROP TRIGGER IF EXISTS entities_ad;
+             DROP TRIGGER IF EXISTS entities_au;
+             DROP TABLE IF EXISTS entities_fts;
+             COMMIT;",
+        )
+        .context("Failed to migrate entities table (description column)")?;
+    }
+
+    // Databases created before entities had timestamps: rows keep NULL (unknown) times
+    if entities_lack_column(conn, "updated_at")? {
+        conn.execute_batch(
+            "BEGIN;
+             ALTER TABLE entities ADD COLUMN created_at TEXT;
+             ALTER TABLE entities ADD COLUMN updated_at TEXT;
+             COMMIT;",
+        )
+        .context("Failed to migrate entities table (timestamp columns)")?;
+    }
+
+    // Databases created before entities had stable ids: give every existing row one
+    if entities_lack_column(conn, "id")? {
+        conn.execute_batch(
+            "BEGIN;
+             ALTER TABLE entities ADD COLUMN id TEXT;
+             UPDATE entities SET id = lower(hex(randomblob(16)));
+             COMMIT;",
+        )
+        .context("Failed to migrate entities table (id column)")?;
+    }
+
+    // Databases that indexed observations as their stored JSON array: drop the entity
+    // triggers so they are recreated below, then reindex
+    let needs_text_index =
+        schema_object_exists(conn, "entities_ai")? && !entity_triggers_index_text(conn)?;
+    if needs_text_index {
+        conn.execute_batch(
+            "BEGIN;
+             DROP TRIGGER IF EXISTS entities_ai;
+             DROP TRIGGER IF EXISTS entities_ad;
+             DROP TRIGGER IF EXISTS entities_au;
+             COMMIT;",
+        )
+        .context("Failed to migrate entity full-text triggers")?;
+    }
+
+    // Databases created before entities had a confidence score: existing rows have none
+    if entities_lack_column(conn, "confidence")? {
+        conn.execute_batch(
+            "ALTER TABLE entities
+             ADD COLUMN confidence REAL CHECK (confidence BETWEEN 0.0 AND 1.0);",
+        )
+        .context("Failed to migrate entities table (confidence column)")?;
+    }
+
+    // Databases created before creates were tagged with a batch: existing rows have none
+    for table in ["entities", "relations"] {
+        if table_lacks_column(conn, table, "batch_id")? {
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN batch_id TEXT;", table))
+                .with_context(|| format!("Failed to migrate {} table (batch_id column)", table))?;
+        }
+    }
+
+    // Databases created before entities carried metadata: existing rows have none
+    if entities_lack_column(conn, "metadata")? {
+        conn.execute_batch(
+            "ALTER TABLE entities
+             ADD COLUMN metadata TEXT CHECK (metadata IS NULL OR json_valid(metadata));",
+        )
+        .context("Failed to migrate entities table (metadata column)")?;
+    }
+
+    // Create schema
+    conn.execute_batch(SCHEMA)?;
+
+    // Compressed databases from before observation times: SCHEMA just added triggers that
+    // cannot read their stored arrays
+    if observation_triggers_decode(conn)? && !trigger_decodes(conn, "observation_times_ai")? {
+        conn.execute_batch(&format!(
+            "BEGIN;{}COMMIT;",
+            COMPRESSED_OBSERVATION_TIME_TRIGGERS
+        ))
+        .context("Failed to create observation time triggers")?;
+    }
+
+    if options.no_fts {
+        conn.execute_batch(DROP_FTS)
+            .context("Failed to drop full-text indexes")?;
+    } else {
+        // Compressed databases get entity triggers that decode observations first; FTS_SCHEMA
+        // then only adds what is missing
+        if observation_triggers_decode(conn)? && !schema_object_exists(conn, "entities_ai")? {
+            conn.execute_batch(COMPRESSED_ENTITY_TRIGGERS)
+                .context("Failed to create entity full-text triggers")?;
+        }
+        conn.execute_batch(FTS_SCHEMA)?;
+
+        if needs_description || needs_text_index || !had_entities_fts {
+            conn.execute_batch(&format!("BEGIN;{}COMMIT;", SQL_REINDEX_ENTITIES))
+                .context("Failed to rebuild entity full-text index")?;
+        }
+        if !had_relations_fts {
+            conn.execute_batch("INSERT INTO relations_fts(relations_fts) VALUES ('rebuild');")
+                .context("Failed to build relation full-text index")?;
+        }
+    }
+    if !had_unique_observations {
+        dedup_stored_observations(conn)?;
+    }
+    if options.compress_observations && !observation_triggers_decode(conn)? {
+        let entity_triggers = if options.no_fts {
+            ""
+        } else {
+            COMPRESSED_ENTITY_TRIGGERS
+        };
+        conn.execute_batch(&format!(
+            "BEGIN;{}{}{}
+             UPDATE entities SET observations = observations_encode(observations);
+             COMMIT;",
+            COMPRESS_OBSERVATIONS, COMPRESSED_OBSERVATION_TIME_TRIGGERS, entity_triggers
+        ))
+        .context("Failed to enable observation compression")?;
+    }
+    if options.name_type_keys && !keys_carry_type(conn)? {
+        key_entities_by_type(conn)?;
+    }
+    Ok(())
+}
+
+/// Whether the database keys entities by name and type (see NAME_TYPE_KEY_TRIGGERS)
+fn keys_carry_type(conn: &Connection) -> Result<bool> {
+    schema_object_exists(conn, "entities_name_type_key_ai")
+}
+
+/// Switch a database to name#type keys, failing without changes if a key would be longer
+/// than lookups accept
+fn key_entities_by_type(conn: &Connection) -> Result<()> {
+    let too_long: Option<String> = conn
+        .query_row(
+            "SELECT name FROM entities
+             WHERE length(name) + 1 + length(entity_type) > ?1 LIMIT 1",
+            params![MAX_NAME_LENGTH],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to check entity key lengths")?;
+    if let Some(name) = too_long {
+        bail!(
+            "Cannot key entities by name and type: the key of entity '{}' would be longer than {} characters",
+            name,
+            MAX_NAME_LENGTH
+        );
+    }
+    conn.execute_batch(&format!(
+        "BEGIN;{}{}COMMIT;",
+        KEY_ENTITIES_BY_TYPE, NAME_TYPE_KEY_TRIGGERS
+    ))
+    .context("Failed to key entities by name and type")
+}
+
+/// Key of an entity in a database keyed by name and type; a name already carrying its
+/// type ("Mercury#planet" of type planet) is taken as the key itself
+fn typed_key(name: &str, entity_type: &str) -> String {
+    match name.rsplit_once('#') {
+        Some((bare, suffix)) if !bare.is_empty() && suffix == entity_type => name.to_string(),
+        _ => format!("{}#{}", name, entity_type),
+    }
+}
+
+/// For a name that has no entity in a database keyed by name and type, a note listing the
+/// keys it could have meant ("" otherwise), to append to "does not exist" errors
+fn typed_key_hint(conn: &Connection, name: &str) -> String {
+    let keys = || -> Result<Vec<String>> {
+        if !keys_carry_type(conn)? || entity_exists(conn, name)? {
+            return Ok(Vec::new());
+        }
+        // Keys "name#type" sort between "name#" and "name$"; the type has no '#'
+        let keys = conn
+            .prepare_cached(
+                "SELECT name FROM entities
+                 WHERE name > ?1 || '#' AND name < ?1 || '$'
+                   AND instr(substr(name, length(?1) + 2), '#') = 0
+                 ORDER BY name LIMIT 5",
+            )?
+            .query_map(params![name], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(keys)
+    };
+    match keys() {
+        Ok(keys) if !keys.is_empty() => format!(
+            " (entities are keyed by name#type: did you mean {}?)",
+            keys.iter()
+                .map(|key| format!("'{}'", key))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Whether the set check triggers read observations through observations_json, i.e. the
+/// database has been switched to compressed observations (which it then keeps)
+fn observation_triggers_decode(conn: &Connection) -> Result<bool> {
+    trigger_decodes(conn, "entities_observations_unique_ai")
+}
+
+/// Whether a trigger reads observations through observations_json
+fn trigger_decodes(conn: &Connection, trigger: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master
+                       WHERE name = ?1 AND sql LIKE '%observations_json%')",
+        params![trigger],
+        |row| row.get(0),
+    )
+    .context("Failed to inspect entity triggers")
+}
+
+/// Whether the entity triggers index the text of observations rather than the JSON array
+fn entity_triggers_index_text(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master
+                       WHERE name = 'entities_ai' AND sql LIKE '%group_concat%')",
+        [],
+        |row| row.get(0),
+    )
+    .context("Failed to inspect entity triggers")
+}
+
+pub struct Database {
+    pool: Pool<SqliteConnectionManager>,
+    /// Read-only pool on the replica (--read-db-path) serving the read tools, if configured
+    read_pool: Option<Pool<SqliteConnectionManager>>,
+    options: DatabaseOptions,
+}
+
+impl Database {
+    /// Open or create database with connection pool
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_options(path, DatabaseOptions::default())
+    }
+
+    /// Open or create database with connection pool and custom options
+    pub fn open_with_options(path: &Path, mut options: DatabaseOptions) -> Result<Self> {
+        // Validate path first (resolved once the options are checked)
+        validate_db_extension(path)?;
+
+        // Allowlist entries must themselves be valid types, otherwise they could never match
+        for allowed in options.entity_types.iter().flatten() {
+            validate_type(allowed, "Allowed entity type")?;
+        }
+        for allowed in options.relation_types.iter().flatten() {
+            validate_type(allowed, "Allowed relation type")?;
+        }
+
+        if options.compress_observations && !cfg!(feature = "compress-observations") {
+            bail!("Observation compression needs a build with the compress-observations feature");
+        }
+
+        if let Some(max) = options.max_entity_name_length {
+            if max == 0 || max > MAX_NAME_LENGTH {
+                bail!(
+                    "Invalid maximum entity name length {} (must be 1-{})",
+                    max,
+                    MAX_NAME_LENGTH
+                );
+            }
+        }
+
+        if let Some(min_idle) = options.pool_min_idle {
+            if min_idle > POOL_SIZE {
+                bail!(
+                    "Pool minimum idle connections {} exceed the pool size {}",
+                    min_idle,
+                    POOL_SIZE
+                );
+            }
+        }
+        if options.pool_idle_timeout == Some(Duration::ZERO) {
+            bail!("Pool idle timeout must be positive");
+        }
+        if let Some(window) = options.observation_buffer {
+            if window.is_zero() || window > MAX_OBSERVATION_BUFFER {
+                bail!(
+                    "Observation buffer window must be between 1 ms and {} ms",
+                    MAX_OBSERVATION_BUFFER.as_millis()
+                );
+            }
+        }
+
+        // PRAGMA mmap_size takes a signed 64-bit value
+        if let Some(mmap_size) = options.mmap_size {
+            if mmap_size > i64::MAX as u64 {
+                bail!("mmap size {} is too large", mmap_size);
+            }
+        }
+
+        // Create parent directory if needed, resolve '..' and symlinks, check containment
+        let path = resolve_db_path(
+            path,
+            &DbPathPolicy {
+                create_dirs: !options.no_create_dirs,
+                allowed_dir: options.allowed_db_dir.clone(),
+            },
+        )?;
+
+        let manager = SqliteConnectionManager::file(&path);
+        let pool = pool_builder(&options)
+            .connection_customizer(Box::new(SqliteCustomizer {
+                cache_size: options.cache_size,
+                mmap_size: options.mmap_size,
+                compress_observations: options.compress_observations,
+            })) // Apply PRAGMAs per-connection
+            .build(manager)
+            .context("Failed to create connection pool")?;
+
+        // Initialize schema on first connection (WAL mode persists in DB file)
+        {
+            let conn = pool.get().context("Failed to get connection from pool")?;
+
+            // WAL mode for concurrent reads (persisted in DB, only need to set once)
+            conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+
+            prepare_schema(&conn, &options)?;
+            // Once switched, the database stays keyed by name and type
+            options.name_type_keys = keys_carry_type(&conn)?;
+        }
+
+        if options.prewarm {
+            prewarm_pool(&pool, options.no_fts)?;
+        }
+
+        let read_pool = match &options.read_replica {
+            Some(replica) => Some(open_read_replica(replica, &options)?),
+            None => None,
+        };
+
+        Ok(Self {
+            pool,
+            read_pool,
+            options,
+        })
+    }
+
+    /// Get a pooled connection, arming the query timeout (if configured) for this operation
+    fn connection(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        self.checkout(&self.pool)
+    }
+
+    /// Get a connection for a read tool: from the replica pool if there is one, else the primary
+    fn read_connection(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        self.checkout(self.read_pool.as_ref().unwrap_or(&self.pool))
+    }
+
+    fn checkout(
+        &self,
+        pool: &Pool<SqliteConnectionManager>,
+    ) -> Result<PooledConnection<SqliteConnectionManager>> {
+        let budget = CALL_BUDGET.with(|budget| budget.borrow().clone());
+        if let Some(budget) = &budget {
+            // The caller already reported a timeout: do not start anything new
+            if budget.is_abandoned() {
+                return Err(OperationTimedOut {
+                    after: self.options.query_timeout.unwrap_or_default(),
+                }
+                .into());
+            }
+        }
+
+        let conn = pool.get().map_err(|e| {
+            // Timed out with every connection handed out: overload, not a broken database
+            let state = pool.state();
+            if state.connections >= pool.max_size() && state.idle_connections == 0 {
+                anyhow::Error::new(PoolExhausted {
+                    connections: pool.max_size(),
+                    waited: pool.connection_timeout(),
+                })
+            } else {
+                anyhow::Error::new(e).context("Failed to get database connection from pool")
+            }
+        })?;
+
+        // Re-armed on every checkout, so a deadline never leaks into the next operation
+        match budget {
+            Some(budget) => {
+                let deadline = budget.deadline();
+                conn.progress_handler(
+                    PROGRESS_HANDLER_OPS,
+                    Some(move || Instant::now() >= deadline),
+                );
+                // A commit after the caller gave up is turned into a rollback
+                conn.commit_hook(Some(move || !budget.begin_commit()));
+            }
+            None => {
+                if let Some(timeout) = self.options.query_timeout {
+                    let deadline = Instant::now() + timeout;
+                    conn.progress_handler(
+                        PROGRESS_HANDLER_OPS,
+                        Some(move || Instant::now() >= deadline),
+                    );
+                }
+                conn.commit_hook(None::<fn() -> bool>);
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Verify the database is usable: runs `SELECT 1` and `PRAGMA integrity_check`
+    /// Returns an error describing the first problems found
+    pub fn health_check(&self) -> Result<()> {
+        let conn = self.connection()?;
+        check_health(&conn, self.options.no_fts)
+    }
+
+    /// Run the health check against a database file without opening it for writing
+    /// One read-only connection: no journal mode change, migrations, index rebuilds or pool
+    pub fn check_file(path: &Path, options: &DatabaseOptions) -> Result<()> {
+        validate_db_extension(path)?;
+        if !path.is_file() {
+            bail!("database file not found");
+        }
+        let path = resolve_db_path(
+            path,
+            &DbPathPolicy {
+                create_dirs: false,
+                allowed_dir: options.allowed_db_dir.clone(),
+            },
+        )?;
+
+        let conn = Connection::open_with_flags(
+            &path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("Failed to open '{}' read-only", path.display()))?;
+        check_health(&conn, options.no_fts)
+    }
+
+    /// Relations whose 'from' or 'to' names no entity (left behind by writes made with
+    /// foreign keys off, e.g. bulk imports by other tools, or by databases older than the keys)
+    pub fn dangling_relations(&self) -> Result<DanglingRelations> {
+        let conn = self.connection()?;
+        dangling_relations(&conn)
+    }
+
+    /// Create a bare entity of the given type for every missing endpoint of a dangling
+    /// relation, returning the created entities; the relations themselves are kept
+    /// With name#type keys, only endpoints ending in "#<entity_type>" are created
+    pub fn materialize_dangling(&self, entity_type: &str) -> Result<Vec<Entity>> {
+        self.validate_new_type(entity_type, "Entity type")?;
+        validate_allowed_type(
+            entity_type,
+            self.options.entity_types.as_ref(),
+            "Entity type",
+        )?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for materializing endpoints")?;
+
+        let mut missing = dangling_relations(&tx)?.missing;
+        if self.options.name_type_keys {
+            // Only endpoints that are keys of this type can become entities of it
+            missing.retain(|name| typed_key(name, entity_type) == *name);
+        }
+        let mut created = Vec::with_capacity(missing.len());
+        {
+            let mut stmt = tx
+                .prepare_cached(SQL_INSERT_ENTITY)
+                .context("Failed to prepare insert statement for entities")?;
+            for name in missing {
+                // Already referenced, so only the basic rules apply (not --max-entity-name-length)
+                validate_name(&name, "Entity name")?;
+                let id: Option<String> = stmt
+                    .query_row(
+                        params![
+                            &name,
+                            entity_type,
+                            "[]",
+                            None::<String>,
+                            None::<f64>,
+                            None::<String>,
+                            None::<String>
+                        ],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .with_context(|| format!("Failed to insert entity '{}'", name))?;
+                created.push(Entity {
+                    name,
+                    entity_type: entity_type.to_string(),
+                    observations: Vec::new(),
+                    description: None,
+                    id,
+                    confidence: None,
+                    metadata: None,
+                });
+            }
+        }
+
+        tx.commit()
+            .context("Failed to commit transaction for materializing endpoints")?;
+        Ok(created)
+    }
+
+    /// Compare the row counts of entities and relations with their full-text indexes
+    pub fn search_index_counts(&self) -> Result<Vec<SearchIndexCount>> {
+        self.check_fts_enabled()?;
+        let conn = self.connection()?;
+        search_index_counts(&conn)
+    }
+
+    /// Rebuild both full-text indexes from the entities and relations tables
+    pub fn rebuild_search_index(&self) -> Result<()> {
+        self.check_fts_enabled()?;
+        let conn = self.connection()?;
+        conn.execute_batch(&format!(
+            "BEGIN;{}
+             INSERT INTO relations_fts(relations_fts) VALUES ('rebuild');
+             COMMIT;",
+            SQL_REINDEX_ENTITIES
+        ))
+        .context("Failed to rebuild full-text indexes")
+    }
+
+    /// Checkpoint the WAL into the main database file and truncate it
+    /// Returns false if readers or writers kept the checkpoint from completing
+    pub fn checkpoint(&self) -> Result<bool> {
+        let conn = self.connection()?;
+        let busy: i64 = conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))
+            .context("Failed to checkpoint WAL")?;
+        Ok(busy == 0)
+    }
+
+    /// Copy the whole database into a new file with SQLite's online backup API
+    /// The copy is a consistent snapshot; other connections keep working meanwhile
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        let conn = self.connection()?;
+        conn.backup(MAIN_DB, path, None)
+            .with_context(|| format!("Failed to back up database to '{}'", path.display()))
+    }
+
+    /// Replace the contents of the live database with a backup file, returning the new revision
+    /// The backup is written through a pooled connection, so every other connection sees the
+    /// restored graph on its next read. The revision moves past both the current and the
+    /// restored one, so a graph ETag handed out before is never reused for different data.
+    pub fn restore_from(&self, path: &Path) -> Result<u64> {
+        if !path.is_file() {
+            bail!("Backup file '{}' does not exist", path.display());
+        }
+        // Reject foreign files before the live database is touched
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open backup '{}'", path.display()))?;
+        if !schema_object_exists(&source, "entities")? {
+            bail!("'{}' is not a memory database", path.display());
+        }
+        drop(source);
+
+        let before = self.revision()?;
+        let mut conn = self.connection()?;
+        conn.restore(MAIN_DB, path, None::<fn(Progress)>)
+            .with_context(|| format!("Failed to restore database from '{}'", path.display()))?;
+
+        // Backups taken by older versions get the same migrations as on open
+        prepare_schema(&conn, &self.options)?;
+
+        let revision = before.max(read_revision(&conn)?) + 1;
+        conn.execute(
+            "UPDATE graph_revision SET revision = ?1 WHERE id = 1",
+            params![revision as i64],
+        )
+        .context("Failed to update graph revision after restore")?;
+        Ok(revision)
+    }
+
+    /// Create entities (returns only newly created entities)
+    pub fn create_entities(&self, entities: &[Entity]) -> Result<Vec<Entity>> {
+        Ok(self.create_entities_detailed(entities)?.created)
+    }
+
+    /// Create entities, also reporting which ones were deduplicated into existing entities
+    /// Optimized: Uses INSERT OR IGNORE with tracking, no full table scan
+    /// Wrapped in transaction for atomicity
+    pub fn create_entities_detailed(&self, entities: &[Entity]) -> Result<CreateEntitiesResult> {
+        if entities.is_empty() {
+            return Ok(CreateEntitiesResult::default());
+        }
+
+        // Validate all entities before starting transaction (observations normalized per format)
+        let entities = self.prepare_entities(entities)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for creating entities")?;
+        let batch_id = new_batch_id(&tx)?;
+        let result = self.insert_entities(&tx, &entities, &batch_id)?;
+        tx.commit()
+            .context("Failed to commit transaction for creating entities")?;
+        Ok(result)
+    }
+
+    /// Replace an entity's type and observations in one transaction, creating it if missing
+    /// Relations, id and description of an existing entity are kept (the description is
+    /// only used on create); near-duplicate names are not mapped (the name is exact)
+    pub fn replace_entity(&self, entity: &Entity) -> Result<ReplacedEntity> {
+        let entity = self
+            .prepare_entities(std::slice::from_ref(entity))?
+            .pop()
+            .context("Replacement entity missing after validation")?;
+        let obs_json = serde_json::to_string(&entity.observations).with_context(|| {
+            format!(
+                "Failed to serialize observations for entity '{}'",
+                entity.name
+            )
+        })?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for replacing entity")?;
+        let replaced = overwrite_entity(&tx, &entity)?;
+        if !replaced {
+            tx.prepare_cached(SQL_INSERT_ENTITY)
+                .context("Failed to prepare insert statement for entities")?
+                .query_row(
+                    params![
+                        &entity.name,
+                        &entity.entity_type,
+                        &obs_json,
+                        &entity.description,
+                        entity.confidence,
+                        None::<String>,
+                        entity.metadata.as_ref().map(|m| m.to_string())
+                    ],
+                    |row| row.get::<_, String>(0),
+                )
+                .with_context(|| format!("Failed to insert entity '{}'", entity.name))?;
+        }
+        let stored = self
+            .read_entities_by_names(&tx, std::slice::from_ref(&entity.name))?
+            .pop()
+            .with_context(|| format!("Entity '{}' vanished after replace", entity.name))?;
+        tx.commit()
+            .context("Failed to commit transaction for replacing entity")?;
+
+        Ok(ReplacedEntity {
+            entity: stored,
+            created: !replaced,
+        })
+    }
+
+    /// Longest name accepted for new entities
+    fn entity_name_limit(&self) -> usize {
+        self.options
+            .max_entity_name_length
+            .unwrap_or(MAX_NAME_LENGTH)
+    }
+
+    /// Validate the name of an entity about to be created (full policy, configured length)
+    fn validate_new_entity_name(&self, name: &str) -> Result<()> {
+        validate_name(name, "Entity name")?;
+        check_length(
+            name,
+            self.entity_name_limit(),
+            self.options.length_unit,
+            "Entity name",
+        )
+    }
+
+    /// Validate a type about to be stored (full policy, configured length unit)
+    fn validate_new_type(&self, type_str: &str, field: &str) -> Result<()> {
+        validate_type(type_str, field)?;
+        check_length(type_str, MAX_TYPE_LENGTH, self.options.length_unit, field)
+    }
+
+    /// Validate entities, returning them with observations normalized per the configured format
+    fn prepare_entities(&self, entities: &[Entity]) -> Result<Vec<Entity>> {
+        validate_batch(
+            entities,
+            |entity| entity.name.clone(),
+            |entity| self.prepare_entity(entity),
+        )
+    }
+
+    /// Validate one entity, returning it with observations normalized per the configured format
+    fn prepare_entity(&self, entity: &Entity) -> Result<Entity> {
+        self.validate_new_entity_name(&entity.name)?;
+        self.validate_new_type(&entity.entity_type, "Entity type")?;
+        validate_allowed_type(
+            &entity.entity_type,
+            self.options.entity_types.as_ref(),
+            "Entity type",
+        )?;
+        // Observations are a set: repeats within the request collapse into one
+        let observations = entity
+            .observations
+            .iter()
+            .map(|obs| {
+                validate_observation(
+                    obs,
+                    self.options.observation_format,
+                    self.options.length_unit,
+                )
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(unique_observations)?;
+        if let Some(description) = &entity.description {
+            validate_description(description, self.options.length_unit)?;
+        }
+        if let Some(confidence) = entity.confidence {
+            validate_confidence(confidence)?;
+        }
+        if let Some(metadata) = &entity.metadata {
+            validate_metadata(metadata)?;
+        }
+        let name = if self.options.name_type_keys {
+            let key = typed_key(&entity.name, &entity.entity_type);
+            check_lookup_length(&key, MAX_NAME_LENGTH, "Entity key (name#type)")?;
+            key
+        } else {
+            entity.name.clone()
+        };
+        Ok(Entity {
+            name,
+            observations,
+            ..entity.clone()
+        })
+    }
+
+    /// Helper: insert already validated entities inside the caller's transaction
+    fn insert_entities(
+        &self,
+        tx: &Connection,
+        entities: &[Entity],
+        batch_id: &str,
+    ) -> Result<CreateEntitiesResult> {
+        let mut result = CreateEntitiesResult {
+            batch_id: Some(batch_id.to_string()),
+            ..Default::default()
+        };
+
+        // Normalized name -> stored name, only built when deduplication is enabled
+        let dedup = self.options.dedup_on_create;
+        let mut known = match dedup {
+            Some(dedup) => load_normalized_names(tx, &dedup)?,
+            None => HashMap::new(),
+        };
+
+        let mut stmt = tx
+            .prepare_cached(SQL_INSERT_ENTITY)
+            .context("Failed to prepare insert statement for entities")?;
+
+        // INSERT OR IGNORE returns 0 if row already exists, 1 if inserted
+        for entity in entities {
+            if let Some(dedup) = dedup {
+                let key = dedup.normalize(&entity.name);
+                if let Some(existing) = known.get(&key) {
+                    let merged = if dedup.merge_observations {
+                        merge_observations(tx, existing, &entity.observations)?
+                    } else {
+                        Vec::new()
+                    };
+                    result.deduplicated.push(DeduplicatedEntity {
+                        name: entity.name.clone(),
+                        existing_name: existing.clone(),
+                        merged_observations: merged,
+                    });
+                    continue;
+                }
+                known.insert(key, entity.name.clone());
+            }
+
+            let obs_json = serde_json::to_string(&entity.observations).context(format!(
+                "Failed to serialize observations for entity '{}'",
+                entity.name
+            ))?;
+            let id: Option<String> = stmt
+                .query_row(
+                    params![
+                        &entity.name,
+                        &entity.entity_type,
+                        &obs_json,
+                        &entity.description,
+                        entity.confidence,
+                        batch_id,
+                        entity.metadata.as_ref().map(|m| m.to_string())
+                    ],
+                    |row| row.get(0),
+                )
+                .optional()
+                .with_context(|| format!("Failed to insert entity '{}'", entity.name))?;
+
+            // Track only newly inserted entities, with the id they were given
+            if let Some(id) = id {
+                result.created.push(Entity {
+                    id: Some(id),
+                    ..entity.clone()
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    /// Create relations (returns only newly created relations)
+    pub fn create_relations(&self, relations: &[Relation]) -> Result<Vec<Relation>> {
+        Ok(self.create_relations_detailed(relations)?.created)
+    }
+
+    /// Create relations, also reporting the batch id they were tagged with
+    /// Optimized: Uses INSERT OR IGNORE with tracking, no full table scan
+    /// Wrapped in transaction for atomicity
+    pub fn create_relations_detailed(
+        &self,
+        relations: &[Relation],
+    ) -> Result<CreateRelationsResult> {
+        if relations.is_empty() {
+            return Ok(CreateRelationsResult::default());
+        }
+
+        // Validate all relations before starting transaction
+        self.validate_relations(relations)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for creating relations")?;
+        let (relations, resolved_aliases) = resolve_aliases(&tx, relations)?;
+        if !resolved_aliases.is_empty() {
+            // An alias may turn a relation into a self-relation
+            self.validate_relations(&relations)?;
+        }
+        let batch_id = new_batch_id(&tx)?;
+        let created = insert_relations(&tx, &relations, &batch_id)?;
+        tx.commit()
+            .context("Failed to commit transaction for creating relations")?;
+        Ok(CreateRelationsResult {
+            created,
+            batch_id: Some(batch_id),
+            resolved_aliases,
+        })
+    }
+
+    /// Validate relation names and types (including the relation type allowlist and the
+    /// self-relation policy)
+    fn validate_relations(&self, relations: &[Relation]) -> Result<()> {
+        validate_batch(relations, relation_label, |rel| self.validate_relation(rel))?;
+        Ok(())
+    }
+
+    /// Validate one relation's names and type (see validate_relations)
+    fn validate_relation(&self, rel: &Relation) -> Result<()> {
+        validate_endpoint(&rel.from, "From entity")?;
+        validate_endpoint(&rel.to, "To entity")?;
+        if self.options.disallow_self_relations && rel.from == rel.to {
+            bail!(
+                "Self-relation '{}' -> '{}' is not allowed (from and to must differ)",
+                rel.from,
+                rel.to
+            );
+        }
+        self.validate_new_type(&rel.relation_type, "Relation type")?;
+        validate_allowed_type(
+            &rel.relation_type,
+            self.options.relation_types.as_ref(),
+            "Relation type",
+        )
+    }
+
+    /// Dry run of create_entities validation: one pass/fail verdict per entity, nothing
+    /// written. Existing names pass (create would skip them, not fail)
+    pub fn check_entities(&self, entities: &[Entity]) -> Vec<ItemCheck> {
+        entities
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| {
+                ItemCheck::new(index, entity.name.clone(), self.prepare_entity(entity))
+            })
+            .collect()
+    }
+
+    /// Dry run of create_relations validation, including that both endpoints exist (or are
+    /// among `pending` entity names, e.g. ones about to be created in the same batch); an
+    /// endpoint naming an alias counts as its entity
+    pub fn check_relations(
+        &self,
+        relations: &[Relation],
+        pending: &[String],
+    ) -> Result<Vec<ItemCheck>> {
+        let conn = self.read_connection()?;
+        let mut checks = Vec::with_capacity(relations.len());
+        for (index, rel) in relations.iter().enumerate() {
+            let result = self.validate_relation(rel).and_then(|()| {
+                for endpoint in [&rel.from, &rel.to] {
+                    if !pending.contains(endpoint)
+                        && !entity_exists(&conn, endpoint)?
+                        && alias_target(&conn, endpoint)?.is_none()
+                    {
+                        bail!(
+                            "Entity '{}' does not exist{}",
+                            endpoint,
+                            typed_key_hint(&conn, endpoint)
+                        );
+                    }
+                }
+                Ok(())
+            });
+            checks.push(ItemCheck::new(index, relation_label(rel), result));
+        }
+        Ok(checks)
+    }
+
+    /// True if the database holds no entities and no relations
+    pub fn is_empty(&self) -> Result<bool> {
+        let conn = self.connection()?;
+        conn.query_row(
+            "SELECT NOT EXISTS(SELECT 1 FROM entities) AND NOT EXISTS(SELECT 1 FROM relations)",
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to check whether the database is empty")
+    }
+
+    /// Import a whole graph (entities first, then relations) in a single transaction
+    /// Existing entities/relations are left untouched; returns only what was newly created
+    pub fn import_graph(&self, graph: &KnowledgeGraph) -> Result<KnowledgeGraph> {
+        let entities = self.prepare_entities(&graph.entities)?;
+        self.validate_relations(&graph.relations)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for import")?;
+        let batch_id = new_batch_id(&tx)?;
+        let created = self.insert_entities(&tx, &entities, &batch_id)?;
+        let relations = insert_relations(&tx, &graph.relations, &batch_id)?;
+        tx.commit().context("Failed to commit import transaction")?;
+
+        Ok(KnowledgeGraph {
+            entities: created.created,
+            relations,
+        })
+    }
+
+    /// Import a graph in a single transaction, merging into what already exists
+    /// Unlike import_graph, entities that already exist are handled by the conflict policy:
+    /// kept, overwritten (type and observations), or given the observations they lack
+    pub fn merge_graph(
+        &self,
+        graph: &KnowledgeGraph,
+        policy: ConflictPolicy,
+    ) -> Result<MergeGraphResult> {
+        let entities = self.prepare_entities(&graph.entities)?;
+        self.validate_relations(&graph.relations)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for merging graph")?;
+        let batch_id = new_batch_id(&tx)?;
+        let inserted = self.insert_entities(&tx, &entities, &batch_id)?;
+
+        // Deduplicated entities were already handled by the dedup settings
+        let handled: HashSet<&str> = inserted
+            .created
+            .iter()
+            .map(|e| e.name.as_str())
+            .chain(inserted.deduplicated.iter().map(|d| d.name.as_str()))
+            .collect();
+        let mut merged_observations = Vec::new();
+        let mut updated = Vec::new();
+        let mut skipped = Vec::new();
+        for entity in &entities {
+            if handled.contains(entity.name.as_str()) {
+                continue;
+            }
+            let changed = match policy {
+                ConflictPolicy::Skip => false,
+                ConflictPolicy::Overwrite => overwrite_entity(&tx, entity)?,
+                ConflictPolicy::MergeObservations => {
+                    let added = merge_observations(&tx, &entity.name, &entity.observations)?;
+                    let changed = !added.is_empty();
+                    if changed {
+                        merged_observations.push(ObservationResult {
+                            entity_name: entity.name.clone(),
+                            added_observations: added,
+                        });
+                    }
+                    changed
+                }
+            };
+            // An entity sent twice is reported once, as updated if either copy changed it
+            if changed {
+                skipped.retain(|name| name != &entity.name);
+                if !updated.contains(&entity.name) {
+                    updated.push(entity.name.clone());
+                }
+            } else if !updated.contains(&entity.name) && !skipped.contains(&entity.name) {
+                skipped.push(entity.name.clone());
+            }
+        }
+
+        let relations = insert_relations(&tx, &graph.relations, &batch_id)?;
+        tx.commit().context("Failed to commit merge transaction")?;
+
+        Ok(MergeGraphResult {
+            entities: inserted.created,
+            relations,
+            merged_observations,
+            updated,
+            skipped,
+            batch_id,
+        })
+    }
+
+    /// Add observations to multiple entities (batch operation)
+    /// Wrapped in transaction for atomicity
+    pub fn add_observations(&self, inputs: &[ObservationInput]) -> Result<Vec<ObservationResult>> {
+        // Validate all inputs before starting transaction (observations normalized per format)
+        let inputs = self.prepare_observation_inputs(inputs)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for adding observations")?;
+        let results = insert_observations(&tx, &inputs)?;
+        tx.commit()
+            .context("Failed to commit transaction for adding observations")?;
+        Ok(results)
+    }
+
+    /// What add_observations would add, without writing: inputs are validated and normalized,
+    /// missing entities fail the batch, and observations the entity already has are dropped
+    pub fn preview_observations(
+        &self,
+        inputs: &[ObservationInput],
+    ) -> Result<Vec<ObservationResult>> {
+        let inputs = self.prepare_observation_inputs(inputs)?;
+
+        let conn = self.connection()?;
+        inputs
+            .into_iter()
+            .map(|input| {
+                let existing = load_observations(&conn, &input.entity_name, "add observations")?;
+                let mut added: Vec<String> = Vec::new();
+                for obs in input.contents {
+                    if !existing.contains(&obs) && !added.contains(&obs) {
+                        added.push(obs);
+                    }
+                }
+                Ok(ObservationResult {
+                    entity_name: input.entity_name,
+                    added_observations: added,
+                })
+            })
+            .collect()
+    }
+
+    /// Append one observation to every listed entity in a single transaction
+    /// Names that match no entity are skipped and reported instead of failing the batch;
+    /// repeated names are handled once
+    pub fn add_observation_to_many(
+        &self,
+        names: &[String],
+        observation: &str,
+    ) -> Result<FanOutObservationResult> {
+        validate_entity_names(names)?;
+        let observation = validate_observation(
+            observation,
+            self.options.observation_format,
+            self.options.length_unit,
+        )?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for adding observation")?;
+
+        let mut inputs: Vec<ObservationInput> = Vec::new();
+        let mut missing = Vec::new();
+        for name in names {
+            if inputs.iter().any(|i| &i.entity_name == name) || missing.contains(name) {
+                continue;
+            }
+            if entity_exists(&tx, name)? {
+                inputs.push(ObservationInput {
+                    entity_name: name.clone(),
+                    contents: vec![observation.clone()],
+                });
+            } else {
+                missing.push(name.clone());
+            }
+        }
+        let results = insert_observations(&tx, &inputs)?;
+
+        tx.commit()
+            .context("Failed to commit transaction for adding observation")?;
+
+        let (added, already_present): (Vec<_>, Vec<_>) = results
+            .into_iter()
+            .partition(|r| !r.added_observations.is_empty());
+        Ok(FanOutObservationResult {
+            observation,
+            added: added.into_iter().map(|r| r.entity_name).collect(),
+            already_present: already_present.into_iter().map(|r| r.entity_name).collect(),
+            missing,
+        })
+    }
+
+    /// Append the observations of source to target (skipping ones target already has),
+    /// leaving both entities in place
+    pub fn copy_observations(&self, source: &str, target: &str) -> Result<ObservationResult> {
+        validate_name(source, "Source entity name")?;
+        validate_name(target, "Target entity name")?;
+        if source == target {
+            bail!("Cannot copy observations of '{}' onto itself", source);
+        }
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for copying observations")?;
+
+        let contents = load_observations(&tx, source, "copy observations")?;
+        let mut results = insert_observations(
+            &tx,
+            &[ObservationInput {
+                entity_name: target.to_string(),
+                contents,
+            }],
+        )?;
+
+        tx.commit()
+            .context("Failed to commit transaction for copying observations")?;
+        Ok(results.remove(0))
+    }
+
+    /// Check whether an entity has an observation: exact text first, then ignoring case and
+    /// extra whitespace. A missing entity is reported, not an error
+    pub fn has_observation(
+        &self,
+        entity_name: &str,
+        observation: &str,
+    ) -> Result<ObservationCheck> {
+        validate_name(entity_name, "Entity name")?;
+
+        let conn = self.connection()?;
+        let stored: Option<String> = conn
+            .prepare_cached(SQL_SELECT_OBSERVATIONS)
+            .and_then(|mut stmt| stmt.query_row(params![entity_name], |row| row.get(0)))
+            .optional()
+            .with_context(|| format!("Database error querying entity '{}'", entity_name))?;
+        let Some(stored) = stored else {
+            return Ok(ObservationCheck {
+                entity_exists: false,
+                found: false,
+                matched: None,
+            });
+        };
+        let observations = decode_observations(&stored)
+            .with_context(|| format!("Corrupted observations data for entity '{}'", entity_name))?;
+
+        let normalized = similarity::normalize_text(observation);
+        let matched = observations
+            .iter()
+            .find(|obs| *obs == observation)
+            .or_else(|| {
+                observations
+                    .iter()
+                    .find(|obs| similarity::normalize_text(obs) == normalized)
+            })
+            .cloned();
+        Ok(ObservationCheck {
+            entity_exists: true,
+            found: matched.is_some(),
+            matched,
+        })
+    }
+
+    /// Validate observation inputs, returning them with contents normalized per the configured format
+    fn prepare_observation_inputs(
+        &self,
+        inputs: &[ObservationInput],
+    ) -> Result<Vec<ObservationInput>> {
+        validate_batch(
+            inputs,
+            |input| input.entity_name.clone(),
+            |input| {
+                validate_name(&input.entity_name, "Entity name")?;
+                let contents = input
+                    .contents
+                    .iter()
+                    .map(|obs| {
+                        validate_observation(
+                            obs,
+                            self.options.observation_format,
+                            self.options.length_unit,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(ObservationInput {
+                    entity_name: input.entity_name.clone(),
+                    contents,
+                })
+            },
+        )
+    }
+
+    /// Apply staged operations in order, all in one transaction (all or nothing)
+    /// Every operation is validated before the transaction starts; errors name the operation
+    pub fn apply_operations(&self, operations: &[GraphOperation]) -> Result<Vec<OperationResult>> {
+        let prepared = operations
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                self.prepare_operation(op)
+                    .with_context(|| format!("Operation #{} ({})", i, op.name()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for staged operations")?;
+        // Everything the operations create forms one batch
+        let batch_id = new_batch_id(&tx)?;
+        let results = prepared
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                self.apply_operation(&tx, op, &batch_id)
+                    .with_context(|| format!("Operation #{} ({})", i, op.name()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        tx.commit()
+            .context("Failed to commit transaction for staged operations")?;
+        Ok(results)
+    }
+
+    /// Helper: apply one validated operation inside the caller's transaction
+    fn apply_operation(
+        &self,
+        tx: &Connection,
+        op: &GraphOperation,
+        batch_id: &str,
+    ) -> Result<OperationResult> {
+        Ok(match op {
+            GraphOperation::CreateEntities { entities } => {
+                OperationResult::CreateEntities(self.insert_entities(tx, entities, batch_id)?)
+            }
+            GraphOperation::CreateRelations { relations } => {
+                let (relations, resolved) = resolve_aliases(tx, relations)?;
+                if !resolved.is_empty() {
+                    self.validate_relations(&relations)?;
+                }
+                OperationResult::CreateRelations {
+                    relations: insert_relations(tx, &relations, batch_id)?,
+                }
+            }
+            GraphOperation::AddObservations { observations } => OperationResult::AddObservations {
+                results: insert_observations(tx, observations)?,
+            },
+            GraphOperation::DeleteEntities { names } => OperationResult::DeleteEntities {
+                deleted: remove_entities(tx, names)?,
+            },
+            GraphOperation::DeleteObservations { deletions } => {
+                remove_observations(tx, deletions)?;
+                OperationResult::DeleteObservations {
+                    entities: deletions.iter().map(|d| d.entity_name.clone()).collect(),
+                }
+            }
+            GraphOperation::DeleteRelations { relations } => OperationResult::DeleteRelations {
+                deleted: remove_relations(tx, &resolve_aliases(tx, relations)?.0)?,
+            },
+        })
+    }
+
+    /// Check an operation the way its tool would, without touching the database
+    pub fn validate_operation(&self, operation: &GraphOperation) -> Result<()> {
+        self.prepare_operation(operation).map(|_| ())
+    }
+
+    /// Validate an operation, returning it with inputs normalized like the direct tool call
+    fn prepare_operation(&self, operation: &GraphOperation) -> Result<GraphOperation> {
+        Ok(match operation {
+            GraphOperation::CreateEntities { entities } => GraphOperation::CreateEntities {
+                entities: self.prepare_entities(entities)?,
+            },
+            GraphOperation::CreateRelations { relations } => {
+                self.validate_relations(relations)?;
+                operation.clone()
+            }
+            GraphOperation::AddObservations { observations } => GraphOperation::AddObservations {
+                observations: self.prepare_observation_inputs(observations)?,
+            },
+            GraphOperation::DeleteEntities { names } => {
+                validate_entity_names(names)?;
+                operation.clone()
+            }
+            GraphOperation::DeleteObservations { deletions } => {
+                validate_observation_deletions(deletions)?;
+                operation.clone()
+            }
+            GraphOperation::DeleteRelations { relations } => {
+                validate_relation_keys(relations)?;
+                operation.clone()
+            }
+        })
+    }
+
+    /// Delete entities (cascade delete via FOREIGN KEY)
+    /// Wrapped in transaction for atomicity when deleting multiple entities
+    pub fn delete_entities(&self, names: &[String]) -> Result<usize> {
+        if names.is_empty() {
+            return Ok(0);
+        }
+
+        // Validate all entity names before starting transaction
+        validate_entity_names(names)?;
+
+        let conn = self.connection()?;
+
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for deleting entities")?;
+
+        // FOREIGN KEY CASCADE auto-deletes relations!
+        let count = remove_entities(&tx, names)?;
+
+        tx.commit()
+            .context("Failed to commit transaction for deleting entities")?;
+
+        Ok(count)
+    }
+
+    /// Delete observations from multiple entities (batch operation)
+    /// Wrapped in transaction for atomicity
+    pub fn delete_observations(&self, deletions: &[ObservationDeletion]) -> Result<()> {
+        // Validate all deletions before starting transaction
+        validate_observation_deletions(deletions)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for deleting observations")?;
+        remove_observations(&tx, deletions)?;
+        tx.commit()
+            .context("Failed to commit transaction for deleting observations")?;
+        Ok(())
+    }
+
+    /// Reorder observations of multiple entities (batch operation)
+    /// Listed indices move to the front in the given order, the rest keep their relative order
+    /// Wrapped in transaction for atomicity
+    pub fn reorder_observations(
+        &self,
+        reorders: &[ObservationReorder],
+    ) -> Result<Vec<EntityObservations>> {
+        // Validate all reorders before starting transaction
+        validate_batch(
+            reorders,
+            |reorder| reorder.entity_name.clone(),
+            |reorder| {
+                validate_name(&reorder.entity_name, "Entity name")?;
+                let mut seen = HashSet::new();
+                if let Some(dup) = reorder.order.iter().find(|&&i| !seen.insert(i)) {
+                    bail!(
+                        "Duplicate observation index {} in order for entity '{}'",
+                        dup,
+                        reorder.entity_name
+                    );
+                }
+                Ok(())
+            },
+        )?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for reordering observations")?;
+        let mut results = Vec::new();
+
+        for reorder in reorders {
+            let observations =
+                load_observations(&tx, &reorder.entity_name, "reorder observations")?;
+            validate_indices(&reorder.order, observations.len(), &reorder.entity_name)?;
+
+            let mut reordered: Vec<String> = reorder
+                .order
+                .iter()
+                .map(|&i| observations[i].clone())
+                .collect();
+            reordered.extend(
+                observations
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| !reorder.order.contains(i))
+                    .map(|(_, obs)| obs),
+            );
+
+            store_observations(&tx, &reorder.entity_name, &reordered)?;
+            results.push(EntityObservations {
+                entity_name: reorder.entity_name.clone(),
+                observations: reordered,
+            });
+        }
+
+        tx.commit()
+            .context("Failed to commit transaction for reordering observations")?;
+        Ok(results)
+    }
+
+    /// Delete relations
+    /// Wrapped in transaction for atomicity
+    pub fn delete_relations(&self, relations: &[Relation]) -> Result<usize> {
+        Ok(self.delete_relations_detailed(relations)?.deleted)
+    }
+
+    /// Delete relations, also reporting the endpoints that were given as aliases
+    pub fn delete_relations_detailed(
+        &self,
+        relations: &[Relation],
+    ) -> Result<DeleteRelationsResult> {
+        if relations.is_empty() {
+            return Ok(DeleteRelationsResult::default());
+        }
+
+        // Validate all relations before starting transaction
+        validate_relation_keys(relations)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for deleting relations")?;
+        let (relations, resolved_aliases) = resolve_aliases(&tx, relations)?;
+        let deleted = remove_relations(&tx, &relations)?;
+        tx.commit()
+            .context("Failed to commit transaction for deleting relations")?;
+        Ok(DeleteRelationsResult {
+            deleted,
+            resolved_aliases,
+        })
+    }
+
+    /// Change the type of a single relation in place (e.g. employs -> manages)
+    /// Errors if the relation does not exist or the new type would duplicate an existing relation
+    pub fn change_relation_type(&self, relation: &Relation, new_type: &str) -> Result<Relation> {
+        validate_endpoint(&relation.from, "From entity")?;
+        validate_endpoint(&relation.to, "To entity")?;
+        validate_type(&relation.relation_type, "Relation type")?;
+        self.validate_new_type(new_type, "New relation type")?;
+        validate_allowed_type(
+            new_type,
+            self.options.relation_types.as_ref(),
+            "Relation type",
+        )?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for changing relation type")?;
+
+        let exists = |relation_type: &str| -> Result<bool> {
+            tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM relations
+                 WHERE from_entity = ?1 AND to_entity = ?2 AND relation_type = ?3)",
+                params![&relation.from, &relation.to, relation_type],
+                |row| row.get(0),
+            )
+            .context("Failed to look up relation")
+        };
+
+        if !exists(&relation.relation_type)? {
+            bail!(
+                "Cannot change relation type: relation '{}' -> '{}' (type: '{}') does not exist",
+                relation.from,
+                relation.to,
+                relation.relation_type
+            );
+        }
+        if new_type != relation.relation_type && exists(new_type)? {
+            bail!(
+                "Cannot change relation type: relation '{}' -> '{}' (type: '{}') already exists; \
+                 delete one of the two relations instead",
+                relation.from,
+                relation.to,
+                new_type
+            );
+        }
+
+        tx.execute(
+            "UPDATE relations SET relation_type = ?4
+             WHERE from_entity = ?1 AND to_entity = ?2 AND relation_type = ?3",
+            params![
+                &relation.from,
+                &relation.to,
+                &relation.relation_type,
+                new_type
+            ],
+        )
+        .context("Failed to update relation type")?;
+
+        tx.commit()
+            .context("Failed to commit transaction for changing relation type")?;
+        Ok(Relation {
+            from: relation.from.clone(),
+            to: relation.to.clone(),
+            relation_type: new_type.to_string(),
+        })
+    }
+
+    /// Swap the direction of a single relation in place (A -t-> B becomes B -t-> A)
+    /// The row keeps its id; if B -t-> A already exists, A -t-> B is just removed (merged)
+    pub fn reverse_relation(&self, relation: &Relation) -> Result<ReversedRelation> {
+        validate_endpoint(&relation.from, "From entity")?;
+        validate_endpoint(&relation.to, "To entity")?;
+        validate_type(&relation.relation_type, "Relation type")?;
+
+        let reversed = Relation {
+            from: relation.to.clone(),
+            to: relation.from.clone(),
+            relation_type: relation.relation_type.clone(),
+        };
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for reversing relation")?;
+
+        let exists = |rel: &Relation| -> Result<bool> {
+            tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM relations
+                 WHERE from_entity = ?1 AND to_entity = ?2 AND relation_type = ?3)",
+                params![&rel.from, &rel.to, &rel.relation_type],
+                |row| row.get(0),
+            )
+            .context("Failed to look up relation")
+        };
+
+        // The relation's endpoints exist whenever the relation does (FOREIGN KEY)
+        if !exists(relation)? {
+            bail!(
+                "Cannot reverse relation: relation '{}' -> '{}' (type: '{}') does not exist",
+                relation.from,
+                relation.to,
+                relation.relation_type
+            );
+        }
+
+        // A self-relation is its own reverse
+        let merged = relation.from != relation.to && exists(&reversed)?;
+        if merged {
+            remove_relations(&tx, std::slice::from_ref(relation))?;
+        } else {
+            tx.execute(
+                "UPDATE relations SET from_entity = to_entity, to_entity = from_entity
+                 WHERE from_entity = ?1 AND to_entity = ?2 AND relation_type = ?3",
+                params![&relation.from, &relation.to, &relation.relation_type],
+            )
+            .context("Failed to reverse relation")?;
+        }
+
+        tx.commit()
+            .context("Failed to commit transaction for reversing relation")?;
+        Ok(ReversedRelation {
+            relation: reversed,
+            merged,
+        })
+    }
+
+    /// Set or clear (None) the description of an existing entity, returning the updated entity
+    pub fn set_entity_description(
+        &self,
+        entity_name: &str,
+        description: Option<&str>,
+    ) -> Result<Entity> {
+        validate_name(entity_name, "Entity name")?;
+        if let Some(description) = description {
+            validate_description(description, self.options.length_unit)?;
+        }
+
+        let conn = self.connection()?;
+        let updated = conn
+            .execute(
+                "UPDATE entities
+                 SET description = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                 WHERE name = ?2",
+                params![description, entity_name],
+            )
+            .context("Failed to update entity description")?;
+        if updated == 0 {
+            bail!(
+                "Cannot set description: entity '{}' does not exist{}",
+                entity_name,
+                typed_key_hint(&conn, entity_name)
+            );
+        }
+
+        self.read_entities_by_names(&conn, &[entity_name.to_string()])?
+            .pop()
+            .with_context(|| format!("Entity '{}' vanished after update", entity_name))
+    }
+
+    /// Set or clear (None) the confidence score of an existing entity, returning the updated entity
+    pub fn set_entity_confidence(
+        &self,
+        entity_name: &str,
+        confidence: Option<f64>,
+    ) -> Result<Entity> {
+        validate_name(entity_name, "Entity name")?;
+        if let Some(confidence) = confidence {
+            validate_confidence(confidence)?;
+        }
+
+        let conn = self.connection()?;
+        let updated = conn
+            .execute(
+                "UPDATE entities
+                 SET confidence = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                 WHERE name = ?2",
+                params![confidence, entity_name],
+            )
+            .context("Failed to update entity confidence")?;
+        if updated == 0 {
+            bail!(
+                "Cannot set confidence: entity '{}' does not exist{}",
+                entity_name,
+                typed_key_hint(&conn, entity_name)
+            );
+        }
+
+        self.read_entities_by_names(&conn, &[entity_name.to_string()])?
+            .pop()
+            .with_context(|| format!("Entity '{}' vanished after update", entity_name))
+    }
+
+    /// Set or clear (None) the metadata of an existing entity, returning the updated entity
+    pub fn set_entity_metadata(
+        &self,
+        entity_name: &str,
+        metadata: Option<&serde_json::Value>,
+    ) -> Result<Entity> {
+        validate_name(entity_name, "Entity name")?;
+        if let Some(metadata) = metadata {
+            validate_metadata(metadata)?;
+        }
+
+        let conn = self.connection()?;
+        let updated = conn
+            .execute(
+                "UPDATE entities
+                 SET metadata = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                 WHERE name = ?2",
+                params![metadata.map(|m| m.to_string()), entity_name],
+            )
+            .context("Failed to update entity metadata")?;
+        if updated == 0 {
+            bail!(
+                "Cannot set metadata: entity '{}' does not exist{}",
+                entity_name,
+                typed_key_hint(&conn, entity_name)
+            );
+        }
+
+        self.read_entities_by_names(&conn, &[entity_name.to_string()])?
+            .pop()
+            .with_context(|| format!("Entity '{}' vanished after update", entity_name))
+    }
+
+    /// Give an existing entity aliases that relation endpoints may name instead of it,
+    /// returning the ones it did not have yet
+    /// An alias cannot be the name of an entity or an alias of another entity
+    pub fn add_aliases(&self, entity_name: &str, aliases: &[String]) -> Result<Vec<String>> {
+        validate_name(entity_name, "Entity name")?;
+        validate_aliases(aliases)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for adding aliases")?;
+        if !entity_exists(&tx, entity_name)? {
+            bail!(
+                "Cannot add aliases: entity '{}' does not exist{}",
+                entity_name,
+                typed_key_hint(&tx, entity_name)
+            );
+        }
+        let mut added = Vec::new();
+        for alias in aliases {
+            if entity_exists(&tx, alias)? {
+                bail!("Alias '{}' is already the name of an entity", alias);
+            }
+            let owner: Option<String> = tx
+                .query_row(
+                    "SELECT entity_name FROM entity_aliases WHERE alias = ?1",
+                    params![alias],
+                    |row| row.get(0),
+                )
+                .optional()
+                .with_context(|| format!("Database error querying alias '{}'", alias))?;
+            match owner {
+                Some(owner) if owner == entity_name => {}
+                Some(owner) => bail!("Alias '{}' already belongs to entity '{}'", alias, owner),
+                None => {
+                    tx.execute(
+                        "INSERT INTO entity_aliases (alias, entity_name) VALUES (?1, ?2)",
+                        params![alias, entity_name],
+                    )
+                    .with_context(|| format!("Failed to add alias '{}'", alias))?;
+                    added.push(alias.clone());
+                }
+            }
+        }
+        tx.commit()
+            .context("Failed to commit transaction for adding aliases")?;
+        Ok(added)
+    }
+
+    /// Remove aliases, whichever entity they belong to, returning the ones that existed
+    pub fn remove_aliases(&self, aliases: &[String]) -> Result<Vec<String>> {
+        validate_aliases(aliases)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for removing aliases")?;
+        let mut removed = Vec::new();
+        for alias in aliases {
+            let deleted = tx
+                .execute(
+                    "DELETE FROM entity_aliases WHERE alias = ?1",
+                    params![alias],
+                )
+                .with_context(|| format!("Failed to remove alias '{}'", alias))?;
+            if deleted > 0 {
+                removed.push(alias.clone());
+            }
+        }
+        tx.commit()
+            .context("Failed to commit transaction for removing aliases")?;
+        Ok(removed)
+    }
+
+    /// Aliases of an existing entity, sorted
+    pub fn entity_aliases(&self, entity_name: &str) -> Result<EntityAliases> {
+        validate_name(entity_name, "Entity name")?;
+
+        let conn = self.read_connection()?;
+        if !entity_exists(&conn, entity_name)? {
+            bail!(
+                "Entity '{}' does not exist{}",
+                entity_name,
+                typed_key_hint(&conn, entity_name)
+            );
+        }
+        let aliases = conn
+            .prepare_cached(
+                "SELECT alias FROM entity_aliases WHERE entity_name = ?1 ORDER BY alias",
+            )?
+            .query_map(params![entity_name], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .context("Failed to read aliases")?;
+        Ok(EntityAliases {
+            entity_name: entity_name.to_string(),
+            aliases,
+        })
+    }
+
+    /// Read entire graph
+    pub fn read_graph(&self) -> Result<KnowledgeGraph> {
+        self.read_graph_ordered(false)
+    }
+
+    /// Read entire graph in deterministic order for reproducible exports:
+    /// entities by name, relations by (from, to, relation_type)
+    pub fn read_graph_sorted(&self) -> Result<KnowledgeGraph> {
+        self.read_graph_ordered(true)
+    }
+
+    /// Read one page of the graph: entities by name (passing the window's confidence filter),
+    /// with the relations touching them whose other endpoint passes the filter too
+    pub fn read_graph_page(&self, window: ReadWindow) -> Result<GraphPage> {
+        window.validate()?;
+        let conn = self.read_connection()?;
+
+        let filter = window.confidence_filter("");
+        let total: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM entities WHERE {}", filter),
+                [],
+                |row| row.get(0),
+            )
+            .context("Failed to count entities")?;
+        let entities = conn
+            .prepare_cached(&format!(
+                "{} WHERE {} ORDER BY name {}",
+                SQL_READ_ENTITIES,
+                filter,
+                window.slice()
+            ))?
+            .query_map([], entity_row)?
+            .map(|row| decode_entity(row?))
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to read entities")?;
+        let relations = relations_touching(&conn, &entities, &window)?;
+
+        Ok(GraphPage {
+            entities,
+            relations,
+            total: total as usize,
+            offset: window.offset,
+            limit: window.limit,
+        })
+    }
+
+    fn read_graph_ordered(&self, sorted: bool) -> Result<KnowledgeGraph> {
+        let conn = self.read_connection()?;
+
+        let entities = self
+            .read_all_entities(&conn, sorted)
+            .context("Failed to read entities")?;
+        let relations = self
+            .read_all_relations(&conn, sorted)
+            .context("Failed to read relations")?;
+
+        Ok(KnowledgeGraph {
+            entities,
+            relations,
+        })
+    }
+
+    /// Entities and relations that one create call inserted and that still exist, sorted
+    pub fn get_batch(&self, batch_id: &str) -> Result<KnowledgeGraph> {
+        let batch_id = batch_id.trim();
+        if batch_id.is_empty() {
+            bail!("Batch id cannot be empty");
+        }
+
+        let conn = self.read_connection()?;
+        let entities = conn
+            .prepare_cached(&format!(
+                "{} WHERE batch_id = ?1 ORDER BY name",
+                SQL_READ_ENTITIES
+            ))?
+            .query_map([batch_id], entity_row)?
+            .map(|row| decode_entity(row?))
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to read batch entities")?;
+        let relations = conn
+            .prepare_cached(&format!(
+                "{} WHERE batch_id = ?1 ORDER BY from_entity, to_entity, relation_type",
+                SQL_READ_RELATIONS
+            ))?
+            .query_map([batch_id], |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read batch relations")?;
+
+        Ok(KnowledgeGraph {
+            entities,
+            relations,
+        })
+    }
+
+    /// Visit every entity (by name), then every relation (by from, to, type), all read in one
+    /// transaction. Rows are decoded one at a time, so memory use does not grow with the
+    /// graph; an error from the visitor stops the walk and is returned
+    pub fn for_each_record(&self, mut visit: impl FnMut(GraphRecord) -> Result<()>) -> Result<()> {
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start read transaction")?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities ORDER BY name",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next().context("Failed to read entities")? {
+                visit(GraphRecord::Entity(decode_entity(entity_row(row)?)?))?;
+            }
+
+            let mut stmt = tx.prepare_cached(
+                "SELECT from_entity, to_entity, relation_type FROM relations
+                 ORDER BY from_entity, to_entity, relation_type",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next().context("Failed to read relations")? {
+                visit(GraphRecord::Relation(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                }))?;
+            }
+        }
+
+        tx.commit().context("Failed to finish read transaction")
+    }
+
+    /// Up to `limit` entities by name, starting after the entity named `after` (from the first
+    /// without it). Paging by key holds no connection or transaction between pages, so a
+    /// walk over all pages may see rows changed in the meantime, but never one row twice
+    pub fn entities_after(&self, after: Option<&str>, limit: usize) -> Result<Vec<Entity>> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "{} WHERE ?1 IS NULL OR name > ?1 ORDER BY name LIMIT ?2",
+            SQL_READ_ENTITIES
+        ))?;
+        let rows = stmt.query_map(params![after, limit as i64], entity_row)?;
+        rows.map(|row| decode_entity(row.context("Failed to read entities")?))
+            .collect()
+    }
+
+    /// Up to `limit` relations by (from, to, type), starting after `after`; see entities_after
+    pub fn relations_after(&self, after: Option<&Relation>, limit: usize) -> Result<Vec<Relation>> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "{} WHERE ?1 IS NULL OR (from_entity, to_entity, relation_type) > (?1, ?2, ?3)
+             ORDER BY from_entity, to_entity, relation_type LIMIT ?4",
+            SQL_READ_RELATIONS
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                after.map(|r| &r.from),
+                after.map(|r| &r.to),
+                after.map(|r| &r.relation_type),
+                limit as i64
+            ],
+            |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            },
+        )?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read relations")
+    }
+
+    /// Current graph revision: increases with every change to entities or relations
+    pub fn revision(&self) -> Result<u64> {
+        let conn = self.connection()?;
+        read_revision(&conn)
+    }
+
+    /// Read the whole graph in deterministic order together with the revision it reflects
+    /// Both are read in one transaction, so the revision always matches the returned data
+    pub fn read_graph_with_revision(&self) -> Result<(u64, KnowledgeGraph)> {
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start read transaction")?;
+
+        let revision = read_revision(&tx)?;
+        let entities = self
+            .read_all_entities(&tx, true)
+            .context("Failed to read entities")?;
+        let relations = self
+            .read_all_relations(&tx, true)
+            .context("Failed to read relations")?;
+        tx.commit().context("Failed to finish read transaction")?;
+
+        Ok((
+            revision,
+            KnowledgeGraph {
+                entities,
+                relations,
+            },
+        ))
+    }
+
+    /// Read entire graph as adjacency list (entity -> outgoing edges)
+    /// Relations are consumed in a single pass without materializing a relation list
+    pub fn read_adjacency(&self) -> Result<AdjacencyList> {
+        let conn = self.connection()?;
+
+        let mut adjacency: AdjacencyList = self
+            .read_all_entities(&conn, false)
+            .context("Failed to read entities")?
+            .into_iter()
+            .map(|e| {
+                let entry = AdjacencyEntry {
+                    entity_type: e.entity_type,
+                    observations: e.observations,
+                    description: e.description,
+                    edges: Vec::new(),
+                };
+                (e.name, entry)
+            })
+            .collect();
+
+        let mut stmt = conn.prepare(
+            "SELECT from_entity, to_entity, relation_type FROM relations
+             ORDER BY from_entity, relation_type, to_entity",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let from: String = row.get(0)?;
+            // Skip edges from missing entities (only possible if FOREIGN KEYs were bypassed)
+            if let Some(entry) = adjacency.get_mut(&from) {
+                entry.edges.push(AdjacencyEdge {
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                });
+            }
+        }
+
+        Ok(adjacency)
+    }
+
+    /// Helper: read all entities from database
+    fn read_all_entities(&self, conn: &Connection, sorted: bool) -> Result<Vec<Entity>> {
+        let mut stmt = if sorted {
+            conn.prepare_cached(
+                "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities ORDER BY name",
+            )?
+        } else {
+            conn.prepare_cached(SQL_READ_ENTITIES)?
+        };
+        let rows = stmt.query_map([], entity_row)?;
+
+        // Pre-allocate based on size hint from iterator
+        let mut entities = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            entities.push(decode_entity(row?)?);
+        }
+        Ok(entities)
+    }
+
+    /// Helper: read all relations from database
+    fn read_all_relations(&self, conn: &Connection, sorted: bool) -> Result<Vec<Relation>> {
+        let mut stmt = if sorted {
+            conn.prepare_cached(
+                "SELECT from_entity, to_entity, relation_type FROM relations
+                 ORDER BY from_entity, to_entity, relation_type",
+            )?
+        } else {
+            conn.prepare_cached(SQL_READ_RELATIONS)?
+        };
+        let rows = stmt.query_map([], |row| {
+            Ok(Relation {
+                from: row.get(0)?,
+                to: row.get(1)?,
+                relation_type: row.get(2)?,
+            })
+        })?;
+
+        // Pre-allocate based on size hint from iterator
+        let mut relations = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            relations.push(row?);
+        }
+        Ok(relations)
+    }
+
+    /// Compute connectivity metrics from COUNT queries (the graph is never loaded)
+    pub fn graph_metrics(&self) -> Result<GraphMetrics> {
+        let conn = self.connection()?;
+
+        let (entity_count, relation_count, orphan_count): (i64, i64, i64) = conn
+            .query_row(
+                "SELECT
+                    (SELECT COUNT(*) FROM entities),
+                    (SELECT COUNT(*) FROM relations),
+                    (SELECT COUNT(*) FROM entities e
+                     WHERE NOT EXISTS (SELECT 1 FROM relations WHERE from_entity = e.name)
+                       AND NOT EXISTS (SELECT 1 FROM relations WHERE to_entity = e.name))",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .context("Failed to compute graph metrics")?;
+
+        let n = entity_count as f64;
+        let e = relation_count as f64;
+        let possible_edges = n * (n - 1.0);
+
+        Ok(GraphMetrics {
+            entity_count: entity_count as usize,
+            relation_count: relation_count as usize,
+            density: if possible_edges > 0.0 {
+                e / possible_edges
+            } else {
+                0.0
+            },
+            average_degree: if n > 0.0 { 2.0 * e / n } else { 0.0 },
+            orphan_count: orphan_count as usize,
+        })
+    }
+
+    /// Soft issues with entities about to be created; never blocks the write
+    /// (blank/very short observations, names near the length limit, look-alike new types)
+    pub fn entity_warnings(&self, entities: &[Entity]) -> Result<Vec<String>> {
+        let conn = self.connection()?;
+        let vocabulary = type_vocabulary(&conn, "entities", "entity_type")?;
+        let mut warnings = Vec::new();
+        for (index, entity) in entities.iter().enumerate() {
+            let limit = self.entity_name_limit();
+            let unit = self.options.length_unit;
+            let length = unit.measure(&entity.name);
+            if length >= limit * NEAR_LIMIT_NAME_PERCENT / 100 {
+                warnings.push(warning(
+                    index,
+                    &entity.name,
+                    format!(
+                        "name is {} {}, close to the {} {} limit",
+                        length, unit, limit, unit
+                    ),
+                ));
+            }
+            if let Some(message) = type_warning("entity", &entity.entity_type, &vocabulary) {
+                warnings.push(warning(index, &entity.name, message));
+            }
+            warnings.extend(observation_warnings(
+                index,
+                &entity.name,
+                &entity.observations,
+            ));
+        }
+        Ok(warnings)
+    }
+
+    /// Soft issues with relations about to be created (look-alike new relation types)
+    pub fn relation_warnings(&self, relations: &[Relation]) -> Result<Vec<String>> {
+        let conn = self.connection()?;
+        let vocabulary = type_vocabulary(&conn, "relations", "relation_type")?;
+        let mut warned = HashSet::new();
+        Ok(relations
+            .iter()
+            .enumerate()
+            // One warning per new type is enough, however many relations use it
+            .filter(|(_, rel)| warned.insert(rel.relation_type.clone()))
+            .filter_map(|(index, rel)| {
+                type_warning("relation", &rel.relation_type, &vocabulary)
+                    .map(|message| warning(index, &relation_label(rel), message))
+            })
+            .collect())
+    }
+
+    /// Soft issues with observations about to be added (blank or very short ones)
+    pub fn observation_input_warnings(&self, inputs: &[ObservationInput]) -> Vec<String> {
+        inputs
+            .iter()
+            .enumerate()
+            .flat_map(|(index, input)| {
+                observation_warnings(index, &input.entity_name, &input.contents)
+            })
+            .collect()
+    }
+
+    /// Find clusters of likely duplicate entities by name
+    /// Without a threshold only names equal after trim + lowercase are grouped; with one,
+    /// names whose trigram similarity reaches it are grouped as well
+    pub fn find_duplicates(&self, threshold: Option<f64>) -> Result<Vec<DuplicateCluster>> {
+        if let Some(threshold) = threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                bail!(
+                    "Invalid threshold: {} (must be greater than 0 and at most 1)",
+                    threshold
+                );
+            }
+        }
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare("SELECT name, entity_type FROM entities ORDER BY name")?;
+        let (names, types): (Vec<String>, Vec<String>) = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<(String, String)>, _>>()
+            .context("Failed to read entity names")?
+            .into_iter()
+            .unzip();
+
+        Ok(similarity::cluster_names(&names, threshold)
+            .into_iter()
+            .map(|members| DuplicateCluster {
+                entities: members
+                    .into_iter()
+                    .map(|i| DuplicateCandidate {
+                        name: names[i].clone(),
+                        entity_type: types[i].clone(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Group relation types that look like the same relation spelled differently, largest groups
+    /// first: types with equal normalized forms ("worksAt", "works-at", "works_at") always
+    /// group; with a threshold, trigram-similar ones do too (as in find_duplicates)
+    pub fn find_relation_type_synonyms(
+        &self,
+        threshold: Option<f64>,
+    ) -> Result<Vec<RelationTypeSynonyms>> {
+        if let Some(threshold) = threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                bail!(
+                    "Invalid threshold: {} (must be greater than 0 and at most 1)",
+                    threshold
+                );
+            }
+        }
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT relation_type, COUNT(*) FROM relations
+             GROUP BY relation_type ORDER BY relation_type",
+        )?;
+        let (types, counts): (Vec<String>, Vec<u64>) = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?
+            .collect::<std::result::Result<Vec<(String, u64)>, _>>()
+            .context("Failed to read relation types")?
+            .into_iter()
+            .unzip();
+
+        let mut groups: Vec<RelationTypeSynonyms> =
+            similarity::cluster_identifiers(&types, threshold)
+                .into_iter()
+                .map(|members| {
+                    let mut usages: Vec<RelationTypeUsage> = members
+                        .into_iter()
+                        .map(|i| RelationTypeUsage {
+                            relation_type: types[i].clone(),
+                            count: counts[i],
+                        })
+                        .collect();
+                    usages.sort_by(|a, b| {
+                        b.count.cmp(&a.count).then_with(|| {
+                            let snake = |t: &str| similarity::normalize_identifier(t) == t;
+                            snake(&b.relation_type)
+                                .cmp(&snake(&a.relation_type))
+                                .then_with(|| a.relation_type.cmp(&b.relation_type))
+                        })
+                    });
+                    RelationTypeSynonyms {
+                        canonical: usages[0].relation_type.clone(),
+                        relations: usages.iter().map(|u| u.count).sum(),
+                        types: usages,
+                    }
+                })
+                .collect();
+        groups.sort_by(|a, b| {
+            b.relations
+                .cmp(&a.relations)
+                .then_with(|| a.canonical.cmp(&b.canonical))
+        });
+        Ok(groups)
+    }
+
+    /// Aggregate relations by (from entity type, relation type, to entity type)
+    /// Most frequent patterns first
+    pub fn relation_matrix(&self) -> Result<Vec<RelationTypeCount>> {
+        let conn = self.connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT f.entity_type, r.relation_type, t.entity_type, COUNT(*)
+                 FROM relations r
+                 INNER JOIN entities f ON f.name = r.from_entity
+                 INNER JOIN entities t ON t.name = r.to_entity
+                 GROUP BY f.entity_type, r.relation_type, t.entity_type
+                 ORDER BY COUNT(*) DESC, f.entity_type, r.relation_type, t.entity_type",
+            )
+            .context("Failed to prepare relation matrix query")?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(RelationTypeCount {
+                from_entity_type: row.get(0)?,
+                relation_type: row.get(1)?,
+                to_entity_type: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })?;
+
+        let mut counts = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            counts.push(row?);
+        }
+        Ok(counts)
+    }
+
+    /// Describe the implicit schema: for every entity type (sorted), the relation types leading
+    /// from and to it, with the entity type at the other end (built on relation_matrix)
+    pub fn infer_ontology(&self) -> Result<Vec<TypeOntology>> {
+        let mut ontology: Vec<TypeOntology> = {
+            let conn = self.connection()?;
+            let mut stmt = conn
+                .prepare(
+                    "SELECT entity_type, COUNT(*) FROM entities
+                     GROUP BY entity_type ORDER BY entity_type",
+                )
+                .context("Failed to prepare entity type count query")?;
+            let rows = stmt.query_map([], |row| {
+                Ok(TypeOntology {
+                    entity_type: row.get(0)?,
+                    entities: row.get(1)?,
+                    outgoing: Vec::new(),
+                    incoming: Vec::new(),
+                })
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        // Most frequent patterns come first, so every link list ends up ordered by count
+        for pattern in self.relation_matrix()? {
+            if let Some(from) = ontology
+                .iter_mut()
+                .find(|t| t.entity_type == pattern.from_entity_type)
+            {
+                from.outgoing.push(TypeLink {
+                    relation_type: pattern.relation_type.clone(),
+                    entity_type: pattern.to_entity_type.clone(),
+                    count: pattern.count,
+                });
+            }
+            if let Some(to) = ontology
+                .iter_mut()
+                .find(|t| t.entity_type == pattern.to_entity_type)
+            {
+                to.incoming.push(TypeLink {
+                    relation_type: pattern.relation_type,
+                    entity_type: pattern.from_entity_type,
+                    count: pattern.count,
+                });
+            }
+        }
+        Ok(ontology)
+    }
+
+    /// Most recently created or updated entities first (uses idx_entities_updated_at)
+    /// Entities without timestamps (created before they existed) come last
+    pub fn recent_entities(&self, limit: usize, offset: usize) -> Result<Vec<RecentEntity>> {
+        validate_page_limit(limit)?;
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, entity_type, observations, description, id, confidence, metadata,
+                    created_at, updated_at
+             FROM entities
+             ORDER BY updated_at DESC NULLS LAST, name
+             LIMIT ?1 OFFSET ?2",
+        )?;
+        let rows = stmt.query_map(params![limit as i64, offset as i64], |row| {
+            Ok((
+                entity_row(row)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        })?;
+
+        let mut entities = Vec::with_capacity(limit);
+        for row in rows {
+            let (entity, created_at, updated_at) = row?;
+            entities.push(RecentEntity {
+                entity: decode_entity(entity)?,
+                created_at,
+                updated_at,
+            });
+        }
+        Ok(entities)
+    }
+
+    /// Random sample of entities, optionally of one type; with a seed the same graph always
+    /// gives the same sample (and order), without one every call differs
+    /// The offset skips the first entities of the shuffle, so seeded calls can page through it
+    pub fn sample_entities(
+        &self,
+        count: usize,
+        entity_type: Option<&str>,
+        seed: Option<u64>,
+        offset: usize,
+    ) -> Result<Vec<Entity>> {
+        validate_page_limit(count)?;
+        if let Some(entity_type) = entity_type {
+            validate_type(entity_type, "Entity type")?;
+        }
+
+        let conn = self.connection()?;
+        // Only the sort key is computed for every row; just the sampled rows are decoded
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities
+             WHERE ?1 IS NULL OR entity_type = ?1
+             ORDER BY CASE WHEN ?3 IS NULL THEN RANDOM() ELSE sample_key(rowid, ?3) END
+             LIMIT ?2 OFFSET ?4",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    entity_type,
+                    count as i64,
+                    seed.map(|seed| seed as i64),
+                    offset as i64
+                ],
+                entity_row,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to sample entities")?;
+        rows.into_iter().map(decode_entity).collect()
+    }
+
+    /// Entities that have no observations yet (placeholders, often auto-created relation
+    /// endpoints), optionally of one type, by name
+    pub fn find_empty_entities(
+        &self,
+        entity_type: Option<&str>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Entity>> {
+        validate_page_limit(limit)?;
+        if let Some(entity_type) = entity_type {
+            validate_type(entity_type, "Entity type")?;
+        }
+
+        let conn = self.connection()?;
+        // An empty array is never compressed, so the stored text is always '[]'
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities
+             WHERE observations = '[]' AND (?1 IS NULL OR entity_type = ?1)
+             ORDER BY name
+             LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![entity_type, limit as i64, offset as i64],
+                entity_row,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to find empty entities")?;
+        rows.into_iter().map(decode_entity).collect()
+    }
+
+    /// Delete every entity without observations (optionally of one type), returning the
+    /// deleted names; relations to them cascade like in delete_entities
+    pub fn delete_empty_entities(&self, entity_type: Option<&str>) -> Result<Vec<String>> {
+        if let Some(entity_type) = entity_type {
+            validate_type(entity_type, "Entity type")?;
+        }
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(
+            "DELETE FROM entities
+             WHERE observations = '[]' AND (?1 IS NULL OR entity_type = ?1)
+             RETURNING name",
+        )?;
+        let mut names = stmt
+            .query_map(params![entity_type], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to delete empty entities")?;
+        names.sort();
+        Ok(names)
+    }
+
+    /// Delete every entity matching a full-text query in one transaction, returning the
+    /// deleted names; with dry_run nothing is deleted and the names that would be are returned
+    /// Relations to deleted entities cascade like in delete_entities
+    pub fn delete_by_query(
+        &self,
+        query: &str,
+        mode: MatchMode,
+        dry_run: bool,
+    ) -> Result<Vec<String>> {
+        // An empty query means "everything" to search_nodes; never here
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            bail!("Search query cannot be empty");
+        }
+        self.check_query_length(trimmed)?;
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for deleting by query")?;
+        let (sql, search_params) =
+            self.entity_search(SQL_SEARCH_ENTITY_NAMES, "e.name, NULL", trimmed, mode);
+        let mut names = tx
+            .prepare_cached(&sql)
+            .context("Failed to prepare search query")?
+            .query_map(params_from_iter(&search_params), |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(search_error)
+            .context("Failed to search entities")?;
+        names.sort();
+
+        if !dry_run {
+            // FOREIGN KEY CASCADE auto-deletes relations!
+            remove_entities(&tx, &names)?;
+            tx.commit()
+                .context("Failed to commit transaction for deleting by query")?;
+        }
+        Ok(names)
+    }
+
+    /// Add buffered read counts; names of entities deleted meanwhile are ignored
+    pub fn record_access(&self, counts: &[(String, u64)]) -> Result<()> {
+        if counts.is_empty() {
+            return Ok(());
+        }
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for recording access")?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO entity_access (name, access_count, last_accessed)
+                 SELECT ?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                 WHERE EXISTS (SELECT 1 FROM entities WHERE name = ?1)
+                 ON CONFLICT(name) DO UPDATE SET
+                     access_count = access_count + excluded.access_count,
+                     last_accessed = excluded.last_accessed",
+            )?;
+            for (name, count) in counts {
+                stmt.execute(params![name, *count as i64])
+                    .with_context(|| format!("Failed to record access to '{}'", name))?;
+            }
+        }
+        tx.commit()
+            .context("Failed to commit transaction for recording access")
+    }
+
+    /// Run PageRank over the relation graph and cache the scores, replacing the previous run
+    /// Each relation is one link from its source to its target; entities without outgoing
+    /// relations spread their score over every entity
+    pub fn compute_importance(&self, iterations: u32) -> Result<ImportanceRun> {
+        if iterations == 0 || iterations > MAX_IMPORTANCE_ITERATIONS {
+            bail!(
+                "Invalid iterations {} (must be 1-{})",
+                iterations,
+                MAX_IMPORTANCE_ITERATIONS
+            );
+        }
+
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start transaction for computing importance")?;
+
+        let names = {
+            let mut stmt = tx.prepare_cached("SELECT name FROM entities ORDER BY name")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            let mut names = Vec::new();
+            for row in rows {
+                names.push(row?);
+                if names.len() > MAX_IMPORTANCE_ENTITIES {
+                    bail!(
+                        "Graph too large for importance scores (more than {} entities)",
+                        MAX_IMPORTANCE_ENTITIES
+                    );
+                }
+            }
+            names
+        };
+        let relation_count: i64 =
+            tx.query_row("SELECT COUNT(*) FROM relations", [], |row| row.get(0))?;
+        if relation_count as usize > MAX_IMPORTANCE_RELATIONS {
+            bail!(
+                "Graph too large for importance scores ({} relations, max {})",
+                relation_count,
+                MAX_IMPORTANCE_RELATIONS
+            );
+        }
+        let relations = self
+            .read_all_relations(&tx, false)
+            .context("Failed to read relations")?;
+
+        // Edge list by index; relations with a missing endpoint are skipped
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let edges: Vec<(usize, usize)> = relations
+            .iter()
+            .filter_map(|rel| Some((*index.get(rel.from.as_str())?, *index.get(rel.to.as_str())?)))
+            .collect();
+
+        let n = names.len();
+        let mut out_degree = vec![0usize; n];
+        for &(from, _) in &edges {
+            out_degree[from] += 1;
+        }
+
+        let mut scores = vec![1.0 / n.max(1) as f64; n];
+        let mut run = 0;
+        let mut converged = n == 0;
+        while run < iterations && !converged {
+            run += 1;
+            let dangling: f64 = (0..n)
+                .filter(|&i| out_degree[i] == 0)
+                .map(|i| scores[i])
+                .sum();
+            let base = (1.0 - IMPORTANCE_DAMPING + IMPORTANCE_DAMPING * dangling) / n as f64;
+            let mut next = vec![base; n];
+            for &(from, to) in &edges {
+                next[to] += IMPORTANCE_DAMPING * scores[from] / out_degree[from] as f64;
+            }
+            let change: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+            scores = next;
+            converged = change < IMPORTANCE_TOLERANCE;
+        }
+
+        tx.execute("DELETE FROM entity_importance", [])
+            .context("Failed to clear importance scores")?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO entity_importance (name, importance) VALUES (?1, ?2)",
+            )?;
+            for (name, score) in names.iter().zip(&scores) {
+                stmt.execute(params![name, score])
+                    .with_context(|| format!("Failed to store importance of '{}'", name))?;
+            }
+        }
+        tx.commit()
+            .context("Failed to commit transaction for computing importance")?;
+
+        Ok(ImportanceRun {
+            entities: n,
+            relations: edges.len(),
+            iterations: run,
+            converged,
+        })
+    }
+
+    /// Entities with the highest cached importance (empty until compute_importance has run)
+    pub fn top_important(&self, limit: usize) -> Result<Vec<ImportantEntity>> {
+        validate_page_limit(limit)?;
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT e.name, e.entity_type, i.importance
+             FROM entity_importance i
+             JOIN entities e ON e.name = i.name
+             ORDER BY i.importance DESC, e.name
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(ImportantEntity {
+                name: row.get(0)?,
+                entity_type: row.get(1)?,
+                importance: row.get(2)?,
+            })
+        })?;
+
+        let mut entities = Vec::with_capacity(limit);
+        for row in rows {
+            entities.push(row?);
+        }
+        Ok(entities)
+    }
+
+    /// Entities ranked by how many observations they hold, ties by name
+    /// Counted in SQL from the stored JSON array (decoded first when compressed)
+    pub fn entities_by_observation_count(
+        &self,
+        order: SortOrder,
+        limit: usize,
+    ) -> Result<Vec<ObservationCount>> {
+        validate_page_limit(limit)?;
+
+        let direction = match order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT name, entity_type, json_array_length(observations_json(observations)) AS n
+             FROM entities
+             ORDER BY n {}, name
+             LIMIT ?1",
+            direction
+        ))?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(ObservationCount {
+                name: row.get(0)?,
+                entity_type: row.get(1)?,
+                observation_count: row.get::<_, i64>(2)? as u64,
+            })
+        })?;
+
+        let mut entities = Vec::with_capacity(limit);
+        for row in rows {
+            entities.push(row?);
+        }
+        Ok(entities)
+    }
+
+    /// Distinct observation values across all entities with the number of entities holding
+    /// each, paginated; `contains` keeps values containing it (case-insensitive for ASCII)
+    pub fn list_observations(
+        &self,
+        limit: usize,
+        offset: usize,
+        contains: Option<&str>,
+    ) -> Result<ObservationValuePage> {
+        validate_page_limit(limit)?;
+
+        let conn = self.connection()?;
+        // json_each unnests the observation arrays; observations are unique per entity,
+        // so COUNT(*) per value is the number of entities holding it
+        let unnested = "FROM entities e, json_each(observations_json(e.observations)) o
+                        WHERE ?1 IS NULL OR instr(lower(o.value), lower(?1)) > 0";
+
+        let total: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(DISTINCT o.value) {}", unnested),
+                params![contains],
+                |row| row.get(0),
+            )
+            .context("Failed to count observation values")?;
+
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT o.value, COUNT(*) AS n {}
+             GROUP BY o.value
+             ORDER BY n DESC, o.value
+             LIMIT ?2 OFFSET ?3",
+            unnested
+        ))?;
+        let rows = stmt.query_map(params![contains, limit as i64, offset as i64], |row| {
+            Ok(ObservationValue {
+                observation: row.get(0)?,
+                entity_count: row.get::<_, i64>(1)? as u64,
+            })
+        })?;
+
+        let mut observations = Vec::new();
+        for row in rows {
+            observations.push(row?);
+        }
+
+        Ok(ObservationValuePage {
+            observations,
+            total: total as usize,
+            offset,
+            limit,
+        })
+    }
+
+    /// Entities read least often (never-read ones first, then least recently read)
+    pub fn least_accessed(&self, limit: usize) -> Result<Vec<EntityAccess>> {
+        validate_page_limit(limit)?;
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT e.name, e.entity_type, COALESCE(a.access_count, 0), a.last_accessed
+             FROM entities e
+             LEFT JOIN entity_access a ON a.name = e.name
+             ORDER BY COALESCE(a.access_count, 0), a.last_accessed NULLS FIRST, e.name
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(EntityAccess {
+                name: row.get(0)?,
+                entity_type: row.get(1)?,
+                access_count: row.get::<_, i64>(2)? as u64,
+                last_accessed: row.get(3)?,
+            })
+        })?;
+
+        let mut entities = Vec::with_capacity(limit);
+        for row in rows {
+            entities.push(row?);
+        }
+        Ok(entities)
+    }
+
+    /// List entities of several types at once, grouped by type with per-type totals
+    /// Each group is paged separately: offset and limit apply within every type
+    pub fn list_entities_by_types(
+        &self,
+        entity_types: &[String],
+        limit: usize,
+        offset: usize,
+    ) -> Result<EntityGroupPage> {
+        if entity_types.is_empty() {
+            bail!("At least one entity type is required");
+        }
+        if entity_types.len() > MAX_LISTED_TYPES {
+            bail!(
+                "Too many entity types ({}, max {})",
+                entity_types.len(),
+                MAX_LISTED_TYPES
+            );
+        }
+        validate_batch(entity_types, String::clone, |entity_type| {
+            validate_type(entity_type, "Entity type")
+        })?;
+        validate_page_limit(limit)?;
+
+        let mut types: Vec<&String> = Vec::with_capacity(entity_types.len());
+        for entity_type in entity_types {
+            if !types.contains(&entity_type) {
+                types.push(entity_type);
+            }
+        }
+        let mut groups: Vec<EntityTypeGroup> = types
+            .iter()
+            .map(|entity_type| EntityTypeGroup {
+                entity_type: entity_type.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let group_of = |entity_type: &str| types.iter().position(|t| *t == entity_type);
+
+        let conn = self.connection()?;
+        let placeholders = build_placeholders(types.len(), 1);
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT entity_type, COUNT(*) FROM entities
+             WHERE entity_type IN ({})
+             GROUP BY entity_type",
+            placeholders
+        ))?;
+        let counts = stmt
+            .query_map(rusqlite::params_from_iter(&types), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to count entities by type")?;
+        for (entity_type, total) in counts {
+            if let Some(group) = group_of(&entity_type) {
+                groups[group].total = total as usize;
+            }
+        }
+
+        // One pass for every type: rank entities by name within their type, keep this page
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name, entity_type, observations, description, id, confidence, metadata FROM (
+                 SELECT name, entity_type, observations, description, id, confidence, metadata,
+                        ROW_NUMBER() OVER (PARTITION BY entity_type ORDER BY name) AS rank
+                 FROM entities
+                 WHERE entity_type IN ({})
+             )
+             WHERE rank > ?{} AND rank <= ?{}
+             ORDER BY entity_type, name",
+            placeholders,
+            types.len() + 1,
+            types.len() + 2
+        ))?;
+        let mut values: Vec<rusqlite::types::Value> = types
+            .iter()
+            .map(|entity_type| entity_type.to_string().into())
+            .collect();
+        values.push((offset as i64).into());
+        values.push((offset.saturating_add(limit) as i64).into());
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), entity_row)?;
+        for row in rows {
+            let entity = decode_entity(row?)?;
+            if let Some(group) = group_of(&entity.entity_type) {
+                groups[group].entities.push(entity);
+            }
+        }
+
+        Ok(EntityGroupPage {
+            groups,
+            offset,
+            limit,
+        })
+    }
+
+    /// List entities with exactly the given type, ordered by name (uses idx_entity_type)
+    pub fn list_entities_by_type(
+        &self,
+        entity_type: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<EntityPage> {
+        validate_type(entity_type, "Entity type")?;
+        validate_page_limit(limit)?;
+
+        let conn = self.connection()?;
+
+        let total: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM entities WHERE entity_type = ?1",
+                params![entity_type],
+                |row| row.get(0),
+            )
+            .context("Failed to count entities by type")?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities
+             WHERE entity_type = ?1
+             ORDER BY name
+             LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![entity_type, limit as i64, offset as i64],
+            entity_row,
+        )?;
+
+        let mut entities = Vec::new();
+        for row in rows {
+            entities.push(decode_entity(row?)?);
+        }
+
+        Ok(EntityPage {
+            entities,
+            total: total as usize,
+            offset,
+            limit,
+        })
+    }
+
+    /// List relations of an exact type, paginated and ordered by (from, to)
+    /// Uses the relation_type index; optionally includes the endpoint entities of the page
+    pub fn list_relations_by_type(
+        &self,
+        relation_type: &str,
+        limit: usize,
+        offset: usize,
+        include_entities: bool,
+    ) -> Result<RelationPage> {
+        validate_type(relation_type, "Relation type")?;
+        validate_page_limit(limit)?;
+
+        let conn = self.connection()?;
+
+        let total: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM relations WHERE relation_type = ?1",
+                params![relation_type],
+                |row| row.get(0),
+            )
+            .context("Failed to count relations by type")?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT from_entity, to_entity, relation_type FROM relations
+             WHERE relation_type = ?1
+             ORDER BY from_entity, to_entity
+             LIMIT ?2 OFFSET ?3",
+        )?;
+        let relations = stmt
+            .query_map(params![relation_type, limit as i64, offset as i64], |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to list relations by type")?;
+
+        let entities = if include_entities && !relations.is_empty() {
+            let names: Vec<String> = relations
+                .iter()
+                .flat_map(|r| [r.from.clone(), r.to.clone()])
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            let mut entities = self.read_entities_by_names(&conn, &names)?;
+            entities.sort_by(|a, b| a.name.cmp(&b.name));
+            Some(entities)
+        } else if include_entities {
+            Some(Vec::new())
+        } else {
+            None
+        };
+
+        Ok(RelationPage {
+            relations,
+            entities,
+            total: total as usize,
+            offset,
+            limit,
+        })
+    }
+
+    /// Search using FTS5 full-text search (every term must match)
+    pub fn search_nodes(&self, query: Option<&str>) -> Result<KnowledgeGraph> {
+        self.search_nodes_matching(query, MatchMode::All)
+    }
+
+    /// Search using FTS5 full-text search, combining terms per match mode
+    pub fn search_nodes_matching(
+        &self,
+        query: Option<&str>,
+        mode: MatchMode,
+    ) -> Result<KnowledgeGraph> {
+        // No query or empty query = return full graph
+        let trimmed = query.map(|q| q.trim()).unwrap_or("");
+        if trimmed.is_empty() {
+            return self.read_graph();
+        }
+
+        self.check_query_length(trimmed)?;
+
+        let conn = self.read_connection()?;
+
+        // FTS5 search - much faster than LIKE for text search
+        let entities = self
+            .search_entities_fts(&conn, trimmed, mode, None)
+            .context("Failed to search entities")?;
+
+        // Get relations only between found entities
+        let relations = self
+            .get_relations_between(&conn, &entities)
+            .context("Failed to get relations for search results")?;
+
+        Ok(KnowledgeGraph {
+            entities,
+            relations,
+        })
+    }
+
+    /// One window of search_nodes_matching (or search_nodes_and_relations): the matching
+    /// entities by name, with the relations between them and, if asked for, the relations
+    /// matching the query that touch them; an empty query reads a page of the whole graph
+    pub fn search_nodes_window(
+        &self,
+        query: Option<&str>,
+        mode: MatchMode,
+        relation_matches: bool,
+        window: ReadWindow,
+    ) -> Result<KnowledgeGraph> {
+        let trimmed = query.map(|q| q.trim()).unwrap_or("");
+        if trimmed.is_empty() {
+            let page = self.read_graph_page(window)?;
+            return Ok(KnowledgeGraph {
+                entities: page.entities,
+                relations: page.relations,
+            });
+        }
+        window.validate()?;
+        self.check_query_length(trimmed)?;
+
+        let conn = self.read_connection()?;
+        let entities = self
+            .search_entities_fts(&conn, trimmed, mode, Some(&window))
+            .context("Failed to search entities")?;
+        let mut relations = self
+            .get_relations_between(&conn, &entities)
+            .context("Failed to get relations for search results")?;
+        if relation_matches {
+            let names: HashSet<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+            let mut seen: HashSet<Relation> = relations.iter().cloned().collect();
+            for relation in self.search_relations_matching(trimmed, mode, None)? {
+                let touches =
+                    names.contains(relation.from.as_str()) || names.contains(relation.to.as_str());
+                if touches && seen.insert(relation.clone()) {
+                    relations.push(relation);
+                }
+            }
+        }
+
+        Ok(KnowledgeGraph {
+            entities,
+            relations,
+        })
+    }
+
+    /// Search entities like search_nodes_matching, returning them ranked by bm25() relevance,
+    /// most relevant first; needs a non-empty query and the full-text index
+    /// With a window only that slice of the ranking is read
+    pub fn search_nodes_scored(
+        &self,
+        query: &str,
+        mode: MatchMode,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<ScoredEntity>> {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            bail!("Scored search needs a query");
+        }
+        if self.options.no_fts {
+            bail!("Scored search needs the full-text index (disabled by --no-fts)");
+        }
+        self.check_query_length(trimmed)?;
+
+        let sql = match &window {
+            Some(window) => {
+                window.validate()?;
+                format!(
+                    "{} AND {} {} {}",
+                    SQL_SEARCH_ENTITIES_SCORED,
+                    window.confidence_filter("e."),
+                    SQL_SCORED_ORDER,
+                    window.slice()
+                )
+            }
+            None => format!("{} {}", SQL_SEARCH_ENTITIES_SCORED, SQL_SCORED_ORDER),
+        };
+
+        let conn = self.read_connection()?;
+        let mut stmt = conn
+            .prepare_cached(&sql)
+            .context("Failed to prepare search query")?;
+        let rows = stmt.query_map(params![sanitize_fts5_query(trimmed, mode)], |row| {
+            Ok((entity_row(row)?, row.get::<_, f64>(7)?))
+        })?;
+
+        let mut scored = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            let (entity, score) = row
+                .map_err(search_error)
+                .context("Failed to search entities")?;
+            scored.push(ScoredEntity {
+                entity: decode_entity(entity)?,
+                score,
+            });
+        }
+        Ok(scored)
+    }
+
+    /// Search entities like search_nodes_matching, selecting only names (and types) so
+    /// observations are never read or decoded; an empty query lists every entity by name
+    /// With a window only that slice of the matches (by name) is read
+    pub fn search_entity_hits(
+        &self,
+        query: Option<&str>,
+        mode: MatchMode,
+        projection: SearchProjection,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<SearchHit>> {
+        if let Some(window) = &window {
+            window.validate()?;
+        }
+        let slice = window.map(|w| w.slice()).unwrap_or_default();
+        let with_types = match projection {
+            SearchProjection::Full => bail!("Full search results are read by search_nodes"),
+            SearchProjection::Names => false,
+            SearchProjection::NamesTypes => true,
+        };
+        let conn = self.read_connection()?;
+
+        let trimmed = query.map(|q| q.trim()).unwrap_or("");
+        let mut stmt;
+        let rows = if trimmed.is_empty() {
+            let columns = if with_types {
+                "name, entity_type"
+            } else {
+                "name, NULL"
+            };
+            stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM entities ORDER BY name {}",
+                columns, slice
+            ))?;
+            stmt.query_map([], search_hit)?
+        } else {
+            self.check_query_length(trimmed)?;
+            let (sql, search_params) = if with_types {
+                self.entity_search(
+                    SQL_SEARCH_ENTITY_NAMES_TYPES,
+                    "e.name, e.entity_type",
+                    trimmed,
+                    mode,
+                )
+            } else {
+                self.entity_search(SQL_SEARCH_ENTITY_NAMES, "e.name, NULL", trimmed, mode)
+            };
+            let sql = match &window {
+                Some(_) => format!("{} ORDER BY e.name {}", sql, slice),
+                None => sql,
+            };
+            stmt = conn
+                .prepare_cached(&sql)
+                .context("Failed to prepare search query")?;
+            stmt.query_map(params_from_iter(search_params), search_hit)?
+        };
+
+        let mut hits = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            hits.push(
+                row.map_err(search_error)
+                    .context("Failed to search entities")?,
+            );
+        }
+        Ok(hits)
+    }
+
+    /// Search like search_nodes, but also return relations whose own text (endpoints, type)
+    /// matches the query, even when their endpoints are not among the matched entities
+    pub fn search_nodes_and_relations(
+        &self,
+        query: Option<&str>,
+        mode: MatchMode,
+    ) -> Result<KnowledgeGraph> {
+        let mut graph = self.search_nodes_matching(query, mode)?;
+
+        let trimmed = query.map(|q| q.trim()).unwrap_or("");
+        if trimmed.is_empty() {
+            // Full graph already contains every relation
+            return Ok(graph);
+        }
+
+        let matched = self.search_relations_matching(trimmed, mode, None)?;
+        let mut seen: HashSet<Relation> = graph.relations.iter().cloned().collect();
+        for rel in matched {
+            if seen.insert(rel.clone()) {
+                graph.relations.push(rel);
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Search relations by their text (from, to, relation type) using FTS5
+    /// With a window only that slice of the matches (by from, to, type) is read
+    pub fn search_relations(
+        &self,
+        query: &str,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<Relation>> {
+        self.search_relations_matching(query, MatchMode::All, window)
+    }
+
+    /// Search relations by their text, combining terms per match mode
+    fn search_relations_matching(
+        &self,
+        query: &str,
+        mode: MatchMode,
+        window: Option<ReadWindow>,
+    ) -> Result<Vec<Relation>> {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            bail!("Search query cannot be empty");
+        }
+        self.check_query_length(trimmed)?;
+        if let Some(window) = &window {
+            window.validate()?;
+        }
+
+        let conn = self.connection()?;
+        let (sql, search_params) = if self.options.no_fts {
+            let (filter, patterns) = like_filter(trimmed, mode, like_relation_term);
+            (
+                format!(
+                    "SELECT r.from_entity, r.to_entity, r.relation_type FROM relations r WHERE {}",
+                    filter
+                ),
+                patterns,
+            )
+        } else {
+            (
+                SQL_SEARCH_RELATIONS.to_string(),
+                vec![sanitize_fts5_query(trimmed, mode)],
+            )
+        };
+        let sql = match &window {
+            Some(window) => format!(
+                "{} ORDER BY r.from_entity, r.to_entity, r.relation_type {}",
+                sql,
+                window.slice()
+            ),
+            None => sql,
+        };
+
+        let mut stmt = conn
+            .prepare_cached(&sql)
+            .context("Failed to prepare relation search query")?;
+        let rows = stmt.query_map(params_from_iter(search_params), |row| {
+            Ok(Relation {
+                from: row.get(0)?,
+                to: row.get(1)?,
+                relation_type: row.get(2)?,
+            })
+        })?;
+
+        let mut relations = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            relations.push(row.map_err(search_error)?);
+        }
+        Ok(relations)
+    }
+
+    /// Reject index maintenance when the full-text indexes were dropped (no_fts)
+    fn check_fts_enabled(&self) -> Result<()> {
+        if self.options.no_fts {
+            bail!("Full-text search is disabled (--no-fts); there is no index to check or rebuild");
+        }
+        Ok(())
+    }
+
+    /// Reject search queries shorter than min_query_length: they match too much to be useful
+    fn check_query_length(&self, query: &str) -> Result<()> {
+        let min = self.options.min_query_length;
+        let length = query_length(query);
+        if length < min {
+            bail!(
+                "Search query '{}' is too short ({} characters, minimum {}); use a more specific query",
+                query,
+                length,
+                min
+            );
+        }
+        Ok(())
+    }
+
+    /// Search statement for entities and its parameters: `fts_sql` with the sanitized query,
+    /// or with full-text search disabled a LIKE scan selecting `columns` of entities `e`
+    fn entity_search(
+        &self,
+        fts_sql: &str,
+        columns: &str,
+        query: &str,
+        mode: MatchMode,
+    ) -> (String, Vec<String>) {
+        if self.options.no_fts {
+            let (filter, patterns) = like_filter(query, mode, like_entity_term);
+            (
+                format!("SELECT {} FROM entities e WHERE ({})", columns, filter),
+                patterns,
+            )
+        } else {
+            // Sanitize query to prevent FTS5 syntax errors
+            (fts_sql.to_string(), vec![sanitize_fts5_query(query, mode)])
+        }
+    }
+
+    /// Helper: search entities using FTS5 (or LIKE without full-text search)
+    fn search_entities_fts(
+        &self,
+        conn: &Connection,
+        query: &str,
+        mode: MatchMode,
+        window: Option<&ReadWindow>,
+    ) -> Result<Vec<Entity>> {
+        let (sql, search_params) = self.entity_search(
+            SQL_SEARCH_ENTITIES,
+            "e.name, e.entity_type, e.observations, e.description, e.id, e.confidence, e.metadata",
+            query,
+            mode,
+        );
+        let sql = match window {
+            Some(window) => format!(
+                "{} AND {} ORDER BY e.name {}",
+                sql,
+                window.confidence_filter("e."),
+                window.slice()
+            ),
+            None => sql,
+        };
+        let mut stmt = conn
+            .prepare_cached(&sql)
+            .context("Failed to prepare search query")?;
+
+        let rows = stmt.query_map(params_from_iter(search_params), entity_row)?;
+
+        // Pre-allocate based on size hint from iterator
+        let mut entities = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            entities.push(decode_entity(row.map_err(search_error)?)?);
+        }
+        Ok(entities)
+    }
+
+    /// Up to `limit` entities matching a search by name, starting after the entity named
+    /// `after` (from the first without it); needs a non-empty query
+    /// Paged by key like entities_after, so streamed searches hold no connection between pages
+    pub fn search_matches_after(
+        &self,
+        query: &str,
+        mode: MatchMode,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Entity>> {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            bail!("Streaming search needs a query");
+        }
+        self.check_query_length(trimmed)?;
+
+        let conn = self.read_connection()?;
+        let (sql, mut search_params) = self.entity_search(
+            SQL_SEARCH_ENTITIES,
+            "e.name, e.entity_type, e.observations, e.description, e.id, e.confidence, e.metadata",
+            trimmed,
+            mode,
+        );
+        let sql = match after {
+            Some(after) => {
+                search_params.push(after.to_string());
+                format!(
+                    "{} AND e.name > ?{} ORDER BY e.name LIMIT {}",
+                    sql,
+                    search_params.len(),
+                    limit
+                )
+            }
+            None => format!("{} ORDER BY e.name LIMIT {}", sql, limit),
+        };
+        let mut stmt = conn
+            .prepare_cached(&sql)
+            .context("Failed to prepare search query")?;
+        let rows = stmt.query_map(params_from_iter(search_params), entity_row)?;
+
+        let mut entities = Vec::with_capacity(limit.min(rows.size_hint().0));
+        for row in rows {
+            entities.push(decode_entity(
+                row.map_err(search_error)
+                    .context("Failed to search entities")?,
+            )?);
+        }
+        Ok(entities)
+    }
+
+    /// Helper: get relations where BOTH from and to are in the given entities
+    fn get_relations_between(
+        &self,
+        conn: &Connection,
+        entities: &[Entity],
+    ) -> Result<Vec<Relation>> {
+        if entities.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let entity_names: HashSet<_> = entities.iter().map(|e| &e.name).collect();
+
+        let placeholders_from = build_placeholders(entity_names.len(), 1);
+        let placeholders_to = build_placeholders(entity_names.len(), entity_names.len() + 1);
+
+        let query = format!(
+            "SELECT from_entity, to_entity, relation_type FROM relations
+             WHERE from_entity IN ({}) AND to_entity IN ({})",
+            placeholders_from, placeholders_to
+        );
+
+        // Build params: first all names for FROM, then all names for TO
+        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(entity_names.len() * 2);
+        for name in &entity_names {
+            params.push(*name);
+        }
+        for name in &entity_names {
+            params.push(*name);
+        }
+
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt.query_map(params.as_slice(), |row| {
+            Ok(Relation {
+                from: row.get(0)?,
+                to: row.get(1)?,
+                relation_type: row.get(2)?,
+            })
+        })?;
+
+        // Pre-allocate based on size hint from iterator
+        let mut relations = Vec::with_capacity(rows.size_hint().0);
+        for row in rows {
+            relations.push(row?);
+        }
+        Ok(relations)
+    }
+
+    /// Extract the n-hop neighborhood around root (relations followed in both directions)
+    /// Returns the reached entities and all relations among them
+    pub fn get_subgraph(&self, root: &str, depth: u32) -> Result<KnowledgeGraph> {
+        validate_name(root, "Root entity")?;
+        if depth > MAX_SUBGRAPH_DEPTH {
+            bail!(
+                "Depth must be at most {} (got {})",
+                MAX_SUBGRAPH_DEPTH,
+                depth
+            );
+        }
+
+        let conn = self.connection()?;
+        if !entity_exists(&conn, root)? {
+            bail!(
+                "Cannot get subgraph: entity '{}' does not exist{}",
+                root,
+                typed_key_hint(&conn, root)
+            );
+        }
+
+        // Breadth-first expansion, one query per level
+        let mut visited: BTreeSet<String> = BTreeSet::from([root.to_string()]);
+        let mut frontier = vec![root.to_string()];
+        for _ in 0..depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let placeholders = build_placeholders(frontier.len(), 1);
+            let query = format!(
+                "SELECT from_entity, to_entity FROM relations
+                 WHERE from_entity IN ({0}) OR to_entity IN ({0})",
+                placeholders
+            );
+            let params: Vec<&dyn rusqlite::ToSql> =
+                frontier.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+
+            let mut stmt = conn.prepare(&query)?;
+            let mut rows = stmt.query(params.as_slice())?;
+            let mut next = Vec::new();
+            while let Some(row) = rows.next()? {
+                for idx in 0..2 {
+                    let name: String = row.get(idx)?;
+                    if !visited.contains(&name) {
+                        visited.insert(name.clone());
+                        next.push(name);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let names: Vec<String> = visited.into_iter().collect();
+        let entities = self
+            .read_entities_by_names(&conn, &names)
+            .context("Failed to read subgraph entities")?;
+        let relations = self
+            .get_relations_between(&conn, &entities)
+            .context("Failed to read subgraph relations")?;
+
+        Ok(KnowledgeGraph {
+            entities,
+            relations,
+        })
+    }
+
+    /// Breadth-first traversal from root following relations in the given direction
+    /// Each entity is visited once, at its shortest distance; the order is deterministic
+    pub fn traverse(&self, root: &str, depth: u32, direction: Direction) -> Result<Traversal> {
+        validate_name(root, "Root entity")?;
+        if depth > MAX_SUBGRAPH_DEPTH {
+            bail!(
+                "Depth must be at most {} (got {})",
+                MAX_SUBGRAPH_DEPTH,
+                depth
+            );
+        }
+
+        let conn = self.connection()?;
+        if !entity_exists(&conn, root)? {
+            bail!(
+                "Cannot traverse: entity '{}' does not exist{}",
+                root,
+                typed_key_hint(&conn, root)
+            );
+        }
+
+        // (name, distance, parent) in visit order, one query per level
+        let mut visited: HashSet<String> = HashSet::from([root.to_string()]);
+        let mut order: Vec<(String, u32, Option<String>)> = vec![(root.to_string(), 0, None)];
+        let mut frontier = vec![root.to_string()];
+        for distance in 1..=depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let placeholders = build_placeholders(frontier.len(), 1);
+            let filter = match direction {
+                Direction::Outgoing => format!("from_entity IN ({})", placeholders),
+                Direction::Incoming => format!("to_entity IN ({})", placeholders),
+                Direction::Both => {
+                    format!("from_entity IN ({0}) OR to_entity IN ({0})", placeholders)
+                }
+            };
+            let query = format!(
+                "SELECT from_entity, to_entity FROM relations WHERE {}",
+                filter
+            );
+            let params: Vec<&dyn rusqlite::ToSql> =
+                frontier.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+
+            // Frontier entity -> neighbors sorted by name
+            let in_frontier: HashSet<&str> = frontier.iter().map(String::as_str).collect();
+            let mut adjacent: HashMap<String, BTreeSet<String>> = HashMap::new();
+            let mut stmt = conn.prepare(&query)?;
+            let mut rows = stmt.query(params.as_slice())?;
+            while let Some(row) = rows.next()? {
+                let from: String = row.get(0)?;
+                let to: String = row.get(1)?;
+                if direction != Direction::Incoming && in_frontier.contains(from.as_str()) {
+                    adjacent.entry(from.clone()).or_default().insert(to.clone());
+                }
+                if direction != Direction::Outgoing && in_frontier.contains(to.as_str()) {
+                    adjacent.entry(to).or_default().insert(from);
+                }
+            }
+
+            let mut next = Vec::new();
+            for parent in &frontier {
+                for name in adjacent.remove(parent).unwrap_or_default() {
+                    if visited.insert(name.clone()) {
+                        order.push((name.clone(), distance, Some(parent.clone())));
+                        next.push(name);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let names: Vec<String> = order.iter().map(|(name, _, _)| name.clone()).collect();
+        let mut entities: HashMap<String, Entity> = self
+            .read_entities_by_names(&conn, &names)
+            .context("Failed to read traversed entities")?
+            .into_iter()
+            .map(|e| (e.name.clone(), e))
+            .collect();
+
+        Ok(Traversal {
+            root: root.to_string(),
+            entities: order
+                .into_iter()
+                .filter_map(|(name, distance, parent)| {
+                    entities.remove(&name).map(|entity| TraversalNode {
+                        entity,
+                        distance,
+                        parent,
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    /// Up to max_paths distinct shortest paths from one entity to another (at most max_depth hops)
+    /// Paths differ in at least one relation; their order is deterministic
+    pub fn find_paths(
+        &self,
+        from: &str,
+        to: &str,
+        max_depth: u32,
+        max_paths: usize,
+        direction: Direction,
+    ) -> Result<PathResult> {
+        validate_name(from, "Start entity")?;
+        validate_name(to, "End entity")?;
+        if max_depth > MAX_SUBGRAPH_DEPTH {
+            bail!(
+                "Depth must be at most {} (got {})",
+                MAX_SUBGRAPH_DEPTH,
+                max_depth
+            );
+        }
+        if max_paths == 0 || max_paths > MAX_PATHS {
+            bail!(
+                "Path count must be between 1 and {} (got {})",
+                MAX_PATHS,
+                max_paths
+            );
+        }
+
+        let conn = self.connection()?;
+        for name in [from, to] {
+            if !entity_exists(&conn, name)? {
+                bail!(
+                    "Cannot find path: entity '{}' does not exist{}",
+                    name,
+                    typed_key_hint(&conn, name)
+                );
+            }
+        }
+
+        // Level-by-level BFS recording, for every entity, all edges that reach it at its distance
+        let mut distance: HashMap<String, u32> = HashMap::from([(from.to_string(), 0)]);
+        let mut predecessors: HashMap<String, Vec<(String, Relation)>> = HashMap::new();
+        let mut frontier = vec![from.to_string()];
+        for level in 1..=max_depth {
+            if frontier.is_empty() || distance.contains_key(to) {
+                break;
+            }
+            let placeholders = build_placeholders(frontier.len(), 1);
+            let filter = match direction {
+                Direction::Outgoing => format!("from_entity IN ({})", placeholders),
+                Direction::Incoming => format!("to_entity IN ({})", placeholders),
+                Direction::Both => {
+                    format!("from_entity IN ({0}) OR to_entity IN ({0})", placeholders)
+                }
+            };
+            let query = format!(
+                "SELECT from_entity, to_entity, relation_type FROM relations WHERE {}",
+                filter
+            );
+            let params: Vec<&dyn rusqlite::ToSql> =
+                frontier.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+
+            let in_frontier: HashSet<&str> = frontier.iter().map(String::as_str).collect();
+            let mut next = BTreeSet::new();
+            let mut stmt = conn.prepare(&query)?;
+            let mut rows = stmt.query(params.as_slice())?;
+            while let Some(row) = rows.next()? {
+                let relation = Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                };
+                let mut steps = Vec::with_capacity(2);
+                if direction != Direction::Incoming && in_frontier.contains(relation.from.as_str())
+                {
+                    steps.push((relation.from.clone(), relation.to.clone()));
+                }
+                if direction != Direction::Outgoing && in_frontier.contains(relation.to.as_str()) {
+                    steps.push((relation.to.clone(), relation.from.clone()));
+                }
+                for (current, other) in steps {
+                    match distance.get(&other) {
+                        Some(&d) if d < level => continue,
+                        Some(_) => {}
+                        None => {
+                            distance.insert(other.clone(), level);
+                            next.insert(other.clone());
+                        }
+                    }
+                    predecessors
+                        .entry(other)
+                        .or_default()
+                        .push((current, relation.clone()));
+                }
+            }
+            frontier = next.into_iter().collect();
+        }
 
-            let obs_json = serde_json::to_string(&observations).with_context(|| {
-                format!(
-                    "Failed to serialize observations for entity '{}'",
-                    deletion.entity_name
-                )
-            })?;
-            tx.execute(
-                "UPDATE entities SET observations = ?1 WHERE name = ?2",
-                params![&obs_json, &deletion.entity_name],
-            )
-            .with_context(|| {
-                format!(
-                    "Failed to delete observations from entity '{}'",
-                    deletion.entity_name
-                )
-            })?;
+        let mut paths = Vec::new();
+        if from == to {
+            paths.push(EntityPath {
+                entities: vec![from.to_string()],
+                relations: Vec::new(),
+            });
+        } else if distance.contains_key(to) {
+            for edges in predecessors.values_mut() {
+                edges.sort_by(|a, b| {
+                    (&a.0, &a.1.relation_type, &a.1.from, &a.1.to).cmp(&(
+                        &b.0,
+                        &b.1.relation_type,
+                        &b.1.from,
+                        &b.1.to,
+                    ))
+                });
+            }
+            let mut entities = vec![to.to_string()];
+            let mut relations = Vec::new();
+            collect_paths(
+                &predecessors,
+                from,
+                &mut entities,
+                &mut relations,
+                &mut paths,
+                max_paths,
+            );
         }
 
-        tx.commit()
-            .context("Failed to commit transaction for deleting observations")?;
-        Ok(())
+        Ok(PathResult {
+            from: from.to_string(),
+            to: to.to_string(),
+            paths,
+        })
     }
 
-    /// Delete relations
-    /// Wrapped in transaction for atomicity
-    pub fn delete_relations(&self, relations: &[Relation]) -> Result<usize> {
-        if relations.is_empty() {
-            return Ok(0);
+    /// Longest acyclic chain of relations starting at an entity, following relations of one
+    /// type (or any type) in the given direction for at most max_depth hops
+    /// Longest simple paths take exponential time in general, so the search also stops after
+    /// MAX_CHAIN_STEPS followed relations; `exhaustive` says whether it ran to completion
+    pub fn longest_chain(
+        &self,
+        name: &str,
+        relation_type: Option<&str>,
+        direction: Direction,
+        max_depth: u32,
+    ) -> Result<ChainResult> {
+        validate_name(name, "Entity name")?;
+        if let Some(relation_type) = relation_type {
+            validate_type(relation_type, "Relation type")?;
+        }
+        if max_depth == 0 || max_depth > MAX_CHAIN_DEPTH {
+            bail!(
+                "Depth must be between 1 and {} (got {})",
+                MAX_CHAIN_DEPTH,
+                max_depth
+            );
         }
 
-        // Validate all relations before starting transaction
-        for rel in relations {
-            validate_name(&rel.from, "From entity")?;
-            validate_name(&rel.to, "To entity")?;
-            validate_type(&rel.relation_type, "Relation type")?;
+        let conn = self.connection()?;
+        if !entity_exists(&conn, name)? {
+            bail!(
+                "Cannot find chain: entity '{}' does not exist{}",
+                name,
+                typed_key_hint(&conn, name)
+            );
         }
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
-        let tx = conn
-            .unchecked_transaction()
-            .context("Failed to start transaction for deleting relations")?;
-        let mut count = 0;
+        let start = EntityPath {
+            entities: vec![name.to_string()],
+            relations: Vec::new(),
+        };
+        let mut search = ChainSearch {
+            conn: &conn,
+            relation_type,
+            direction,
+            max_depth: max_depth as usize,
+            steps_left: MAX_CHAIN_STEPS,
+            edges: HashMap::new(),
+            best: start.clone(),
+            exhaustive: true,
+            cyclic: false,
+        };
+        let mut entities = start.entities;
+        let mut relations = Vec::new();
+        search.extend(&mut entities, &mut relations)?;
 
-        {
-            let mut stmt = tx.prepare_cached(
-                "DELETE FROM relations WHERE from_entity = ?1 AND to_entity = ?2 AND relation_type = ?3"
-            ).context("Failed to prepare delete statement for relations")?;
-
-            for rel in relations {
-                count += stmt
-                    .execute(params![&rel.from, &rel.to, &rel.relation_type])
-                    .with_context(|| {
-                        format!(
-                            "Failed to delete relation '{}' -> '{}' (type: '{}')",
-                            rel.from, rel.to, rel.relation_type
-                        )
-                    })?;
-            }
+        Ok(ChainResult {
+            start: name.to_string(),
+            chain: search.best,
+            exhaustive: search.exhaustive,
+            cyclic: search.cyclic,
+        })
+    }
+
+    /// Get direct neighbors of an entity, each relation annotated with its direction
+    /// relative to the center (a self-relation is reported once, as outgoing)
+    pub fn get_neighbors(&self, name: &str, direction: Direction) -> Result<Neighborhood> {
+        validate_name(name, "Entity name")?;
+
+        let conn = self.connection()?;
+
+        if !entity_exists(&conn, name)? {
+            bail!(
+                "Cannot get neighbors: entity '{}' does not exist{}",
+                name,
+                typed_key_hint(&conn, name)
+            );
         }
 
-        tx.commit()
-            .context("Failed to commit transaction for deleting relations")?;
-        Ok(count)
-    }
+        let mut relations = Vec::new();
+        if direction != Direction::Incoming {
+            let mut stmt = conn.prepare_cached(
+                "SELECT from_entity, to_entity, relation_type FROM relations WHERE from_entity = ?1",
+            )?;
+            let rows = stmt.query_map(params![name], |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            })?;
+            for row in rows {
+                relations.push(DirectedRelation {
+                    relation: row?,
+                    direction: EdgeDirection::Outgoing,
+                });
+            }
+        }
+        if direction != Direction::Outgoing {
+            let mut stmt = conn.prepare_cached(
+                "SELECT from_entity, to_entity, relation_type FROM relations
+                 WHERE to_entity = ?1 AND from_entity != ?1",
+            )?;
+            let rows = stmt.query_map(params![name], |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            })?;
+            for row in rows {
+                relations.push(DirectedRelation {
+                    relation: row?,
+                    direction: EdgeDirection::Incoming,
+                });
+            }
+        }
 
-    /// Read entire graph
-    pub fn read_graph(&self) -> Result<KnowledgeGraph> {
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
+        // Collect the other endpoint of every relation (excluding the center itself)
+        let neighbor_names: Vec<String> = relations
+            .iter()
+            .map(|r| match r.direction {
+                EdgeDirection::Outgoing => &r.relation.to,
+                EdgeDirection::Incoming => &r.relation.from,
+            })
+            .filter(|n| n.as_str() != name)
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
 
-        let entities = self
-            .read_all_entities(&conn)
-            .context("Failed to read entities")?;
-        let relations = self
-            .read_all_relations(&conn)
-            .context("Failed to read relations")?;
+        let entities = if neighbor_names.is_empty() {
+            Vec::new()
+        } else {
+            self.read_entities_by_names(&conn, &neighbor_names)
+                .context("Failed to read neighbor entities")?
+        };
 
-        Ok(KnowledgeGraph {
+        Ok(Neighborhood {
+            center: name.to_string(),
             entities,
             relations,
         })
     }
 
-    /// Helper: read all entities from database
-    fn read_all_entities(&self, conn: &Connection) -> Result<Vec<Entity>> {
-        let mut stmt = conn.prepare("SELECT name, entity_type, observations FROM entities")?;
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })?;
+    /// Relations of one entity grouped by direction and type, with the other endpoints
+    pub fn relation_summary(&self, name: &str) -> Result<RelationSummary> {
+        validate_name(name, "Entity name")?;
 
-        // Pre-allocate based on size hint from iterator
-        let mut entities = Vec::with_capacity(rows.size_hint().0);
-        for row in rows {
-            let (name, entity_type, obs_json) = row?;
-            let observations: Vec<String> = serde_json::from_str(&obs_json)
-                .with_context(|| format!("Corrupted observations for entity '{}'", name))?;
-            entities.push(Entity {
+        let conn = self.connection()?;
+        if !entity_exists(&conn, name)? {
+            bail!(
+                "Cannot summarize relations: entity '{}' does not exist{}",
                 name,
-                entity_type,
-                observations,
-            });
+                typed_key_hint(&conn, name)
+            );
         }
-        Ok(entities)
+
+        let mut summary = RelationSummary {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        // Self-loops are outgoing only, like in get_neighbors
+        for (sql, groups) in [
+            (
+                "SELECT relation_type, to_entity FROM relations
+                 WHERE from_entity = ?1
+                 ORDER BY relation_type, to_entity",
+                &mut summary.outgoing,
+            ),
+            (
+                "SELECT relation_type, from_entity FROM relations
+                 WHERE to_entity = ?1 AND from_entity != ?1
+                 ORDER BY relation_type, from_entity",
+                &mut summary.incoming,
+            ),
+        ] {
+            let mut stmt = conn.prepare_cached(sql)?;
+            let rows = stmt.query_map(params![name], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (relation_type, other) = row.context("Failed to read relations")?;
+                groups.entry(relation_type).or_default().push(other);
+            }
+        }
+        Ok(summary)
     }
 
-    /// Helper: read all relations from database
-    fn read_all_relations(&self, conn: &Connection) -> Result<Vec<Relation>> {
-        let mut stmt =
-            conn.prepare("SELECT from_entity, to_entity, relation_type FROM relations")?;
-        let rows = stmt.query_map([], |row| {
-            Ok(Relation {
-                from: row.get(0)?,
-                to: row.get(1)?,
-                relation_type: row.get(2)?,
+    /// An entity's observations with the time each was added, in stored order or newest first
+    pub fn observation_times(&self, name: &str, newest_first: bool) -> Result<ObservationTimes> {
+        validate_name(name, "Entity name")?;
+
+        let conn = self.connection()?;
+        if !entity_exists(&conn, name)? {
+            bail!(
+                "Cannot get observation times: entity '{}' does not exist{}",
+                name,
+                typed_key_hint(&conn, name)
+            );
+        }
+        // Unknown times sort last when newest first
+        let order = if newest_first {
+            "t.added_at IS NULL, t.added_at DESC, o.key DESC"
+        } else {
+            "o.key"
+        };
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT o.value, t.added_at
+             FROM entities e, json_each(observations_json(e.observations)) o
+             LEFT JOIN observation_times t
+                 ON t.entity_name = e.name AND t.observation = o.value
+             WHERE e.name = ?1
+             ORDER BY {}",
+            order
+        ))?;
+        let rows = stmt.query_map(params![name], |row| {
+            Ok(TimedObservation {
+                text: row.get(0)?,
+                added_at: row.get(1)?,
             })
         })?;
 
-        // Pre-allocate based on size hint from iterator
-        let mut relations = Vec::with_capacity(rows.size_hint().0);
+        let mut observations = Vec::new();
         for row in rows {
-            relations.push(row?);
+            observations.push(row.context("Failed to read observation times")?);
         }
-        Ok(relations)
+        Ok(ObservationTimes {
+            entity_name: name.to_string(),
+            observations,
+        })
     }
 
-    /// Search using FTS5 full-text search
-    pub fn search_nodes(&self, query: Option<&str>) -> Result<KnowledgeGraph> {
-        // No query or empty query = return full graph
-        let trimmed = query.map(|q| q.trim()).unwrap_or("");
-        if trimmed.is_empty() {
-            return self.read_graph();
+    /// An entity's observations, optionally followed by its relations as "<from> <type> <to>"
+    pub fn get_facts(&self, name: &str, include_relations: bool) -> Result<EntityFacts> {
+        validate_name(name, "Entity name")?;
+
+        let conn = self.connection()?;
+        let entity = self
+            .read_entities_by_names(&conn, &[name.to_string()])
+            .context("Failed to read entity")?
+            .pop()
+            .with_context(|| {
+                format!(
+                    "Cannot get facts: entity '{}' does not exist{}",
+                    name,
+                    typed_key_hint(&conn, name)
+                )
+            })?;
+
+        let mut facts = entity.observations;
+        if include_relations {
+            // Outgoing first, then incoming; a self-loop is listed once
+            let mut stmt = conn.prepare_cached(
+                "SELECT from_entity, relation_type, to_entity FROM relations
+                 WHERE from_entity = ?1 OR to_entity = ?1
+                 ORDER BY from_entity != ?1, relation_type, from_entity, to_entity",
+            )?;
+            let rows = stmt.query_map(params![name], |row| {
+                Ok(format!(
+                    "{} {} {}",
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?
+                ))
+            })?;
+            for row in rows {
+                facts.push(row.context("Failed to read relations")?);
+            }
         }
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
+        Ok(EntityFacts {
+            name: entity.name,
+            entity_type: entity.entity_type,
+            description: entity.description,
+            facts,
+        })
+    }
 
-        // Sanitize query to prevent FTS5 syntax errors
-        let safe_query = sanitize_fts5_query(trimmed);
+    /// Entities exactly two hops from `name` in the given direction, minus its direct
+    /// neighbors and itself ("friends of friends"), with the neighbors they are reached via
+    pub fn second_degree(&self, name: &str, direction: Direction) -> Result<SecondDegree> {
+        validate_name(name, "Entity name")?;
 
-        // FTS5 search - much faster than LIKE for text search
-        let entities = self
-            .search_entities_fts(&conn, &safe_query)
-            .context("Failed to search entities")?;
+        let conn = self.connection()?;
+        if !entity_exists(&conn, name)? {
+            bail!(
+                "Cannot find second-degree neighbors: entity '{}' does not exist{}",
+                name,
+                typed_key_hint(&conn, name)
+            );
+        }
 
-        // Get relations only between found entities
-        let relations = self
-            .get_relations_between(&conn, &entities)
-            .context("Failed to get relations for search results")?;
+        // Direct neighbors, then every (via, neighbor) step out of them, in the direction
+        let first = match direction {
+            Direction::Outgoing => "SELECT to_entity FROM relations WHERE from_entity = ?1",
+            Direction::Incoming => "SELECT from_entity FROM relations WHERE to_entity = ?1",
+            Direction::Both => {
+                "SELECT to_entity FROM relations WHERE from_entity = ?1
+                 UNION SELECT from_entity FROM relations WHERE to_entity = ?1"
+            }
+        };
+        let outgoing = "SELECT from_entity, to_entity FROM relations
+                        WHERE from_entity IN (SELECT neighbor FROM first)";
+        let incoming = "SELECT to_entity, from_entity FROM relations
+                        WHERE to_entity IN (SELECT neighbor FROM first)";
+        let step = match direction {
+            Direction::Outgoing => outgoing.to_string(),
+            Direction::Incoming => incoming.to_string(),
+            Direction::Both => format!("{} UNION {}", outgoing, incoming),
+        };
+        let query = format!(
+            "WITH first(neighbor) AS ({}), hops(via, neighbor) AS ({})
+             SELECT DISTINCT via, neighbor FROM hops
+             WHERE neighbor != ?1 AND neighbor NOT IN (SELECT neighbor FROM first)
+             ORDER BY neighbor, via",
+            first, step
+        );
+        let mut via: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let rows = conn
+            .prepare_cached(&query)?
+            .query_map(params![name], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to find second-degree neighbors")?;
+        for (through, neighbor) in rows {
+            via.entry(neighbor).or_default().push(through);
+        }
 
-        Ok(KnowledgeGraph {
+        let names: Vec<String> = via.keys().cloned().collect();
+        let mut entities = if names.is_empty() {
+            Vec::new()
+        } else {
+            self.read_entities_by_names(&conn, &names)
+                .context("Failed to read second-degree entities")?
+        };
+        entities.sort_by(|x, y| x.name.cmp(&y.name));
+
+        Ok(SecondDegree {
+            center: name.to_string(),
             entities,
-            relations,
+            via,
         })
     }
 
-    /// Helper: search entities using FTS5
-    fn search_entities_fts(&self, conn: &Connection, fts_query: &str) -> Result<Vec<Entity>> {
-        let mut stmt = conn
-            .prepare(
-                "SELECT e.name, e.entity_type, e.observations
-                 FROM entities e
-                 INNER JOIN entities_fts fts ON e.rowid = fts.rowid
-                 WHERE entities_fts MATCH ?1",
-            )
-            .context("Failed to prepare FTS5 search query")?;
+    /// Number and types of the relations from `a` to `b` (directed) or between them either way
+    pub fn relation_count_between(
+        &self,
+        a: &str,
+        b: &str,
+        directed: bool,
+    ) -> Result<RelationCountBetween> {
+        validate_name(a, "Entity name")?;
+        validate_name(b, "Entity name")?;
 
-        let rows = stmt.query_map(params![fts_query], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
+        let conn = self.connection()?;
+        for name in [a, b] {
+            if !entity_exists(&conn, name)? {
+                bail!(
+                    "Cannot count relations: entity '{}' does not exist{}",
+                    name,
+                    typed_key_hint(&conn, name)
+                );
+            }
+        }
+
+        let mut stmt = conn.prepare_cached(if directed {
+            "SELECT relation_type, COUNT(*) FROM relations
+             WHERE from_entity = ?1 AND to_entity = ?2
+             GROUP BY relation_type ORDER BY relation_type"
+        } else {
+            "SELECT relation_type, COUNT(*) FROM relations
+             WHERE (from_entity = ?1 AND to_entity = ?2) OR (from_entity = ?2 AND to_entity = ?1)
+             GROUP BY relation_type ORDER BY relation_type"
+        })?;
+        let rows = stmt.query_map(params![a, b], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?;
 
-        // Pre-allocate based on size hint from iterator
-        let mut entities = Vec::with_capacity(rows.size_hint().0);
+        let mut result = RelationCountBetween {
+            a: a.to_string(),
+            b: b.to_string(),
+            directed,
+            ..Default::default()
+        };
         for row in rows {
-            let (name, entity_type, obs_json) = row?;
-            let observations: Vec<String> = serde_json::from_str(&obs_json)
-                .with_context(|| format!("Corrupted observations for entity '{}'", name))?;
-            entities.push(Entity {
-                name,
-                entity_type,
-                observations,
-            });
+            let (relation_type, count) = row.context("Failed to count relations")?;
+            result.count += count as usize;
+            result.relation_types.push(relation_type);
         }
-        Ok(entities)
+        Ok(result)
     }
 
-    /// Helper: get relations where BOTH from and to are in the given entities
-    fn get_relations_between(
-        &self,
-        conn: &Connection,
-        entities: &[Entity],
-    ) -> Result<Vec<Relation>> {
-        if entities.is_empty() {
-            return Ok(Vec::new());
+    /// Grow a community from `seed` over the relation graph (direction ignored): repeatedly add
+    /// the outside entity linked to the most members (ties by name), stopping at `max_size`
+    /// or when the next entity would bring the density below `min_density`
+    pub fn community(&self, seed: &str, max_size: usize, min_density: f64) -> Result<Community> {
+        validate_name(seed, "Entity name")?;
+        if max_size == 0 || max_size > MAX_COMMUNITY_SIZE {
+            bail!(
+                "Invalid max size {} (must be 1-{})",
+                max_size,
+                MAX_COMMUNITY_SIZE
+            );
+        }
+        if !(0.0..=1.0).contains(&min_density) {
+            bail!("Invalid min density {} (must be 0.0-1.0)", min_density);
         }
 
-        let entity_names: HashSet<_> = entities.iter().map(|e| &e.name).collect();
+        let conn = self.connection()?;
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start read transaction")?;
+        if !entity_exists(&tx, seed)? {
+            bail!(
+                "Cannot find community: entity '{}' does not exist{}",
+                seed,
+                typed_key_hint(&tx, seed)
+            );
+        }
+        let relations = self
+            .read_all_relations(&tx, true)
+            .context("Failed to read relations")?;
 
-        let placeholders_from = build_placeholders(entity_names.len(), 1);
-        let placeholders_to = build_placeholders(entity_names.len(), entity_names.len() + 1);
+        // Undirected simple graph: one link per linked pair, self-relations ignored
+        let mut links: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+        for rel in relations.iter().filter(|rel| rel.from != rel.to) {
+            links.entry(&rel.from).or_default().insert(&rel.to);
+            links.entry(&rel.to).or_default().insert(&rel.from);
+        }
 
-        let query = format!(
-            "SELECT from_entity, to_entity, relation_type FROM relations
-             WHERE from_entity IN ({}) AND to_entity IN ({})",
-            placeholders_from, placeholders_to
-        );
+        let mut members: Vec<&str> = vec![seed];
+        let mut internal_links = 0usize;
+        // Outside entities linked to the community -> links to members
+        let mut frontier: BTreeMap<&str, usize> = BTreeMap::new();
+        let join = |name: &str, frontier: &mut BTreeMap<_, _>, members: &[&str]| {
+            for &neighbor in links.get(name).into_iter().flatten() {
+                if !members.contains(&neighbor) {
+                    *frontier.entry(neighbor).or_insert(0) += 1;
+                }
+            }
+        };
+        join(seed, &mut frontier, &members);
 
-        // Build params: first all names for FROM, then all names for TO
-        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(entity_names.len() * 2);
-        for name in &entity_names {
-            params.push(*name);
-        }
-        for name in &entity_names {
-            params.push(*name);
+        while members.len() < max_size {
+            // max_by_key returns the last maximum: iterating names in reverse picks the first
+            let Some((&next, &linked)) = frontier.iter().rev().max_by_key(|(_, &linked)| linked)
+            else {
+                break;
+            };
+            let size = members.len() + 1;
+            let density = (internal_links + linked) as f64 / (size * (size - 1) / 2) as f64;
+            if density < min_density {
+                break;
+            }
+            frontier.remove(next);
+            members.push(next);
+            internal_links += linked;
+            join(next, &mut frontier, &members);
         }
 
-        let mut stmt = conn.prepare(&query)?;
-        let rows = stmt.query_map(params.as_slice(), |row| {
-            Ok(Relation {
-                from: row.get(0)?,
-                to: row.get(1)?,
-                relation_type: row.get(2)?,
+        let member_set: HashSet<&str> = members.iter().copied().collect();
+        let mut entities = self
+            .read_entities_by_names(
+                &tx,
+                &members.iter().map(|m| m.to_string()).collect::<Vec<_>>(),
+            )
+            .context("Failed to read community entities")?;
+        entities.sort_by_key(|e| members.iter().position(|m| *m == e.name));
+        let internal: Vec<Relation> = relations
+            .iter()
+            .filter(|rel| {
+                member_set.contains(rel.from.as_str()) && member_set.contains(rel.to.as_str())
             })
-        })?;
+            .cloned()
+            .collect();
+        tx.commit().context("Failed to finish read transaction")?;
 
-        // Pre-allocate based on size hint from iterator
-        let mut relations = Vec::with_capacity(rows.size_hint().0);
-        for row in rows {
-            relations.push(row?);
+        let pairs = members.len() * (members.len() - 1) / 2;
+        Ok(Community {
+            seed: seed.to_string(),
+            entities,
+            relations: internal,
+            density: if pairs == 0 {
+                1.0
+            } else {
+                internal_links as f64 / pairs as f64
+            },
+        })
+    }
+
+    /// Entities connected to both `a` and `b` in the given direction, with the linking relations
+    /// Outgoing: both point to it; Incoming: it points to both; Both: either way
+    pub fn common_neighbors(
+        &self,
+        a: &str,
+        b: &str,
+        direction: Direction,
+    ) -> Result<CommonNeighbors> {
+        validate_name(a, "Entity name")?;
+        validate_name(b, "Entity name")?;
+        if a == b {
+            bail!("Cannot find common neighbors of '{}' with itself", a);
         }
-        Ok(relations)
+
+        let conn = self.connection()?;
+        for name in [a, b] {
+            if !entity_exists(&conn, name)? {
+                bail!(
+                    "Cannot find common neighbors: entity '{}' does not exist{}",
+                    name,
+                    typed_key_hint(&conn, name)
+                );
+            }
+        }
+
+        // Neighbor set of the entity bound to ?N, as a single-column select
+        let neighbors = |n: u32| match direction {
+            Direction::Outgoing => {
+                format!("SELECT to_entity AS neighbor FROM relations WHERE from_entity = ?{n}")
+            }
+            Direction::Incoming => {
+                format!("SELECT from_entity AS neighbor FROM relations WHERE to_entity = ?{n}")
+            }
+            Direction::Both => format!(
+                "SELECT to_entity AS neighbor FROM relations WHERE from_entity = ?{n}
+                 UNION SELECT from_entity FROM relations WHERE to_entity = ?{n}"
+            ),
+        };
+        let query = format!(
+            "SELECT neighbor FROM ({}) INTERSECT SELECT neighbor FROM ({})
+             EXCEPT SELECT ?1 EXCEPT SELECT ?2
+             ORDER BY neighbor",
+            neighbors(1),
+            neighbors(2)
+        );
+        let shared: Vec<String> = conn
+            .prepare_cached(&query)?
+            .query_map(params![a, b], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()
+            .context("Failed to intersect neighbor sets")?;
+        if shared.is_empty() {
+            return Ok(CommonNeighbors {
+                a: a.to_string(),
+                b: b.to_string(),
+                ..Default::default()
+            });
+        }
+
+        // Relations between {a, b} and the shared neighbors, restricted to the direction
+        let ends = build_placeholders(shared.len(), 3);
+        let outgoing = format!("(from_entity IN (?1, ?2) AND to_entity IN ({}))", ends);
+        let incoming = format!("(to_entity IN (?1, ?2) AND from_entity IN ({}))", ends);
+        let filter = match direction {
+            Direction::Outgoing => outgoing,
+            Direction::Incoming => incoming,
+            Direction::Both => format!("{} OR {}", outgoing, incoming),
+        };
+        let query = format!(
+            "SELECT from_entity, to_entity, relation_type FROM relations WHERE {}
+             ORDER BY from_entity, to_entity, relation_type",
+            filter
+        );
+        let mut params: Vec<&dyn rusqlite::ToSql> = vec![&a, &b];
+        params.extend(shared.iter().map(|s| s as &dyn rusqlite::ToSql));
+        let relations = conn
+            .prepare(&query)?
+            .query_map(params.as_slice(), |row| {
+                Ok(Relation {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    relation_type: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read relations to common neighbors")?;
+
+        let mut entities = self
+            .read_entities_by_names(&conn, &shared)
+            .context("Failed to read common neighbor entities")?;
+        entities.sort_by(|x, y| x.name.cmp(&y.name));
+
+        Ok(CommonNeighbors {
+            a: a.to_string(),
+            b: b.to_string(),
+            entities,
+            relations,
+        })
     }
 
     /// Open specific nodes by names
@@ -760,14 +6633,11 @@ impl Database {
         }
 
         // Validate all entity names
-        for name in names {
-            validate_name(name, "Entity name")?;
-        }
+        validate_batch(names, String::clone, |name| {
+            validate_name(name, "Entity name")
+        })?;
 
-        let conn = self
-            .pool
-            .get()
-            .context("Failed to get database connection from pool")?;
+        let conn = self.read_connection()?;
 
         // Get entities by names
         let entities = self
@@ -785,40 +6655,127 @@ impl Database {
         })
     }
 
+    /// Read entities by name, without the relations between them (unknown names are skipped)
+    pub fn entities_by_names(&self, names: &[String]) -> Result<Vec<Entity>> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = self.connection()?;
+        self.read_entities_by_names(&conn, names)
+            .context("Failed to read entities")
+    }
+
+    /// Open specific nodes by their stable ids (unknown ids are skipped, like unknown names)
+    pub fn open_nodes_by_id(&self, ids: &[String]) -> Result<KnowledgeGraph> {
+        if ids.is_empty() {
+            return Ok(KnowledgeGraph::default());
+        }
+
+        validate_batch(ids, String::clone, |id| validate_entity_id(id))?;
+
+        let conn = self.read_connection()?;
+        let entities = self
+            .read_entities_where(&conn, "id", ids)
+            .context("Failed to read entities")?;
+        let relations = self
+            .get_relations_between(&conn, &entities)
+            .context("Failed to get relations")?;
+
+        Ok(KnowledgeGraph {
+            entities,
+            relations,
+        })
+    }
+
+    /// Inspect nodes by name: each requested name maps to its entity (None if missing),
+    /// in/out degree and observation count, with the missing names listed separately
+    pub fn inspect_nodes(&self, names: &[String]) -> Result<NodeInspections> {
+        if names.is_empty() {
+            return Ok(NodeInspections::default());
+        }
+
+        validate_batch(names, String::clone, |name| {
+            validate_name(name, "Entity name")
+        })?;
+
+        let conn = self.read_connection()?;
+        let mut nodes: BTreeMap<String, NodeInspection> = names
+            .iter()
+            .map(|name| (name.clone(), NodeInspection::default()))
+            .collect();
+
+        for entity in self
+            .read_entities_by_names(&conn, names)
+            .context("Failed to read entities")?
+        {
+            if let Some(node) = nodes.get_mut(&entity.name) {
+                node.observation_count = entity.observations.len() as u64;
+                node.entity = Some(entity);
+            }
+        }
+
+        let placeholders = build_placeholders(names.len(), 1);
+        let params: Vec<&dyn rusqlite::ToSql> =
+            names.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+        for (column, outgoing) in [("from_entity", true), ("to_entity", false)] {
+            let query = format!(
+                "SELECT {c}, COUNT(*) FROM relations WHERE {c} IN ({p}) GROUP BY {c}",
+                c = column,
+                p = placeholders
+            );
+            let degrees = conn
+                .prepare(&query)?
+                .query_map(params.as_slice(), |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .context("Failed to count node degrees")?;
+            for (name, degree) in degrees {
+                if let Some(node) = nodes.get_mut(&name) {
+                    if outgoing {
+                        node.out_degree = degree;
+                    } else {
+                        node.in_degree = degree;
+                    }
+                }
+            }
+        }
+
+        let missing = nodes
+            .iter()
+            .filter(|(_, node)| node.entity.is_none())
+            .map(|(name, _)| name.clone())
+            .collect();
+        Ok(NodeInspections { nodes, missing })
+    }
+
     /// Helper: read entities by specific names
-    fn read_entities_by_names(
+    fn read_entities_by_names(&self, conn: &Connection, names: &[String]) -> Result<Vec<Entity>> {
+        self.read_entities_where(conn, "name", names)
+    }
+
+    /// Helper: read entities whose `column` (name or id) is one of `values`
+    fn read_entities_where(
         &self,
         conn: &Connection,
-        names: &[String],
+        column: &str,
+        values: &[String],
     ) -> Result<Vec<Entity>> {
-        let placeholders = build_placeholders(names.len(), 1);
+        let placeholders = build_placeholders(values.len(), 1);
         let query = format!(
-            "SELECT name, entity_type, observations FROM entities WHERE name IN ({})",
-            placeholders
+            "SELECT name, entity_type, observations, description, id, confidence, metadata FROM entities WHERE {} IN ({})",
+            column, placeholders
         );
 
         let params: Vec<&dyn rusqlite::ToSql> =
-            names.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+            values.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
 
         let mut stmt = conn.prepare(&query)?;
-        let rows = stmt.query_map(params.as_slice(), |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })?;
+        let rows = stmt.query_map(params.as_slice(), entity_row)?;
 
-        let mut entities = Vec::with_capacity(names.len());
+        let mut entities = Vec::with_capacity(values.len());
         for row in rows {
-            let (name, entity_type, obs_json) = row?;
-            let observations: Vec<String> = serde_json::from_str(&obs_json)
-                .with_context(|| format!("Corrupted observations for entity '{}'", name))?;
-            entities.push(Entity {
-                name,
-                entity_type,
-                observations,
-            });
+            entities.push(decode_entity(row?)?);
         }
         Ok(entities)
     }
@@ -0,0 +1,103 @@
+use memory_mcp_rs::similarity::normalize_identifier;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Argument field that the tool's input schema does not declare (--strict-args)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UnknownField {
+    /// Where the field was sent, e.g. "entities[0].entity_type"
+    pub path: String,
+    /// Declared field with the same spelling-normalized name, e.g. "entityType"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Fields of `arguments` that `schema` (a tool input schema) does not declare
+/// Objects are checked where the schema lists their properties and allows no others;
+/// arrays are checked item by item, and `$ref`s are resolved against the schema's `$defs`
+/// Free-form values (no declared properties) and enum alternatives are not checked
+pub fn unknown_fields(
+    schema: &Map<String, Value>,
+    arguments: &Map<String, Value>,
+) -> Vec<UnknownField> {
+    let root = Value::Object(schema.clone());
+    let mut unknown = Vec::new();
+    check_object(&root, &root, arguments, "", &mut unknown);
+    unknown
+}
+
+/// Follow a local `$ref` ("#/$defs/Entity") to the schema it names
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+        return schema;
+    };
+    reference
+        .strip_prefix("#/")
+        .and_then(|pointer| root.pointer(&format!("/{}", pointer)))
+        .unwrap_or(schema)
+}
+
+fn check_object(
+    root: &Value,
+    schema: &Value,
+    object: &Map<String, Value>,
+    path: &str,
+    unknown: &mut Vec<UnknownField>,
+) {
+    let schema = resolve(root, schema);
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    let open = schema
+        .get("additionalProperties")
+        .is_some_and(|additional| additional != &Value::Bool(false));
+
+    for (key, value) in object {
+        let field_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        match properties.get(key) {
+            Some(property) => check_value(root, property, value, &field_path, unknown),
+            None if !open => {
+                let normalized = normalize_identifier(key);
+                unknown.push(UnknownField {
+                    path: field_path,
+                    suggestion: properties
+                        .keys()
+                        .find(|known| normalize_identifier(known) == normalized)
+                        .cloned(),
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+fn check_value(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    unknown: &mut Vec<UnknownField>,
+) {
+    let schema = resolve(root, schema);
+    match value {
+        Value::Object(object) => check_object(root, schema, object, path, unknown),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check_value(
+                        root,
+                        item_schema,
+                        item,
+                        &format!("{}[{}]", path, i),
+                        unknown,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,203 @@
+//! CLI behavior tests (flags that run without starting a transport)
+
+use std::process::Command;
+use tempfile::TempDir;
+
+use memory_mcp_rs::manager::KnowledgeGraphManager;
+
+/// Run the server binary with given args and capture its output
+fn run_binary(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_memory-mcp-rs"))
+        .args(args)
+        .output()
+        .expect("Failed to run binary")
+}
+
+#[test]
+fn test_check_healthy_database() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    KnowledgeGraphManager::new(db_path.clone()).expect("Failed to create database");
+
+    let output = run_binary(&["--check", "--db-path", db_path.to_str().unwrap()]);
+
+    assert!(output.status.success(), "Healthy database should exit 0");
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("OK"));
+}
+
+#[test]
+fn test_check_missing_database() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("missing.db");
+
+    let output = run_binary(&["--check", "--db-path", db_path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+    assert!(!db_path.exists(), "--check must not create the database");
+}
+
+#[test]
+fn test_check_does_not_create_directories() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let missing_dir = db_dir.path().join("missing");
+    let db_path = missing_dir.join("test.db");
+
+    let output = run_binary(&["--check", "--db-path", db_path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("FAIL"));
+    assert!(!missing_dir.exists(), "--check must not create directories");
+}
+
+#[test]
+fn test_check_does_not_modify_database() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("plain.db");
+    // A bare SQLite file in rollback-journal mode with none of the server's tables
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch("CREATE TABLE unrelated (id INTEGER PRIMARY KEY);")
+        .unwrap();
+    drop(conn);
+    let before = std::fs::read(&db_path).unwrap();
+
+    let output = run_binary(&[
+        "--check",
+        "--no-fts",
+        "--db-path",
+        db_path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success(), "Readable database should exit 0");
+    assert_eq!(
+        std::fs::read(&db_path).unwrap(),
+        before,
+        "--check must not write"
+    );
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let mode: String = conn
+        .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(mode, "delete", "--check must not switch the journal mode");
+    let tables: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'entities'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(tables, 0, "--check must not run migrations");
+}
+
+#[test]
+fn test_check_corrupted_database() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("corrupt.db");
+    std::fs::write(&db_path, b"this is definitely not a sqlite database file").unwrap();
+
+    let output = run_binary(&["--check", "--db-path", db_path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("FAIL"));
+}
+
+#[test]
+fn test_seed_only_applies_to_empty_database() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("seeded.db");
+    let seed_path = db_dir.path().join("seed.jsonl");
+    std::fs::write(
+        &seed_path,
+        r#"{"type":"entity","name":"Alice","entityType":"person","observations":["Seeded"]}
+{"type":"entity","name":"Acme","entityType":"organization","observations":[]}
+{"type":"relation","from":"Alice","to":"Acme","relationType":"works_at"}
+"#,
+    )
+    .unwrap();
+
+    // stdin is closed, so the stdio server exits right after seeding
+    let args = [
+        "--db-path",
+        db_path.to_str().unwrap(),
+        "--seed",
+        seed_path.to_str().unwrap(),
+    ];
+    run_binary(&args);
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let graph = rt.block_on(async {
+        let manager = KnowledgeGraphManager::new(db_path.clone()).unwrap();
+        manager
+            .delete_entities(vec!["Acme".to_string()])
+            .await
+            .unwrap();
+        manager.read_graph().await.unwrap()
+    });
+    assert_eq!(graph.entities.len(), 1);
+    assert!(graph.relations.is_empty());
+
+    // Second start: database has data, seed is skipped (Acme stays deleted)
+    run_binary(&args);
+    let graph = rt.block_on(async {
+        KnowledgeGraphManager::new(db_path.clone())
+            .unwrap()
+            .read_graph()
+            .await
+            .unwrap()
+    });
+    assert_eq!(graph.entities.len(), 1);
+    assert_eq!(graph.entities[0].name, "Alice");
+}
+
+#[test]
+fn test_backup_on_start_rotates_backups() {
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("memory.db");
+    let backup_dir = db_dir.path().join("backups");
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        KnowledgeGraphManager::new(db_path.clone())
+            .unwrap()
+            .create_entities(vec![memory_mcp_rs::graph::Entity {
+                name: "Alice".to_string(),
+                entity_type: "person".to_string(),
+                observations: vec!["Backed up".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            }])
+            .await
+            .unwrap();
+    });
+    std::fs::create_dir_all(&backup_dir).unwrap();
+    std::fs::write(backup_dir.join("notes.db"), b"not a backup").unwrap();
+
+    // stdin is closed, so each start backs up and exits
+    let args = [
+        "--db-path",
+        db_path.to_str().unwrap(),
+        "--backup-on-start",
+        "--backup-keep",
+        "2",
+    ];
+    for _ in 0..3 {
+        run_binary(&args);
+    }
+
+    let backups = memory_mcp_rs::backup::list_backups(&backup_dir).unwrap();
+    assert_eq!(backups.len(), 2);
+    assert!(
+        backup_dir.join("notes.db").exists(),
+        "foreign files are kept"
+    );
+
+    let graph = rt.block_on(async {
+        KnowledgeGraphManager::new(backups[1].clone())
+            .unwrap()
+            .read_graph()
+            .await
+            .unwrap()
+    });
+    assert_eq!(graph.entities[0].name, "Alice");
+}
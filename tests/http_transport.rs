@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::net::TcpListener;
 use std::process::{Child, Command};
 use std::time::Duration;
@@ -32,6 +33,11 @@ async fn wait_for_server(port: u16, timeout_secs: u64) -> bool {
 
 /// Start server subprocess in stream mode
 fn start_server(port: u16, db_path: &str) -> Child {
+    start_server_with_args(port, db_path, &[])
+}
+
+/// Start server subprocess in stream mode with extra CLI arguments
+fn start_server_with_args(port: u16, db_path: &str, extra: &[&str]) -> Child {
     Command::new("cargo")
         .args([
             "run",
@@ -42,10 +48,125 @@ fn start_server(port: u16, db_path: &str) -> Child {
             "--db-path",
             db_path,
         ])
+        .args(extra)
         .spawn()
         .expect("Failed to start server")
 }
 
+/// JSON-RPC initialize request body for the MCP endpoint
+fn initialize_request() -> serde_json::Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "http-test", "version": "0.0.0"}
+        }
+    })
+}
+
+/// Extract the first JSON-RPC message from an SSE response body
+fn parse_sse_message(body: &str) -> serde_json::Value {
+    let data = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim)
+        .find(|data| !data.is_empty())
+        .unwrap_or_else(|| panic!("No SSE data in body: {}", body));
+    serde_json::from_str(data).expect("SSE data is not JSON")
+}
+
+/// Minimal MCP client session over streamable HTTP
+struct McpSession {
+    client: reqwest::Client,
+    url: String,
+    session_id: String,
+}
+
+impl McpSession {
+    /// Perform the initialize handshake
+    async fn connect(port: u16) -> Self {
+        let client = reqwest::Client::new();
+        let url = format!("http://127.0.0.1:{}/mcp", port);
+
+        let response = client
+            .post(&url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(&initialize_request())
+            .send()
+            .await
+            .expect("Failed to send initialize");
+        assert!(response.status().is_success());
+        let session_id = response
+            .headers()
+            .get("mcp-session-id")
+            .expect("No session id")
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let session = Self {
+            client,
+            url,
+            session_id,
+        };
+        session
+            .post(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/initialized"
+            }))
+            .await;
+        session
+    }
+
+    async fn post(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.client
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .header("mcp-session-id", &self.session_id)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    /// Send a JSON-RPC request and return the whole response message
+    async fn request(&self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let response = self
+            .post(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": method,
+                "params": params
+            }))
+            .await;
+        assert!(response.status().is_success());
+        let body = response.text().await.expect("Failed to read response");
+        parse_sse_message(&body)
+    }
+
+    /// Call a tool and return the JSON-RPC result
+    async fn call_tool(&self, name: &str, arguments: serde_json::Value) -> serde_json::Value {
+        let response = self
+            .post(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": name, "arguments": arguments}
+            }))
+            .await;
+        assert!(response.status().is_success());
+        let body = response.text().await.expect("Failed to read response");
+        let message = parse_sse_message(&body);
+        message
+            .get("result")
+            .cloned()
+            .unwrap_or_else(|| panic!("Tool call failed: {}", message))
+    }
+}
+
 #[tokio::test]
 async fn test_http_server_health_check() {
     let port = find_available_port();
@@ -202,3 +323,1592 @@ async fn test_server_with_logging() {
     // Cleanup log file
     std::fs::remove_file(&log_file).ok();
 }
+
+#[tokio::test]
+async fn test_compression_skips_mcp_stream() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(port, db_path.to_str().unwrap(), &["--compress"]);
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    // MCP responses are SSE streams and must not be buffered by the compressor
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("Accept", "application/json, text/event-stream")
+        .header("Accept-Encoding", "gzip, br")
+        .json(&initialize_request())
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert!(response.status().is_success());
+    assert!(response.headers().get("content-encoding").is_none());
+    let body = response.text().await.expect("Failed to read response");
+    assert!(body.contains("memory-mcp-rs"), "Unexpected body: {}", body);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_structured_content_is_versioned() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": []}
+            ]}),
+        )
+        .await;
+    let result = session.call_tool("read_graph", serde_json::json!({})).await;
+
+    let structured = &result["structuredContent"];
+    assert_eq!(structured["schema_version"], 6);
+    assert_eq!(structured["data"]["entities"][0]["name"], "Alice");
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_structured_content_keys_are_sorted() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [{
+                "name": "Alice", "entityType": "person", "observations": ["Engineer"],
+                "description": "Team lead", "confidence": 0.9
+            }]}),
+        )
+        .await;
+
+    // Check the raw text: parsing into a Value would hide the order
+    let response = session
+        .post(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "read_graph", "arguments": {}}
+        }))
+        .await;
+    let body = response.text().await.expect("Failed to read response");
+    let structured = &body[body
+        .find("\"structuredContent\"")
+        .expect("No structured content")..];
+    let positions: Vec<usize> = [
+        "\"data\"",
+        "\"confidence\"",
+        "\"description\"",
+        "\"entityType\"",
+        "\"id\"",
+        "\"name\"",
+        "\"observations\"",
+        "\"relations\"",
+        "\"schema_version\"",
+    ]
+    .iter()
+    .map(|key| {
+        structured
+            .find(key)
+            .unwrap_or_else(|| panic!("{} missing", key))
+    })
+    .collect();
+    assert!(
+        positions.windows(2).all(|pair| pair[0] < pair[1]),
+        "Keys out of order: {}",
+        structured
+    );
+
+    // The /graph route uses the same order
+    let graph = reqwest::get(format!("http://127.0.0.1:{}/graph", port))
+        .await
+        .expect("Failed to send request")
+        .text()
+        .await
+        .unwrap();
+    assert!(graph.find("\"entities\"").unwrap() < graph.find("\"relations\"").unwrap());
+    assert!(graph.find("\"entityType\"").unwrap() < graph.find("\"name\"").unwrap());
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_oversized_request_body_rejected() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(
+        port,
+        db_path.to_str().unwrap(),
+        &["--max-body-size", "1024"],
+    );
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let mut request = initialize_request();
+    request["params"]["clientInfo"]["name"] = serde_json::json!("x".repeat(4096));
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&request)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 413);
+
+    // Small requests still go through
+    let session = McpSession::connect(port).await;
+    let result = session.call_tool("read_graph", serde_json::json!({})).await;
+    assert_eq!(result["structuredContent"]["schema_version"], 6);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_rate_limit_exempts_health() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server =
+        start_server_with_args(port, db_path.to_str().unwrap(), &["--rate-limit", "2"]);
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let client = reqwest::Client::new();
+    let mut limited = None;
+    for _ in 0..10 {
+        let response = client
+            .get(format!("http://127.0.0.1:{}/graph", port))
+            .send()
+            .await
+            .expect("Failed to send request");
+        if response.status().as_u16() == 429 {
+            limited = Some(response);
+            break;
+        }
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let limited = limited.expect("Burst of requests was never limited");
+    assert!(limited.headers().contains_key("retry-after"));
+
+    // A made-up session id does not buy a fresh bucket
+    let response = client
+        .get(format!("http://127.0.0.1:{}/graph", port))
+        .header("mcp-session-id", "rotated-session")
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 429);
+
+    for _ in 0..10 {
+        let response = client
+            .get(format!("http://127.0.0.1:{}/health", port))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_graph_route_conditional_get() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let client = reqwest::Client::new();
+    let graph_url = format!("http://127.0.0.1:{}/graph", port);
+
+    let response = client.get(&graph_url).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["entities"], serde_json::json!([]));
+
+    // Unchanged graph: 304 without body, same ETag
+    let response = client
+        .get(&graph_url)
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 304);
+    assert_eq!(response.headers()["etag"], etag.as_str());
+    assert!(response.text().await.unwrap().is_empty());
+
+    // Health carries the same revision ETag
+    let response = client
+        .get(format!("http://127.0.0.1:{}/health", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["etag"], etag.as_str());
+
+    // A write bumps the revision, so the old ETag no longer matches
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": []}
+            ]}),
+        )
+        .await;
+
+    let response = client
+        .get(&graph_url)
+        .header("If-None-Match", format!("\"stale\", W/{}", etag))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_ne!(response.headers()["etag"], etag.as_str());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["entities"][0]["name"], "Alice");
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_graph_ndjson_route_streams_records() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["Engineer"]},
+                {"name": "Acme", "entityType": "company", "observations": []}
+            ]}),
+        )
+        .await;
+    session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "Alice", "to": "Acme", "relationType": "works_at"}
+            ]}),
+        )
+        .await;
+
+    let response = reqwest::get(format!("http://127.0.0.1:{}/graph.ndjson", port))
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let body = response.text().await.expect("Failed to read response");
+    let records: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Malformed NDJSON line"))
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["name"], "Acme");
+    assert_eq!(records[1]["name"], "Alice");
+    assert_eq!(records[2]["type"], "relation");
+    assert_eq!(records[2]["relationType"], "works_at");
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_create_relations_include_endpoints() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["Engineer"]},
+                {"name": "Acme", "entityType": "company", "observations": []}
+            ]}),
+        )
+        .await;
+
+    let plain = session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "Alice", "to": "Acme", "relationType": "works_at"}
+            ]}),
+        )
+        .await;
+    assert!(plain["structuredContent"]["data"]
+        .get("endpoints")
+        .is_none());
+
+    let result = session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({
+                "relations": [
+                    {"from": "Alice", "to": "Acme", "relationType": "works_at"},
+                    {"from": "Acme", "to": "Alice", "relationType": "employs"}
+                ],
+                "includeEndpoints": true
+            }),
+        )
+        .await;
+    let structured = &result["structuredContent"]["data"];
+    assert_eq!(structured["relations"].as_array().unwrap().len(), 1);
+    let endpoints = structured["endpoints"].as_array().unwrap();
+    assert_eq!(endpoints.len(), 2);
+    let alice = endpoints.iter().find(|e| e["name"] == "Alice").unwrap();
+    assert_eq!(alice["observations"], serde_json::json!(["Engineer"]));
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_create_tools_lean_results() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    let entities = session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({
+                "entities": [
+                    {"name": "Alice", "entityType": "person", "observations": ["Engineer"]},
+                    {"name": "Bob", "entityType": "person", "observations": []},
+                    {"name": "Acme", "entityType": "company", "observations": []}
+                ],
+                "verbose": false
+            }),
+        )
+        .await;
+    // Every create reports the batch it tagged its results with
+    let mut data = entities["structuredContent"]["data"].clone();
+    assert!(data["batchId"].is_string());
+    data.as_object_mut().unwrap().remove("batchId");
+    assert_eq!(
+        data,
+        serde_json::json!({"count": 3, "names": ["Alice", "Bob", "Acme"]})
+    );
+
+    let relations = session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({
+                "relations": [
+                    {"from": "Alice", "to": "Acme", "relationType": "works_at"},
+                    {"from": "Bob", "to": "Acme", "relationType": "works_at"}
+                ],
+                "verbose": false
+            }),
+        )
+        .await;
+    let mut data = relations["structuredContent"]["data"].clone();
+    assert!(data["batchId"].is_string());
+    data.as_object_mut().unwrap().remove("batchId");
+    assert_eq!(
+        data,
+        serde_json::json!({"count": 2, "names": ["Alice", "Acme", "Bob"]})
+    );
+
+    let star = session
+        .call_tool(
+            "create_star_relations",
+            serde_json::json!({
+                "center": "Alice",
+                "others": ["Bob"],
+                "relationType": "knows",
+                "direction": "out",
+                "verbose": false
+            }),
+        )
+        .await;
+    assert_eq!(star["structuredContent"]["data"]["count"], 1);
+    assert!(star["structuredContent"]["data"].get("relations").is_none());
+
+    let conflict = session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "create_relations", "arguments": {
+                "relations": [{"from": "Bob", "to": "Alice", "relationType": "knows"}],
+                "includeEndpoints": true,
+                "verbose": false
+            }}),
+        )
+        .await;
+    assert_eq!(conflict["error"]["code"], -32602);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_shutdown_timeout_bounds_drain() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(
+        port,
+        db_path.to_str().unwrap(),
+        &["--shutdown-timeout", "1"],
+    );
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    // Standalone SSE stream: a connection that never finishes on its own
+    let session = McpSession::connect(port).await;
+    let stream = session
+        .client
+        .get(&session.url)
+        .header("Accept", "text/event-stream")
+        .header("mcp-session-id", &session.session_id)
+        .send()
+        .await
+        .expect("Failed to open SSE stream");
+    assert!(stream.status().is_success());
+
+    let status = Command::new("kill")
+        .args(["-TERM", &server.id().to_string()])
+        .status()
+        .expect("Failed to send SIGTERM");
+    assert!(status.success());
+
+    // Exits after the 1s grace period despite the open stream
+    let start = std::time::Instant::now();
+    loop {
+        if server.try_wait().expect("Failed to poll server").is_some() {
+            break;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Server did not exit after the shutdown timeout"
+        );
+        sleep(Duration::from_millis(100)).await;
+    }
+    drop(stream);
+}
+
+#[tokio::test]
+async fn test_graph_and_entity_resources() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice Smith", "entityType": "person", "observations": ["Engineer"]}
+            ]}),
+        )
+        .await;
+
+    let listed = session
+        .request("resources/list", serde_json::json!({}))
+        .await;
+    assert_eq!(listed["result"]["resources"][0]["uri"], "memory://graph");
+    let templates = session
+        .request("resources/templates/list", serde_json::json!({}))
+        .await;
+    assert_eq!(
+        templates["result"]["resourceTemplates"][0]["uriTemplate"],
+        "memory://entity/{name}"
+    );
+
+    let graph = session
+        .request(
+            "resources/read",
+            serde_json::json!({"uri": "memory://graph"}),
+        )
+        .await;
+    let contents = &graph["result"]["contents"][0];
+    assert_eq!(contents["mimeType"], "application/json");
+    let body: serde_json::Value = serde_json::from_str(contents["text"].as_str().unwrap()).unwrap();
+    assert_eq!(body["entities"][0]["name"], "Alice Smith");
+
+    // Entity names are percent-encoded in the URI
+    let entity = session
+        .request(
+            "resources/read",
+            serde_json::json!({"uri": "memory://entity/Alice%20Smith"}),
+        )
+        .await;
+    let text = entity["result"]["contents"][0]["text"].as_str().unwrap();
+    let body: serde_json::Value = serde_json::from_str(text).unwrap();
+    assert_eq!(body["observations"], serde_json::json!(["Engineer"]));
+
+    let missing = session
+        .request(
+            "resources/read",
+            serde_json::json!({"uri": "memory://entity/Bob"}),
+        )
+        .await;
+    assert_eq!(missing["error"]["code"], -32002);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_prompts() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    let listed = session.request("prompts/list", serde_json::json!({})).await;
+    let names: Vec<&str> = listed["result"]["prompts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["summarize_entity", "extract_knowledge"]);
+
+    let prompt = session
+        .request(
+            "prompts/get",
+            serde_json::json!({"name": "summarize_entity", "arguments": {"name": "Alice"}}),
+        )
+        .await;
+    let text = prompt["result"]["messages"][0]["content"]["text"]
+        .as_str()
+        .unwrap();
+    assert!(text.contains("open_nodes"));
+    assert!(text.contains("'Alice'"));
+
+    let missing = session
+        .request(
+            "prompts/get",
+            serde_json::json!({"name": "extract_knowledge", "arguments": {}}),
+        )
+        .await;
+    assert_eq!(missing["error"]["code"], -32602);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_max_observations_returned_truncates() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["one", "two", "three"]},
+                {"name": "Bob", "entityType": "person", "observations": ["only"]}
+            ]}),
+        )
+        .await;
+
+    let result = session
+        .call_tool(
+            "open_nodes",
+            serde_json::json!({"names": ["Alice", "Bob"], "maxObservationsReturned": 2}),
+        )
+        .await;
+    let entities = result["structuredContent"]["data"]["entities"]
+        .as_array()
+        .unwrap();
+    let alice = entities.iter().find(|e| e["name"] == "Alice").unwrap();
+    let bob = entities.iter().find(|e| e["name"] == "Bob").unwrap();
+    assert_eq!(alice["observations"], serde_json::json!(["one", "two"]));
+    assert_eq!(alice["truncated"], true);
+    assert!(bob.get("truncated").is_none());
+
+    // Without the parameter everything is returned
+    let result = session.call_tool("read_graph", serde_json::json!({})).await;
+    let entities = result["structuredContent"]["data"]["entities"]
+        .as_array()
+        .unwrap();
+    assert!(entities.iter().all(|e| e.get("truncated").is_none()));
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_create_entities_returns_warnings() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    let result = session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["Engineer"]}
+            ]}),
+        )
+        .await;
+    assert!(result["structuredContent"]["data"]
+        .get("warnings")
+        .is_none());
+
+    let result = session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Bob", "entityType": "Person", "observations": ["ok"]}
+            ]}),
+        )
+        .await;
+    assert_eq!(result["isError"], false);
+    let data = &result["structuredContent"]["data"];
+    assert_eq!(data["entities"].as_array().unwrap().len(), 1);
+    assert_eq!(data["warnings"].as_array().unwrap().len(), 2);
+    let text = result["content"][0]["text"].as_str().unwrap();
+    assert!(text.contains("Warnings (2):"));
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_transaction_stages_writes_per_session() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let agent = McpSession::connect(port).await;
+    let observer = McpSession::connect(port).await;
+    let entity_count = |result: serde_json::Value| {
+        result["structuredContent"]["data"]["entities"]
+            .as_array()
+            .unwrap()
+            .len()
+    };
+
+    agent
+        .call_tool("begin_transaction", serde_json::json!({}))
+        .await;
+    let staged = agent
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": []},
+                {"name": "Acme", "entityType": "organization", "observations": []}
+            ]}),
+        )
+        .await;
+    assert_eq!(
+        staged["structuredContent"]["data"]["staged"],
+        "create_entities"
+    );
+    agent
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "Alice", "to": "Acme", "relationType": "works_at"}
+            ]}),
+        )
+        .await;
+
+    // Nothing is visible until commit, in this session or any other
+    let read = agent.call_tool("read_graph", serde_json::json!({})).await;
+    assert_eq!(entity_count(read), 0);
+
+    // The transaction belongs to the agent's session: the observer writes directly
+    observer
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Bob", "entityType": "person", "observations": []}
+            ]}),
+        )
+        .await;
+    let rollback = observer
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "rollback_transaction", "arguments": {}}),
+        )
+        .await;
+    assert_eq!(rollback["error"]["code"], -32602);
+
+    let commit = agent
+        .call_tool("commit_transaction", serde_json::json!({}))
+        .await;
+    let operations = commit["structuredContent"]["data"]["operations"]
+        .as_array()
+        .unwrap();
+    assert_eq!(operations.len(), 2);
+    assert_eq!(operations[1]["op"], "create_relations");
+    let read = observer
+        .call_tool("read_graph", serde_json::json!({}))
+        .await;
+    assert_eq!(entity_count(read), 3);
+
+    // Rolled back writes never land
+    agent
+        .call_tool("begin_transaction", serde_json::json!({}))
+        .await;
+    agent
+        .call_tool(
+            "delete_entities",
+            serde_json::json!({"entity_names": ["Alice", "Acme", "Bob"]}),
+        )
+        .await;
+    let rollback = agent
+        .call_tool("rollback_transaction", serde_json::json!({}))
+        .await;
+    assert_eq!(rollback["structuredContent"]["data"]["discarded"], 1);
+    let read = agent.call_tool("read_graph", serde_json::json!({})).await;
+    assert_eq!(entity_count(read), 3);
+
+    // Writes that cannot be staged are refused instead of bypassing the transaction
+    agent
+        .call_tool("begin_transaction", serde_json::json!({}))
+        .await;
+    let response = agent
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "reverse_relation", "arguments": {
+                "from": "Alice", "to": "Acme", "relationType": "works_at"
+            }}),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert!(response["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("bypass the open transaction"));
+    // ...including read tools with a delete option
+    let response = agent
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "find_empty_entities", "arguments": {
+                "delete": true, "confirm": true
+            }}),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], -32602);
+    agent
+        .call_tool("rollback_transaction", serde_json::json!({}))
+        .await;
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_export_search_results_writes_matches() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Apollo", "entityType": "project", "observations": ["Launch project"]},
+                {"name": "Alice", "entityType": "person", "observations": ["Leads Apollo"]},
+                {"name": "Bob", "entityType": "person", "observations": ["Works on billing"]}
+            ]}),
+        )
+        .await;
+    session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "Alice", "to": "Apollo", "relationType": "leads"},
+                {"from": "Bob", "to": "Apollo", "relationType": "knows_of"}
+            ]}),
+        )
+        .await;
+
+    let result = session
+        .call_tool(
+            "export_search_results",
+            serde_json::json!({"query": "Apollo", "destination": "apollo.json"}),
+        )
+        .await;
+    let data = &result["structuredContent"]["data"];
+    assert_eq!(data["entities"], 2);
+    assert_eq!(data["relations"], 1);
+
+    let written = std::fs::read_to_string(db_dir.path().join("exports").join("apollo.json"))
+        .expect("Export file missing");
+    let graph: serde_json::Value = serde_json::from_str(&written).unwrap();
+    assert_eq!(graph["entities"].as_array().unwrap().len(), 2);
+
+    let empty = session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "export_search_results",
+                "arguments": {"query": " ", "destination": "all.json"}}),
+        )
+        .await;
+    assert_eq!(empty["error"]["code"], -32602);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_restore_checkpoint_requires_confirm() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["Engineer"]}
+            ]}),
+        )
+        .await;
+    let created = session
+        .call_tool("create_checkpoint", serde_json::json!({"name": "base"}))
+        .await;
+    assert_eq!(created["structuredContent"]["data"]["name"], "base");
+    assert!(db_dir.path().join("checkpoints").join("base.db").is_file());
+
+    session
+        .call_tool(
+            "delete_entities",
+            serde_json::json!({"entity_names": ["Alice"]}),
+        )
+        .await;
+
+    let unconfirmed = session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "restore_checkpoint", "arguments": {"name": "base"}}),
+        )
+        .await;
+    assert_eq!(unconfirmed["error"]["code"], -32602);
+
+    session
+        .call_tool(
+            "restore_checkpoint",
+            serde_json::json!({"name": "base", "confirm": true}),
+        )
+        .await;
+    let graph = session.call_tool("read_graph", serde_json::json!({})).await;
+    let entities = graph["structuredContent"]["data"]["entities"]
+        .as_array()
+        .unwrap();
+    assert_eq!(entities.len(), 1);
+
+    let listed = session
+        .call_tool("list_checkpoints", serde_json::json!({}))
+        .await;
+    assert_eq!(
+        listed["structuredContent"]["data"]["checkpoints"][0]["name"],
+        "base"
+    );
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_search_route_streams_matches() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["Rust engineer"]},
+                {"name": "Bob", "entityType": "person", "observations": ["Rust reviewer"]},
+                {"name": "Acme", "entityType": "company", "observations": []}
+            ]}),
+        )
+        .await;
+
+    let response = reqwest::get(format!("http://127.0.0.1:{}/search?q=rust", port))
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let body = response.text().await.expect("Failed to read response");
+    let events: Vec<(&str, serde_json::Value)> = body
+        .split("\n\n")
+        .filter_map(|event| {
+            let name = event.lines().find_map(|l| l.strip_prefix("event: "))?;
+            let data = event.lines().find_map(|l| l.strip_prefix("data: "))?;
+            Some((name, serde_json::from_str(data).unwrap()))
+        })
+        .collect();
+    let mut names: Vec<_> = events
+        .iter()
+        .filter(|(name, _)| *name == "entity")
+        .map(|(_, entity)| entity["name"].as_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Alice", "Bob"]);
+    assert_eq!(
+        events.last().unwrap(),
+        &("done", serde_json::json!({"count": 2}))
+    );
+
+    let response = reqwest::get(format!("http://127.0.0.1:{}/search?q=%20", port))
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 400);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_events_stream_reports_changes() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server(port, db_path.to_str().unwrap());
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let mut events = reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{}/events", port))
+        .send()
+        .await
+        .expect("Failed to open event stream");
+    assert_eq!(
+        events.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": []}
+            ]}),
+        )
+        .await;
+
+    // Read until the change arrives (keep-alive comments may come first)
+    let mut received = String::new();
+    while !received.contains("\n\n") {
+        let chunk = tokio::time::timeout(Duration::from_secs(10), events.chunk())
+            .await
+            .expect("No event within timeout")
+            .expect("Event stream failed")
+            .expect("Event stream ended");
+        received.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    assert!(received.contains("event: change"), "Got: {}", received);
+    assert!(received.contains("id: "), "Got: {}", received);
+    let data = received
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .expect("No data line");
+    let change: serde_json::Value = serde_json::from_str(data).unwrap();
+    assert_eq!(change["type"], "entities_created");
+    assert_eq!(change["names"], serde_json::json!(["Alice"]));
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_tool_audit_file_records_calls() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let audit_path = db_dir.path().join("audit.jsonl");
+    let mut server = start_server_with_args(
+        port,
+        db_path.to_str().unwrap(),
+        &["--tool-audit-file", audit_path.to_str().unwrap()],
+    );
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": ["Engineer"]}
+            ]}),
+        )
+        .await;
+    let long_query = "x".repeat(300);
+    session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "search_nodes", "arguments": {"query": long_query}}),
+        )
+        .await;
+    session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "restore_checkpoint", "arguments": {"name": "base"}}),
+        )
+        .await;
+
+    let audit = std::fs::read_to_string(&audit_path).expect("No audit file");
+    let records: Vec<serde_json::Value> = audit
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Audit line is not JSON"))
+        .collect();
+    assert_eq!(records.len(), 3);
+
+    assert_eq!(records[0]["tool"], "create_entities");
+    assert_eq!(records[0]["success"], true);
+    assert_eq!(records[0]["arguments"]["entities"], "<1 items>");
+    assert!(records[0]["timestamp"].as_str().unwrap().ends_with('Z'));
+    assert!(records[0].get("error").is_none());
+
+    assert_eq!(records[1]["tool"], "search_nodes");
+    let query = records[1]["arguments"]["query"].as_str().unwrap();
+    assert_eq!(query, format!("{}...", "x".repeat(100)));
+    assert_eq!(records[2]["tool"], "restore_checkpoint");
+    assert_eq!(records[2]["success"], false);
+    assert_eq!(records[2]["arguments"]["name"], "base");
+    assert!(records[2]["error"].as_str().unwrap().contains("confirm"));
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_strict_args_rejects_unknown_fields() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(port, db_path.to_str().unwrap(), &["--strict-args"]);
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    // Declared fields pass, including free-form metadata
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [{
+                "name": "Alice",
+                "entityType": "person",
+                "observations": [],
+                "metadata": {"anyKey": 1}
+            }]}),
+        )
+        .await;
+
+    // snake_case instead of the camelCase name: serde alone would ignore it
+    let response = session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "set_entity_description", "arguments": {
+                "entity_name": "Alice",
+                "description": "Engineer"
+            }}),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], -32602);
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(message.contains("'entity_name' (did you mean 'entityName'?)"));
+
+    let response = session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "create_entities", "arguments": {"entities": [
+                {"name": "Bob", "entity_type": "person", "entityType": "person", "observations": [], "note": "x"}
+            ]}}),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(
+        response["error"]["data"]["unknownFields"],
+        serde_json::json!([
+            {"path": "entities[0].entity_type", "suggestion": "entityType"},
+            {"path": "entities[0].note"}
+        ])
+    );
+    let graph = session.call_tool("read_graph", serde_json::json!({})).await;
+    assert_eq!(
+        graph["structuredContent"]["data"]["entities"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_max_response_entities_pages_reads() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(
+        port,
+        db_path.to_str().unwrap(),
+        &["--max-response-entities", "2"],
+    );
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": []},
+                {"name": "Bob", "entityType": "person", "observations": []},
+                {"name": "Carol", "entityType": "person", "observations": []}
+            ]}),
+        )
+        .await;
+    session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "Alice", "to": "Bob", "relationType": "knows"},
+                {"from": "Carol", "to": "Carol", "relationType": "likes"}
+            ]}),
+        )
+        .await;
+
+    let first = session
+        .call_tool("read_graph", serde_json::json!({"sorted": true}))
+        .await;
+    let data = &first["structuredContent"]["data"];
+    let names: Vec<&str> = data["entities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Alice", "Bob"]);
+    assert_eq!(data["relations"].as_array().unwrap().len(), 1);
+    assert_eq!(data["hasMore"], true);
+
+    let second = session
+        .call_tool("next_page", serde_json::json!({"cursor": data["cursor"]}))
+        .await;
+    let data = &second["structuredContent"]["data"];
+    assert_eq!(data["entities"].as_array().unwrap().len(), 1);
+    assert_eq!(data["entities"][0]["name"], "Carol");
+    assert_eq!(data["relations"][0]["relationType"], "likes");
+    assert_eq!(data["hasMore"], false);
+    assert!(data.get("cursor").is_none());
+
+    // Results within the cap are untouched
+    let opened = session
+        .call_tool("open_nodes", serde_json::json!({"names": ["Alice"]}))
+        .await;
+    assert!(opened["structuredContent"]["data"].get("hasMore").is_none());
+
+    let invalid = session
+        .request(
+            "tools/call",
+            serde_json::json!({"name": "next_page", "arguments": {"cursor": "zz"}}),
+        )
+        .await;
+    assert_eq!(invalid["error"]["code"], -32602);
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_max_response_entities_caps_every_read_tool() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(
+        port,
+        db_path.to_str().unwrap(),
+        &["--max-response-entities", "2"],
+    );
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "Alice", "entityType": "person", "observations": []},
+                {"name": "Bob", "entityType": "person", "observations": []},
+                {"name": "Carol", "entityType": "person", "observations": []},
+                {"name": "Dave", "entityType": "person", "observations": []},
+                {"name": "Acme", "entityType": "company", "observations": []}
+            ]}),
+        )
+        .await;
+    session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "Alice", "to": "Acme", "relationType": "works_at"},
+                {"from": "Bob", "to": "Acme", "relationType": "works_at"},
+                {"from": "Carol", "to": "Acme", "relationType": "works_at"}
+            ]}),
+        )
+        .await;
+
+    // Follow next_page to the end, collecting the names in `key` of every page
+    async fn collect_pages(
+        session: &McpSession,
+        tool: &str,
+        arguments: serde_json::Value,
+        key: &str,
+    ) -> Vec<Vec<String>> {
+        let mut result = session.call_tool(tool, arguments).await;
+        let mut pages = Vec::new();
+        loop {
+            let data = &result["structuredContent"]["data"];
+            pages.push(
+                data[key]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|item| {
+                        item.get("name")
+                            .or_else(|| item.get("from"))
+                            .and_then(serde_json::Value::as_str)
+                            .unwrap()
+                            .to_string()
+                    })
+                    .collect(),
+            );
+            if data["hasMore"] != true {
+                break;
+            }
+            assert!(pages.len() < 10, "{} keeps paging: {:?}", tool, pages);
+            result = session
+                .call_tool("next_page", serde_json::json!({"cursor": data["cursor"]}))
+                .await;
+        }
+        pages
+    }
+
+    let pages = collect_pages(
+        &session,
+        "list_entities_by_type",
+        serde_json::json!({"entityType": "person"}),
+        "entities",
+    )
+    .await;
+    assert_eq!(pages, [vec!["Alice", "Bob"], vec!["Carol", "Dave"]]);
+
+    let pages = collect_pages(
+        &session,
+        "search_relations",
+        serde_json::json!({"query": "works_at"}),
+        "relations",
+    )
+    .await;
+    assert_eq!(pages, [vec!["Alice", "Bob"], vec!["Carol"]]);
+
+    // A sample keeps its order across pages, so no entity repeats
+    let pages = collect_pages(
+        &session,
+        "sample_entities",
+        serde_json::json!({"count": 5}),
+        "entities",
+    )
+    .await;
+    assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+    let sampled: HashSet<&String> = pages.iter().flatten().collect();
+    assert_eq!(sampled.len(), 5);
+
+    // A read-only listing of empty entities is capped; the same tool deleting is not
+    let pages = collect_pages(
+        &session,
+        "find_empty_entities",
+        serde_json::json!({"limit": 3}),
+        "entities",
+    )
+    .await;
+    assert_eq!(pages, [vec!["Acme", "Alice"], vec!["Bob"]]);
+
+    // recent_entities is cut in the query: an explicit limit is kept across pages
+    let recent = session
+        .call_tool("recent_entities", serde_json::json!({"limit": 10}))
+        .await;
+    let data = &recent["structuredContent"]["data"];
+    assert_eq!(data["entities"].as_array().unwrap().len(), 2);
+    assert_eq!(data["hasMore"], true);
+
+    // Grouped listings split the cap between the types
+    let grouped = session
+        .call_tool(
+            "list_entities_by_types",
+            serde_json::json!({"types": ["person", "company"]}),
+        )
+        .await;
+    let data = &grouped["structuredContent"]["data"];
+    assert_eq!(data["groups"][0]["entities"].as_array().unwrap().len(), 1);
+    assert_eq!(data["groups"][1]["entities"].as_array().unwrap().len(), 1);
+    assert_eq!(data["limit"], 1);
+    assert_eq!(data["hasMore"], true);
+
+    // Tools without paging arguments are cut after the fact
+    let pages = collect_pages(
+        &session,
+        "traverse",
+        serde_json::json!({"root": "Acme", "depth": 1, "direction": "both"}),
+        "entities",
+    )
+    .await;
+    assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2]);
+
+    // Paged graph reads come from a query slice, relations limited to the page
+    let page = session
+        .call_tool("read_graph", serde_json::json!({"limit": 1, "offset": 1}))
+        .await;
+    let data = &page["structuredContent"]["data"];
+    assert_eq!(data["entities"][0]["name"], "Alice");
+    assert_eq!(data["relations"].as_array().unwrap().len(), 1);
+    assert_eq!(data["total"], 5);
+    assert!(data.get("hasMore").is_none());
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
+
+#[tokio::test]
+async fn test_relation_tools_report_resolved_aliases() {
+    let port = find_available_port();
+    let db_dir = TempDir::new().expect("Failed to create tempdir");
+    let db_path = db_dir.path().join("test.db");
+    let mut server = start_server_with_args(port, db_path.to_str().unwrap(), &[]);
+
+    assert!(
+        wait_for_server(port, 30).await,
+        "Server failed to start within timeout"
+    );
+
+    let session = McpSession::connect(port).await;
+    session
+        .call_tool(
+            "create_entities",
+            serde_json::json!({"entities": [
+                {"name": "United States", "entityType": "country", "observations": []},
+                {"name": "NATO", "entityType": "alliance", "observations": []}
+            ]}),
+        )
+        .await;
+    let added = session
+        .call_tool(
+            "add_aliases",
+            serde_json::json!({"entityName": "United States", "aliases": ["US"]}),
+        )
+        .await;
+    assert_eq!(added["structuredContent"]["data"]["added"][0], "US");
+
+    let created = session
+        .call_tool(
+            "create_relations",
+            serde_json::json!({"relations": [
+                {"from": "US", "to": "NATO", "relationType": "member_of"}
+            ]}),
+        )
+        .await;
+    let data = &created["structuredContent"]["data"];
+    assert_eq!(data["relations"][0]["from"], "United States");
+    assert_eq!(
+        data["resolvedAliases"],
+        serde_json::json!([
+            {"index": 0, "endpoint": "from", "alias": "US", "entity": "United States"}
+        ])
+    );
+
+    let deleted = session
+        .call_tool(
+            "delete_relations",
+            serde_json::json!({"relations": [
+                {"from": "US", "to": "NATO", "relationType": "member_of"}
+            ]}),
+        )
+        .await;
+    let data = &deleted["structuredContent"]["data"];
+    assert_eq!(data["deleted"], 1);
+    assert_eq!(data["resolvedAliases"][0]["alias"], "US");
+
+    let listed = session
+        .call_tool(
+            "list_aliases",
+            serde_json::json!({"entityName": "United States"}),
+        )
+        .await;
+    assert_eq!(
+        listed["structuredContent"]["data"]["aliases"],
+        serde_json::json!(["US"])
+    );
+
+    // Cleanup
+    server.kill().expect("Failed to kill server");
+    let _ = server.wait();
+}
@@ -1,5 +1,21 @@
-use memory_mcp_rs::graph::{Entity, ObservationDeletion, ObservationInput, Relation};
+use memory_mcp_rs::export::{self, DotOptions, ExportFormat};
+use memory_mcp_rs::graph::{
+    ConflictPolicy, Direction, EdgeDirection, Entity, GraphEvent, GraphOperation, KnowledgeGraph,
+    MatchMode, ObservationCount, ObservationDeletion, ObservationInput, ObservationReorder,
+    ObservationValuePage, OperationResult, Relation, ResolvedAlias, SearchHit, SearchProjection,
+    SortOrder, TypeLink,
+};
+use memory_mcp_rs::import::{self, ForeignFormat};
 use memory_mcp_rs::manager::KnowledgeGraphManager;
+use memory_mcp_rs::path::{resolve_db_path, DbPathPolicy};
+use memory_mcp_rs::storage::{
+    is_pool_exhausted, is_query_timeout, validation_failures, CacheSize, DatabaseOptions,
+    DedupOptions, LengthUnit, ObservationFormat, ReadWindow, MAX_PAGE_SIZE,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tempfile::TempDir;
 
 /// Helper to create temp database file with .db extension
@@ -9,6 +25,28 @@ fn create_temp_db() -> (TempDir, std::path::PathBuf) {
     (dir, path)
 }
 
+/// Entity with a name, type and observations and every optional field unset
+fn entity(name: &str, entity_type: &str, observations: &[&str]) -> Entity {
+    Entity {
+        name: name.to_string(),
+        entity_type: entity_type.to_string(),
+        observations: observations.iter().map(|o| o.to_string()).collect(),
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    }
+}
+
+/// Relation of the given type between two entity names
+fn relation(from: &str, to: &str, relation_type: &str) -> Relation {
+    Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    }
+}
+
 #[tokio::test]
 async fn test_create_and_read_entities() {
     let (_dir, path) = create_temp_db();
@@ -19,6 +57,10 @@ async fn test_create_and_read_entities() {
         name: "Alice".to_string(),
         entity_type: "person".to_string(),
         observations: vec!["Works at Acme Corp".to_string()],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
     }];
 
     let created = manager.create_entities(entities).await.unwrap();
@@ -43,11 +85,19 @@ async fn test_create_relations() {
             name: "Alice".to_string(),
             entity_type: "person".to_string(),
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         },
         Entity {
             name: "Acme Corp".to_string(),
             entity_type: "organization".to_string(),
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         },
     ];
     manager.create_entities(entities).await.unwrap();
@@ -84,6 +134,61 @@ async fn test_relation_requires_entities() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn test_validation_dry_run_reports_every_item() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    let checks = manager
+        .check_entities(vec![
+            entity("Dave", "person", &["Engineer"]),
+            entity("", "person", &["Engineer"]),
+            entity("Alice", "person", &["Engineer"]),
+            Entity {
+                confidence: Some(2.0),
+                ..entity("Erin", "person", &["Engineer"])
+            },
+        ])
+        .await
+        .unwrap();
+    let valid: Vec<_> = checks.iter().map(|c| c.valid).collect();
+    assert_eq!(valid, [true, false, true, false]);
+    assert_eq!(checks[3].index, 3);
+    assert!(checks[3].error.as_deref().unwrap().contains("onfidence"));
+    assert!(checks[0].error.is_none());
+
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "knows".to_string(),
+    };
+    let checks = manager
+        .check_relations(
+            vec![
+                relation("Alice", "Carol"),
+                relation("Alice", "Dave"),
+                relation("Alice", "Zed"),
+            ],
+            vec!["Dave".to_string()],
+        )
+        .await
+        .unwrap();
+    let valid: Vec<_> = checks.iter().map(|c| c.valid).collect();
+    assert_eq!(valid, [true, true, false]);
+    assert_eq!(checks[2].name, "Alice -> Zed (knows)");
+    assert!(checks[2]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("'Zed' does not exist"));
+
+    // Nothing was written
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 3);
+    assert_eq!(graph.relations.len(), 3);
+}
+
 #[tokio::test]
 async fn test_deduplication() {
     let (_dir, path) = create_temp_db();
@@ -94,6 +199,10 @@ async fn test_deduplication() {
         name: "Alice".to_string(),
         entity_type: "person".to_string(),
         observations: vec![],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
     };
 
     let created1 = manager.create_entities(vec![entity.clone()]).await.unwrap();
@@ -116,6 +225,10 @@ async fn test_add_observations() {
         name: "Alice".to_string(),
         entity_type: "person".to_string(),
         observations: vec!["Works at Acme".to_string()],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
     };
     manager.create_entities(vec![entity]).await.unwrap();
 
@@ -138,6 +251,143 @@ async fn test_add_observations() {
         .contains(&"Lives in Paris".to_string()));
 }
 
+#[tokio::test]
+async fn test_add_observation_to_many() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Carol".to_string(),
+            contents: vec!["Reviewed in Q1".to_string()],
+        }])
+        .await
+        .unwrap();
+
+    let names = ["Alice", "Bob", "Carol", "Dave", "Alice"]
+        .map(String::from)
+        .to_vec();
+    let result = manager
+        .add_observation_to_many(names, "Reviewed in Q1".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result.added, vec!["Alice", "Bob"]);
+    assert_eq!(result.already_present, vec!["Carol"]);
+    assert_eq!(result.missing, vec!["Dave"]);
+
+    let graph = manager.read_graph().await.unwrap();
+    for entity in &graph.entities {
+        let count = entity
+            .observations
+            .iter()
+            .filter(|o| *o == "Reviewed in Q1")
+            .count();
+        assert_eq!(count, 1, "{}", entity.name);
+    }
+
+    // An invalid name fails the whole call before anything is written
+    assert!(manager
+        .add_observation_to_many(
+            vec!["Alice".to_string(), "".to_string()],
+            "x y z".to_string()
+        )
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_has_observation() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Lives in  Paris".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    let check = |name: &str, observation: &str| {
+        manager.has_observation(name.to_string(), observation.to_string())
+    };
+
+    let exact = check("Alice", "Lives in  Paris").await.unwrap();
+    assert!(exact.entity_exists && exact.found);
+    assert_eq!(exact.matched.as_deref(), Some("Lives in  Paris"));
+
+    let normalized = check("Alice", " lives IN paris ").await.unwrap();
+    assert!(normalized.found);
+    assert_eq!(normalized.matched.as_deref(), Some("Lives in  Paris"));
+
+    let absent = check("Alice", "Lives in London").await.unwrap();
+    assert!(absent.entity_exists && !absent.found);
+    assert_eq!(absent.matched, None);
+
+    let missing = check("Zed", "Lives in Paris").await.unwrap();
+    assert!(!missing.entity_exists && !missing.found);
+}
+
+#[tokio::test]
+async fn test_copy_observations() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![
+            entity("Widget_v1", "product", &["Ships in blue", "Made in Lyon"]),
+            entity("Widget_v2", "product", &["Made in Lyon"]),
+        ])
+        .await
+        .unwrap();
+
+    let result = manager
+        .copy_observations("Widget_v1".to_string(), "Widget_v2".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result.entity_name, "Widget_v2");
+    assert_eq!(result.added_observations, vec!["Ships in blue"]);
+
+    let graph = manager
+        .open_nodes(vec!["Widget_v1".to_string(), "Widget_v2".to_string()])
+        .await
+        .unwrap();
+    let observations = |name: &str| {
+        graph
+            .entities
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap()
+            .observations
+            .clone()
+    };
+    assert_eq!(
+        observations("Widget_v1"),
+        vec!["Ships in blue", "Made in Lyon"]
+    );
+    assert_eq!(
+        observations("Widget_v2"),
+        vec!["Made in Lyon", "Ships in blue"]
+    );
+
+    // Copying again adds nothing; self-copies and missing entities are errors
+    let again = manager
+        .copy_observations("Widget_v1".to_string(), "Widget_v2".to_string())
+        .await
+        .unwrap();
+    assert!(again.added_observations.is_empty());
+    assert!(manager
+        .copy_observations("Widget_v1".to_string(), "Widget_v1".to_string())
+        .await
+        .is_err());
+    assert!(manager
+        .copy_observations("Widget_v1".to_string(), "Widget_v3".to_string())
+        .await
+        .is_err());
+}
+
 #[tokio::test]
 async fn test_cascade_delete() {
     let (_dir, path) = create_temp_db();
@@ -150,11 +400,19 @@ async fn test_cascade_delete() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -193,6 +451,10 @@ async fn test_delete_observations() {
             name: "Alice".to_string(),
             entity_type: "person".to_string(),
             observations: vec!["Works at Acme".to_string(), "Lives in Paris".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await
         .unwrap();
@@ -202,6 +464,7 @@ async fn test_delete_observations() {
         .delete_observations(vec![ObservationDeletion {
             entity_name: "Alice".to_string(),
             observations: vec!["Lives in Paris".to_string()],
+            indices: vec![],
         }])
         .await
         .unwrap();
@@ -223,11 +486,19 @@ async fn test_delete_relations() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -270,11 +541,19 @@ async fn test_search_nodes() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Lives in Paris".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Lives in London".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -311,16 +590,28 @@ async fn test_open_nodes() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Charlie".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -351,6 +642,10 @@ async fn test_persistence() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Test".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             }])
             .await
             .unwrap();
@@ -379,6 +674,10 @@ async fn test_validation_empty_entity_name() {
             name: "".to_string(), // Empty name
             entity_type: "person".to_string(),
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await;
 
@@ -397,6 +696,10 @@ async fn test_validation_entity_name_too_long() {
             name: long_name,
             entity_type: "person".to_string(),
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await;
 
@@ -404,6 +707,124 @@ async fn test_validation_entity_name_too_long() {
     assert!(result.unwrap_err().to_string().contains("too long"));
 }
 
+#[tokio::test]
+async fn test_max_entity_name_length_applies_to_new_entities_only() {
+    let (_dir, path) = create_temp_db();
+
+    // Created under the default limit
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    manager
+        .create_entities(vec![entity("Bartholomew", "person", &[])])
+        .await
+        .unwrap();
+    drop(manager);
+
+    let options = DatabaseOptions {
+        max_entity_name_length: Some(8),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    let err = manager
+        .create_entities(vec![entity("Alexandra", "person", &[])])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too long (9 bytes, max 8 bytes)"));
+    manager
+        .create_entities(vec![entity("Alice", "person", &[])])
+        .await
+        .unwrap();
+
+    // The longer existing name is still a valid lookup key and relation endpoint
+    assert_eq!(
+        manager
+            .open_nodes(vec!["Bartholomew".to_string()])
+            .await
+            .unwrap()
+            .entities
+            .len(),
+        1
+    );
+    let relation = Relation {
+        from: "Alice".to_string(),
+        to: "Bartholomew".to_string(),
+        relation_type: "knows".to_string(),
+    };
+    assert_eq!(
+        manager
+            .create_relations(vec![relation])
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // The configured limit cannot exceed the hard one
+    for max in [0, 257] {
+        let options = DatabaseOptions {
+            max_entity_name_length: Some(max),
+            ..Default::default()
+        };
+        assert!(KnowledgeGraphManager::with_options(path.clone(), options).is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_length_unit_for_multibyte_text() {
+    let (_dir, path) = create_temp_db();
+    // 100 characters, 300 bytes
+    let name = "東".repeat(100);
+    let long_entity = || Entity {
+        observations: vec!["é".repeat(3000)],
+        description: Some("ß".repeat(2000)),
+        ..entity(&name, "city", &[])
+    };
+
+    // Default: bytes, and the error says so
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    let err = manager
+        .create_entities(vec![
+            long_entity(),
+            Entity {
+                name: "Observed".to_string(),
+                description: None,
+                ..long_entity()
+            },
+            Entity {
+                name: "Described".to_string(),
+                observations: vec![],
+                ..long_entity()
+            },
+        ])
+        .await
+        .unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("Entity name too long (300 bytes, max 256 bytes)"));
+    assert!(message.contains("Observation too long (6000 bytes, max 4096 bytes)"));
+    assert!(message.contains("Description too long (4000 bytes, max 2048 bytes)"));
+    drop(manager);
+
+    let options = DatabaseOptions {
+        length_unit: LengthUnit::Chars,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    manager.create_entities(vec![long_entity()]).await.unwrap();
+    let err = manager
+        .create_entities(vec![Entity {
+            name: "東".repeat(257),
+            ..long_entity()
+        }])
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("(257 chars, max 256 chars)"));
+    drop(manager);
+
+    // Still a valid lookup key when limits count bytes again
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let graph = manager.open_nodes(vec![name.clone()]).await.unwrap();
+    assert_eq!(graph.entities.len(), 1);
+}
+
 #[tokio::test]
 async fn test_validation_entity_name_invalid_chars() {
     let (_dir, path) = create_temp_db();
@@ -415,6 +836,10 @@ async fn test_validation_entity_name_invalid_chars() {
             name: "Alice\0Bob".to_string(),
             entity_type: "person".to_string(),
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await;
 
@@ -436,6 +861,10 @@ async fn test_validation_entity_type_invalid_chars() {
             name: "Alice".to_string(),
             entity_type: "per son".to_string(), // Space not allowed
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await;
 
@@ -457,6 +886,10 @@ async fn test_validation_observation_too_long() {
             name: "Alice".to_string(),
             entity_type: "person".to_string(),
             observations: vec![long_obs],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await;
 
@@ -476,11 +909,19 @@ async fn test_validation_relation_type_valid() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -512,11 +953,19 @@ async fn test_fts5_phrase_search() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Works at Acme Corporation".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Works for different company".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -542,11 +991,19 @@ async fn test_fts5_multi_word_search() {
                 name: "Alice".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Senior software engineer at Google".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
             Entity {
                 name: "Bob".to_string(),
                 entity_type: "person".to_string(),
                 observations: vec!["Junior developer at Microsoft".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
             },
         ])
         .await
@@ -561,6 +1018,174 @@ async fn test_fts5_multi_word_search() {
     assert_eq!(result.entities[0].name, "Alice");
 }
 
+#[tokio::test]
+async fn test_observation_times_follow_writes() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_with_observations(&manager, &["First", "Second"]).await;
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Third".to_string()],
+        }])
+        .await
+        .unwrap();
+
+    let times = manager
+        .observation_times("Alice".to_string(), false)
+        .await
+        .unwrap();
+    let texts: Vec<_> = times.observations.iter().map(|o| o.text.as_str()).collect();
+    assert_eq!(texts, ["First", "Second", "Third"]);
+    assert!(times.observations.iter().all(|o| o.added_at.is_some()));
+    assert!(times.observations[2].added_at > times.observations[0].added_at);
+    let created_at = times.observations[0].added_at.clone();
+
+    // Newest first; reordering keeps the times, deleting drops them
+    let newest = manager
+        .observation_times("Alice".to_string(), true)
+        .await
+        .unwrap();
+    assert_eq!(newest.observations[0].text, "Third");
+    manager
+        .reorder_observations(vec![ObservationReorder {
+            entity_name: "Alice".to_string(),
+            order: vec![1],
+        }])
+        .await
+        .unwrap();
+    manager
+        .delete_observations(vec![ObservationDeletion {
+            entity_name: "Alice".to_string(),
+            observations: vec!["Third".to_string()],
+            indices: vec![],
+        }])
+        .await
+        .unwrap();
+    let times = manager
+        .observation_times("Alice".to_string(), false)
+        .await
+        .unwrap();
+    let texts: Vec<_> = times.observations.iter().map(|o| o.text.as_str()).collect();
+    assert_eq!(texts, ["Second", "First"]);
+    assert_eq!(times.observations[1].added_at, created_at);
+
+    assert!(manager
+        .observation_times("Nobody".to_string(), false)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_stream_search_yields_each_match() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Carol".to_string(),
+            contents: vec!["Plays chess".to_string()],
+        }])
+        .await
+        .unwrap();
+
+    let mut matches = manager.stream_search("chess".to_string(), MatchMode::All);
+    let mut names = Vec::new();
+    while let Some(entity) = matches.recv().await {
+        names.push(entity.unwrap().name);
+    }
+    assert_eq!(names, ["Carol"]);
+
+    // A failing search ends the stream with its error
+    let mut matches = manager.stream_search("   ".to_string(), MatchMode::All);
+    assert!(matches.recv().await.unwrap().is_err());
+    assert!(matches.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn test_stream_search_pages_without_holding_connections() {
+    for no_fts in [false, true] {
+        let (_dir, path) = create_temp_db();
+        let options = DatabaseOptions {
+            no_fts,
+            pool_timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+        let entities: Vec<Entity> = (0..150)
+            .map(|i| {
+                let game = if i % 2 == 0 {
+                    "Plays chess"
+                } else {
+                    "Plays go"
+                };
+                entity(&format!("Player_{:03}", i), "person", &[game])
+            })
+            .collect();
+        manager.create_entities(entities).await.unwrap();
+
+        // Several pages of matches, in name order and each exactly once
+        let mut matches = manager.stream_search("chess go".to_string(), MatchMode::Any);
+        let mut names = Vec::new();
+        while let Some(entity) = matches.recv().await {
+            names.push(entity.unwrap().name);
+        }
+        let expected: Vec<_> = (0..150).map(|i| format!("Player_{:03}", i)).collect();
+        assert_eq!(names, expected, "no_fts={}", no_fts);
+
+        // Unread streams hold no connection while they wait for their reader
+        let streams: Vec<_> = (0..15)
+            .map(|_| manager.stream_search("chess".to_string(), MatchMode::All))
+            .collect();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(manager.read_graph().await.unwrap().entities.len(), 150);
+        drop(streams);
+    }
+}
+
+#[tokio::test]
+async fn test_fts5_operator_only_queries_match_nothing() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Lives near the sea".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+
+    for query in ["*", "()", "\"", "( ) * -"] {
+        let result = manager.search_nodes(Some(query.to_string())).await.unwrap();
+        assert!(result.entities.is_empty(), "query {:?} matched", query);
+        assert!(manager
+            .search_relations(query.to_string(), None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    // Reserved words are searched as plain words
+    let result = manager
+        .search_nodes(Some("NEAR".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+
+    // Punctuation-only terms no longer spoil an OR search
+    let result = manager
+        .search_nodes_matching(Some("alice ()".to_string()), MatchMode::Any)
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+}
+
 // ============================================================================
 // PATH VALIDATION TESTS
 // ============================================================================
@@ -586,6 +1211,58 @@ fn test_path_validation_valid_extension() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_resolve_db_path_policy() {
+    let tmp_dir = TempDir::new().unwrap();
+    let root = tmp_dir.path().canonicalize().unwrap();
+    let allowed = root.join("allowed");
+    let create = DbPathPolicy {
+        create_dirs: true,
+        allowed_dir: None,
+    };
+
+    // '..' is resolved and missing directories are created
+    let resolved = resolve_db_path(&root.join("a/b/../graph.db"), &create).unwrap();
+    assert_eq!(resolved, root.join("a/graph.db"));
+    assert!(root.join("a").is_dir());
+
+    let strict = DbPathPolicy {
+        create_dirs: false,
+        allowed_dir: None,
+    };
+    let err = resolve_db_path(&root.join("missing/graph.db"), &strict).unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+    assert!(resolve_db_path(&root.join("a/graph.txt"), &create).is_err());
+
+    std::fs::create_dir(&allowed).unwrap();
+    let contained = DbPathPolicy {
+        create_dirs: true,
+        allowed_dir: Some(allowed.clone()),
+    };
+    assert!(resolve_db_path(&allowed.join("sub/graph.db"), &contained).is_ok());
+    let err = resolve_db_path(&allowed.join("../escape.db"), &contained).unwrap_err();
+    assert!(err.to_string().contains("outside the allowed directory"));
+
+    // A symlink inside the allowed directory may not lead out of it
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&root, allowed.join("link")).unwrap();
+        assert!(resolve_db_path(&allowed.join("link/graph.db"), &contained).is_err());
+        // ...and nothing is created out there before the containment check fails
+        assert!(resolve_db_path(&allowed.join("link/made/graph.db"), &contained).is_err());
+        assert!(!root.join("made").exists());
+    }
+
+    let options = DatabaseOptions {
+        allowed_db_dir: Some(allowed),
+        ..Default::default()
+    };
+    let Err(err) = KnowledgeGraphManager::with_options(root.join("graph.db"), options) else {
+        panic!("database outside the allowed directory opened");
+    };
+    assert!(err.to_string().contains("outside the allowed directory"));
+}
+
 // ============================================================================
 // ERROR CONTEXT TESTS
 // ============================================================================
@@ -620,6 +1297,10 @@ async fn test_error_context_relation_missing_entities() {
             name: "Alice".to_string(),
             entity_type: "person".to_string(),
             observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
         }])
         .await
         .unwrap();
@@ -639,3 +1320,5156 @@ async fn test_error_context_relation_missing_entities() {
     assert!(err_msg.contains("Bob")); // To entity
     assert!(err_msg.contains("does not exist") || err_msg.contains("do not exist"));
 }
+
+// ============================================================================
+// TYPE ALLOWLIST TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_relation_type_allowlist() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        relation_types: Some(["knows".to_string(), "works_at".to_string()].into()),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    manager
+        .create_entities(vec![
+            Entity {
+                name: "Alice".to_string(),
+                entity_type: "person".to_string(),
+                observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            },
+            Entity {
+                name: "Bob".to_string(),
+                entity_type: "person".to_string(),
+                observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            },
+        ])
+        .await
+        .unwrap();
+
+    // Allowed type is accepted
+    let created = manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Bob".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+    assert_eq!(created.len(), 1);
+
+    // Unknown type is rejected with the permitted values listed
+    let result = manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Bob".to_string(),
+            relation_type: "is_acquainted_with".to_string(),
+        }])
+        .await;
+    let err_msg = result.unwrap_err().to_string();
+    assert!(err_msg.contains("is_acquainted_with"));
+    assert!(err_msg.contains("permitted: knows, works_at"));
+}
+
+#[tokio::test]
+async fn test_disallow_self_relations() {
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "likes".to_string(),
+    };
+
+    // Permissive by default
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    assert!(manager
+        .read_graph()
+        .await
+        .unwrap()
+        .relations
+        .contains(&relation("Alice", "Alice")));
+
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        disallow_self_relations: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    manager
+        .create_entities(
+            ["Alice", "Bob"]
+                .iter()
+                .map(|n| Entity {
+                    name: n.to_string(),
+                    entity_type: "person".to_string(),
+                    observations: vec![],
+                    description: None,
+                    id: None,
+                    confidence: None,
+                    metadata: None,
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    // The whole batch is rejected, naming the loop
+    let err = manager
+        .create_relations(vec![relation("Alice", "Bob"), relation("Bob", "Bob")])
+        .await
+        .unwrap_err();
+    let failures = validation_failures(&err).unwrap();
+    assert_eq!(failures.failures.len(), 1);
+    assert_eq!(failures.failures[0].index, 1);
+    assert!(err.to_string().contains("not allowed"));
+    assert!(manager.read_graph().await.unwrap().relations.is_empty());
+
+    manager
+        .create_relations(vec![relation("Alice", "Bob")])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_entity_type_allowlist() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        entity_types: Some(["person".to_string()].into()),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    let result = manager
+        .create_entities(vec![Entity {
+            name: "Acme".to_string(),
+            entity_type: "organization".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await;
+    let err_msg = result.unwrap_err().to_string();
+    assert!(err_msg.contains("not allowed"));
+    assert!(err_msg.contains("person"));
+
+    // Nothing was written
+    let graph = manager.read_graph().await.unwrap();
+    assert!(graph.entities.is_empty());
+}
+
+#[test]
+fn test_allowlist_rejects_invalid_entries() {
+    let tmp_dir = TempDir::new().unwrap();
+    let options = DatabaseOptions {
+        relation_types: Some(["works at".to_string()].into()), // Space not allowed in types
+        ..Default::default()
+    };
+
+    let result = KnowledgeGraphManager::with_options(tmp_dir.path().join("test.db"), options);
+    assert!(result.is_err());
+}
+
+// ============================================================================
+// OBSERVATION ORDERING TESTS
+// ============================================================================
+
+/// Helper: create a single entity with the given observations
+async fn create_with_observations(manager: &KnowledgeGraphManager, observations: &[&str]) {
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: observations.iter().map(|s| s.to_string()).collect(),
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_observations_by_index() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_with_observations(&manager, &["first", "second", "third"]).await;
+
+    manager
+        .delete_observations(vec![ObservationDeletion {
+            entity_name: "Alice".to_string(),
+            observations: vec![],
+            indices: vec![0, 2],
+        }])
+        .await
+        .unwrap();
+
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities[0].observations, vec!["second".to_string()]);
+
+    // Out-of-range index is rejected
+    let result = manager
+        .delete_observations(vec![ObservationDeletion {
+            entity_name: "Alice".to_string(),
+            observations: vec![],
+            indices: vec![5],
+        }])
+        .await;
+    assert!(result.unwrap_err().to_string().contains("out of range"));
+}
+
+#[tokio::test]
+async fn test_reorder_observations() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_with_observations(&manager, &["a", "b", "c", "d"]).await;
+
+    // Move-to-front: listed indices first, rest keep relative order
+    let results = manager
+        .reorder_observations(vec![ObservationReorder {
+            entity_name: "Alice".to_string(),
+            order: vec![2],
+        }])
+        .await
+        .unwrap();
+    assert_eq!(results[0].observations, vec!["c", "a", "b", "d"]);
+
+    // Full permutation
+    manager
+        .reorder_observations(vec![ObservationReorder {
+            entity_name: "Alice".to_string(),
+            order: vec![3, 2, 1, 0],
+        }])
+        .await
+        .unwrap();
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities[0].observations, vec!["d", "b", "a", "c"]);
+
+    // Duplicate indices are rejected
+    let result = manager
+        .reorder_observations(vec![ObservationReorder {
+            entity_name: "Alice".to_string(),
+            order: vec![1, 1],
+        }])
+        .await;
+    assert!(result.unwrap_err().to_string().contains("Duplicate"));
+}
+
+// ============================================================================
+// HEALTH CHECK TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_health_check() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager.health_check().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_search_index_check_and_rebuild() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    manager
+        .create_entities(vec![entity("Alice", "person", &["Plays chess"])])
+        .await
+        .unwrap();
+    let counts = manager.search_index_counts().await.unwrap();
+    assert!(counts.iter().all(|c| c.is_consistent()));
+
+    // Without the insert trigger new entities never reach the index
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch("DROP TRIGGER entities_ai;")
+        .unwrap();
+    manager
+        .create_entities(vec![entity("Bob", "person", &["Plays chess"])])
+        .await
+        .unwrap();
+
+    let counts = manager.search_index_counts().await.unwrap();
+    let entities = counts.iter().find(|c| c.table == "entities").unwrap();
+    assert_eq!((entities.rows, entities.indexed), (2, 1));
+    let err = manager.health_check().await.unwrap_err();
+    assert!(err.to_string().contains("Full-text index of entities"));
+
+    manager.rebuild_search_index().await.unwrap();
+    let counts = manager.search_index_counts().await.unwrap();
+    assert!(counts.iter().all(|c| c.is_consistent()));
+    let found = manager.search_nodes(Some("Bob".to_string())).await.unwrap();
+    assert_eq!(found.entities.len(), 1);
+    manager.health_check().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_search_index_holds_observation_text_only() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        min_query_length: 1,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options.clone()).unwrap();
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec![
+                "Line one\nLine two".to_string(),
+                "Said \"hello\"".to_string(),
+            ],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+
+    let count = |query: &str| {
+        let query = query.to_string();
+        let manager = &manager;
+        async move {
+            manager
+                .search_nodes(Some(query))
+                .await
+                .unwrap()
+                .entities
+                .len()
+        }
+    };
+    assert_eq!(count("hello").await, 1);
+    assert_eq!(count("two").await, 1);
+    // JSON punctuation and escapes ("\nLine" would be the token "nline") are not indexed
+    assert_eq!(count("[").await, 0);
+    assert_eq!(count("nline").await, 0);
+    drop(manager);
+
+    // Databases indexed from the raw JSON array are reindexed as text on open
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "DROP TRIGGER entities_ai;
+             CREATE TRIGGER entities_ai AFTER INSERT ON entities BEGIN
+                 INSERT INTO entities_fts(rowid, name, entity_type, observations, description)
+                 VALUES (new.rowid, new.name, new.entity_type, new.observations, new.description);
+             END;
+             INSERT INTO entities_fts(entities_fts) VALUES ('rebuild');",
+        )
+        .unwrap();
+    let polluted: i64 = rusqlite::Connection::open(&path)
+        .unwrap()
+        .query_row(
+            "SELECT COUNT(*) FROM entities_fts WHERE entities_fts MATCH '\"nline\"'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(polluted, 1);
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    let found = manager
+        .search_nodes(Some("nline".to_string()))
+        .await
+        .unwrap();
+    assert!(found.entities.is_empty());
+    let found = manager
+        .search_nodes(Some("hello".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+    let counts = manager.search_index_counts().await.unwrap();
+    assert!(counts.iter().all(|c| c.is_consistent()));
+}
+
+#[tokio::test]
+async fn test_no_create_dirs_requires_existing_directory() {
+    let (dir, _path) = create_temp_db();
+    let nested = dir.path().join("missing").join("graph.db");
+    let options = DatabaseOptions {
+        no_create_dirs: true,
+        ..Default::default()
+    };
+    let Err(err) = KnowledgeGraphManager::with_options(nested.clone(), options.clone()) else {
+        panic!("missing directory created despite no_create_dirs");
+    };
+    assert!(err.to_string().contains("does not exist"));
+    assert!(!dir.path().join("missing").exists());
+
+    // An existing directory is fine
+    std::fs::create_dir(dir.path().join("missing")).unwrap();
+    KnowledgeGraphManager::with_options(nested, options).unwrap();
+}
+
+#[tokio::test]
+async fn test_search_nodes_scored_ranks_by_relevance() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![
+            entity("Passing", "note", &["Once visited Rome"]),
+            entity("Fan", "note", &["Rome rome Rome", "Loves Rome"]),
+            entity("Other", "note", &["Lives in Paris"]),
+        ])
+        .await
+        .unwrap();
+
+    let results = manager
+        .search_nodes_scored("rome".to_string(), MatchMode::All, None)
+        .await
+        .unwrap();
+    let names: Vec<&str> = results.iter().map(|r| r.entity.name.as_str()).collect();
+    assert_eq!(names, vec!["Fan", "Passing"]);
+    assert!(results[0].score > results[1].score);
+    assert_eq!(results[0].entity.observations.len(), 2);
+
+    let err = manager
+        .search_nodes_scored("  ".to_string(), MatchMode::All, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("needs a query"));
+}
+
+#[tokio::test]
+async fn test_no_fts_falls_back_to_like_search() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        no_fts: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Bob".to_string(),
+            contents: vec!["Plays 100% chess".to_string()],
+        }])
+        .await
+        .unwrap();
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let fts_objects: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name LIKE '%_fts%'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(fts_objects, 0);
+
+    // Substrings match, in any field; LIKE wildcards in the query are literal
+    let found = manager.search_nodes(Some("hes".to_string())).await.unwrap();
+    assert_eq!(found.entities.len(), 1);
+    assert_eq!(found.entities[0].name, "Bob");
+    let found = manager
+        .search_nodes(Some("100%".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+    assert!(manager
+        .search_nodes(Some("1_0".to_string()))
+        .await
+        .unwrap()
+        .entities
+        .is_empty());
+    let found = manager
+        .search_nodes_matching(Some("alice carol".to_string()), MatchMode::Any)
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 2);
+    assert_eq!(found.relations.len(), 2);
+    let relations = manager
+        .search_relations("manag".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(relations.len(), 1);
+
+    assert!(manager.search_index_counts().await.is_err());
+    manager.health_check().await.unwrap();
+    drop(manager);
+
+    // Opening with full-text search again indexes everything written meanwhile
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let found = manager
+        .search_nodes(Some("chess".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+    let counts = manager.search_index_counts().await.unwrap();
+    assert!(counts.iter().all(|c| c.is_consistent() && c.rows > 0));
+}
+
+#[tokio::test]
+async fn test_dangling_relations_materialize() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    create_neighborhood(&manager).await;
+    assert!(manager
+        .dangling_relations()
+        .await
+        .unwrap()
+        .relations
+        .is_empty());
+
+    // Write the way a bulk importer with foreign keys turned off would
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             INSERT INTO relations (from_entity, to_entity, relation_type)
+             VALUES ('Alice', 'Ghost', 'haunts'), ('Ghost', 'Phantom', 'knows');",
+        )
+        .unwrap();
+
+    let dangling = manager.dangling_relations().await.unwrap();
+    assert_eq!(dangling.relations.len(), 2);
+    assert_eq!(dangling.missing, vec!["Ghost", "Phantom"]);
+
+    let created = manager
+        .materialize_dangling("placeholder".to_string())
+        .await
+        .unwrap();
+    let names: Vec<_> = created.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Ghost", "Phantom"]);
+    assert!(created
+        .iter()
+        .all(|e| e.entity_type == "placeholder" && e.id.is_some()));
+
+    // The relations survive and now connect real entities
+    assert!(manager
+        .dangling_relations()
+        .await
+        .unwrap()
+        .relations
+        .is_empty());
+    let graph = manager.open_nodes(vec!["Ghost".to_string()]).await.unwrap();
+    assert_eq!(graph.entities.len(), 1);
+    let graph = manager.read_graph().await.unwrap();
+    assert!(graph.relations.iter().any(|r| r.relation_type == "haunts"));
+    assert!(manager
+        .materialize_dangling("placeholder".to_string())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_prewarm_open() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        prewarm: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    // Primed statements behave exactly like freshly compiled ones
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Likes tea".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    let found = manager.search_nodes(Some("tea".to_string())).await.unwrap();
+    assert_eq!(found.entities.len(), 1);
+    assert!(manager.health_check().await.is_ok());
+}
+
+#[test]
+fn test_parse_cache_size() {
+    assert_eq!("2000".parse::<CacheSize>().unwrap(), CacheSize::Pages(2000));
+    assert_eq!(
+        "65536k".parse::<CacheSize>().unwrap(),
+        CacheSize::Kibibytes(65536)
+    );
+    assert_eq!(
+        "64MiB".parse::<CacheSize>().unwrap(),
+        CacheSize::Kibibytes(64 * 1024)
+    );
+    assert!("0".parse::<CacheSize>().is_err());
+    assert!("-5".parse::<CacheSize>().is_err());
+    assert!("lots".parse::<CacheSize>().is_err());
+}
+
+#[tokio::test]
+async fn test_cache_and_mmap_tuning_open() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        cache_size: Some(CacheSize::Kibibytes(16 * 1024)),
+        mmap_size: Some(64 * 1024 * 1024),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Likes tea".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    let found = manager.search_nodes(Some("tea".to_string())).await.unwrap();
+    assert_eq!(found.entities.len(), 1);
+
+    let options = DatabaseOptions {
+        mmap_size: Some(u64::MAX),
+        ..Default::default()
+    };
+    assert!(KnowledgeGraphManager::with_options(path, options).is_err());
+}
+
+// ============================================================================
+// AGGREGATE QUERY TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_relation_matrix() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &[]),
+            entity("Bob", "person", &[]),
+            entity("Acme", "organization", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "Acme", "works_at"),
+            relation("Bob", "Acme", "works_at"),
+            relation("Alice", "Bob", "knows"),
+        ])
+        .await
+        .unwrap();
+
+    let matrix = manager.relation_matrix().await.unwrap();
+    assert_eq!(matrix.len(), 2);
+
+    // Most frequent pattern first
+    assert_eq!(matrix[0].from_entity_type, "person");
+    assert_eq!(matrix[0].relation_type, "works_at");
+    assert_eq!(matrix[0].to_entity_type, "organization");
+    assert_eq!(matrix[0].count, 2);
+
+    assert_eq!(matrix[1].relation_type, "knows");
+    assert_eq!(matrix[1].to_entity_type, "person");
+    assert_eq!(matrix[1].count, 1);
+}
+
+#[tokio::test]
+async fn test_infer_ontology() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &[]),
+            entity("Bob", "person", &[]),
+            entity("Acme", "organization", &[]),
+            entity("Lonely", "note", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "Acme", "works_at"),
+            relation("Bob", "Acme", "works_at"),
+            relation("Alice", "Bob", "knows"),
+        ])
+        .await
+        .unwrap();
+
+    let ontology = manager.infer_ontology().await.unwrap();
+    let types: Vec<_> = ontology.iter().map(|t| t.entity_type.as_str()).collect();
+    assert_eq!(types, vec!["note", "organization", "person"]);
+
+    // Types without relations are still listed
+    assert_eq!(ontology[0].entities, 1);
+    assert!(ontology[0].outgoing.is_empty() && ontology[0].incoming.is_empty());
+
+    let organization = &ontology[1];
+    assert!(organization.outgoing.is_empty());
+    assert_eq!(
+        organization.incoming,
+        vec![TypeLink {
+            relation_type: "works_at".to_string(),
+            entity_type: "person".to_string(),
+            count: 2,
+        }]
+    );
+
+    let person = &ontology[2];
+    assert_eq!(person.entities, 2);
+    let outgoing: Vec<_> = person
+        .outgoing
+        .iter()
+        .map(|l| (l.relation_type.as_str(), l.entity_type.as_str(), l.count))
+        .collect();
+    assert_eq!(
+        outgoing,
+        vec![("works_at", "organization", 2), ("knows", "person", 1)]
+    );
+    assert_eq!(person.incoming.len(), 1);
+    assert_eq!(person.incoming[0].relation_type, "knows");
+}
+
+#[tokio::test]
+async fn test_read_adjacency() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager
+        .create_entities(vec![
+            Entity {
+                name: "Alice".to_string(),
+                entity_type: "person".to_string(),
+                observations: vec!["Lives in Paris".to_string()],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            },
+            Entity {
+                name: "Acme".to_string(),
+                entity_type: "organization".to_string(),
+                observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            },
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Acme".to_string(),
+            relation_type: "works_at".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    let adjacency = manager.read_adjacency().await.unwrap();
+    assert_eq!(adjacency.len(), 2);
+
+    let alice = &adjacency["Alice"];
+    assert_eq!(alice.entity_type, "person");
+    assert_eq!(alice.observations, vec!["Lives in Paris".to_string()]);
+    assert_eq!(alice.edges.len(), 1);
+    assert_eq!(alice.edges[0].to, "Acme");
+    assert_eq!(alice.edges[0].relation_type, "works_at");
+
+    // Only outgoing edges are listed
+    assert!(adjacency["Acme"].edges.is_empty());
+}
+
+#[tokio::test]
+async fn test_graph_metrics() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    // Empty graph: no division by zero
+    let empty = manager.graph_metrics().await.unwrap();
+    assert_eq!(empty.entity_count, 0);
+    assert_eq!(empty.density, 0.0);
+    assert_eq!(empty.average_degree, 0.0);
+
+    // Alice, Bob, Carol + 3 relations (one self-loop), plus an orphan
+    create_neighborhood(&manager).await;
+    manager
+        .create_entities(vec![Entity {
+            name: "Dave".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+
+    let metrics = manager.graph_metrics().await.unwrap();
+    assert_eq!(metrics.entity_count, 4);
+    assert_eq!(metrics.relation_count, 3);
+    assert_eq!(metrics.orphan_count, 1);
+    assert!((metrics.density - 3.0 / 12.0).abs() < 1e-9);
+    assert!((metrics.average_degree - 1.5).abs() < 1e-9);
+}
+
+// ============================================================================
+// TRAVERSAL TESTS
+// ============================================================================
+
+/// Helper: Alice -knows-> Bob, Carol -manages-> Alice, Alice -likes-> Alice
+async fn create_neighborhood(manager: &KnowledgeGraphManager) {
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &[]),
+            entity("Bob", "person", &[]),
+            entity("Carol", "person", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "Bob", "knows"),
+            relation("Carol", "Alice", "manages"),
+            relation("Alice", "Alice", "likes"),
+        ])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_inspect_nodes_reports_missing_and_degrees() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Lives in Paris".to_string(), "Plays chess".to_string()],
+        }])
+        .await
+        .unwrap();
+
+    let result = manager
+        .inspect_nodes(vec![
+            "Alice".to_string(),
+            "Bob".to_string(),
+            "Nobody".to_string(),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(result.nodes.len(), 3);
+    assert_eq!(result.missing, vec!["Nobody"]);
+
+    // Alice's self-loop counts on both sides
+    let alice = &result.nodes["Alice"];
+    assert_eq!(alice.entity.as_ref().unwrap().name, "Alice");
+    assert_eq!((alice.in_degree, alice.out_degree), (2, 2));
+    assert_eq!(alice.observation_count, 2);
+    let bob = &result.nodes["Bob"];
+    assert_eq!(
+        (bob.in_degree, bob.out_degree, bob.observation_count),
+        (1, 0, 0)
+    );
+    let nobody = &result.nodes["Nobody"];
+    assert!(nobody.entity.is_none());
+    assert_eq!((nobody.in_degree, nobody.out_degree), (0, 0));
+
+    assert!(manager
+        .inspect_nodes(vec![])
+        .await
+        .unwrap()
+        .nodes
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_get_batch_returns_what_one_call_created() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    // Existing entities are skipped, so they stay in their original batch
+    let created = manager
+        .create_entities_detailed(vec![
+            entity("Dave", "person", &[]),
+            entity("Alice", "person", &[]),
+            entity("Erin", "person", &[]),
+        ])
+        .await
+        .unwrap();
+    let batch_id = created.batch_id.unwrap();
+    let relations = manager
+        .create_relations_detailed(vec![Relation {
+            from: "Dave".to_string(),
+            to: "Erin".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+    assert_ne!(relations.batch_id.as_deref(), Some(batch_id.as_str()));
+
+    let batch = manager.get_batch(batch_id).await.unwrap();
+    let names: Vec<&str> = batch.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Dave", "Erin"]);
+    assert!(batch.relations.is_empty());
+
+    let batch = manager
+        .get_batch(relations.batch_id.unwrap())
+        .await
+        .unwrap();
+    assert!(batch.entities.is_empty());
+    assert_eq!(batch.relations.len(), 1);
+
+    assert!(manager
+        .get_batch("unknown".to_string())
+        .await
+        .unwrap()
+        .entities
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_relation_summary_groups_by_direction_and_type() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Carol".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    let summary = manager.relation_summary("Alice".to_string()).await.unwrap();
+    assert_eq!(summary.name, "Alice");
+    let json = serde_json::to_value(&summary).unwrap();
+    // The self-loop is only listed as outgoing
+    assert_eq!(
+        json["outgoing"],
+        serde_json::json!({"knows": ["Bob", "Carol"], "likes": ["Alice"]})
+    );
+    assert_eq!(json["incoming"], serde_json::json!({"manages": ["Carol"]}));
+
+    let bob = manager.relation_summary("Bob".to_string()).await.unwrap();
+    assert!(bob.outgoing.is_empty());
+    assert_eq!(bob.incoming["knows"], vec!["Alice"]);
+
+    let err = manager
+        .relation_summary("Nobody".to_string())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_get_facts_flattens_observations_and_relations() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Speaks Spanish".to_string()],
+        }])
+        .await
+        .unwrap();
+
+    let facts = manager.get_facts("Alice".to_string(), true).await.unwrap();
+    assert_eq!(facts.entity_type, "person");
+    assert_eq!(
+        facts.facts,
+        vec![
+            "Speaks Spanish",
+            "Alice knows Bob",
+            "Alice likes Alice",
+            "Carol manages Alice"
+        ]
+    );
+
+    let facts = manager.get_facts("Alice".to_string(), false).await.unwrap();
+    assert_eq!(facts.facts, vec!["Speaks Spanish"]);
+
+    let err = manager
+        .get_facts("Nobody".to_string(), true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_relation_count_between() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .create_relations(vec![
+            Relation {
+                from: "Alice".to_string(),
+                to: "Bob".to_string(),
+                relation_type: "works_with".to_string(),
+            },
+            Relation {
+                from: "Bob".to_string(),
+                to: "Alice".to_string(),
+                relation_type: "knows".to_string(),
+            },
+        ])
+        .await
+        .unwrap();
+
+    let both = manager
+        .relation_count_between("Alice".to_string(), "Bob".to_string(), false)
+        .await
+        .unwrap();
+    assert_eq!(both.count, 3);
+    assert_eq!(both.relation_types, vec!["knows", "works_with"]);
+
+    let directed = manager
+        .relation_count_between("Bob".to_string(), "Alice".to_string(), true)
+        .await
+        .unwrap();
+    assert_eq!(directed.count, 1);
+    assert_eq!(directed.relation_types, vec!["knows"]);
+
+    let none = manager
+        .relation_count_between("Bob".to_string(), "Carol".to_string(), false)
+        .await
+        .unwrap();
+    assert_eq!(none.count, 0);
+    assert!(none.relation_types.is_empty());
+
+    let err = manager
+        .relation_count_between("Alice".to_string(), "Nobody".to_string(), false)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_second_degree_excludes_direct_neighbors() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .create_entities(vec![Entity {
+            name: "Dave".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "knows".to_string(),
+    };
+    manager
+        .create_relations(vec![
+            relation("Bob", "Dave"),
+            relation("Carol", "Dave"),
+            relation("Bob", "Carol"),
+        ])
+        .await
+        .unwrap();
+
+    // Carol is a direct (incoming) neighbor, so only Dave is two hops away
+    let both = manager
+        .second_degree("Alice".to_string(), Direction::Both)
+        .await
+        .unwrap();
+    assert_eq!(both.center, "Alice");
+    let names: Vec<&str> = both.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Dave"]);
+    assert_eq!(both.via["Dave"], vec!["Bob", "Carol"]);
+
+    // Following outgoing edges only, Carol is reached through Bob
+    let outgoing = manager
+        .second_degree("Alice".to_string(), Direction::Outgoing)
+        .await
+        .unwrap();
+    let names: Vec<&str> = outgoing.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Carol", "Dave"]);
+    assert_eq!(outgoing.via["Dave"], vec!["Bob"]);
+
+    let err = manager
+        .second_degree("Nobody".to_string(), Direction::Both)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_get_neighbors_annotates_direction() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    let result = manager
+        .get_neighbors("Alice".to_string(), Direction::Both)
+        .await
+        .unwrap();
+
+    assert_eq!(result.center, "Alice");
+    let mut names: Vec<_> = result.entities.iter().map(|e| e.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Bob", "Carol"]);
+
+    // Self-relation is reported once
+    assert_eq!(result.relations.len(), 3);
+    for rel in &result.relations {
+        match rel.relation.relation_type.as_str() {
+            "knows" | "likes" => assert_eq!(rel.direction, EdgeDirection::Outgoing),
+            "manages" => assert_eq!(rel.direction, EdgeDirection::Incoming),
+            other => panic!("unexpected relation {}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_get_neighbors_single_direction() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    let incoming = manager
+        .get_neighbors("Alice".to_string(), Direction::Incoming)
+        .await
+        .unwrap();
+    assert_eq!(incoming.entities.len(), 1);
+    assert_eq!(incoming.entities[0].name, "Carol");
+    assert!(incoming
+        .relations
+        .iter()
+        .all(|r| r.direction == EdgeDirection::Incoming));
+
+    let missing = manager
+        .get_neighbors("Nobody".to_string(), Direction::Both)
+        .await;
+    assert!(missing.unwrap_err().to_string().contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_common_neighbors_intersects_neighbor_sets() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+    manager
+        .create_entities(vec![
+            entity("Acme", "organization", &[]),
+            entity("Globex", "organization", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "Acme", "works_at"),
+            relation("Bob", "Acme", "works_at"),
+            relation("Bob", "Acme", "invests_in"),
+            relation("Globex", "Alice", "employs"),
+            relation("Bob", "Globex", "knows"),
+        ])
+        .await
+        .unwrap();
+
+    // Either direction: Acme (both point to it) and Globex (points to Alice, Bob points to it)
+    let both = manager
+        .common_neighbors("Alice".to_string(), "Bob".to_string(), Direction::Both)
+        .await
+        .unwrap();
+    let names: Vec<_> = both.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Acme", "Globex"]);
+    assert_eq!(
+        both.relations,
+        vec![
+            relation("Alice", "Acme", "works_at"),
+            relation("Bob", "Acme", "invests_in"),
+            relation("Bob", "Acme", "works_at"),
+            relation("Bob", "Globex", "knows"),
+            relation("Globex", "Alice", "employs"),
+        ]
+    );
+
+    // Alice -> Bob makes them neighbors of each other, but never common neighbors
+    assert!(!names.contains(&"Alice") && !names.contains(&"Bob"));
+
+    let outgoing = manager
+        .common_neighbors("Alice".to_string(), "Bob".to_string(), Direction::Outgoing)
+        .await
+        .unwrap();
+    let names: Vec<_> = outgoing.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Acme"]);
+    assert!(outgoing.relations.iter().all(|r| r.to == "Acme"));
+
+    let incoming = manager
+        .common_neighbors("Alice".to_string(), "Acme".to_string(), Direction::Incoming)
+        .await
+        .unwrap();
+    assert!(incoming.entities.is_empty() && incoming.relations.is_empty());
+
+    let err = manager
+        .common_neighbors("Alice".to_string(), "Nobody".to_string(), Direction::Both)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+    assert!(manager
+        .common_neighbors("Alice".to_string(), "Alice".to_string(), Direction::Both)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_traverse_bfs_order() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .create_entities(vec![Entity {
+            name: "Dave".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![Relation {
+            from: "Bob".to_string(),
+            to: "Dave".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    // Carol -> Alice -> Bob -> Dave, Alice's self-relation is ignored
+    let result = manager
+        .traverse("Carol".to_string(), 3, Direction::Outgoing)
+        .await
+        .unwrap();
+    let visits: Vec<(&str, u32, Option<&str>)> = result
+        .entities
+        .iter()
+        .map(|n| (n.entity.name.as_str(), n.distance, n.parent.as_deref()))
+        .collect();
+    assert_eq!(
+        visits,
+        vec![
+            ("Carol", 0, None),
+            ("Alice", 1, Some("Carol")),
+            ("Bob", 2, Some("Alice")),
+            ("Dave", 3, Some("Bob")),
+        ]
+    );
+
+    // Both directions from Alice: one level holds Bob and Carol, sorted by name
+    let result = manager
+        .traverse("Alice".to_string(), 1, Direction::Both)
+        .await
+        .unwrap();
+    let names: Vec<&str> = result
+        .entities
+        .iter()
+        .map(|n| n.entity.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Alice", "Bob", "Carol"]);
+
+    // Nothing points at Carol
+    let result = manager
+        .traverse("Carol".to_string(), 2, Direction::Incoming)
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+
+    assert!(manager
+        .traverse("Alice".to_string(), 11, Direction::Both)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_find_paths_with_typed_edges() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+    manager
+        .create_entities(
+            ["Alice", "BetaCorp", "Acme", "Dan", "Loner"]
+                .map(|name| entity(name, "node", &[]))
+                .to_vec(),
+        )
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "BetaCorp", "works_at"),
+            relation("Alice", "BetaCorp", "contracts_for"),
+            relation("BetaCorp", "Acme", "subsidiary_of"),
+            relation("Alice", "Dan", "knows"),
+            relation("Acme", "Dan", "employs"),
+        ])
+        .await
+        .unwrap();
+
+    let result = manager
+        .find_paths("Alice".into(), "Acme".into(), 6, 1, Direction::Both)
+        .await
+        .unwrap();
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(
+        result.paths[0].describe(),
+        "Alice -contracts_for-> BetaCorp -subsidiary_of-> Acme"
+    );
+
+    // Every shortest path, including the one walking a relation backwards
+    let result = manager
+        .find_paths("Alice".into(), "Acme".into(), 6, 10, Direction::Both)
+        .await
+        .unwrap();
+    let described: Vec<String> = result.paths.iter().map(|p| p.describe()).collect();
+    assert_eq!(
+        described,
+        vec![
+            "Alice -contracts_for-> BetaCorp -subsidiary_of-> Acme",
+            "Alice -works_at-> BetaCorp -subsidiary_of-> Acme",
+            "Alice -knows-> Dan <-employs- Acme",
+        ]
+    );
+    assert_eq!(result.paths[2].relations[1].from, "Acme");
+
+    // Following relations forward only rules out the path through Dan
+    let result = manager
+        .find_paths("Alice".into(), "Acme".into(), 6, 10, Direction::Outgoing)
+        .await
+        .unwrap();
+    assert_eq!(result.paths.len(), 2);
+
+    let too_short = manager
+        .find_paths("Alice".into(), "Acme".into(), 1, 10, Direction::Both)
+        .await
+        .unwrap();
+    assert!(too_short.paths.is_empty());
+    let unconnected = manager
+        .find_paths("Alice".into(), "Loner".into(), 6, 1, Direction::Both)
+        .await
+        .unwrap();
+    assert!(unconnected.paths.is_empty());
+
+    assert!(manager
+        .find_paths("Alice".into(), "Nobody".into(), 6, 1, Direction::Both)
+        .await
+        .is_err());
+    assert!(manager
+        .find_paths("Alice".into(), "Acme".into(), 6, 0, Direction::Both)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_longest_chain() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+    manager
+        .create_entities(
+            ["app", "http", "bytes", "libc", "serde", "json"]
+                .map(|name| entity(name, "crate", &[]))
+                .to_vec(),
+        )
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("app", "http", "depends_on"),
+            relation("http", "bytes", "depends_on"),
+            relation("bytes", "libc", "depends_on"),
+            relation("app", "libc", "depends_on"),
+            relation("bytes", "serde", "uses"),
+            relation("serde", "json", "uses"),
+        ])
+        .await
+        .unwrap();
+
+    let chain = |start: &str, relation_type: Option<&str>, direction, depth| {
+        manager.longest_chain(
+            start.to_string(),
+            relation_type.map(str::to_string),
+            direction,
+            depth,
+        )
+    };
+
+    // The shortcut app -> libc loses against the three-hop chain
+    let result = chain("app", Some("depends_on"), Direction::Outgoing, 20)
+        .await
+        .unwrap();
+    assert_eq!(
+        result.chain.describe(),
+        "app -depends_on-> http -depends_on-> bytes -depends_on-> libc"
+    );
+    assert!(result.exhaustive);
+    assert!(!result.cyclic);
+
+    let result = chain("app", None, Direction::Outgoing, 20).await.unwrap();
+    assert_eq!(
+        result.chain.entities,
+        vec!["app", "http", "bytes", "serde", "json"]
+    );
+
+    let result = chain("libc", Some("depends_on"), Direction::Incoming, 20)
+        .await
+        .unwrap();
+    assert_eq!(result.chain.entities, vec!["libc", "bytes", "http", "app"]);
+
+    let result = chain("app", None, Direction::Outgoing, 2).await.unwrap();
+    assert_eq!(result.chain.relations.len(), 2);
+    assert!(!result.exhaustive);
+
+    let result = chain("json", None, Direction::Outgoing, 20).await.unwrap();
+    assert_eq!(result.chain.entities, vec!["json"]);
+    assert!(result.chain.relations.is_empty());
+
+    // A relation back into the chain is skipped and reported
+    manager
+        .create_relations(vec![relation("libc", "http", "depends_on")])
+        .await
+        .unwrap();
+    let result = chain("app", Some("depends_on"), Direction::Outgoing, 20)
+        .await
+        .unwrap();
+    assert_eq!(result.chain.relations.len(), 3);
+    assert!(result.cyclic);
+
+    assert!(chain("nobody", None, Direction::Outgoing, 20)
+        .await
+        .is_err());
+    assert!(chain("app", None, Direction::Outgoing, 0).await.is_err());
+}
+
+// ============================================================================
+// QUERY TIMEOUT TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_query_timeout_interrupts_long_operation() {
+    let (_dir, path) = create_temp_db();
+
+    // Populate without a timeout
+    {
+        let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+        let entities = (0..500)
+            .map(|i| Entity {
+                name: format!("Entity_{}", i),
+                entity_type: "thing".to_string(),
+                observations: vec![format!("observation {}", i)],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            })
+            .collect();
+        manager.create_entities(entities).await.unwrap();
+    }
+
+    // A zero budget expires before the full scan finishes
+    let options = DatabaseOptions {
+        query_timeout: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    let err = manager.read_graph().await.unwrap_err();
+    assert!(is_query_timeout(&err), "Unexpected error: {:#}", err);
+
+    // A generous budget does not get in the way
+    let options = DatabaseOptions {
+        query_timeout: Some(Duration::from_secs(30)),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 500);
+}
+
+#[tokio::test]
+async fn test_query_timeout_bounds_waiting_calls() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        query_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    create_neighborhood(&manager).await;
+
+    // A writer waiting on another process's lock runs no statements SQLite could interrupt
+    let lock = rusqlite::Connection::open(&path).unwrap();
+    lock.execute_batch("BEGIN EXCLUSIVE;").unwrap();
+
+    let started = std::time::Instant::now();
+    let err = manager
+        .delete_entities(vec!["Carol".to_string()])
+        .await
+        .unwrap_err();
+    assert!(is_query_timeout(&err), "Unexpected error: {:#}", err);
+    assert!(err.to_string().contains("timed out after 200 ms"));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // The abandoned delete still gets the lock once it is released, but must not commit
+    lock.execute_batch("ROLLBACK;").unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let graph = manager.open_nodes(vec!["Carol".to_string()]).await.unwrap();
+    assert_eq!(graph.entities.len(), 1, "timed-out delete was committed");
+}
+
+#[tokio::test]
+async fn test_pool_min_idle_is_bounded_by_pool_size() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        pool_min_idle: Some(16),
+        ..Default::default()
+    };
+    let Err(err) = KnowledgeGraphManager::with_options(path.clone(), options) else {
+        panic!("min idle above the pool size accepted");
+    };
+    assert!(err.to_string().contains("exceed the pool size"));
+
+    // An empty idle floor with fast reaping still serves requests
+    let options = DatabaseOptions {
+        pool_min_idle: Some(0),
+        pool_idle_timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    create_neighborhood(&manager).await;
+    assert_eq!(manager.read_graph().await.unwrap().entities.len(), 3);
+}
+
+// Needs named pipes (mkfifo) to hold connections
+#[cfg(unix)]
+#[tokio::test]
+async fn test_pool_exhaustion_reports_busy() {
+    let (dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        pool_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    let entities: Vec<Entity> = (0..200)
+        // Large enough that an export overfills the pipe buffer
+        .map(|i| entity(&format!("Entity_{}", i), "thing", &[&"x".repeat(1000)]))
+        .collect();
+    manager.create_entities(entities).await.unwrap();
+
+    // Unread NDJSON streams hold no connection while they wait for their reader
+    let streams: Vec<_> = (0..15).map(|_| manager.stream_ndjson()).collect();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(manager.read_graph().await.unwrap().entities.len(), 200);
+    drop(streams);
+
+    // Exports into unread pipes: each one blocks mid-write, holding its connection
+    let pipes: Vec<PathBuf> = (0..15)
+        .map(|i| {
+            let pipe = dir.path().join(format!("export_{}.ndjson", i));
+            let status = std::process::Command::new("mkfifo")
+                .arg(&pipe)
+                .status()
+                .unwrap();
+            assert!(status.success());
+            pipe
+        })
+        .collect();
+    let manager = Arc::new(manager);
+    let exports: Vec<_> = pipes
+        .iter()
+        .map(|pipe| {
+            let (manager, pipe) = (manager.clone(), pipe.clone());
+            tokio::spawn(async move { manager.export_ndjson(pipe).await })
+        })
+        .collect();
+    let readers: Vec<_> = pipes
+        .into_iter()
+        .map(|pipe| tokio::task::spawn_blocking(move || std::fs::File::open(pipe).unwrap()))
+        .collect();
+    let mut opened = Vec::new();
+    for reader in readers {
+        opened.push(reader.await.unwrap());
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let err = manager.read_graph().await.unwrap_err();
+    assert!(is_pool_exhausted(&err), "Unexpected error: {:#}", err);
+    assert!(err
+        .to_string()
+        .contains("All 15 database connections are busy (waited 200 ms)"));
+
+    // Drained exports give their connections back
+    let drains: Vec<_> = opened
+        .into_iter()
+        .map(|mut file| {
+            tokio::task::spawn_blocking(move || {
+                std::io::copy(&mut file, &mut std::io::sink()).unwrap()
+            })
+        })
+        .collect();
+    for export in exports {
+        assert_eq!(export.await.unwrap().unwrap(), (200, 0));
+    }
+    for drain in drains {
+        drain.await.unwrap();
+    }
+    assert_eq!(manager.read_graph().await.unwrap().entities.len(), 200);
+}
+
+#[tokio::test]
+async fn test_min_query_length_rejects_short_searches() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        min_query_length: 3,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    create_neighborhood(&manager).await;
+
+    for query in ["a", "ab", "\"a\" b", "  x  "] {
+        let err = manager
+            .search_nodes(Some(query.to_string()))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("too short"),
+            "{}: {:#}",
+            query,
+            err
+        );
+    }
+    assert!(manager
+        .search_relations("kn".to_string(), None)
+        .await
+        .is_err());
+
+    // Long enough queries and the explicit full-graph request still work
+    let found = manager
+        .search_nodes(Some("Alice".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+    let everything = manager.search_nodes(None).await.unwrap();
+    assert_eq!(everything.entities.len(), 3);
+    assert_eq!(
+        manager
+            .search_relations("knows".to_string(), None)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+// ============================================================================
+// LIST BY TYPE TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_list_entities_by_type_paginates() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let mut entities: Vec<Entity> = (0..5)
+        .map(|i| Entity {
+            name: format!("Person_{}", i),
+            entity_type: "person".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        })
+        .collect();
+    // Mentions "person" in an observation but has a different type
+    entities.push(Entity {
+        name: "Acme".to_string(),
+        entity_type: "organization".to_string(),
+        observations: vec!["Employs every person in town".to_string()],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    });
+    manager.create_entities(entities).await.unwrap();
+
+    let first = manager
+        .list_entities_by_type("person".to_string(), 2, 0)
+        .await
+        .unwrap();
+    assert_eq!(first.total, 5);
+    let names: Vec<_> = first.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Person_0", "Person_1"]);
+
+    let last = manager
+        .list_entities_by_type("person".to_string(), 2, 4)
+        .await
+        .unwrap();
+    assert_eq!(last.entities.len(), 1);
+    assert_eq!(last.entities[0].name, "Person_4");
+
+    let none = manager
+        .list_entities_by_type("robot".to_string(), 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(none.total, 0);
+    assert!(none.entities.is_empty());
+
+    assert!(manager
+        .list_entities_by_type("person".to_string(), 0, 0)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_list_entities_by_types_groups_and_paginates() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let mut entities: Vec<Entity> = (0..3)
+        .map(|i| entity(&format!("Person_{}", i), "person", &[]))
+        .collect();
+    entities.push(entity("Acme", "organization", &[]));
+    entities.push(entity("Rust", "language", &[]));
+    manager.create_entities(entities).await.unwrap();
+
+    let types = |types: &[&str]| types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    let page = manager
+        .list_entities_by_types(types(&["organization", "person", "robot", "person"]), 2, 0)
+        .await
+        .unwrap();
+    let groups: Vec<_> = page
+        .groups
+        .iter()
+        .map(|g| {
+            let names: Vec<_> = g.entities.iter().map(|e| e.name.as_str()).collect();
+            (g.entity_type.as_str(), g.total, names)
+        })
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            ("organization", 1, vec!["Acme"]),
+            ("person", 3, vec!["Person_0", "Person_1"]),
+            ("robot", 0, vec![]),
+        ]
+    );
+
+    // The offset applies within each type
+    let next = manager
+        .list_entities_by_types(types(&["person", "organization"]), 2, 2)
+        .await
+        .unwrap();
+    assert_eq!(next.groups[0].entities.len(), 1);
+    assert_eq!(next.groups[0].entities[0].name, "Person_2");
+    assert!(next.groups[1].entities.is_empty());
+    assert_eq!(next.groups[1].total, 1);
+
+    assert!(manager
+        .list_entities_by_types(Vec::new(), 10, 0)
+        .await
+        .is_err());
+    assert!(manager
+        .list_entities_by_types(types(&["person", "bad type!"]), 10, 0)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_read_windows_slice_in_the_query() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "knows".to_string(),
+    };
+    manager
+        .create_entities(vec![
+            entity(
+                "Dave",
+                "person",
+                &[format!("{} knows rust", "Dave").as_str()],
+            ),
+            entity(
+                "Alice",
+                "person",
+                &[format!("{} knows rust", "Alice").as_str()],
+            ),
+            entity(
+                "Carol",
+                "person",
+                &[format!("{} knows rust", "Carol").as_str()],
+            ),
+            entity("Bob", "person", &[format!("{} knows rust", "Bob").as_str()]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "Bob"),
+            relation("Carol", "Dave"),
+            relation("Dave", "Alice"),
+        ])
+        .await
+        .unwrap();
+    manager
+        .set_entity_confidence("Bob".to_string(), Some(0.2))
+        .await
+        .unwrap();
+
+    let window = |limit, offset| ReadWindow {
+        limit,
+        offset,
+        min_confidence: None,
+    };
+    let names =
+        |entities: &[Entity]| -> Vec<String> { entities.iter().map(|e| e.name.clone()).collect() };
+
+    // Pages are by name, with the relations touching the page
+    let page = manager.read_graph_page(window(2, 0)).await.unwrap();
+    assert_eq!(page.total, 4);
+    assert_eq!(names(&page.entities), ["Alice", "Bob"]);
+    assert_eq!(page.relations.len(), 2);
+    let page = manager.read_graph_page(window(2, 2)).await.unwrap();
+    assert_eq!(names(&page.entities), ["Carol", "Dave"]);
+    assert_eq!(page.relations.len(), 2);
+
+    // The confidence filter applies before slicing
+    let filtered = ReadWindow {
+        min_confidence: Some(0.5),
+        ..window(2, 0)
+    };
+    let page = manager.read_graph_page(filtered).await.unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(names(&page.entities), ["Alice", "Carol"]);
+    assert!(page
+        .relations
+        .iter()
+        .all(|r| r.from != "Bob" && r.to != "Bob"));
+
+    // Searches page their matches, with only relations between the page's entities
+    let found = manager
+        .search_nodes_window(
+            Some("rust".to_string()),
+            MatchMode::All,
+            false,
+            window(2, 1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&found.entities), ["Bob", "Carol"]);
+    assert!(found.relations.is_empty());
+    // Matched relations come along when they touch the page
+    let found = manager
+        .search_nodes_window(
+            Some("knows".to_string()),
+            MatchMode::All,
+            true,
+            window(2, 1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(found.relations.len(), 2);
+
+    let relations = manager
+        .search_relations("knows".to_string(), Some(window(2, 1)))
+        .await
+        .unwrap();
+    let froms: Vec<&str> = relations.iter().map(|r| r.from.as_str()).collect();
+    assert_eq!(froms, ["Carol", "Dave"]);
+
+    let recent = manager.recent_entities(2, 3).await.unwrap();
+    assert_eq!(recent.len(), 1);
+    assert_eq!(
+        manager
+            .find_empty_entities(None, 10, 0)
+            .await
+            .unwrap()
+            .len(),
+        0
+    );
+
+    assert!(manager.read_graph_page(window(0, 0)).await.is_err());
+    assert!(manager
+        .read_graph_page(window(MAX_PAGE_SIZE + 1, 0))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_sample_entities() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let entities: Vec<Entity> = (0..40)
+        .map(|i| Entity {
+            name: format!("Entity_{:02}", i),
+            entity_type: if i % 4 == 0 { "robot" } else { "person" }.to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        })
+        .collect();
+    manager.create_entities(entities).await.unwrap();
+
+    let names = |entities: Vec<Entity>| entities.into_iter().map(|e| e.name).collect::<Vec<_>>();
+
+    let sample = manager.sample_entities(5, None, None, 0).await.unwrap();
+    assert_eq!(sample.len(), 5);
+    let distinct: HashSet<_> = sample.iter().map(|e| e.name.clone()).collect();
+    assert_eq!(distinct.len(), 5);
+
+    // Fewer entities of the type than requested: all of them
+    let robots = manager
+        .sample_entities(50, Some("robot".to_string()), None, 0)
+        .await
+        .unwrap();
+    assert_eq!(robots.len(), 10);
+    assert!(robots.iter().all(|e| e.entity_type == "robot"));
+
+    // A seed makes the sample reproducible; different seeds shuffle differently
+    let seeded = names(manager.sample_entities(8, None, Some(7), 0).await.unwrap());
+    assert_eq!(
+        seeded,
+        names(manager.sample_entities(8, None, Some(7), 0).await.unwrap())
+    );
+    assert_ne!(
+        seeded,
+        names(manager.sample_entities(8, None, Some(8), 0).await.unwrap())
+    );
+    let mut sorted = seeded.clone();
+    sorted.sort();
+    assert_ne!(seeded, sorted);
+
+    assert!(manager.sample_entities(0, None, None, 0).await.is_err());
+}
+
+#[tokio::test]
+async fn test_find_and_delete_empty_entities() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &["Likes tea"]),
+            entity("Bob", "person", &[]),
+            entity("Carol", "person", &["Was emptied"]),
+            entity("Widget", "product", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Bob".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+    manager
+        .delete_observations(vec![ObservationDeletion {
+            entity_name: "Carol".to_string(),
+            observations: vec!["Was emptied".to_string()],
+            indices: vec![],
+        }])
+        .await
+        .unwrap();
+
+    let names = |entities: Vec<Entity>| entities.into_iter().map(|e| e.name).collect::<Vec<_>>();
+    assert_eq!(
+        names(manager.find_empty_entities(None, 100, 0).await.unwrap()),
+        vec!["Bob", "Carol", "Widget"]
+    );
+    assert_eq!(
+        names(
+            manager
+                .find_empty_entities(Some("product".to_string()), 100, 0)
+                .await
+                .unwrap()
+        ),
+        vec!["Widget"]
+    );
+    assert_eq!(
+        names(manager.find_empty_entities(None, 2, 0).await.unwrap()),
+        vec!["Bob", "Carol"]
+    );
+    assert!(manager.find_empty_entities(None, 0, 0).await.is_err());
+
+    let deleted = manager
+        .delete_empty_entities(Some("person".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(deleted, vec!["Bob", "Carol"]);
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(names(graph.entities), vec!["Alice", "Widget"]);
+    assert!(graph.relations.is_empty());
+
+    assert_eq!(
+        manager.delete_empty_entities(None).await.unwrap(),
+        vec!["Widget"]
+    );
+    assert!(manager
+        .delete_empty_entities(None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_by_query() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager
+        .create_entities(vec![
+            entity("Phoenix", "project", &["Cancelled rewrite"]),
+            entity(
+                "Phoenix_Docs",
+                "project",
+                &["Docs for the cancelled rewrite"],
+            ),
+            entity("Atlas", "project", &["Active platform"]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![Relation {
+            from: "Phoenix_Docs".to_string(),
+            to: "Atlas".to_string(),
+            relation_type: "links_to".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    // Dry run only reports
+    let names = manager
+        .delete_by_query("cancelled".to_string(), MatchMode::All, true)
+        .await
+        .unwrap();
+    assert_eq!(names, vec!["Phoenix", "Phoenix_Docs"]);
+    assert_eq!(manager.read_graph().await.unwrap().entities.len(), 3);
+
+    let names = manager
+        .delete_by_query("cancelled".to_string(), MatchMode::All, false)
+        .await
+        .unwrap();
+    assert_eq!(names, vec!["Phoenix", "Phoenix_Docs"]);
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 1);
+    assert_eq!(graph.entities[0].name, "Atlas");
+    assert!(graph.relations.is_empty());
+
+    // Nothing left to match; an empty query never means "everything"
+    assert!(manager
+        .delete_by_query("cancelled".to_string(), MatchMode::All, false)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(manager
+        .delete_by_query("  ".to_string(), MatchMode::All, false)
+        .await
+        .is_err());
+    assert_eq!(manager.read_graph().await.unwrap().entities.len(), 1);
+}
+
+#[tokio::test]
+async fn test_list_relations_by_type() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .create_relations(vec![Relation {
+            from: "Bob".to_string(),
+            to: "Carol".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    let page = manager
+        .list_relations_by_type("knows".to_string(), 1, 0, false)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.relations.len(), 1);
+    assert_eq!(page.relations[0].from, "Alice");
+    assert!(page.entities.is_none());
+
+    let page = manager
+        .list_relations_by_type("knows".to_string(), 10, 1, true)
+        .await
+        .unwrap();
+    assert_eq!(page.relations[0].from, "Bob");
+    let names: Vec<_> = page.entities.unwrap().into_iter().map(|e| e.name).collect();
+    assert_eq!(names, vec!["Bob", "Carol"]);
+
+    let none = manager
+        .list_relations_by_type("hates".to_string(), 10, 0, true)
+        .await
+        .unwrap();
+    assert_eq!(none.total, 0);
+    assert_eq!(none.entities, Some(vec![]));
+
+    assert!(manager
+        .list_relations_by_type("knows".to_string(), 1001, 0, false)
+        .await
+        .is_err());
+}
+
+// ============================================================================
+// DEDUPLICATION TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_dedup_on_create_maps_near_duplicates() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        dedup_on_create: Some(DedupOptions {
+            case_fold: true,
+            merge_observations: true,
+        }),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    let org = |name: &str, obs: &[&str]| Entity {
+        name: name.to_string(),
+        entity_type: "organization".to_string(),
+        observations: obs.iter().map(|o| o.to_string()).collect(),
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    };
+
+    manager
+        .create_entities(vec![org("Acme", &["Makes anvils"])])
+        .await
+        .unwrap();
+
+    // "ACME " matches the stored "Acme"; "Globex" and "globex" collapse within the batch
+    let result = manager
+        .create_entities_detailed(vec![
+            org("ACME ", &["Makes anvils", "Based in the desert"]),
+            org("Globex", &[]),
+            org("globex", &[]),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(result.created.len(), 1);
+    assert_eq!(result.created[0].name, "Globex");
+    assert_eq!(result.deduplicated.len(), 2);
+    assert_eq!(result.deduplicated[0].name, "ACME ");
+    assert_eq!(result.deduplicated[0].existing_name, "Acme");
+    assert_eq!(
+        result.deduplicated[0].merged_observations,
+        vec!["Based in the desert"]
+    );
+    assert_eq!(result.deduplicated[1].existing_name, "Globex");
+
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 2);
+    let acme = graph.entities.iter().find(|e| e.name == "Acme").unwrap();
+    assert_eq!(acme.observations.len(), 2);
+}
+
+#[tokio::test]
+async fn test_dedup_case_sensitive_only_trims() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        dedup_on_create: Some(DedupOptions::default()),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    let result = manager
+        .create_entities_detailed(vec![
+            entity("Acme", "organization", &["note"]),
+            entity("ACME", "organization", &["note"]),
+            entity(" Acme", "organization", &["note"]),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(result.created.len(), 2);
+    assert_eq!(result.deduplicated.len(), 1);
+    assert_eq!(result.deduplicated[0].name, " Acme");
+    // Merging disabled: nothing added to the existing entity
+    assert!(result.deduplicated[0].merged_observations.is_empty());
+}
+
+// ============================================================================
+// RELATION EDIT TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_change_relation_type() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let relation = |relation_type: &str| Relation {
+        from: "Alice".to_string(),
+        to: "Bob".to_string(),
+        relation_type: relation_type.to_string(),
+    };
+
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &[]),
+            entity("Bob", "person", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![relation("employs"), relation("knows")])
+        .await
+        .unwrap();
+
+    let updated = manager
+        .change_relation_type(relation("employs"), "manages".to_string())
+        .await
+        .unwrap();
+    assert_eq!(updated, relation("manages"));
+
+    let graph = manager.read_graph().await.unwrap();
+    assert!(graph.relations.contains(&relation("manages")));
+    assert!(!graph.relations.contains(&relation("employs")));
+
+    // Target already exists -> clear error, nothing changed
+    let err = manager
+        .change_relation_type(relation("manages"), "knows".to_string())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+
+    // Missing relation
+    let err = manager
+        .change_relation_type(relation("employs"), "hired".to_string())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+
+    // Invalid new type
+    assert!(manager
+        .change_relation_type(relation("manages"), "bad type!".to_string())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_reverse_relation() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+
+    // Carol manages Alice -> Alice manages Carol, updated in place
+    let result = manager
+        .reverse_relation(relation("Carol", "Alice", "manages"))
+        .await
+        .unwrap();
+    assert_eq!(result.relation, relation("Alice", "Carol", "manages"));
+    assert!(!result.merged);
+    let graph = manager.read_graph().await.unwrap();
+    assert!(graph
+        .relations
+        .contains(&relation("Alice", "Carol", "manages")));
+    assert!(!graph
+        .relations
+        .contains(&relation("Carol", "Alice", "manages")));
+    assert_eq!(graph.relations.len(), 3);
+
+    // The reverse already exists: the backwards relation is dropped instead of colliding
+    manager
+        .create_relations(vec![relation("Bob", "Alice", "knows")])
+        .await
+        .unwrap();
+    let result = manager
+        .reverse_relation(relation("Bob", "Alice", "knows"))
+        .await
+        .unwrap();
+    assert!(result.merged);
+    let graph = manager.read_graph().await.unwrap();
+    let knows: Vec<_> = graph
+        .relations
+        .iter()
+        .filter(|r| r.relation_type == "knows")
+        .collect();
+    assert_eq!(knows, vec![&relation("Alice", "Bob", "knows")]);
+
+    // Self-relations reverse onto themselves
+    let result = manager
+        .reverse_relation(relation("Alice", "Alice", "likes"))
+        .await
+        .unwrap();
+    assert_eq!(result.relation, relation("Alice", "Alice", "likes"));
+    assert!(!result.merged);
+
+    let err = manager
+        .reverse_relation(relation("Bob", "Carol", "knows"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
+#[tokio::test]
+async fn test_create_star_relations() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let entities = ["Project", "a.rs", "b.rs", "c.rs"]
+        .iter()
+        .map(|n| Entity {
+            name: n.to_string(),
+            entity_type: "node".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        })
+        .collect();
+    manager.create_entities(entities).await.unwrap();
+
+    let files = vec!["a.rs".to_string(), "b.rs".to_string(), "c.rs".to_string()];
+    let created = manager
+        .create_star_relations(
+            "Project".to_string(),
+            files.clone(),
+            "belongs_to".to_string(),
+            EdgeDirection::Incoming,
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.len(), 3);
+    assert!(created
+        .iter()
+        .all(|r| r.to == "Project" && r.relation_type == "belongs_to"));
+
+    let out = manager
+        .create_star_relations(
+            "Project".to_string(),
+            vec!["a.rs".to_string()],
+            "contains".to_string(),
+            EdgeDirection::Outgoing,
+        )
+        .await
+        .unwrap();
+    assert_eq!(out[0].from, "Project");
+    assert_eq!(out[0].to, "a.rs");
+
+    // Missing entity fails the whole batch
+    assert!(manager
+        .create_star_relations(
+            "Project".to_string(),
+            vec!["b.rs".to_string(), "missing.rs".to_string()],
+            "uses".to_string(),
+            EdgeDirection::Outgoing,
+        )
+        .await
+        .is_err());
+    let graph = manager.read_graph().await.unwrap();
+    assert!(!graph.relations.iter().any(|r| r.relation_type == "uses"));
+}
+
+// ============================================================================
+// DETERMINISTIC EXPORT TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_read_graph_sorted_is_deterministic() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+
+    // Insert in non-sorted order
+    manager
+        .create_entities(vec![
+            entity("Charlie", "node", &[]),
+            entity("Alpha", "node", &[]),
+            entity("Bravo", "node", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Bravo", "Alpha", "links"),
+            relation("Alpha", "Charlie", "links"),
+            relation("Alpha", "Bravo", "zeta"),
+            relation("Alpha", "Bravo", "alpha"),
+        ])
+        .await
+        .unwrap();
+
+    let graph = manager.read_graph_sorted().await.unwrap();
+    let names: Vec<_> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Alpha", "Bravo", "Charlie"]);
+    assert_eq!(
+        graph.relations,
+        vec![
+            relation("Alpha", "Bravo", "alpha"),
+            relation("Alpha", "Bravo", "zeta"),
+            relation("Alpha", "Charlie", "links"),
+            relation("Bravo", "Alpha", "links"),
+        ]
+    );
+}
+
+// ============================================================================
+// RELATION SEARCH TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_search_relations() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    let found = manager
+        .search_relations("manages".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].from, "Carol");
+
+    // Index follows updates and cascade deletes
+    manager
+        .change_relation_type(found[0].clone(), "supervises".to_string())
+        .await
+        .unwrap();
+    assert!(manager
+        .search_relations("manages".to_string(), None)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        manager
+            .search_relations("supervises".to_string(), None)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    manager
+        .delete_entities(vec!["Carol".to_string()])
+        .await
+        .unwrap();
+    assert!(manager
+        .search_relations("supervises".to_string(), None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_search_nodes_with_relation_matches() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    // No entity mentions "knows", so plain search finds nothing
+    let plain = manager
+        .search_nodes(Some("knows".to_string()))
+        .await
+        .unwrap();
+    assert!(plain.relations.is_empty());
+
+    let extended = manager
+        .search_nodes_and_relations(Some("knows".to_string()), MatchMode::All)
+        .await
+        .unwrap();
+    assert!(extended.entities.is_empty());
+    assert_eq!(extended.relations.len(), 1);
+    assert_eq!(extended.relations[0].relation_type, "knows");
+}
+
+#[tokio::test]
+async fn test_search_match_mode_any() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &["Lives in Paris"]),
+            entity("Bob", "person", &["Lives in London"]),
+            entity("Carol", "person", &["Lives in Berlin"]),
+        ])
+        .await
+        .unwrap();
+
+    let all = manager
+        .search_nodes_matching(Some("paris london".to_string()), MatchMode::All)
+        .await
+        .unwrap();
+    assert!(all.entities.is_empty());
+
+    let any = manager
+        .search_nodes_matching(Some("paris london".to_string()), MatchMode::Any)
+        .await
+        .unwrap();
+    let mut names: Vec<_> = any.entities.iter().map(|e| e.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Alice", "Bob"]);
+
+    // Operators typed by the user stay plain terms: "OR" is just another word here
+    let quoted = manager
+        .search_nodes_matching(Some("paris OR london".to_string()), MatchMode::All)
+        .await
+        .unwrap();
+    assert!(quoted.entities.is_empty());
+}
+
+#[tokio::test]
+async fn test_search_projection() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![
+            entity("Bob", "person", &["Lives in Paris"]),
+            entity("Acme", "company", &["Lives in Paris"]),
+        ])
+        .await
+        .unwrap();
+
+    let search = |query: Option<&str>, projection| {
+        manager.search_entity_hits(query.map(String::from), MatchMode::All, projection, None)
+    };
+
+    let names = search(Some("paris"), SearchProjection::Names)
+        .await
+        .unwrap();
+    let mut found: Vec<_> = names.iter().map(|h| h.name.as_str()).collect();
+    found.sort();
+    assert_eq!(found, vec!["Acme", "Bob"]);
+    assert!(names.iter().all(|h| h.entity_type.is_none()));
+
+    let typed = search(Some("bob"), SearchProjection::NamesTypes)
+        .await
+        .unwrap();
+    assert_eq!(
+        typed,
+        vec![SearchHit {
+            name: "Bob".to_string(),
+            entity_type: Some("person".to_string()),
+        }]
+    );
+    assert_eq!(
+        serde_json::to_value(&names[0])
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Empty query lists every entity, ordered by name
+    let listed = search(None, SearchProjection::Names).await.unwrap();
+    assert_eq!(listed[0].name, "Acme");
+    assert_eq!(listed.len(), 2);
+
+    assert!(search(Some("paris"), SearchProjection::Full).await.is_err());
+}
+
+// ============================================================================
+// OBSERVATION FORMAT TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_observation_format_json() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        observation_format: ObservationFormat::Json,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    assert!(manager
+        .create_entities(vec![entity("Config", "settings", &["not json"])])
+        .await
+        .is_err());
+    manager
+        .create_entities(vec![entity("Config", "settings", &[r#"{"retries": 3}"#])])
+        .await
+        .unwrap();
+
+    let err = manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Config".to_string(),
+            contents: vec!["[1, 2".to_string()],
+        }])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not valid JSON"));
+}
+
+#[tokio::test]
+async fn test_observation_format_markdown_strips_html() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        observation_format: ObservationFormat::Markdown,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    manager
+        .create_entities(vec![Entity {
+            name: "Notes".to_string(),
+            entity_type: "document".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+
+    let result = manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Notes".to_string(),
+            contents: vec![
+                "**Bold**<script>alert('x')</script> text".to_string(),
+                "<b onclick=\"steal()\">hi</b> and a < b".to_string(),
+                "See <https://example.com> not <javascript:alert(1)>".to_string(),
+            ],
+        }])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result[0].added_observations,
+        vec![
+            "**Bold** text",
+            "hi and a < b",
+            "See <https://example.com> not ",
+        ]
+    );
+}
+
+// ============================================================================
+// SUBGRAPH EXPORT TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_get_subgraph_depth() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    // Chain A -> B <- C -> D
+    let entities = ["A", "B", "C", "D"]
+        .iter()
+        .map(|n| Entity {
+            name: n.to_string(),
+            entity_type: "node".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        })
+        .collect();
+    manager.create_entities(entities).await.unwrap();
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "link".to_string(),
+    };
+    manager
+        .create_relations(vec![
+            relation("A", "B"),
+            relation("C", "B"),
+            relation("C", "D"),
+        ])
+        .await
+        .unwrap();
+
+    let names = |graph: &KnowledgeGraph| {
+        let mut names: Vec<_> = graph.entities.iter().map(|e| e.name.clone()).collect();
+        names.sort();
+        names
+    };
+
+    let zero = manager.get_subgraph("A".to_string(), 0).await.unwrap();
+    assert_eq!(names(&zero), vec!["A"]);
+    assert!(zero.relations.is_empty());
+
+    let two = manager.get_subgraph("A".to_string(), 2).await.unwrap();
+    assert_eq!(names(&two), vec!["A", "B", "C"]);
+    assert_eq!(two.relations.len(), 2);
+
+    let all = manager.get_subgraph("A".to_string(), 3).await.unwrap();
+    assert_eq!(names(&all), vec!["A", "B", "C", "D"]);
+
+    assert!(manager.get_subgraph("Z".to_string(), 1).await.is_err());
+}
+
+#[test]
+fn test_export_mermaid_sanitizes_ids() {
+    let graph = KnowledgeGraph {
+        entities: vec![
+            entity("C# \"lang\"", "topic", &[]),
+            entity("C_ _lang_", "topic", &[]),
+            entity("end", "topic", &[]),
+        ],
+        relations: vec![
+            Relation {
+                from: "C# \"lang\"".to_string(),
+                to: "end".to_string(),
+                relation_type: "is-a".to_string(),
+            },
+            Relation {
+                from: "end".to_string(),
+                to: "Outside".to_string(),
+                relation_type: "links".to_string(),
+            },
+        ],
+    };
+
+    let mermaid = export::to_mermaid(&graph);
+    let lines: Vec<&str> = mermaid.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "flowchart LR",
+            r#"  e_C___lang_["C#35; #quot;lang#quot; (topic)"]"#,
+            r#"  e_C___lang__2["C_ _lang_ (topic)"]"#,
+            r#"  e_end["end (topic)"]"#,
+            r#"  e_C___lang_ -->|"is-a"| e_end"#,
+            r#"  e_Outside["Outside"]"#,
+            r#"  e_end -->|"links"| e_Outside"#,
+        ]
+    );
+}
+
+#[test]
+fn test_export_render_and_destination() {
+    let graph = KnowledgeGraph {
+        entities: vec![Entity {
+            name: "Say \"hi\"".to_string(),
+            entity_type: "phrase".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }],
+        relations: vec![],
+    };
+    let dot = export::render(&graph, ExportFormat::Dot).unwrap();
+    assert!(dot.starts_with("digraph memory {"));
+    assert!(dot.contains(r#""Say \"hi\"" [label="Say \"hi\"\n(phrase)"];"#));
+
+    let mut typed = graph.clone();
+    typed.entities.push(Entity {
+        name: "Hello".to_string(),
+        entity_type: "phrase".to_string(),
+        observations: vec![],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    });
+    typed.entities.push(Entity {
+        name: "Bob".to_string(),
+        entity_type: "person".to_string(),
+        observations: vec![],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    });
+    let clustered = DotOptions {
+        cluster_by_type: true,
+    };
+    let dot = export::render_with(&typed, ExportFormat::Dot, clustered).unwrap();
+    assert_eq!(dot.matches("subgraph \"cluster_").count(), 2);
+    // Types are emitted sorted, each with its own nodes
+    let phrase = dot.find("subgraph \"cluster_phrase\"").unwrap();
+    let person = dot.find("subgraph \"cluster_person\"").unwrap();
+    assert!(person < phrase);
+    assert!(dot[phrase..].contains("    \"Hello\" [label="));
+    // Colors depend only on the type
+    assert_eq!(
+        dot,
+        export::render_with(&typed, ExportFormat::Dot, clustered).unwrap()
+    );
+    assert!(!export::render(&typed, ExportFormat::Dot)
+        .unwrap()
+        .contains("subgraph"));
+
+    let dir = TempDir::new().unwrap();
+    let ok = export::resolve_export_path(dir.path(), "topics/a.json", ExportFormat::Json).unwrap();
+    assert!(ok.ends_with("topics/a.json"));
+    assert!(ok.starts_with(dir.path().canonicalize().unwrap()));
+
+    for bad in ["../escape.json", "/etc/passwd.json", "a.dot", ""] {
+        assert!(
+            export::resolve_export_path(dir.path(), bad, ExportFormat::Json).is_err(),
+            "accepted {}",
+            bad
+        );
+    }
+
+    // A symlink in the export directory cannot lead writes (or new directories) out of it
+    #[cfg(unix)]
+    {
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("out")).unwrap();
+        assert!(
+            export::resolve_export_path(dir.path(), "out/sub/a.json", ExportFormat::Json).is_err()
+        );
+        assert!(!outside.path().join("sub").exists());
+    }
+}
+
+#[tokio::test]
+async fn test_export_ndjson_roundtrips_through_seed_format() {
+    let (dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    let file = dir.path().join("graph.ndjson");
+    let (entities, relations) = manager.export_ndjson(file.clone()).await.unwrap();
+    assert_eq!((entities, relations), (3, 3));
+
+    let content = std::fs::read_to_string(&file).unwrap();
+    assert_eq!(content.lines().count(), 6);
+    assert!(content
+        .lines()
+        .next()
+        .unwrap()
+        .contains(r#""type":"entity""#));
+    let parsed = import::parse_jsonl(&content).unwrap();
+    let sorted = manager.read_graph_sorted().await.unwrap();
+    assert_eq!(parsed.entities, sorted.entities);
+    assert_eq!(parsed.relations, sorted.relations);
+
+    // The streamed lines are the same records
+    let mut stream = manager.stream_ndjson();
+    let mut streamed = String::new();
+    while let Some(line) = stream.recv().await {
+        streamed.push_str(&line.unwrap());
+    }
+    assert_eq!(streamed, content);
+}
+
+#[tokio::test]
+async fn test_stream_ndjson_pages_through_large_graph() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    // Several pages of entities and of relations, with 'from' names repeated across pages
+    let entities: Vec<Entity> = (0..150)
+        .map(|i| {
+            entity(
+                &format!("Entity_{:03}", i),
+                "thing",
+                &[&format!("Number {}", i)],
+            )
+        })
+        .collect();
+    manager.create_entities(entities).await.unwrap();
+    let relations: Vec<Relation> = (0..150)
+        .flat_map(|i| {
+            ["next", "prev"].map(|relation_type| Relation {
+                from: format!("Entity_{:03}", i / 3),
+                to: format!("Entity_{:03}", (i + 1) % 150),
+                relation_type: relation_type.to_string(),
+            })
+        })
+        .collect();
+    manager.create_relations(relations).await.unwrap();
+
+    let mut stream = manager.stream_ndjson();
+    let mut streamed = String::new();
+    while let Some(line) = stream.recv().await {
+        streamed.push_str(&line.unwrap());
+    }
+    let parsed = import::parse_jsonl(&streamed).unwrap();
+    let sorted = manager.read_graph_sorted().await.unwrap();
+    assert_eq!(parsed.entities, sorted.entities);
+    assert_eq!(parsed.relations, sorted.relations);
+    assert_eq!(parsed.relations.len(), 300);
+}
+
+// ============================================================================
+// ENTITY DESCRIPTION TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_entity_description_roundtrip_and_search() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager
+        .create_entities(vec![
+            Entity {
+                name: "Alice".to_string(),
+                entity_type: "person".to_string(),
+                observations: vec!["Likes tea".to_string()],
+                description: Some("Principal cryptographer".to_string()),
+                id: None,
+                confidence: None,
+                metadata: None,
+            },
+            Entity {
+                name: "Bob".to_string(),
+                entity_type: "person".to_string(),
+                observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            },
+        ])
+        .await
+        .unwrap();
+
+    let graph = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+    assert_eq!(
+        graph.entities[0].description.as_deref(),
+        Some("Principal cryptographer")
+    );
+
+    // Description text is indexed, observations alone would not match
+    let result = manager
+        .search_nodes(Some("cryptographer".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+    assert_eq!(result.entities[0].name, "Alice");
+
+    // Absent descriptions are omitted from JSON output
+    let bob = manager.open_nodes(vec!["Bob".to_string()]).await.unwrap();
+    let json = serde_json::to_value(&bob.entities[0]).unwrap();
+    assert!(json.get("description").is_none());
+}
+
+#[tokio::test]
+async fn test_set_entity_description() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Likes tea".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+
+    let updated = manager
+        .set_entity_description("Alice".to_string(), Some("Team lead".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(updated.description.as_deref(), Some("Team lead"));
+    assert_eq!(updated.observations, vec!["Likes tea"]);
+
+    // FTS follows the update
+    let result = manager
+        .search_nodes(Some("lead".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+
+    let cleared = manager
+        .set_entity_description("Alice".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.description, None);
+    let result = manager
+        .search_nodes(Some("lead".to_string()))
+        .await
+        .unwrap();
+    assert!(result.entities.is_empty());
+
+    let err = manager
+        .set_entity_description("Nobody".to_string(), Some("x".to_string()))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+
+    let err = manager
+        .set_entity_description("Alice".to_string(), Some("x".repeat(5000)))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too long"));
+}
+
+#[tokio::test]
+async fn test_entity_confidence() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager
+        .create_entities(vec![
+            Entity {
+                confidence: Some(0.9),
+                ..entity("Alice", "person", &["Lives in Paris"])
+            },
+            Entity {
+                confidence: Some(0.2),
+                ..entity("Bob", "person", &["Lives in Paris"])
+            },
+            entity("Carol", "person", &["Lives in Paris"]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Bob".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    let graph = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+    assert_eq!(graph.entities[0].confidence, Some(0.9));
+
+    // Low scores and their relations are dropped; unscored entities are kept
+    let mut result = manager
+        .search_nodes(Some("Paris".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 3);
+    result.retain_min_confidence(0.5);
+    let mut names: Vec<_> = result.entities.iter().map(|e| e.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Alice", "Carol"]);
+    assert!(result.relations.is_empty());
+
+    let updated = manager
+        .set_entity_confidence("Bob".to_string(), Some(0.75))
+        .await
+        .unwrap();
+    assert_eq!(updated.confidence, Some(0.75));
+    let cleared = manager
+        .set_entity_confidence("Bob".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.confidence, None);
+
+    let err = manager
+        .set_entity_confidence("Bob".to_string(), Some(1.5))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("between 0.0 and 1.0"));
+    let err = manager
+        .set_entity_confidence("Nobody".to_string(), Some(0.5))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+    assert!(manager
+        .create_entities(vec![Entity {
+            confidence: Some(-0.1),
+            ..entity("Dave", "person", &["Lives in Paris"])
+        }])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_entity_metadata() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let metadata = serde_json::json!({"url": "https://techcorp.example", "coords": [48.85, 2.35]});
+    manager
+        .create_entities(vec![Entity {
+            name: "TechCorp".to_string(),
+            entity_type: "organization".to_string(),
+            observations: vec!["Builds compilers".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: Some(metadata.clone()),
+        }])
+        .await
+        .unwrap();
+
+    let graph = manager
+        .open_nodes(vec!["TechCorp".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(graph.entities[0].metadata, Some(metadata));
+
+    // Metadata is stored, not indexed
+    let result = manager
+        .search_nodes(Some("techcorp.example".to_string()))
+        .await
+        .unwrap();
+    assert!(result.entities.is_empty());
+
+    let updated = manager
+        .set_entity_metadata("TechCorp".to_string(), Some(serde_json::json!(42)))
+        .await
+        .unwrap();
+    assert_eq!(updated.metadata, Some(serde_json::json!(42)));
+    let cleared = manager
+        .set_entity_metadata("TechCorp".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.metadata, None);
+
+    let err = manager
+        .set_entity_metadata(
+            "TechCorp".to_string(),
+            Some(serde_json::json!("x".repeat(9000))),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too long"));
+    let err = manager
+        .set_entity_metadata("Nobody".to_string(), Some(serde_json::json!({})))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_relation_endpoints_resolve_aliases() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "member_of".to_string(),
+    };
+    manager
+        .create_entities(vec![
+            entity("United States", "country", &[]),
+            entity("NATO", "country", &[]),
+            entity("France", "country", &[]),
+        ])
+        .await
+        .unwrap();
+
+    let added = manager
+        .add_aliases(
+            "United States".to_string(),
+            vec!["US".to_string(), "USA".to_string(), "US".to_string()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(added, ["US", "USA"]);
+    // Adding again is a no-op; an entity name or another entity's alias is rejected
+    assert!(manager
+        .add_aliases("United States".to_string(), vec!["US".to_string()])
+        .await
+        .unwrap()
+        .is_empty());
+    let err = manager
+        .add_aliases("France".to_string(), vec!["US".to_string()])
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("already belongs to entity 'United States'"));
+    assert!(manager
+        .add_aliases("France".to_string(), vec!["NATO".to_string()])
+        .await
+        .is_err());
+    assert!(manager
+        .add_aliases("Nowhere".to_string(), vec!["NW".to_string()])
+        .await
+        .is_err());
+    let aliases = manager
+        .entity_aliases("United States".to_string())
+        .await
+        .unwrap();
+    assert_eq!(aliases.aliases, ["US", "USA"]);
+
+    let result = manager
+        .create_relations_detailed(vec![relation("US", "NATO"), relation("France", "NATO")])
+        .await
+        .unwrap();
+    assert_eq!(
+        result.created,
+        [
+            relation("United States", "NATO"),
+            relation("France", "NATO")
+        ]
+    );
+    assert_eq!(
+        result.resolved_aliases,
+        [ResolvedAlias {
+            index: 0,
+            endpoint: "from".to_string(),
+            alias: "US".to_string(),
+            entity: "United States".to_string(),
+        }]
+    );
+    let checks = manager
+        .check_relations(vec![relation("USA", "NATO")], vec![])
+        .await
+        .unwrap();
+    assert!(checks[0].valid);
+
+    let deleted = manager
+        .delete_relations_detailed(vec![relation("USA", "NATO")])
+        .await
+        .unwrap();
+    assert_eq!(deleted.deleted, 1);
+    assert_eq!(deleted.resolved_aliases[0].entity, "United States");
+
+    // Staged writes resolve aliases too
+    manager
+        .apply_operations(vec![GraphOperation::CreateRelations {
+            relations: vec![relation("US", "NATO")],
+        }])
+        .await
+        .unwrap();
+    assert_eq!(manager.read_graph().await.unwrap().relations.len(), 2);
+
+    // An entity named like an alias wins; removed aliases and deleted entities stop resolving
+    manager
+        .create_entities(vec![entity("USA", "country", &[])])
+        .await
+        .unwrap();
+    let result = manager
+        .create_relations_detailed(vec![relation("USA", "NATO")])
+        .await
+        .unwrap();
+    assert!(result.resolved_aliases.is_empty());
+    assert_eq!(result.created, [relation("USA", "NATO")]);
+    assert_eq!(
+        manager
+            .remove_aliases(vec!["US".to_string(), "EU".to_string()])
+            .await
+            .unwrap(),
+        ["US"]
+    );
+    assert!(manager
+        .create_relations(vec![relation("US", "France")])
+        .await
+        .is_err());
+    manager
+        .delete_entities(vec!["United States".to_string()])
+        .await
+        .unwrap();
+    manager
+        .delete_entities(vec!["USA".to_string()])
+        .await
+        .unwrap();
+    assert!(manager
+        .create_relations(vec![relation("USA", "France")])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_replace_entity() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Likes tea".to_string()],
+        }])
+        .await
+        .unwrap();
+    manager
+        .set_entity_description("Alice".to_string(), Some("Team lead".to_string()))
+        .await
+        .unwrap();
+    let before = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+
+    let replacement = |name: &str, entity_type: &str, observations: &[&str]| Entity {
+        name: name.to_string(),
+        entity_type: entity_type.to_string(),
+        observations: observations.iter().map(|o| o.to_string()).collect(),
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    };
+
+    // Existing entity: type and observations overwritten, everything else kept
+    let result = manager
+        .replace_entity(replacement("Alice", "manager", &["Runs the storage team"]))
+        .await
+        .unwrap();
+    assert!(!result.created);
+    assert_eq!(result.entity.entity_type, "manager");
+    assert_eq!(result.entity.observations, vec!["Runs the storage team"]);
+    assert_eq!(result.entity.description.as_deref(), Some("Team lead"));
+    assert_eq!(result.entity.id, before.entities[0].id);
+
+    let graph = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+    assert_eq!(graph.relations.len(), before.relations.len());
+    let found = manager.search_nodes(Some("tea".to_string())).await.unwrap();
+    assert!(found.entities.is_empty());
+    let found = manager
+        .search_nodes(Some("storage".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+
+    // Missing entity: created
+    let result = manager
+        .replace_entity(replacement("Dave", "person", &["New hire"]))
+        .await
+        .unwrap();
+    assert!(result.created);
+    assert!(result.entity.id.is_some());
+
+    // Validated like create_entities
+    assert!(manager
+        .replace_entity(replacement("Dave", "", &[]))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_description_migration_from_old_schema() {
+    let (_dir, path) = create_temp_db();
+
+    // Database as created by versions without the description column
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entities (
+                name TEXT PRIMARY KEY NOT NULL,
+                entity_type TEXT NOT NULL,
+                observations TEXT NOT NULL
+            ) STRICT;
+            CREATE VIRTUAL TABLE entities_fts USING fts5(
+                name, entity_type, observations,
+                content='entities', content_rowid='rowid'
+            );
+            CREATE TRIGGER entities_ai AFTER INSERT ON entities BEGIN
+                INSERT INTO entities_fts(rowid, name, entity_type, observations)
+                VALUES (new.rowid, new.name, new.entity_type, new.observations);
+            END;
+            INSERT INTO entities VALUES ('Alice', 'person', '[\"Speaks Esperanto\"]');",
+        )
+        .unwrap();
+    }
+
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    // Existing rows survive and stay searchable after the FTS rebuild
+    let result = manager
+        .search_nodes(Some("Esperanto".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+    assert_eq!(result.entities[0].description, None);
+
+    manager
+        .set_entity_description("Alice".to_string(), Some("Polyglot".to_string()))
+        .await
+        .unwrap();
+    let result = manager
+        .search_nodes(Some("Polyglot".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 1);
+}
+
+// ============================================================================
+// DUPLICATE DETECTION TESTS
+// ============================================================================
+
+async fn create_named(manager: &KnowledgeGraphManager, names: &[(&str, &str)]) {
+    let entities = names
+        .iter()
+        .map(|(name, entity_type)| Entity {
+            name: name.to_string(),
+            entity_type: entity_type.to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        })
+        .collect();
+    manager.create_entities(entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_find_duplicates_exact_normalized() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_named(
+        &manager,
+        &[
+            ("Acme Corp", "organization"),
+            ("acme corp", "company"),
+            (" ACME CORP ", "organization"),
+            ("Acme Corp.", "organization"),
+            ("Bob", "person"),
+        ],
+    )
+    .await;
+
+    let clusters = manager.find_duplicates(None).await.unwrap();
+    assert_eq!(clusters.len(), 1);
+    let names: Vec<&str> = clusters[0]
+        .entities
+        .iter()
+        .map(|e| e.name.as_str())
+        .collect();
+    assert_eq!(names, vec![" ACME CORP ", "Acme Corp", "acme corp"]);
+    assert_eq!(clusters[0].entities[2].entity_type, "company");
+}
+
+#[tokio::test]
+async fn test_find_duplicates_trigram_threshold() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_named(
+        &manager,
+        &[
+            ("Acme Corp", "organization"),
+            ("Acme Corp.", "organization"),
+            ("Acme Corporation", "organization"),
+            ("Bob", "person"),
+            ("Carol", "person"),
+        ],
+    )
+    .await;
+
+    // Strict threshold: only the near-identical pair
+    let clusters = manager.find_duplicates(Some(0.7)).await.unwrap();
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].entities.len(), 2);
+
+    // Looser threshold pulls in the longer variant, unrelated names stay apart
+    let clusters = manager.find_duplicates(Some(0.5)).await.unwrap();
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].entities.len(), 3);
+
+    assert!(manager.find_duplicates(Some(0.0)).await.is_err());
+    assert!(manager.find_duplicates(Some(1.5)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_find_relation_type_synonyms() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    let relation = |from: &str, to: &str, relation_type: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: relation_type.to_string(),
+    };
+    manager
+        .create_relations(vec![
+            relation("Alice", "Carol", "works_at"),
+            relation("Bob", "Carol", "worksAt"),
+            relation("Bob", "Alice", "Works-At"),
+            relation("Carol", "Bob", "work_at"),
+        ])
+        .await
+        .unwrap();
+
+    // Spelling conventions group exactly; ties go to the snake_case spelling
+    let groups = manager.find_relation_type_synonyms(None).await.unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].canonical, "works_at");
+    assert_eq!(groups[0].relations, 3);
+    let types: Vec<&str> = groups[0]
+        .types
+        .iter()
+        .map(|t| t.relation_type.as_str())
+        .collect();
+    assert_eq!(types, ["works_at", "Works-At", "worksAt"]);
+
+    // A threshold pulls in near spellings, unrelated types stay apart
+    let groups = manager
+        .find_relation_type_synonyms(Some(0.5))
+        .await
+        .unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].types.len(), 4);
+    assert_eq!(groups[0].relations, 4);
+
+    assert!(manager
+        .find_relation_type_synonyms(Some(0.0))
+        .await
+        .is_err());
+}
+
+// ============================================================================
+// IMPORT TESTS
+// ============================================================================
+
+#[test]
+fn test_parse_graph_files() {
+    let graph = import::parse_json(
+        r#"{"entities": [{"name": "Alice", "entityType": "person", "observations": []}]}"#,
+    )
+    .unwrap();
+    assert_eq!(graph.entities.len(), 1);
+    assert!(graph.relations.is_empty());
+
+    let graph = import::parse_jsonl(
+        "{\"type\":\"entity\",\"name\":\"Alice\",\"entityType\":\"person\",\"observations\":[]}\n\n\
+         {\"type\":\"relation\",\"from\":\"Alice\",\"to\":\"Alice\",\"relationType\":\"knows\"}\n",
+    )
+    .unwrap();
+    assert_eq!(graph.entities.len(), 1);
+    assert_eq!(graph.relations.len(), 1);
+
+    let err = import::parse_jsonl("{\"type\":\"entity\",\"name\":\"A\"}\nnot json\n").unwrap_err();
+    assert!(err.to_string().contains("line 1"));
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("seed.txt");
+    std::fs::write(&path, "{}").unwrap();
+    assert!(import::load_graph_file(&path).is_err());
+}
+
+#[tokio::test]
+async fn test_import_graph_is_atomic() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    assert!(manager.is_empty().await.unwrap());
+
+    let alice = Entity {
+        name: "Alice".to_string(),
+        entity_type: "person".to_string(),
+        observations: vec![],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    };
+
+    // Relation to a missing entity rolls back the entities of the same import
+    let broken = KnowledgeGraph {
+        entities: vec![alice.clone()],
+        relations: vec![Relation {
+            from: "Alice".to_string(),
+            to: "Nobody".to_string(),
+            relation_type: "knows".to_string(),
+        }],
+    };
+    assert!(manager.import_graph(broken).await.is_err());
+    assert!(manager.is_empty().await.unwrap());
+
+    let graph = KnowledgeGraph {
+        entities: vec![alice],
+        relations: vec![Relation {
+            from: "Alice".to_string(),
+            to: "Alice".to_string(),
+            relation_type: "knows".to_string(),
+        }],
+    };
+    let created = manager.import_graph(graph.clone()).await.unwrap();
+    assert_eq!(created.entities.len(), 1);
+    assert_eq!(created.relations.len(), 1);
+    assert!(!manager.is_empty().await.unwrap());
+
+    // Re-importing is a no-op
+    let created = manager.import_graph(graph).await.unwrap();
+    assert!(created.entities.is_empty());
+    assert!(created.relations.is_empty());
+}
+
+#[tokio::test]
+async fn test_import_mem0_export() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    manager
+        .create_entities(vec![Entity {
+            name: "alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Likes tea".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+
+    let export = r#"{
+        "results": [
+            {"id": "1", "memory": "Likes tea", "user_id": "alice"},
+            {"id": "2", "memory": "Works at Acme", "user_id": "alice"},
+            {"id": "3", "memory": "Prefers short answers", "agent_id": "helper"},
+            {"id": "4", "memory": "Unscoped note"}
+        ],
+        "relations": [
+            {"source": "alice", "relationship": "works_at", "target": "acme"}
+        ]
+    }"#;
+    let graph = import::parse_foreign(export, ForeignFormat::Mem0).unwrap();
+    let names: Vec<&str> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["alice", "helper", "mem0", "acme"]);
+    assert_eq!(graph.entities[1].entity_type, "agent");
+    assert_eq!(graph.entities[3].entity_type, import::MEM0_NODE_TYPE);
+
+    let result = manager
+        .merge_graph(graph, ConflictPolicy::MergeObservations)
+        .await
+        .unwrap();
+    assert_eq!(result.entities.len(), 3);
+    assert_eq!(result.relations.len(), 1);
+    assert_eq!(result.merged_observations.len(), 1);
+    assert_eq!(
+        result.merged_observations[0].added_observations,
+        vec!["Works at Acme"]
+    );
+
+    // Existing entity keeps its type and gains only the new memory
+    let alice = manager.open_nodes(vec!["alice".to_string()]).await.unwrap();
+    assert_eq!(alice.entities[0].entity_type, "person");
+    assert_eq!(
+        alice.entities[0].observations,
+        vec!["Likes tea", "Works at Acme"]
+    );
+
+    // A bare memory array is accepted too
+    let graph = import::parse_foreign(
+        r#"[{"memory": "Bare", "run_id": "r1"}]"#,
+        ForeignFormat::Mem0,
+    )
+    .unwrap();
+    assert_eq!(graph.entities[0].entity_type, "session");
+    assert!(import::parse_foreign("{\"results\": 5}", ForeignFormat::Mem0).is_err());
+}
+
+#[tokio::test]
+async fn test_merge_graph_conflict_policies() {
+    let relation = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "knows".to_string(),
+    };
+    let incoming = KnowledgeGraph {
+        entities: vec![
+            entity("Alice", "engineer", &["Likes tea", "Writes Rust"]),
+            entity("Bob", "person", &["Runs"]),
+            entity("Carol", "person", &["New here"]),
+        ],
+        relations: vec![relation("Alice", "Bob"), relation("Carol", "Alice")],
+    };
+
+    for (policy, updated, skipped, alice_type, alice_observations) in [
+        (
+            ConflictPolicy::Skip,
+            vec![],
+            vec!["Alice", "Bob"],
+            "person",
+            vec!["Likes tea"],
+        ),
+        (
+            ConflictPolicy::Overwrite,
+            vec!["Alice", "Bob"],
+            vec![],
+            "engineer",
+            vec!["Likes tea", "Writes Rust"],
+        ),
+        (
+            ConflictPolicy::MergeObservations,
+            vec!["Alice"],
+            vec!["Bob"],
+            "person",
+            vec!["Likes tea", "Writes Rust"],
+        ),
+    ] {
+        let (_dir, path) = create_temp_db();
+        let manager = KnowledgeGraphManager::new(path).unwrap();
+        manager
+            .create_entities(vec![
+                entity("Alice", "person", &["Likes tea"]),
+                entity("Bob", "person", &["Runs"]),
+            ])
+            .await
+            .unwrap();
+        manager
+            .create_relations(vec![relation("Alice", "Bob")])
+            .await
+            .unwrap();
+
+        let result = manager.merge_graph(incoming.clone(), policy).await.unwrap();
+        let created: Vec<_> = result.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(created, vec!["Carol"], "{:?}", policy);
+        assert_eq!(result.relations, vec![relation("Carol", "Alice")]);
+        assert_eq!(result.updated, updated, "{:?}", policy);
+        assert_eq!(result.skipped, skipped, "{:?}", policy);
+
+        let graph = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+        assert_eq!(graph.entities[0].entity_type, alice_type, "{:?}", policy);
+        assert_eq!(graph.entities[0].observations, alice_observations);
+        assert_eq!(manager.read_graph().await.unwrap().relations.len(), 2);
+    }
+
+    // Validation runs before anything is written
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let mut invalid = incoming.clone();
+    invalid.entities.push(entity("", "person", &[]));
+    assert!(manager
+        .merge_graph(invalid, ConflictPolicy::Overwrite)
+        .await
+        .is_err());
+    assert!(manager.read_graph().await.unwrap().entities.is_empty());
+}
+
+// ============================================================================
+// REVISION TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_revision_tracks_every_write() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+
+    let start = manager.revision().await.unwrap();
+    create_neighborhood(&manager).await;
+    let after_create = manager.revision().await.unwrap();
+    assert!(after_create > start);
+
+    // Reads do not change the revision
+    manager.read_graph().await.unwrap();
+    assert_eq!(manager.revision().await.unwrap(), after_create);
+
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Bob".to_string(),
+            contents: vec!["New fact".to_string()],
+        }])
+        .await
+        .unwrap();
+    let after_update = manager.revision().await.unwrap();
+    assert!(after_update > after_create);
+
+    // Cascade deletes of relations count too, and the revision survives reopening
+    manager
+        .delete_entities(vec!["Alice".to_string()])
+        .await
+        .unwrap();
+    let after_delete = manager.revision().await.unwrap();
+    assert!(after_delete > after_update);
+    drop(manager);
+
+    let reopened = KnowledgeGraphManager::new(path).unwrap();
+    let (revision, graph) = reopened.read_graph_with_revision().await.unwrap();
+    assert_eq!(revision, after_delete);
+    let names: Vec<_> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Bob", "Carol"]);
+}
+
+#[tokio::test]
+async fn test_subscribe_receives_changes() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let mut changes = manager.subscribe();
+
+    create_neighborhood(&manager).await;
+    let change = changes.recv().await.unwrap();
+    assert_eq!(
+        change.event,
+        GraphEvent::EntitiesCreated {
+            names: vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()]
+        }
+    );
+    let first_revision = change.revision.unwrap();
+    let change = changes.recv().await.unwrap();
+    assert!(matches!(
+        change.event,
+        GraphEvent::RelationsCreated { ref relations } if relations.len() == 3
+    ));
+    assert!(change.revision.unwrap() > first_revision);
+
+    // No-op writes publish nothing
+    manager
+        .delete_entities(vec!["Nobody".to_string()])
+        .await
+        .unwrap();
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Bob".to_string(),
+            contents: vec!["Plays chess".to_string()],
+        }])
+        .await
+        .unwrap();
+    assert_eq!(
+        changes.recv().await.unwrap().event,
+        GraphEvent::EntitiesUpdated {
+            names: vec!["Bob".to_string()]
+        }
+    );
+
+    manager
+        .delete_entities(vec!["Carol".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(
+        changes.recv().await.unwrap().event,
+        GraphEvent::EntitiesDeleted {
+            names: vec!["Carol".to_string()]
+        }
+    );
+    assert!(changes.try_recv().is_err());
+}
+
+// ============================================================================
+// BATCH VALIDATION TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_batch_validation_reports_every_invalid_item() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let err = manager
+        .create_entities(vec![
+            entity("Alice", "person", &[]),
+            entity("Bob", "per$on", &[]),
+            entity("Carol", "person", &[]),
+            entity("", "person", &[]),
+        ])
+        .await
+        .unwrap_err();
+
+    let invalid = validation_failures(&err).expect("structured validation error");
+    let positions: Vec<_> = invalid
+        .failures
+        .iter()
+        .map(|f| (f.index, f.name.as_str()))
+        .collect();
+    assert_eq!(positions, vec![(1, "Bob"), (3, "")]);
+    assert!(invalid.failures[0].message.contains("Entity type"));
+    assert!(err.to_string().starts_with("2 invalid items: #1 'Bob'"));
+
+    // Nothing was written
+    assert!(manager.read_graph().await.unwrap().entities.is_empty());
+
+    let err = manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Bob".to_string(),
+            relation_type: "bad type!".to_string(),
+        }])
+        .await
+        .unwrap_err();
+    let invalid = validation_failures(&err).unwrap();
+    assert_eq!(invalid.failures[0].name, "Alice -> Bob (bad type!)");
+
+    // Errors unrelated to input validation are not reported as such
+    let err = manager
+        .create_relations(vec![Relation {
+            from: "Alice".to_string(),
+            to: "Bob".to_string(),
+            relation_type: "knows".to_string(),
+        }])
+        .await
+        .unwrap_err();
+    assert!(validation_failures(&err).is_none());
+}
+
+// ============================================================================
+// OBSERVATION SET TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_observations_are_a_set_on_every_path() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+
+    // Repeats in the request collapse, keeping first-occurrence order
+    let created = manager
+        .create_entities(vec![Entity {
+            name: "Alice".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["a".to_string(), "b".to_string(), "a".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(created[0].observations, vec!["a", "b"]);
+
+    // Writes that bypass the Rust checks are rejected by the database itself
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let err = conn
+        .execute(
+            "UPDATE entities SET observations = '[\"x\",\"x\"]' WHERE name = 'Alice'",
+            [],
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("Duplicate observation"));
+    let err = conn
+        .execute(
+            "INSERT INTO entities (name, entity_type, observations) VALUES ('Bob', 'person', '[\"y\",\"y\"]')",
+            [],
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("Duplicate observation"));
+
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 1);
+    assert_eq!(graph.entities[0].observations, vec!["a", "b"]);
+}
+
+#[tokio::test]
+async fn test_existing_duplicate_observations_cleaned_on_open() {
+    let (_dir, path) = create_temp_db();
+    KnowledgeGraphManager::new(path.clone()).unwrap();
+
+    // Simulate a database from before the constraint existed
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "DROP TRIGGER entities_observations_unique_ai;
+             DROP TRIGGER entities_observations_unique_au;
+             INSERT INTO entities (name, entity_type, observations)
+             VALUES ('Alice', 'person', '[\"b\",\"a\",\"b\",\"c\",\"a\"]');",
+        )
+        .unwrap();
+    }
+
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let graph = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+    assert_eq!(graph.entities[0].observations, vec!["b", "a", "c"]);
+
+    // And the row is writable again under the constraint
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["d".to_string()],
+        }])
+        .await
+        .unwrap();
+}
+
+// ============================================================================
+// RECENT ENTITIES TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_recent_entities_orders_by_last_change() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+
+    for name in ["Alice", "Bob", "Carol"] {
+        manager
+            .create_entities(vec![Entity {
+                name: name.to_string(),
+                entity_type: "person".to_string(),
+                observations: vec![],
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            }])
+            .await
+            .unwrap();
+        // Timestamps have millisecond resolution
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let recent = manager.recent_entities(10, 0).await.unwrap();
+    let names: Vec<_> = recent.iter().map(|r| r.entity.name.as_str()).collect();
+    assert_eq!(names, vec!["Carol", "Bob", "Alice"]);
+    assert_eq!(recent[0].created_at, recent[0].updated_at);
+
+    // Updating an entity moves it to the front, keeping its creation time
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Back in focus".to_string()],
+        }])
+        .await
+        .unwrap();
+    let recent = manager.recent_entities(2, 0).await.unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].entity.name, "Alice");
+    assert!(recent[0].updated_at > recent[0].created_at);
+    assert_eq!(recent[1].entity.name, "Carol");
+
+    // Flattened JSON shape with camelCase timestamps
+    let json = serde_json::to_value(&recent[0]).unwrap();
+    assert_eq!(json["name"], "Alice");
+    assert!(json["updatedAt"].as_str().unwrap().ends_with('Z'));
+
+    // Rows without timestamps (pre-migration data) sort last
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute(
+            "INSERT INTO entities (name, entity_type, observations) VALUES ('Legacy', 'person', '[]')",
+            [],
+        )
+        .unwrap();
+    }
+    let recent = manager.recent_entities(10, 0).await.unwrap();
+    assert_eq!(recent.last().unwrap().entity.name, "Legacy");
+    assert_eq!(recent.last().unwrap().updated_at, None);
+
+    assert!(manager.recent_entities(0, 0).await.is_err());
+}
+
+#[tokio::test]
+async fn test_least_accessed_counts_reads() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        track_access: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    create_neighborhood(&manager).await;
+
+    manager
+        .open_nodes(vec!["Alice".to_string(), "Bob".to_string()])
+        .await
+        .unwrap();
+    manager
+        .search_nodes(Some("Alice".to_string()))
+        .await
+        .unwrap();
+    // A blank query reads the whole graph: not counted as reading every entity
+    manager.search_nodes(None).await.unwrap();
+
+    let least = manager.least_accessed(10).await.unwrap();
+    let counts: Vec<_> = least
+        .iter()
+        .map(|e| (e.name.as_str(), e.access_count))
+        .collect();
+    assert_eq!(counts, vec![("Carol", 0), ("Bob", 1), ("Alice", 2)]);
+    assert!(least[0].last_accessed.is_none());
+    assert!(least[2].last_accessed.is_some());
+
+    // Counts go with the entity
+    manager
+        .delete_entities(vec!["Alice".to_string()])
+        .await
+        .unwrap();
+    let least = manager.least_accessed(10).await.unwrap();
+    assert_eq!(least.len(), 2);
+}
+
+#[tokio::test]
+async fn test_community_stops_at_sparse_bridge() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let names = ["A", "B", "C", "D", "E", "F"];
+    create_named(&manager, &names.map(|name| (name, "topic"))).await;
+    // Two triangles joined by the bridge C - D
+    let links = [
+        ("A", "B"),
+        ("B", "C"),
+        ("C", "A"),
+        ("C", "D"),
+        ("D", "E"),
+        ("E", "F"),
+        ("F", "D"),
+    ];
+    let relations = links
+        .iter()
+        .map(|(from, to)| Relation {
+            from: from.to_string(),
+            to: to.to_string(),
+            relation_type: "related_to".to_string(),
+        })
+        .collect();
+    manager.create_relations(relations).await.unwrap();
+
+    let community = manager.community("A".to_string(), 10, 0.7).await.unwrap();
+    let members: Vec<_> = community.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(members, ["A", "B", "C"]);
+    assert_eq!(community.relations.len(), 3);
+    assert_eq!(community.density, 1.0);
+
+    // A lower threshold lets the bridge in; the size cap still applies
+    let community = manager.community("A".to_string(), 10, 0.6).await.unwrap();
+    assert_eq!(community.entities.len(), 4);
+    let community = manager.community("A".to_string(), 2, 0.0).await.unwrap();
+    let members: Vec<_> = community.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(members, ["A", "B"]);
+
+    assert!(manager.community("A".to_string(), 0, 0.3).await.is_err());
+    assert!(manager
+        .community("Nobody".to_string(), 5, 0.3)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_importance_ranks_linked_entities_first() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    assert!(manager.top_important(10).await.unwrap().is_empty());
+
+    create_named(
+        &manager,
+        &[
+            ("Hub", "org"),
+            ("A", "person"),
+            ("B", "person"),
+            ("C", "person"),
+        ],
+    )
+    .await;
+    let links = ["A", "B", "C"].map(|from| Relation {
+        from: from.to_string(),
+        to: "Hub".to_string(),
+        relation_type: "works_at".to_string(),
+    });
+    manager.create_relations(links.to_vec()).await.unwrap();
+
+    let run = manager.compute_importance(100).await.unwrap();
+    assert_eq!((run.entities, run.relations), (4, 3));
+    assert!(run.converged);
+    assert!(run.iterations < 100);
+
+    let top = manager.top_important(10).await.unwrap();
+    assert_eq!(top.len(), 4);
+    assert_eq!(top[0].name, "Hub");
+    assert_eq!(top[0].entity_type, "org");
+    assert!(top[0].importance > top[1].importance);
+    let total: f64 = top.iter().map(|e| e.importance).sum();
+    assert!((total - 1.0).abs() < 1e-6);
+
+    // Scores go with the entity; the iteration count is bounded
+    manager
+        .delete_entities(vec!["Hub".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(manager.top_important(10).await.unwrap().len(), 3);
+    assert!(manager.compute_importance(0).await.is_err());
+    assert!(manager.compute_importance(101).await.is_err());
+}
+
+// ============================================================================
+// READ REPLICA TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_read_replica_serves_read_tools() {
+    let (dir, path) = create_temp_db();
+    let replica = dir.path().join("replica.db");
+    let person = |name: &str, observation: &str| Entity {
+        name: name.to_string(),
+        entity_type: "person".to_string(),
+        observations: vec![observation.to_string()],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    };
+
+    // Replica as left behind by the last sync
+    {
+        let manager = KnowledgeGraphManager::new(replica.clone()).unwrap();
+        manager
+            .create_entities(vec![person("Alice", "Synced earlier")])
+            .await
+            .unwrap();
+    }
+
+    let options = DatabaseOptions {
+        read_replica: Some(replica.clone()),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+    manager
+        .create_entities(vec![person("Bob", "Written to the primary")])
+        .await
+        .unwrap();
+
+    // Read tools see the replica
+    let graph = manager.read_graph().await.unwrap();
+    let names: Vec<_> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Alice"]);
+    let found = manager
+        .search_nodes(Some("Synced".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+    let opened = manager
+        .open_nodes(vec!["Alice".to_string(), "Bob".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(opened.entities.len(), 1);
+    assert_eq!(opened.entities[0].name, "Alice");
+
+    // Writes and other tools use the primary
+    let page = manager
+        .list_entities_by_type("person".to_string(), 10, 0)
+        .await
+        .unwrap();
+    let names: Vec<_> = page.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Bob"]);
+    assert!(manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Only on the replica".to_string()],
+        }])
+        .await
+        .is_err());
+
+    // The replica must exist already
+    let options = DatabaseOptions {
+        read_replica: Some(dir.path().join("missing.db")),
+        ..Default::default()
+    };
+    assert!(KnowledgeGraphManager::with_options(dir.path().join("other.db"), options).is_err());
+}
+
+// ============================================================================
+// OBSERVATION COMPRESSION TESTS
+// ============================================================================
+
+#[cfg(feature = "compress-observations")]
+#[tokio::test]
+async fn test_compressed_observations() {
+    let (_dir, path) = create_temp_db();
+    let observations: Vec<String> = (0..20)
+        .map(|i| format!("Walked through the Montparnasse cemetery, visit {}", i))
+        .collect();
+    let stored = |path: &std::path::Path| -> String {
+        rusqlite::Connection::open(path)
+            .unwrap()
+            .query_row(
+                "SELECT observations FROM entities WHERE name = 'Alice'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+    };
+
+    // Created plain, compressed in place by the first start with compression on
+    {
+        let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+        manager
+            .create_entities(vec![Entity {
+                name: "Alice".to_string(),
+                entity_type: "person".to_string(),
+                observations: observations.clone(),
+                description: None,
+                id: None,
+                confidence: None,
+                metadata: None,
+            }])
+            .await
+            .unwrap();
+    }
+    assert!(stored(&path).starts_with('['));
+
+    let options = DatabaseOptions {
+        compress_observations: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    assert!(stored(&path).starts_with("zstd:"));
+    assert!(stored(&path).len() < serde_json::to_string(&observations).unwrap().len());
+
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities[0].observations, observations);
+
+    // The search index holds the text, also after a rebuild
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Alice".to_string(),
+            contents: vec!["Moved to Lyon".to_string()],
+        }])
+        .await
+        .unwrap();
+    let times = manager
+        .observation_times("Alice".to_string(), true)
+        .await
+        .unwrap();
+    assert_eq!(times.observations.len(), observations.len() + 1);
+    assert_eq!(times.observations[0].text, "Moved to Lyon");
+    assert!(times.observations.iter().all(|o| o.added_at.is_some()));
+    let ranked = manager
+        .entities_by_observation_count(SortOrder::Desc, 1)
+        .await
+        .unwrap();
+    assert_eq!(ranked[0].observation_count, observations.len() as u64 + 1);
+    let values = manager
+        .list_observations(10, 0, Some("lyon".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(values.observations[0].observation, "Moved to Lyon");
+    for query in ["Montparnasse", "Lyon"] {
+        let found = manager.search_nodes(Some(query.to_string())).await.unwrap();
+        assert_eq!(found.entities.len(), 1, "query {}", query);
+    }
+    manager.rebuild_search_index().await.unwrap();
+    assert!(manager
+        .search_index_counts()
+        .await
+        .unwrap()
+        .iter()
+        .all(|count| count.is_consistent()));
+    let found = manager
+        .search_nodes(Some("Montparnasse".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(found.entities.len(), 1);
+    drop(manager);
+
+    // Connections without the server's functions cannot write entities any more
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let err = conn
+        .execute(
+            "UPDATE entities SET observations = '[\"x\"]' WHERE name = 'Alice'",
+            [],
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("observations_json"));
+    drop(conn);
+
+    // Without the flag, compressed arrays are still read (and new writes stay plain)
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities[0].observations.len(), 21);
+}
+
+#[cfg(not(feature = "compress-observations"))]
+#[tokio::test]
+async fn test_compress_observations_needs_feature() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        compress_observations: true,
+        ..Default::default()
+    };
+    let Err(err) = KnowledgeGraphManager::with_options(path, options) else {
+        panic!("compression accepted without the feature");
+    };
+    assert!(err.to_string().contains("compress-observations"));
+}
+
+// ============================================================================
+// STABLE ID TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_entities_get_stable_ids() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    create_neighborhood(&manager).await;
+
+    // Ids are assigned on insert and reported back; ids sent by the client are ignored
+    let created = manager
+        .create_entities(vec![Entity {
+            name: "Dave".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec![],
+            description: None,
+            id: Some("0".repeat(32)),
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    let dave_id = created[0].id.clone().unwrap();
+    assert_eq!(dave_id.len(), 32);
+    assert_ne!(dave_id, "0".repeat(32));
+
+    let graph = manager.read_graph().await.unwrap();
+    let ids: HashSet<_> = graph
+        .entities
+        .iter()
+        .map(|e| e.id.clone().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 4, "every entity has a distinct id");
+
+    // Ids survive reopening and unrelated writes
+    manager
+        .add_observations(vec![ObservationInput {
+            entity_name: "Dave".to_string(),
+            contents: vec!["Joined later".to_string()],
+        }])
+        .await
+        .unwrap();
+    drop(manager);
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let alice = &manager
+        .open_nodes(vec!["Alice".to_string()])
+        .await
+        .unwrap()
+        .entities[0];
+    let alice_id = alice.id.clone().unwrap();
+    assert!(ids.contains(&alice_id));
+
+    let opened = manager
+        .open_nodes_by_id(vec![alice_id, dave_id, "f".repeat(32)])
+        .await
+        .unwrap();
+    let mut names: Vec<_> = opened.entities.iter().map(|e| e.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Alice", "Dave"]);
+    assert_eq!(
+        opened
+            .entities
+            .iter()
+            .find(|e| e.name == "Dave")
+            .unwrap()
+            .observations
+            .len(),
+        1
+    );
+
+    let err = manager
+        .open_nodes_by_id(vec!["not-an-id".to_string()])
+        .await
+        .unwrap_err();
+    assert!(validation_failures(&err).is_some());
+}
+
+#[tokio::test]
+async fn test_migration_assigns_ids_to_existing_entities() {
+    let (_dir, path) = create_temp_db();
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entities (
+                name TEXT PRIMARY KEY NOT NULL,
+                entity_type TEXT NOT NULL,
+                observations TEXT NOT NULL
+             ) STRICT;
+             INSERT INTO entities VALUES ('Alice', 'person', '[]'), ('Bob', 'person', '[]');",
+        )
+        .unwrap();
+    }
+
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    let graph = manager.read_graph().await.unwrap();
+    let ids: HashSet<_> = graph.entities.iter().filter_map(|e| e.id.clone()).collect();
+    assert_eq!(ids.len(), 2);
+}
+
+// ============================================================================
+// SOFT WARNING TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_soft_warnings_do_not_block_writes() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    manager
+        .create_entities(vec![Entity {
+            name: "Acme".to_string(),
+            entity_type: "person".to_string(),
+            observations: vec!["Existing entity".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        }])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![Relation {
+            from: "Acme".to_string(),
+            to: "Acme".to_string(),
+            relation_type: "works_at".to_string(),
+        }])
+        .await
+        .unwrap();
+
+    // Clean input: known type, normal observations
+    let clean = vec![Entity {
+        name: "Bob".to_string(),
+        entity_type: "person".to_string(),
+        observations: vec!["Likes Rust".to_string()],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    }];
+    assert!(manager.entity_warnings(clean).await.unwrap().is_empty());
+
+    let long_name = "a".repeat(240);
+    let entities = vec![
+        Entity {
+            name: "Alice".to_string(),
+            entity_type: "Person".to_string(),
+            observations: vec!["ok".to_string(), "   ".to_string(), "Fine".to_string()],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        },
+        Entity {
+            name: long_name.clone(),
+            entity_type: "robot".to_string(),
+            observations: vec![],
+            description: None,
+            id: None,
+            confidence: None,
+            metadata: None,
+        },
+    ];
+    let warnings = manager.entity_warnings(entities.clone()).await.unwrap();
+    assert_eq!(warnings.len(), 4, "{:?}", warnings);
+    assert!(warnings[0].starts_with("#0 'Alice'") && warnings[0].contains("'person'"));
+    assert!(warnings.iter().any(|w| w.contains("'ok' is very short")));
+    assert!(warnings.iter().any(|w| w.contains("blank")));
+    assert!(warnings
+        .iter()
+        .any(|w| w.starts_with("#1") && w.contains("240 bytes")));
+
+    // Warnings are advisory: the write still goes through
+    manager.create_entities(entities).await.unwrap();
+    assert_eq!(
+        manager
+            .open_nodes(vec![long_name])
+            .await
+            .unwrap()
+            .entities
+            .len(),
+        1
+    );
+
+    // Relation types: one warning per look-alike type, none for known or unrelated types
+    let relation = |relation_type: &str| Relation {
+        from: "Alice".to_string(),
+        to: "Acme".to_string(),
+        relation_type: relation_type.to_string(),
+    };
+    let warnings = manager
+        .relation_warnings(vec![
+            relation("work_at"),
+            relation("work_at"),
+            relation("works_at"),
+            relation("knows"),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].contains("'work_at' is new but resembles existing 'works_at'"));
+
+    let warnings = manager.observation_warnings(&[ObservationInput {
+        entity_name: "Bob".to_string(),
+        contents: vec!["x".to_string(), "A real fact".to_string()],
+    }]);
+    assert_eq!(warnings, vec!["#0 'Bob': observation 'x' is very short"]);
+}
+
+// ============================================================================
+// STAGED OPERATION TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_apply_operations_is_all_or_nothing() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+
+    let person = |name: &str| Entity {
+        name: name.to_string(),
+        entity_type: "person".to_string(),
+        observations: vec![],
+        description: None,
+        id: None,
+        confidence: None,
+        metadata: None,
+    };
+    let knows = |from: &str, to: &str| Relation {
+        from: from.to_string(),
+        to: to.to_string(),
+        relation_type: "knows".to_string(),
+    };
+
+    // Later operations see the effects of earlier ones inside the transaction
+    let results = manager
+        .apply_operations(vec![
+            GraphOperation::CreateEntities {
+                entities: vec![person("Alice"), person("Bob"), person("Carol")],
+            },
+            GraphOperation::CreateRelations {
+                relations: vec![knows("Alice", "Bob"), knows("Bob", "Carol")],
+            },
+            GraphOperation::AddObservations {
+                observations: vec![ObservationInput {
+                    entity_name: "Alice".to_string(),
+                    contents: vec!["Joined in 2024".to_string()],
+                }],
+            },
+            GraphOperation::DeleteEntities {
+                names: vec!["Carol".to_string()],
+            },
+        ])
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    match &results[3] {
+        OperationResult::DeleteEntities { deleted } => assert_eq!(*deleted, 1),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 2);
+    assert_eq!(graph.relations, vec![knows("Alice", "Bob")]);
+
+    // A failing operation rolls back the ones before it and is named in the error
+    let err = manager
+        .apply_operations(vec![
+            GraphOperation::CreateEntities {
+                entities: vec![person("Dave")],
+            },
+            GraphOperation::DeleteRelations {
+                relations: vec![knows("Alice", "Bob")],
+            },
+            GraphOperation::CreateRelations {
+                relations: vec![knows("Dave", "Nobody")],
+            },
+        ])
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Operation #2 (create_relations)"));
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 2);
+    assert_eq!(graph.relations.len(), 1);
+
+    // Invalid input is caught before anything runs, like the direct call
+    let invalid = GraphOperation::CreateEntities {
+        entities: vec![person("")],
+    };
+    assert!(manager.validate_operation(&invalid).is_err());
+    let err = manager.apply_operations(vec![invalid]).await.unwrap_err();
+    assert!(validation_failures(&err).is_some());
+}
+
+// ============================================================================
+// CHECKPOINT TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_checkpoint_create_list_restore() {
+    let (dir, path) = create_temp_db();
+    let checkpoints = dir.path().join("checkpoints");
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+
+    assert!(manager
+        .list_checkpoints(checkpoints.clone())
+        .await
+        .unwrap()
+        .is_empty());
+    let info = manager
+        .create_checkpoint(checkpoints.clone(), "base".to_string(), false)
+        .await
+        .unwrap();
+    assert_eq!(info.name, "base");
+    assert!(info.size > 0);
+    assert!(info.created_at.ends_with('Z'));
+
+    // Names are taken unless overwritten, and must be plain file names
+    assert!(manager
+        .create_checkpoint(checkpoints.clone(), "base".to_string(), false)
+        .await
+        .is_err());
+    manager
+        .create_checkpoint(checkpoints.clone(), "base".to_string(), true)
+        .await
+        .unwrap();
+    assert!(manager
+        .create_checkpoint(checkpoints.clone(), "../escape".to_string(), false)
+        .await
+        .is_err());
+
+    let listed = manager.list_checkpoints(checkpoints.clone()).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "base");
+
+    // Change the graph, then go back
+    manager
+        .delete_entities(vec!["Alice".to_string()])
+        .await
+        .unwrap();
+    let before = manager.revision().await.unwrap();
+    let mut changes = manager.subscribe();
+
+    let revision = manager
+        .restore_checkpoint(checkpoints.clone(), "base".to_string())
+        .await
+        .unwrap();
+    assert!(revision > before);
+    assert_eq!(manager.revision().await.unwrap(), revision);
+    assert_eq!(
+        changes.recv().await.unwrap().event,
+        GraphEvent::CheckpointRestored {
+            name: "base".to_string()
+        }
+    );
+
+    let graph = manager.read_graph().await.unwrap();
+    assert_eq!(graph.entities.len(), 3);
+    assert_eq!(graph.relations.len(), 3);
+    assert_eq!(
+        manager
+            .search_nodes(Some("Alice".to_string()))
+            .await
+            .unwrap()
+            .entities
+            .len(),
+        1
+    );
+
+    assert!(manager
+        .restore_checkpoint(checkpoints, "missing".to_string())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_buffered_observations_flush_before_reads() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        observation_buffer: Some(Duration::from_secs(60)),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    create_neighborhood(&manager).await;
+    let add = |name: &str, contents: &[&str]| ObservationInput {
+        entity_name: name.to_string(),
+        contents: contents.iter().map(|c| c.to_string()).collect(),
+    };
+
+    // Calls for the same entity are coalesced; repeats of held observations add nothing
+    let results = manager
+        .add_observations(vec![add("Alice", &["likes tea", "plays chess"])])
+        .await
+        .unwrap();
+    assert_eq!(results[0].added_observations.len(), 2);
+    let results = manager
+        .add_observations(vec![
+            add("Alice", &["plays chess", "reads"]),
+            add("Bob", &["cooks"]),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(results[0].added_observations, vec!["reads".to_string()]);
+    assert_eq!(results[1].added_observations, vec!["cooks".to_string()]);
+
+    // Validation and entity checks still fail the call up front
+    assert!(manager
+        .add_observations(vec![add("Nobody", &["ghost"])])
+        .await
+        .is_err());
+
+    // Reads write held observations first
+    let graph = manager
+        .open_nodes(vec!["Alice".to_string(), "Bob".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(graph.entities[0].observations.len(), 3);
+    assert_eq!(graph.entities[1].observations, vec!["cooks".to_string()]);
+    assert_eq!(manager.flush_observations().await.unwrap(), 0);
+
+    manager
+        .add_observations(vec![add("Carol", &["runs"])])
+        .await
+        .unwrap();
+    assert_eq!(manager.flush_observations().await.unwrap(), 1);
+    let graph = manager
+        .search_nodes(Some("runs".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(graph.entities.len(), 1);
+
+    let options = DatabaseOptions {
+        observation_buffer: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    assert!(KnowledgeGraphManager::with_options(path, options).is_err());
+}
+
+#[tokio::test]
+async fn test_buffered_observations_survive_failed_flush() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        observation_buffer: Some(Duration::from_secs(60)),
+        query_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    create_neighborhood(&manager).await;
+    let add = |name: &str, contents: &[&str]| ObservationInput {
+        entity_name: name.to_string(),
+        contents: contents.iter().map(|c| c.to_string()).collect(),
+    };
+    manager
+        .add_observations(vec![add("Alice", &["likes tea"]), add("Carol", &["runs"])])
+        .await
+        .unwrap();
+
+    // The write cannot get the lock: the batch is held again instead of being lost, and
+    // reads report the failure instead of answering without the held observations
+    let lock = rusqlite::Connection::open(&path).unwrap();
+    lock.execute_batch("BEGIN EXCLUSIVE;").unwrap();
+    assert!(manager.flush_observations().await.is_err());
+    let err = manager
+        .open_nodes(vec!["Alice".to_string()])
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("buffered observations could not be written"),
+        "Unexpected error: {:#}",
+        err
+    );
+    let mut stream = manager.stream_ndjson();
+    assert!(stream.recv().await.unwrap().is_err());
+    assert!(stream.recv().await.is_none());
+    lock.execute_batch("ROLLBACK;").unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // An entity deleted behind the buffer's back is dropped; the rest is written by the
+    // next read, which then sees them
+    let other = KnowledgeGraphManager::new(path).unwrap();
+    other
+        .delete_entities(vec!["Carol".to_string()])
+        .await
+        .unwrap();
+    let graph = manager.open_nodes(vec!["Alice".to_string()]).await.unwrap();
+    assert!(graph.entities[0]
+        .observations
+        .contains(&"likes tea".to_string()));
+    assert_eq!(manager.flush_observations().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_list_observations() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![
+            ObservationInput {
+                entity_name: "Alice".to_string(),
+                contents: vec!["Likes tea".to_string(), "Plays chess".to_string()],
+            },
+            ObservationInput {
+                entity_name: "Bob".to_string(),
+                contents: vec!["Likes tea".to_string()],
+            },
+            ObservationInput {
+                entity_name: "Carol".to_string(),
+                contents: vec!["Likes tea".to_string(), "Likes 100% cocoa".to_string()],
+            },
+        ])
+        .await
+        .unwrap();
+
+    let values = |page: ObservationValuePage| -> Vec<(String, u64)> {
+        page.observations
+            .into_iter()
+            .map(|v| (v.observation, v.entity_count))
+            .collect()
+    };
+    let page = manager.list_observations(100, 0, None).await.unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(
+        values(page),
+        vec![
+            ("Likes tea".to_string(), 3),
+            ("Likes 100% cocoa".to_string(), 1),
+            ("Plays chess".to_string(), 1)
+        ]
+    );
+
+    let page = manager.list_observations(1, 1, None).await.unwrap();
+    assert_eq!(values(page), vec![("Likes 100% cocoa".to_string(), 1)]);
+
+    // Substring filter is case-insensitive and takes LIKE wildcards literally
+    let page = manager
+        .list_observations(100, 0, Some("LIKES".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(page.total, 2);
+    let page = manager
+        .list_observations(100, 0, Some("0%".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(values(page), vec![("Likes 100% cocoa".to_string(), 1)]);
+
+    assert!(manager.list_observations(0, 0, None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_entities_by_observation_count() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path).unwrap();
+    create_neighborhood(&manager).await;
+    manager
+        .add_observations(vec![
+            ObservationInput {
+                entity_name: "Bob".to_string(),
+                contents: vec!["cooks".to_string(), "sings".to_string()],
+            },
+            ObservationInput {
+                entity_name: "Carol".to_string(),
+                contents: vec!["runs".to_string()],
+            },
+        ])
+        .await
+        .unwrap();
+
+    let ranked = |entities: Vec<ObservationCount>| -> Vec<(String, u64)> {
+        entities
+            .into_iter()
+            .map(|e| (e.name, e.observation_count))
+            .collect()
+    };
+    let desc = manager
+        .entities_by_observation_count(SortOrder::Desc, 20)
+        .await
+        .unwrap();
+    assert_eq!(
+        ranked(desc),
+        vec![
+            ("Bob".to_string(), 2),
+            ("Carol".to_string(), 1),
+            ("Alice".to_string(), 0)
+        ]
+    );
+    let asc = manager
+        .entities_by_observation_count(SortOrder::Asc, 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        ranked(asc),
+        vec![("Alice".to_string(), 0), ("Carol".to_string(), 1)]
+    );
+
+    assert!(manager
+        .entities_by_observation_count(SortOrder::Asc, 0)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_name_type_keys_tell_same_names_apart() {
+    let (_dir, path) = create_temp_db();
+    let options = DatabaseOptions {
+        name_type_keys: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path, options).unwrap();
+
+    let created = manager
+        .create_entities(vec![
+            entity("Mercury", "planet", &["Closest to the sun"]),
+            entity("Mercury", "person", &["Sang Bohemian Rhapsody"]),
+            entity("Sun#star", "star", &[]),
+        ])
+        .await
+        .unwrap();
+    let keys: Vec<&str> = created.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(keys, vec!["Mercury#planet", "Mercury#person", "Sun#star"]);
+
+    manager
+        .create_relations(vec![relation("Mercury#planet", "Sun#star", "orbits")])
+        .await
+        .unwrap();
+
+    // Bare names match no entity; the error lists the keys they could mean
+    let err = manager
+        .create_relations(vec![relation("Mercury", "Sun#star", "orbits")])
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("did you mean 'Mercury#person', 'Mercury#planet'?"));
+    let err = manager
+        .get_neighbors("Mercury".to_string(), Direction::Both)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("'Mercury#person', 'Mercury#planet'"));
+    let err = manager
+        .get_neighbors("Venus".to_string(), Direction::Both)
+        .await
+        .unwrap_err();
+    assert!(!err.to_string().contains("did you mean"));
+
+    let neighborhood = manager
+        .get_neighbors("Sun#star".to_string(), Direction::Both)
+        .await
+        .unwrap();
+    assert_eq!(neighborhood.entities.len(), 1);
+    assert_eq!(neighborhood.entities[0].name, "Mercury#planet");
+
+    // Aliases stay plain names and resolve to keys at relation endpoints
+    manager
+        .add_aliases("Sun#star".to_string(), vec!["Sol".to_string()])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![relation("Mercury#person", "Sol", "sings_about")])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        manager
+            .delete_entities(vec!["Mercury#person".to_string()])
+            .await
+            .unwrap(),
+        1
+    );
+    let graph = manager.read_graph().await.unwrap();
+    let names: HashSet<&str> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, HashSet::from(["Mercury#planet", "Sun#star"]));
+    assert_eq!(graph.relations.len(), 1);
+}
+
+#[tokio::test]
+async fn test_name_type_keys_migrate_existing_database() {
+    let (_dir, path) = create_temp_db();
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    manager
+        .create_entities(vec![
+            entity("Alice", "person", &["Likes tea"]),
+            entity("Acme", "company", &[]),
+            // Already looks like a key of another type: must not collide with Alice's
+            entity("Alice#person", "nickname", &[]),
+        ])
+        .await
+        .unwrap();
+    manager
+        .create_relations(vec![
+            relation("Alice", "Acme", "works_at"),
+            relation("Alice#person", "Alice", "names"),
+        ])
+        .await
+        .unwrap();
+    manager
+        .add_aliases("Acme".to_string(), vec!["ACME Inc".to_string()])
+        .await
+        .unwrap();
+    drop(manager);
+
+    let options = DatabaseOptions {
+        name_type_keys: true,
+        ..Default::default()
+    };
+    let manager = KnowledgeGraphManager::with_options(path.clone(), options).unwrap();
+    let graph = manager.read_graph().await.unwrap();
+    let names: HashSet<&str> = graph.entities.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(
+        names,
+        HashSet::from(["Alice#person", "Acme#company", "Alice#person#nickname"])
+    );
+    let relations: HashSet<(&str, &str)> = graph
+        .relations
+        .iter()
+        .map(|r| (r.from.as_str(), r.to.as_str()))
+        .collect();
+    assert_eq!(
+        relations,
+        HashSet::from([
+            ("Alice#person", "Acme#company"),
+            ("Alice#person#nickname", "Alice#person"),
+        ])
+    );
+    let alice = manager
+        .open_nodes(vec!["Alice#person".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(alice.entities[0].observations, vec!["Likes tea"]);
+    let aliases = manager
+        .entity_aliases("Acme#company".to_string())
+        .await
+        .unwrap();
+    assert_eq!(aliases.aliases, vec!["ACME Inc"]);
+    drop(manager);
+
+    // The switch is one-way: opened without the option, new entities still get keys
+    let manager = KnowledgeGraphManager::new(path.clone()).unwrap();
+    let created = manager
+        .create_entities(vec![entity("Bob", "person", &[])])
+        .await
+        .unwrap();
+    assert_eq!(created[0].name, "Bob#person");
+    drop(manager);
+
+    // Rows written from outside the server must carry their type too
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let err = conn
+        .execute(
+            "INSERT INTO entities (name, entity_type, observations) VALUES ('Carol', 'person', '[]')",
+            [],
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("#<entity type>"));
+    let err = conn
+        .execute(
+            "UPDATE entities SET entity_type = 'robot' WHERE name = 'Bob#person'",
+            [],
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("#<entity type>"));
+}